@@ -4,7 +4,7 @@
 use core::slice;
 
 use embassy_executor::Spawner;
-use embassy_time::{Duration, Timer};
+use embassy_time::{with_timeout, Duration, TimeoutError, Timer};
 use esp_backtrace as _;
 #[cfg(not(feature = "smart-led"))]
 use esp_hal::gpio::AnyOutput;
@@ -111,18 +111,32 @@ async fn main(spawner: Spawner) {
     spawner.spawn(reader(rx, led)).ok();
 }
 
+// meters send a transmission every few seconds; a longer pause means the link stalled
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[embassy_executor::task]
 async fn reader(mut rx: UartRx<'static, UART1, Async>, mut led: LedTy) {
     log::info!("Starting reader task!");
     let mut led_state = false;
 
-    let buf = ArrayBuf::<4069>::default();
+    let buf = ArrayBuf::<4096>::default();
     let mut decoder = Decoder::from_buf(buf);
 
     loop {
         // read byte from the pin
         let mut b = 0u8;
-        let r = embedded_io_async::Read::read(&mut rx, slice::from_mut(&mut b)).await;
+        let read = embedded_io_async::Read::read(&mut rx, slice::from_mut(&mut b));
+        let r = match with_timeout(READ_TIMEOUT, read).await {
+            Ok(r) => r,
+            Err(TimeoutError) => {
+                // the link stalled: discard the incomplete transmission (if any)
+                let num_discarded = decoder.reset();
+                if num_discarded > 0 {
+                    log::warn!("Read timed out, discarded {num_discarded} bytes");
+                }
+                continue;
+            }
+        };
 
         // toggle the LED
         toggle_led(&mut led_state, &mut led);
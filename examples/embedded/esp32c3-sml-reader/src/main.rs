@@ -106,7 +106,7 @@ fn main() -> ! {
 
 #[allow(unused)]
 fn read_blocking(pin: &mut impl embedded_io::Read, mut toggle_led: impl FnMut()) -> ! {
-    let buf = ArrayBuf::<4069>::default();
+    let buf = ArrayBuf::<4096>::default();
     let mut decoder = Decoder::from_buf(buf);
 
     loop {
@@ -144,7 +144,7 @@ fn read_polling<PIN: embedded_io::Read + embedded_io::ReadReady>(
     pin: &mut PIN,
     mut toggle_led: impl FnMut(),
 ) -> ! {
-    let buf = ArrayBuf::<4069>::default();
+    let buf = ArrayBuf::<4096>::default();
     let mut decoder = Decoder::from_buf(buf);
 
     let mut last_print_time = 0;
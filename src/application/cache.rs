@@ -0,0 +1,375 @@
+//! Latest values of all OBIS codes of a meter.
+//!
+//! [`ValueCache`] stores the most recent value of each OBIS code together with the
+//! `SecIndex` when it was observed. It is ready-made state for applications serving
+//! the current meter data (e.g. via a REST endpoint) while transmissions arrive in
+//! the background.
+//!
+//! The values are held by a [`CacheStorage`]: [`ArrayStorage`] holds a fixed number
+//! of OBIS codes and doesn't allocate, `HashMapStorage` grows as needed (`std` only).
+//!
+//! Only numeric values are cached, as [`Quantity`] including their scaler and unit.
+//! Values that don't fit into an `i64` and non-numeric values (booleans, octet strings
+//! such as the public key of the meter) are ignored, because storing them without
+//! allocating would need a fixed-size copy of arbitrarily long octet strings. Such
+//! values can be obtained from the parsed transmission using
+//! [`extract::raw_entries`](super::extract::raw_entries).
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{cache::{ArrayStorage, ValueCache}, quantity::Quantity}, parser::ObisCode};
+//! let power = ObisCode::ACTIVE_POWER;
+//! let mut cache = ValueCache::<ArrayStorage<16>>::new();
+//! cache.push(power, 100, Quantity { value: 320, scaler: 0, unit: Some(27) });
+//! cache.push(power, 101, Quantity { value: 280, scaler: 0, unit: Some(27) });
+//!
+//! assert_eq!(cache.get(power).map(|q| q.value), Some(280));
+//! assert_eq!(cache.age_of(power, 105), Some(4));
+//! assert_eq!(cache.get(ObisCode::ENERGY_IMPORT_TOTAL), None);
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{common::ListEntry, ObisCode},
+    util::private,
+};
+
+/// A cached value together with the `SecIndex` when it was observed.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedValue {
+    /// the value
+    pub quantity: Quantity,
+    /// the `SecIndex` when the value was observed
+    pub sec_index: u32,
+}
+
+/// Storage of the values of a [`ValueCache`].
+///
+/// This trait is implemented for arrays of a fixed size ([`ArrayStorage`]) and (if
+/// the `std` feature is used) for hash maps (`HashMapStorage`).
+pub trait CacheStorage: Default + private::Sealed {
+    /// Returns the value of `obis_code`.
+    fn get(&self, obis_code: ObisCode) -> Option<&CachedValue>;
+
+    /// Stores the value of `obis_code`, replacing its previous value.
+    ///
+    /// Returns `false` if the storage is full and the value couldn't be stored.
+    fn insert(&mut self, obis_code: ObisCode, value: CachedValue) -> bool;
+
+    /// Returns the number of stored OBIS codes.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if no values are stored.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the stored values (in unspecified order).
+    fn iter(&self) -> impl Iterator<Item = (ObisCode, CachedValue)> + '_;
+
+    /// Removes all values.
+    fn clear(&mut self);
+}
+
+/// Storage holding the values of up to `N` OBIS codes.
+///
+/// OBIS codes are stored in the order of their first occurrence. Once `N` OBIS
+/// codes are stored, values of further OBIS codes are ignored.
+#[derive(Debug, Clone)]
+pub struct ArrayStorage<const N: usize> {
+    entries: [Option<(ObisCode, CachedValue)>; N],
+}
+
+impl<const N: usize> Default for ArrayStorage<N> {
+    fn default() -> Self {
+        ArrayStorage { entries: [None; N] }
+    }
+}
+
+impl<const N: usize> CacheStorage for ArrayStorage<N> {
+    fn get(&self, obis_code: ObisCode) -> Option<&CachedValue> {
+        self.entries
+            .iter()
+            .flatten()
+            .find_map(|(code, value)| (*code == obis_code).then_some(value))
+    }
+
+    fn insert(&mut self, obis_code: ObisCode, value: CachedValue) -> bool {
+        let Some(slot) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.map_or(true, |(code, _)| code == obis_code))
+        else {
+            return false;
+        };
+        *slot = Some((obis_code, value));
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (ObisCode, CachedValue)> + '_ {
+        self.entries.iter().flatten().copied()
+    }
+
+    fn clear(&mut self) {
+        self.entries = [None; N];
+    }
+}
+
+impl<const N: usize> private::Sealed for ArrayStorage<N> {}
+
+/// Type alias for `std::collections::HashMap<ObisCode, CachedValue>`
+///
+/// *This type is available only if sml-rs is built with the `"std"` feature.*
+#[cfg(feature = "std")]
+pub type HashMapStorage = std::collections::HashMap<ObisCode, CachedValue>;
+
+#[cfg(feature = "std")]
+impl CacheStorage for HashMapStorage {
+    fn get(&self, obis_code: ObisCode) -> Option<&CachedValue> {
+        HashMapStorage::get(self, &obis_code)
+    }
+
+    fn insert(&mut self, obis_code: ObisCode, value: CachedValue) -> bool {
+        HashMapStorage::insert(self, obis_code, value);
+        true
+    }
+
+    fn len(&self) -> usize {
+        HashMapStorage::len(self)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (ObisCode, CachedValue)> + '_ {
+        HashMapStorage::iter(self).map(|(code, value)| (*code, *value))
+    }
+
+    fn clear(&mut self) {
+        HashMapStorage::clear(self);
+    }
+}
+
+#[cfg(feature = "std")]
+impl private::Sealed for HashMapStorage {}
+
+/// Most recent numeric values of the OBIS codes of a meter.
+///
+/// Non-numeric values aren't cached. See the [module-level documentation](self) for
+/// more information.
+#[derive(Debug, Clone, Default)]
+pub struct ValueCache<S: CacheStorage> {
+    storage: S,
+}
+
+impl<S: CacheStorage> ValueCache<S> {
+    /// Constructs an empty cache.
+    #[must_use]
+    pub fn new() -> Self {
+        ValueCache {
+            storage: S::default(),
+        }
+    }
+
+    /// Stores the value of `obis_code` observed at `sec_index`, replacing its previous value.
+    ///
+    /// Returns `false` if the storage is full and the value couldn't be stored.
+    pub fn push(&mut self, obis_code: ObisCode, sec_index: u32, quantity: Quantity) -> bool {
+        self.storage.insert(
+            obis_code,
+            CachedValue {
+                quantity,
+                sec_index,
+            },
+        )
+    }
+
+    /// Stores the value of `entry`.
+    ///
+    /// The value is recorded at [`ListEntry::sec_index_or`]`(sec_index)`, i.e. at its `val_time`
+    /// if present.
+    /// Returns `true` if the value has been stored. Entries without an OBIS code and
+    /// values that aren't numeric or don't fit into an `i64` are ignored (see the
+    /// [module-level documentation](self)).
+    pub fn record(&mut self, entry: &ListEntry<'_>, sec_index: u32) -> bool {
+        let (Some(obis_code), Some(quantity)) =
+            (entry.obis_code(), Quantity::from_list_entry(entry))
+        else {
+            return false;
+        };
+        let time = entry.sec_index_or(sec_index);
+        self.push(obis_code, time, quantity)
+    }
+
+    /// Stores the values of all `GetListResponse` messages of `file` and returns the
+    /// number of stored values.
+    ///
+    /// Entries without a `SecIndex` in their `val_time` are recorded using the
+    /// `act_sensor_time` of their `GetListResponse`. Messages where neither is
+    /// available are ignored.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn merge(&mut self, file: &crate::parser::complete::File<'_>) -> usize {
+        use crate::parser::complete::MessageBody;
+
+        let mut num_stored = 0;
+        for msg in &file.messages {
+            let MessageBody::GetListResponse(glr) = &msg.message_body else {
+                continue;
+            };
+            let Some(sec_index) = glr.act_sensor_time.as_ref().and_then(|t| t.sec_index()) else {
+                continue;
+            };
+            for entry in &glr.val_list {
+                num_stored += usize::from(self.record(entry, sec_index));
+            }
+        }
+        num_stored
+    }
+
+    /// Returns the latest value of `obis_code`.
+    #[must_use]
+    pub fn get(&self, obis_code: ObisCode) -> Option<Quantity> {
+        self.storage.get(obis_code).map(|v| v.quantity)
+    }
+
+    /// Returns the latest value of `obis_code` together with the `SecIndex` when it was observed.
+    #[must_use]
+    pub fn get_cached(&self, obis_code: ObisCode) -> Option<CachedValue> {
+        self.storage.get(obis_code).copied()
+    }
+
+    /// Returns the number of seconds since the latest value of `obis_code` was observed.
+    ///
+    /// Returns `None` if there's no value or if `now` is smaller than the `SecIndex`
+    /// of the value (e.g. because the meter rebooted in the meantime).
+    #[must_use]
+    pub fn age_of(&self, obis_code: ObisCode, now: u32) -> Option<u32> {
+        now.checked_sub(self.storage.get(obis_code)?.sec_index)
+    }
+
+    /// Returns an iterator over the OBIS codes and their latest values (in unspecified order).
+    pub fn iter(&self) -> impl Iterator<Item = (ObisCode, CachedValue)> + '_ {
+        self.storage.iter()
+    }
+
+    /// Returns the number of OBIS codes with a value.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Returns `true` if there are no values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// Removes all values.
+    pub fn clear(&mut self) {
+        self.storage.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::common::{Time, Value};
+
+    const ENERGY: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const POWER: ObisCode = ObisCode::ACTIVE_POWER;
+
+    fn w(value: i64) -> Quantity {
+        Quantity {
+            value,
+            scaler: 0,
+            unit: Some(27),
+        }
+    }
+
+    fn cache<S: CacheStorage>() {
+        let mut cache = ValueCache::<S>::new();
+        assert!(cache.is_empty());
+        assert!(cache.push(POWER, 10, w(100)));
+        assert!(cache.push(POWER, 12, w(200)));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(POWER), Some(w(200)));
+        assert_eq!(
+            cache.get_cached(POWER),
+            Some(CachedValue {
+                quantity: w(200),
+                sec_index: 12
+            })
+        );
+        assert_eq!(cache.age_of(POWER, 15), Some(3));
+        assert_eq!(cache.age_of(POWER, 11), None);
+        assert_eq!(cache.age_of(ENERGY, 15), None);
+
+        let entry = ListEntry::new_test(&ENERGY.0, Value::U32(7)).with_unit(Some(30), 2);
+        assert!(cache.record(&entry, 20));
+        let entry = entry.with_val_time(Some(Time::SecIndex(18)));
+        assert!(cache.record(&entry, 20));
+        assert_eq!(cache.age_of(ENERGY, 20), Some(2));
+        assert!(!cache.record(&ListEntry::new_test(&[1, 2], Value::I8(1)), 20));
+        assert!(!cache.record(&ListEntry::new_test(&POWER.0, Value::Bool(true)), 20));
+        assert_eq!(cache.get(POWER), Some(w(200)));
+        assert_eq!(cache.iter().count(), 2);
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn array_storage() {
+        cache::<ArrayStorage<2>>();
+
+        let mut cache = ValueCache::<ArrayStorage<1>>::new();
+        assert!(cache.push(POWER, 10, w(100)));
+        assert!(!cache.push(ENERGY, 10, w(100)));
+        assert!(cache.push(POWER, 11, w(50)));
+        assert!(cache.iter().eq([(
+            POWER,
+            CachedValue {
+                quantity: w(50),
+                sec_index: 11
+            }
+        )]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn hash_map_storage() {
+        cache::<HashMapStorage>();
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn merge() {
+        use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), -1, |t| i64::from(t) * 10)
+            .register(POWER, Some(27), 0, |_| 300);
+        let mut cache = ValueCache::<ArrayStorage<4>>::new();
+        for t in [5, 6] {
+            let bytes = decode(meter.next_frame(t)).remove(0).unwrap();
+            assert_eq!(cache.merge(&parse(&bytes).unwrap()), 2);
+        }
+        assert_eq!(
+            cache.get(ENERGY),
+            Some(Quantity {
+                value: 60,
+                scaler: -1,
+                unit: Some(30)
+            })
+        );
+        assert_eq!(cache.age_of(POWER, 8), Some(2));
+    }
+}
@@ -0,0 +1,448 @@
+//! Persisting settings in a CRC-protected blob.
+//!
+//! Gateways usually store their configuration in EEPROM or flash, where it can
+//! be corrupted by interrupted writes or worn-out cells. [`Settings`] collects the
+//! sml-rs-related settings of an application (the parser quirks of a meter and
+//! the registers it extracts, together with optional thresholds) and converts them
+//! into a compact byte blob protected by a CRC-16/X-25 checksum.
+//! Neither direction allocates.
+//!
+//! # Format
+//!
+//! - the format version (currently `0x02`)
+//! - a flags byte: bit 0 `ignore_trailing_zeros`, bit 1 `reject_unsupported_versions`,
+//!   bit 2 CRC verification disabled, bit 3 fuzzy resynchronization, bits 4 - 5 the
+//!   [`TransactionIdOrder`] (0: any, 1: big endian, 2: little endian), bit 6
+//!   `empty_octet_strings_as_none`, bit 7 `keep_truncated_lists`
+//! - a second flags byte: bit 0 `skip_unknown_fields` (not present in version `0x01`)
+//! - the number of registers
+//! - 17 bytes for each register: the OBIS code, a flags byte (bit 0: threshold
+//!   present, bit 1: unit present), the threshold's value (`i64`, big endian), scaler
+//!   and unit. Absent fields are set to zero.
+//! - the checksum over all previous bytes (little endian)
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{config::{Register, Settings}, quantity::Quantity}, parser::{ObisCode, ParseOptions}, util::ArrayBuf};
+//! let mut settings = Settings::<8>::new();
+//! settings.parse_options = ParseOptions::new().ignore_trailing_zeros(true);
+//! settings.push_register(Register::new(ObisCode::ENERGY_IMPORT_TOTAL)).unwrap();
+//! let threshold = Quantity { value: 3, scaler: 3, unit: Some(27) };
+//! settings.push_register(Register::new(ObisCode::ACTIVE_POWER).with_threshold(threshold)).unwrap();
+//!
+//! let mut blob = ArrayBuf::<64>::default();
+//! settings.write(&mut blob).unwrap();
+//! assert_eq!(blob.len(), settings.encoded_len());
+//! // ... store `blob` in EEPROM and read it back after a restart ...
+//! assert_eq!(Settings::<8>::read(&blob), Ok(settings));
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{sequence::TransactionIdOrder, ObisCode, ParseOptions},
+    util::{Buffer, CrcMismatch, CRC_X25},
+};
+
+/// Current version of the blob format
+pub const FORMAT_VERSION: u8 = 2;
+
+// number of bytes used for each register
+const REGISTER_LEN: usize = 17;
+
+/// Error returned when writing or reading settings fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The output buffer is full
+    OutOfMemory,
+    /// The blob contains more registers than the `Settings` can hold
+    TooManyRegisters,
+    /// The blob ended unexpectedly
+    UnexpectedEof,
+    /// The blob has been corrupted
+    CrcMismatch(CrcMismatch),
+    /// The blob uses an unsupported format version
+    UnsupportedVersion(u8),
+    /// The blob contains invalid values (e.g. unknown flags)
+    InvalidInput,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::OutOfMemory => f.write_str("settings don't fit into the output buffer"),
+            ConfigError::TooManyRegisters => f.write_str("settings contain too many registers"),
+            ConfigError::UnexpectedEof => f.write_str("settings blob is truncated"),
+            ConfigError::CrcMismatch(crc) => {
+                write!(f, "{crc} in settings blob, the blob is corrupted")
+            }
+            ConfigError::UnsupportedVersion(v) => write!(f, "unsupported settings version {v}"),
+            ConfigError::InvalidInput => f.write_str("settings blob contains invalid values"),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
+
+impl From<crate::util::OutOfMemory> for ConfigError {
+    fn from(_: crate::util::OutOfMemory) -> Self {
+        ConfigError::OutOfMemory
+    }
+}
+
+/// A register extracted by the application.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register {
+    /// OBIS code of the register
+    pub obis_code: ObisCode,
+    /// optional threshold (e.g. for alerts) defined by the application
+    pub threshold: Option<Quantity>,
+}
+
+impl Register {
+    /// Constructs a register without threshold.
+    #[must_use]
+    pub const fn new(obis_code: ObisCode) -> Self {
+        Register {
+            obis_code,
+            threshold: None,
+        }
+    }
+
+    /// Sets the threshold of the register.
+    #[must_use]
+    pub const fn with_threshold(mut self, threshold: Quantity) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+}
+
+/// Settings of an application holding up to `N` registers.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings<const N: usize> {
+    /// options used when parsing transmissions of the meter
+    pub parse_options: ParseOptions,
+    /// whether the transport decoder uses fuzzy resynchronization
+    pub fuzzy_resync: bool,
+    /// byte order of the meter's transaction identifiers
+    pub transaction_id_order: TransactionIdOrder,
+    registers: [Register; N],
+    num_registers: usize,
+}
+
+impl<const N: usize> Default for Settings<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Settings<N> {
+    /// Constructs settings using the default options and no registers.
+    #[must_use]
+    pub const fn new() -> Self {
+        Settings {
+            parse_options: ParseOptions::new(),
+            fuzzy_resync: false,
+            transaction_id_order: TransactionIdOrder::Any,
+            registers: [Register::new(ObisCode::new(0, 0, 0, 0, 0, 0)); N],
+            num_registers: 0,
+        }
+    }
+
+    /// Returns the registers in the order they have been added.
+    #[must_use]
+    pub fn registers(&self) -> &[Register] {
+        &self.registers[..self.num_registers]
+    }
+
+    /// Returns the register with the given OBIS code.
+    #[must_use]
+    pub fn get(&self, obis_code: ObisCode) -> Option<&Register> {
+        self.registers().iter().find(|r| r.obis_code == obis_code)
+    }
+
+    /// Adds a register.
+    ///
+    /// Returns an error if the settings already hold `N` registers. At most 255
+    /// registers can be written to a blob.
+    pub fn push_register(&mut self, register: Register) -> Result<(), ConfigError> {
+        let slot = self
+            .registers
+            .get_mut(self.num_registers)
+            .ok_or(ConfigError::TooManyRegisters)?;
+        *slot = register;
+        self.num_registers += 1;
+        Ok(())
+    }
+
+    /// Removes all registers.
+    pub fn clear_registers(&mut self) {
+        self.num_registers = 0;
+    }
+
+    /// Returns the number of bytes written by [`write`](Settings::write).
+    #[must_use]
+    pub fn encoded_len(&self) -> usize {
+        4 + self.num_registers * REGISTER_LEN + 2
+    }
+
+    /// Appends the blob containing the settings to `out`.
+    ///
+    /// On error, `out` may contain a partially written blob.
+    pub fn write<B: Buffer>(&self, out: &mut B) -> Result<(), ConfigError> {
+        let num_registers =
+            u8::try_from(self.num_registers).map_err(|_| ConfigError::TooManyRegisters)?;
+        let start = out.len();
+        let options = &self.parse_options;
+        let order = match self.transaction_id_order {
+            TransactionIdOrder::Any => 0,
+            TransactionIdOrder::BigEndian => 1,
+            TransactionIdOrder::LittleEndian => 2,
+        };
+        let flags = u8::from(options.ignore_trailing_zeros)
+            | u8::from(options.reject_unsupported_versions) << 1
+            | u8::from(options.skip_crc_verification) << 2
+            | u8::from(self.fuzzy_resync) << 3
+            | order << 4
+            | u8::from(options.empty_octet_strings_as_none) << 6
+            | u8::from(options.keep_truncated_lists) << 7;
+        let flags2 = u8::from(options.skip_unknown_fields);
+        out.extend_from_slice(&[FORMAT_VERSION, flags, flags2, num_registers])?;
+        for register in self.registers() {
+            let threshold = register.threshold.unwrap_or(Quantity {
+                value: 0,
+                scaler: 0,
+                unit: None,
+            });
+            let flags =
+                u8::from(register.threshold.is_some()) | u8::from(threshold.unit.is_some()) << 1;
+            out.extend_from_slice(&register.obis_code.0)?;
+            out.push(flags)?;
+            out.extend_from_slice(&threshold.value.to_be_bytes())?;
+            out.extend_from_slice(&threshold.scaler.to_be_bytes())?;
+            out.push(threshold.unit.unwrap_or(0))?;
+        }
+        let crc = CRC_X25.checksum(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Reads settings from a blob written by [`write`](Settings::write).
+    ///
+    /// Bytes following the blob are ignored, which allows reading the blob from
+    /// a larger storage area. Blobs written using format version `0x01` are supported
+    /// as well.
+    pub fn read(blob: &[u8]) -> Result<Self, ConfigError> {
+        let (header_len, flags, flags2, num_registers) = match *blob {
+            [1, flags, num_registers, ..] => (3, flags, 0, num_registers),
+            [FORMAT_VERSION, flags, flags2, num_registers, ..] => (4, flags, flags2, num_registers),
+            [version, ..] if version != 1 && version != FORMAT_VERSION => {
+                return Err(ConfigError::UnsupportedVersion(version));
+            }
+            _ => return Err(ConfigError::UnexpectedEof),
+        };
+        if flags2 & !1 != 0 {
+            return Err(ConfigError::InvalidInput);
+        }
+        let len = header_len + usize::from(num_registers) * REGISTER_LEN;
+        let (data, crc) = blob
+            .get(..len + 2)
+            .ok_or(ConfigError::UnexpectedEof)?
+            .split_at(len);
+        let expected = u16::from_le_bytes([crc[0], crc[1]]);
+        let computed = CRC_X25.checksum(data);
+        if expected != computed {
+            return Err(ConfigError::CrcMismatch(CrcMismatch { expected, computed }));
+        }
+
+        let mut settings = Settings::new();
+        settings.parse_options = ParseOptions {
+            ignore_trailing_zeros: flags & 1 != 0,
+            reject_unsupported_versions: flags & 1 << 1 != 0,
+            skip_crc_verification: flags & 1 << 2 != 0,
+            empty_octet_strings_as_none: flags & 1 << 6 != 0,
+            keep_truncated_lists: flags & 1 << 7 != 0,
+            skip_unknown_fields: flags2 & 1 != 0,
+        };
+        settings.fuzzy_resync = flags & 1 << 3 != 0;
+        settings.transaction_id_order = match (flags >> 4) & 0b11 {
+            0 => TransactionIdOrder::Any,
+            1 => TransactionIdOrder::BigEndian,
+            2 => TransactionIdOrder::LittleEndian,
+            _ => return Err(ConfigError::InvalidInput),
+        };
+        for chunk in data[header_len..].chunks_exact(REGISTER_LEN) {
+            let [o0, o1, o2, o3, o4, o5, flags, v0, v1, v2, v3, v4, v5, v6, v7, scaler, unit] =
+                *chunk
+            else {
+                return Err(ConfigError::UnexpectedEof);
+            };
+            if flags & !0b11 != 0 {
+                return Err(ConfigError::InvalidInput);
+            }
+            let threshold = Quantity {
+                value: i64::from_be_bytes([v0, v1, v2, v3, v4, v5, v6, v7]),
+                scaler: i8::from_be_bytes([scaler]),
+                unit: (flags & 0b10 != 0).then_some(unit),
+            };
+            settings.push_register(Register {
+                obis_code: ObisCode([o0, o1, o2, o3, o4, o5]),
+                threshold: (flags & 1 != 0).then_some(threshold),
+            })?;
+        }
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::ArrayBuf;
+    use hex_literal::hex;
+
+    fn settings() -> Settings<2> {
+        let mut settings = Settings::new();
+        settings.parse_options = ParseOptions::new()
+            .reject_unsupported_versions(true)
+            .verify_crc(false)
+            .empty_octet_strings_as_none(true)
+            .keep_truncated_lists(true)
+            .skip_unknown_fields(true);
+        settings.fuzzy_resync = true;
+        settings.transaction_id_order = TransactionIdOrder::LittleEndian;
+        let threshold = Quantity {
+            value: -5,
+            scaler: -1,
+            unit: Some(30),
+        };
+        let registers = [
+            Register::new(ObisCode::ENERGY_IMPORT_TOTAL),
+            Register::new(ObisCode::ENERGY_EXPORT_TOTAL).with_threshold(threshold),
+        ];
+        for r in registers {
+            settings.push_register(r).unwrap();
+        }
+        settings
+    }
+
+    #[test]
+    fn roundtrip() {
+        let settings = settings();
+        let mut blob = ArrayBuf::<64>::default();
+        settings.write(&mut blob).unwrap();
+        assert_eq!(
+            &blob[..4 + 2 * REGISTER_LEN],
+            hex!(
+                "02 ee 01 02"
+                "0100010800ff 00 0000000000000000 00 00"
+                "0100020800ff 03 fffffffffffffffb ff 1e"
+            )
+        );
+        assert_eq!(Settings::<2>::read(&blob), Ok(settings.clone()));
+        assert_eq!(
+            settings
+                .get(ObisCode::ENERGY_EXPORT_TOTAL)
+                .unwrap()
+                .threshold
+                .unwrap()
+                .value,
+            -5
+        );
+
+        // trailing bytes (e.g. erased flash) are ignored
+        blob.extend_from_slice(&[0xFF; 8]).unwrap();
+        assert_eq!(Settings::<2>::read(&blob), Ok(settings.clone()));
+
+        let mut empty = ArrayBuf::<8>::default();
+        Settings::<0>::new().write(&mut empty).unwrap();
+        assert_eq!(Settings::<0>::read(&empty), Ok(Settings::new()));
+        assert!(matches!(
+            settings.write(&mut empty),
+            Err(ConfigError::OutOfMemory)
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let settings = settings();
+        let mut blob = ArrayBuf::<64>::default();
+        settings.write(&mut blob).unwrap();
+
+        assert_eq!(
+            Settings::<1>::read(&blob),
+            Err(ConfigError::TooManyRegisters)
+        );
+        assert_eq!(
+            Settings::<2>::read(&blob[..blob.len() - 1]),
+            Err(ConfigError::UnexpectedEof)
+        );
+        assert_eq!(Settings::<2>::read(&[]), Err(ConfigError::UnexpectedEof));
+
+        let mut corrupted = [0; 64];
+        let corrupted = &mut corrupted[..blob.len()];
+        corrupted.copy_from_slice(&blob);
+        corrupted[10] ^= 0x01;
+        assert!(matches!(
+            Settings::<2>::read(corrupted),
+            Err(ConfigError::CrcMismatch(_))
+        ));
+
+        let mut settings = Settings::<1>::new();
+        settings
+            .push_register(Register::new(ObisCode::ACTIVE_POWER))
+            .unwrap();
+        assert_eq!(
+            settings.push_register(Register::new(ObisCode::ACTIVE_POWER)),
+            Err(ConfigError::TooManyRegisters)
+        );
+    }
+
+    #[test]
+    fn invalid_flags() {
+        // re-computes the checksum after modifying the blob
+        let with_crc = |data: &[u8]| {
+            let mut blob = ArrayBuf::<64>::default();
+            blob.extend_from_slice(data).unwrap();
+            blob.extend_from_slice(&CRC_X25.checksum(data).to_le_bytes())
+                .unwrap();
+            blob
+        };
+        assert_eq!(
+            Settings::<0>::read(&with_crc(&[1, 0, 0])),
+            Ok(Settings::new())
+        );
+        assert_eq!(
+            Settings::<0>::read(&with_crc(&[2, 0, 0, 0])),
+            Ok(Settings::new())
+        );
+        assert_eq!(
+            Settings::<0>::read(&with_crc(&[3, 0, 0, 0])),
+            Err(ConfigError::UnsupportedVersion(3))
+        );
+        assert_eq!(
+            Settings::<0>::read(&with_crc(&[2, 0, 0x02, 0])),
+            Err(ConfigError::InvalidInput)
+        );
+        assert_eq!(
+            Settings::<0>::read(&with_crc(&[1, 0x30, 0])),
+            Err(ConfigError::InvalidInput)
+        );
+        let register = hex!("0100010800ff 04 0000000000000000 00 00");
+        let mut data = [0; 20];
+        data[..3].copy_from_slice(&[1, 0, 1]);
+        data[3..].copy_from_slice(&register);
+        assert_eq!(
+            Settings::<1>::read(&with_crc(&data)),
+            Err(ConfigError::InvalidInput)
+        );
+    }
+}
@@ -0,0 +1,409 @@
+//! Compact storage format for sequences of values.
+//!
+//! Data loggers often store the values of each transmission to flash memory,
+//! which has a limited number of write cycles. Most values (e.g. the energy
+//! counters) change only slightly or not at all between transmissions.
+//! [`DeltaEncoder`] serializes sequences of samples by only storing values that
+//! have changed, as differences to their previous values. [`DeltaDecoder`]
+//! reconstructs the samples.
+//!
+//! Both types don't allocate. They keep track of up to `N` OBIS codes.
+//!
+//! # Format
+//!
+//! Each sample is stored as a record consisting of [LEB128](https://en.wikipedia.org/wiki/LEB128)
+//! varints:
+//!
+//! - the time difference to the previous record (the time itself for the first record)
+//! - the number of changed values
+//! - for each changed value:
+//!   - `2 * index` for OBIS codes seen before or `1` followed by the six bytes of the OBIS code
+//!   - the zigzag-encoded difference to the previous value (or to zero for new OBIS codes)
+//!
+//! Records have to be decoded in the order they were encoded, starting with the first record.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::delta::{DeltaDecoder, DeltaEncoder}, parser::ObisCode, util::ArrayBuf};
+//! let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+//! let power = ObisCode::new(1, 0, 16, 7, 0, 255);
+//!
+//! let mut encoder = DeltaEncoder::<8>::new();
+//! let mut storage = ArrayBuf::<64>::default();
+//! encoder.encode(1000, [(energy, 123_456_789), (power, 320)], &mut storage).unwrap();
+//! encoder.encode(1002, [(energy, 123_456_790), (power, 320)], &mut storage).unwrap();
+//! // the second record only contains the time delta and the change of the energy counter
+//! assert_eq!(storage.len(), 23 + 4);
+//!
+//! let mut decoder = DeltaDecoder::<8>::new();
+//! let mut input = &storage[..];
+//! assert_eq!(decoder.decode(&mut input), Ok(1000));
+//! assert_eq!(decoder.decode(&mut input), Ok(1002));
+//! assert!(decoder.values().eq([(energy, 123_456_790), (power, 320)]));
+//! assert!(input.is_empty());
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{parser::ObisCode, util::Buffer};
+
+/// Error returned when encoding or decoding records fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeltaError {
+    /// The output buffer is full
+    OutOfMemory,
+    /// More than `N` different OBIS codes have been used
+    TooManyObisCodes,
+    /// The input ended in the middle of a record
+    UnexpectedEof,
+    /// The input is invalid (e.g. it references an unknown OBIS code)
+    InvalidInput,
+}
+
+impl fmt::Display for DeltaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeltaError::OutOfMemory => {
+                f.write_str("delta record doesn't fit into the output buffer")
+            }
+            DeltaError::TooManyObisCodes => f.write_str("too many different OBIS codes"),
+            DeltaError::UnexpectedEof => f.write_str("input ended in the middle of a delta record"),
+            DeltaError::InvalidInput => f.write_str("invalid delta record"),
+        }
+    }
+}
+
+impl core::error::Error for DeltaError {}
+
+impl From<crate::util::OutOfMemory> for DeltaError {
+    fn from(_: crate::util::OutOfMemory) -> Self {
+        DeltaError::OutOfMemory
+    }
+}
+
+// OBIS codes and their last values, shared by the encoder and the decoder
+#[derive(Debug, Clone)]
+struct State<const N: usize> {
+    values: [(ObisCode, i64); N],
+    len: usize,
+    last_secs: Option<u32>,
+}
+
+impl<const N: usize> State<N> {
+    const fn new() -> Self {
+        State {
+            values: [(ObisCode([0; 6]), 0); N],
+            len: 0,
+            last_secs: None,
+        }
+    }
+
+    fn values(&self) -> &[(ObisCode, i64)] {
+        &self.values[..self.len]
+    }
+
+    fn add(&mut self, code: ObisCode) -> Result<usize, DeltaError> {
+        let slot = self
+            .values
+            .get_mut(self.len)
+            .ok_or(DeltaError::TooManyObisCodes)?;
+        *slot = (code, 0);
+        self.len += 1;
+        Ok(self.len - 1)
+    }
+}
+
+/// Encodes samples into the delta format.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct DeltaEncoder<const N: usize> {
+    state: State<N>,
+}
+
+impl<const N: usize> Default for DeltaEncoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DeltaEncoder<N> {
+    /// Constructs a new encoder.
+    #[must_use]
+    pub const fn new() -> Self {
+        DeltaEncoder {
+            state: State::new(),
+        }
+    }
+
+    /// Appends a record containing the sample at time `secs` to `out`.
+    ///
+    /// Only values that differ from the previous sample are stored. OBIS codes not
+    /// contained in `values` keep their previous value. `secs` is usually the
+    /// `SecIndex` of the transmission. Differences are calculated using wrapping
+    /// arithmetic, so decreasing times and values are supported as well.
+    ///
+    /// If an error occurs, neither the encoder nor `out` are modified.
+    pub fn encode<B: Buffer>(
+        &mut self,
+        secs: u32,
+        values: impl IntoIterator<Item = (ObisCode, i64)>,
+        out: &mut B,
+    ) -> Result<(), DeltaError> {
+        let backup = self.state.clone();
+        let start = out.len();
+        let res = self.encode_record(secs, values, out);
+        if res.is_err() {
+            self.state = backup;
+            out.truncate(start);
+        }
+        res
+    }
+
+    fn encode_record<B: Buffer>(
+        &mut self,
+        secs: u32,
+        values: impl IntoIterator<Item = (ObisCode, i64)>,
+        out: &mut B,
+    ) -> Result<(), DeltaError> {
+        // OBIS codes with an index >= `first_new` are added by this record
+        let first_new = self.state.len;
+        // collect the changes first because their number is written before them
+        let mut changes = [(0usize, 0i64); N];
+        let mut num_changes = 0;
+        for (code, value) in values {
+            let idx = match self.state.values().iter().position(|(c, _)| *c == code) {
+                Some(idx) => idx,
+                None => self.state.add(code)?,
+            };
+            let prev = self.state.values[idx].1;
+            if value == prev && idx < first_new {
+                continue;
+            }
+            let delta = value.wrapping_sub(prev);
+            match changes[..num_changes].iter_mut().find(|(i, _)| *i == idx) {
+                Some(change) => change.1 = change.1.wrapping_add(delta),
+                None => {
+                    changes[num_changes] = (idx, delta);
+                    num_changes += 1;
+                }
+            }
+            self.state.values[idx].1 = value;
+        }
+
+        let delta_secs = secs.wrapping_sub(self.state.last_secs.unwrap_or(0));
+        self.state.last_secs = Some(secs);
+        write_varint(out, delta_secs.into())?;
+        write_varint(out, num_changes as u64)?;
+        for &(idx, delta) in &changes[..num_changes] {
+            if idx >= first_new {
+                // new OBIS codes are added in the order of their first change
+                write_varint(out, 1)?;
+                out.extend_from_slice(&self.state.values[idx].0 .0)?;
+            } else {
+                write_varint(out, (idx as u64) << 1)?;
+            }
+            write_varint(out, zigzag(delta))?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes records of the delta format.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct DeltaDecoder<const N: usize> {
+    state: State<N>,
+}
+
+impl<const N: usize> Default for DeltaDecoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> DeltaDecoder<N> {
+    /// Constructs a new decoder.
+    #[must_use]
+    pub const fn new() -> Self {
+        DeltaDecoder {
+            state: State::new(),
+        }
+    }
+
+    /// Decodes the next record from `input` and returns its time.
+    ///
+    /// `input` is advanced to the start of the next record. The values of the
+    /// sample are available using [`DeltaDecoder::values`] afterwards. If an error
+    /// occurs, neither the decoder nor `input` are modified.
+    pub fn decode(&mut self, input: &mut &[u8]) -> Result<u32, DeltaError> {
+        let backup = self.state.clone();
+        let mut rest = *input;
+        let res = self.decode_record(&mut rest);
+        match res {
+            Ok(_) => *input = rest,
+            Err(_) => self.state = backup,
+        }
+        res
+    }
+
+    fn decode_record(&mut self, input: &mut &[u8]) -> Result<u32, DeltaError> {
+        let delta_secs =
+            u32::try_from(read_varint(input)?).map_err(|_| DeltaError::InvalidInput)?;
+        let secs = delta_secs.wrapping_add(self.state.last_secs.unwrap_or(0));
+        let num_changes = read_varint(input)?;
+        for _ in 0..num_changes {
+            let key = read_varint(input)?;
+            let idx = if key == 1 {
+                let (code, rest) = input
+                    .split_first_chunk::<6>()
+                    .ok_or(DeltaError::UnexpectedEof)?;
+                *input = rest;
+                self.state.add(ObisCode(*code))?
+            } else if key & 1 == 0 && key >> 1 < self.state.len as u64 {
+                (key >> 1) as usize
+            } else {
+                return Err(DeltaError::InvalidInput);
+            };
+            let delta = unzigzag(read_varint(input)?);
+            let value = &mut self.state.values[idx].1;
+            *value = value.wrapping_add(delta);
+        }
+        self.state.last_secs = Some(secs);
+        Ok(secs)
+    }
+
+    /// Returns the values of the last decoded sample.
+    ///
+    /// Values are returned in the order their OBIS codes first appeared.
+    pub fn values(&self) -> impl Iterator<Item = (ObisCode, i64)> + '_ {
+        self.state.values().iter().copied()
+    }
+}
+
+fn zigzag(x: i64) -> u64 {
+    ((x << 1) ^ (x >> 63)) as u64
+}
+
+fn unzigzag(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+fn write_varint<B: Buffer>(out: &mut B, mut x: u64) -> Result<(), DeltaError> {
+    loop {
+        let b = (x & 0x7f) as u8;
+        x >>= 7;
+        if x == 0 {
+            out.push(b)?;
+            return Ok(());
+        }
+        out.push(b | 0x80)?;
+    }
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u64, DeltaError> {
+    let mut x = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&b, rest) = input.split_first().ok_or(DeltaError::UnexpectedEof)?;
+        *input = rest;
+        let bits = u64::from(b & 0x7f);
+        if shift == 63 && bits > 1 {
+            return Err(DeltaError::InvalidInput);
+        }
+        x |= bits << shift;
+        if b & 0x80 == 0 {
+            return Ok(x);
+        }
+    }
+    Err(DeltaError::InvalidInput)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::ArrayBuf;
+
+    const A: ObisCode = ObisCode::new(1, 0, 1, 8, 0, 255);
+    const B: ObisCode = ObisCode::new(1, 0, 2, 8, 0, 255);
+    const C: ObisCode = ObisCode::new(1, 0, 16, 7, 0, 255);
+
+    #[test]
+    fn roundtrip() {
+        let samples: [(u32, &[(ObisCode, i64)]); 5] = [
+            (u32::MAX - 1, &[(A, 0), (B, i64::MIN)]),
+            (1, &[(A, 5), (B, i64::MAX)]),
+            (1, &[(C, -7), (A, 5)]),
+            (0, &[]),
+            (10, &[(A, 4), (A, 6), (C, -7), (B, 0)]),
+        ];
+        let mut encoder = DeltaEncoder::<3>::new();
+        let mut buf = ArrayBuf::<256>::default();
+        for (secs, values) in samples {
+            encoder
+                .encode(secs, values.iter().copied(), &mut buf)
+                .unwrap();
+        }
+
+        let mut decoder = DeltaDecoder::<3>::new();
+        let mut input = &buf[..];
+        let mut expected = [(A, 0), (B, 0), (C, 0)];
+        for (i, (secs, values)) in samples.into_iter().enumerate() {
+            assert_eq!(decoder.decode(&mut input), Ok(secs));
+            for &(code, value) in values {
+                expected.iter_mut().find(|(c, _)| *c == code).unwrap().1 = value;
+            }
+            let num_codes = if i < 2 { 2 } else { 3 };
+            assert!(decoder.values().eq(expected[..num_codes].iter().copied()));
+        }
+        assert!(input.is_empty());
+        assert_eq!(decoder.decode(&mut input), Err(DeltaError::UnexpectedEof));
+    }
+
+    #[test]
+    fn errors() {
+        let mut encoder = DeltaEncoder::<1>::new();
+        let mut buf = ArrayBuf::<14>::default();
+        encoder.encode(1, [(A, 1)], &mut buf).unwrap();
+        assert_eq!(buf.len(), 10);
+        // errors don't modify the encoder or the output
+        assert_eq!(
+            encoder.encode(2, [(A, 2), (B, 1)], &mut buf),
+            Err(DeltaError::TooManyObisCodes)
+        );
+        assert_eq!(
+            encoder.encode(2, [(A, 1000)], &mut buf),
+            Err(DeltaError::OutOfMemory)
+        );
+        assert_eq!(buf.len(), 10);
+        encoder.encode(3, [(A, 2)], &mut buf).unwrap();
+
+        let mut decoder = DeltaDecoder::<1>::new();
+        let mut input = &buf[..];
+        assert_eq!(decoder.decode(&mut input), Ok(1));
+        assert_eq!(decoder.decode(&mut input), Ok(3));
+        assert!(decoder.values().eq([(A, 2)]));
+
+        // unknown index
+        let mut decoder = DeltaDecoder::<1>::new();
+        assert_eq!(
+            decoder.decode(&mut &[0, 1, 2, 0][..]),
+            Err(DeltaError::InvalidInput)
+        );
+        // truncated record
+        let mut input = &buf[..5];
+        assert_eq!(decoder.decode(&mut input), Err(DeltaError::UnexpectedEof));
+        assert_eq!(input.len(), 5);
+        assert_eq!(decoder.values().count(), 0);
+        // overlong varint
+        assert_eq!(
+            decoder.decode(&mut &[0xff; 11][..]),
+            Err(DeltaError::InvalidInput)
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! Comparing the values of two SML Files.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+//!
+//! [`diff`] compares the list entries of two transmissions of the same meter
+//! and reports which OBIS codes have been added or removed and which values have
+//! changed. This is useful to only publish values when they change and for tests
+//! asserting the behavior of a meter over time.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::diff::diff, parser::complete::parse};
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../sample.bin"));
+//! let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+//!
+//! let d = diff(&file, &file);
+//! assert!(d.is_empty());
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::parser::{
+    common::{ListEntry, Value},
+    complete::File,
+    ObisCode,
+};
+
+/// Differences between the list entries of two SML Files.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileDiff<'i> {
+    /// OBIS codes only contained in the newer file
+    pub added: Vec<ObisCode>,
+    /// OBIS codes only contained in the older file
+    pub removed: Vec<ObisCode>,
+    /// values that differ between both files
+    pub changed: Vec<ValueChange<'i>>,
+}
+
+impl FileDiff<'_> {
+    /// Returns `true` if both files contain the same OBIS codes with the same values.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// A value that differs between two SML Files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueChange<'i> {
+    /// OBIS code of the value
+    pub obis_code: ObisCode,
+    /// value contained in the older file
+    pub prev: Value<'i>,
+    /// value contained in the newer file
+    pub next: Value<'i>,
+    /// `next - prev` if both values are integers and the difference fits into an `i64`
+    ///
+    /// The delta is calculated from the raw values and doesn't take the scalers into account.
+    pub delta: Option<i64>,
+}
+
+/// Compares the list entries of two SML Files.
+///
+/// Entries are matched using their OBIS codes. Entries without a valid OBIS
+/// code are ignored. If an OBIS code occurs several times in a file, only its
+/// first occurrence is taken into account. The results are ordered as the
+/// entries appear in the files.
+#[must_use]
+pub fn diff<'i>(prev: &File<'i>, next: &File<'i>) -> FileDiff<'i> {
+    let prev_entries = entries(prev);
+    let next_entries = entries(next);
+    let find = |entries: &[(ObisCode, &ListEntry<'i>)], code: ObisCode| {
+        entries
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, e)| e.value.clone())
+    };
+
+    let mut res = FileDiff::default();
+    for (code, _) in &prev_entries {
+        if find(&next_entries, *code).is_none() {
+            res.removed.push(*code);
+        }
+    }
+    for (code, entry) in &next_entries {
+        match find(&prev_entries, *code) {
+            None => res.added.push(*code),
+            Some(prev_value) if prev_value != entry.value => {
+                let delta = match (prev_value.as_i64(), entry.value.as_i64()) {
+                    (Some(p), Some(n)) => n.checked_sub(p),
+                    _ => None,
+                };
+                res.changed.push(ValueChange {
+                    obis_code: *code,
+                    prev: prev_value,
+                    next: entry.value.clone(),
+                    delta,
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    res
+}
+
+// returns the first entry of each OBIS code
+fn entries<'a, 'i>(file: &'a File<'i>) -> Vec<(ObisCode, &'a ListEntry<'i>)> {
+    let mut res: Vec<(ObisCode, &ListEntry<'i>)> = Vec::new();
+    for (_, entry) in file.list_entries() {
+        if let Some(code) = entry.obis_code() {
+            if !res.iter().any(|(c, _)| *c == code) {
+                res.push((code, entry));
+            }
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{
+        common::{CloseResponse, ListEntry},
+        complete::{GetListResponse, Message, MessageBody},
+    };
+    use alloc::vec;
+
+    fn file<'i>(val_list: Vec<ListEntry<'i>>) -> File<'i> {
+        let msg = |message_body| Message {
+            transaction_id: &[],
+            group_no: 0,
+            abort_on_error: 0,
+            message_body,
+            crc_valid: true,
+            unknown_fields: 0,
+        };
+        File {
+            messages: vec![
+                msg(MessageBody::GetListResponse(GetListResponse {
+                    client_id: None,
+                    server_id: &[],
+                    list_name: None,
+                    act_sensor_time: None,
+                    val_list,
+                    list_signature: None,
+                    act_gateway_time: None,
+                })),
+                msg(MessageBody::CloseResponse(CloseResponse {
+                    global_signature: None,
+                })),
+            ],
+        }
+    }
+
+    const ENERGY: [u8; 6] = [1, 0, 1, 8, 0, 255];
+    const POWER: [u8; 6] = [1, 0, 16, 7, 0, 255];
+    const VENDOR: [u8; 6] = [129, 129, 199, 130, 3, 255];
+
+    #[test]
+    fn basic() {
+        let prev = file(vec![
+            ListEntry::new_test(&VENDOR, Value::Bytes(b"ABC")),
+            ListEntry::new_test(&ENERGY, Value::U64(1000)),
+            ListEntry::new_test(&POWER, Value::I32(-20)),
+        ]);
+        let next = file(vec![
+            ListEntry::new_test(&ENERGY, Value::U64(1003)),
+            ListEntry::new_test(&POWER, Value::I32(-20)),
+            ListEntry::new_test(&[1, 2, 3], Value::Bool(true)),
+            ListEntry::new_test(&[1, 0, 2, 8, 0, 255], Value::U64(5)),
+        ]);
+        let d = diff(&prev, &next);
+        assert_eq!(d.removed, [ObisCode(VENDOR)]);
+        assert_eq!(d.added, [ObisCode::new(1, 0, 2, 8, 0, 255)]);
+        assert_eq!(
+            d.changed,
+            [ValueChange {
+                obis_code: ObisCode(ENERGY),
+                prev: Value::U64(1000),
+                next: Value::U64(1003),
+                delta: Some(3),
+            }]
+        );
+        assert!(!d.is_empty());
+        assert!(diff(&next, &next).is_empty());
+    }
+
+    #[test]
+    fn delta() {
+        let changes = |prev, next| diff(&file(vec![prev]), &file(vec![next])).changed;
+        assert_eq!(
+            changes(
+                ListEntry::new_test(&POWER, Value::I8(5)),
+                ListEntry::new_test(&POWER, Value::U32(1))
+            )[0]
+            .delta,
+            Some(-4)
+        );
+        assert_eq!(
+            changes(
+                ListEntry::new_test(&POWER, Value::I64(i64::MIN)),
+                ListEntry::new_test(&POWER, Value::I64(1))
+            )[0]
+            .delta,
+            None
+        );
+        assert_eq!(
+            changes(
+                ListEntry::new_test(&POWER, Value::Bytes(b"a")),
+                ListEntry::new_test(&POWER, Value::I64(1))
+            )[0]
+            .delta,
+            None
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let prev = file(vec![
+            ListEntry::new_test(&POWER, Value::I32(1)),
+            ListEntry::new_test(&POWER, Value::I32(2)),
+        ]);
+        let next = file(vec![ListEntry::new_test(&POWER, Value::I32(1))]);
+        assert!(diff(&prev, &next).is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Detecting duplicate OBIS codes.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+//!
+//! A `GetListResponse` should contain each OBIS code at most once. Duplicates
+//! usually indicate bugs in the meter firmware or captures that have been tampered
+//! with. [`find_duplicates`] reports them and [`remove_duplicates`] resolves them
+//! according to a [`DuplicatePolicy`].
+//!
+//! OBIS codes contained in different `GetListResponse` messages of the same file
+//! aren't considered duplicates.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::duplicates::{remove_duplicates, DuplicatePolicy}, parser::complete::parse};
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../sample.bin"));
+//! let mut file = parse(decoded[0].as_ref().unwrap()).unwrap();
+//! // the sample doesn't contain duplicates
+//! assert_eq!(remove_duplicates(&mut file, DuplicatePolicy::Reject), Ok(Vec::new()));
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::parser::{
+    complete::{EntryPosition, File, MessageBody},
+    ObisCode,
+};
+
+/// A list entry whose OBIS code already occurred in the same `GetListResponse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Duplicate {
+    /// the duplicated OBIS code
+    pub obis_code: ObisCode,
+    /// position of the first entry with this OBIS code
+    pub first: EntryPosition,
+    /// position of the duplicate entry
+    pub duplicate: EntryPosition,
+}
+
+/// Defines how [`remove_duplicates`] handles duplicate OBIS codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Return an error and leave the file unchanged
+    Reject,
+    /// Keep the first entry and remove the later ones
+    KeepFirst,
+    /// Keep the last entry and remove the earlier ones
+    KeepLast,
+}
+
+/// Error returned by [`remove_duplicates`] when using [`DuplicatePolicy::Reject`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateError {
+    /// the duplicates contained in the file
+    pub duplicates: Vec<Duplicate>,
+}
+
+impl fmt::Display for DuplicateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("duplicate OBIS codes in SML file:")?;
+        let mut sep = " ";
+        for duplicate in &self.duplicates {
+            write!(f, "{sep}{}", duplicate.obis_code)?;
+            sep = ", ";
+        }
+        Ok(())
+    }
+}
+
+impl core::error::Error for DuplicateError {}
+
+/// Returns the duplicate OBIS codes within each `GetListResponse` of `file`.
+///
+/// Each entry repeating an earlier OBIS code is reported once, referencing the first
+/// entry with that code. Entries without a valid OBIS code are ignored.
+#[must_use]
+pub fn find_duplicates(file: &File<'_>) -> Vec<Duplicate> {
+    let mut duplicates = Vec::new();
+    for (message_index, msg) in file.messages.iter().enumerate() {
+        let MessageBody::GetListResponse(glr) = &msg.message_body else {
+            continue;
+        };
+        let codes: Vec<_> = glr.val_list.iter().map(|e| e.obis_code()).collect();
+        for (entry_index, code) in codes.iter().enumerate() {
+            let Some(obis_code) = *code else {
+                continue;
+            };
+            let Some(first) = codes[..entry_index].iter().position(|c| *c == *code) else {
+                continue;
+            };
+            let pos = |entry_index| EntryPosition {
+                message_index,
+                entry_index,
+            };
+            duplicates.push(Duplicate {
+                obis_code,
+                first: pos(first),
+                duplicate: pos(entry_index),
+            });
+        }
+    }
+    duplicates
+}
+
+/// Removes duplicate OBIS codes from `file` according to `policy`.
+///
+/// Returns the duplicates that were found (positions refer to the file before
+/// removing entries). With [`DuplicatePolicy::Reject`], the file is left unchanged
+/// and an error is returned if it contains duplicates.
+pub fn remove_duplicates(
+    file: &mut File<'_>,
+    policy: DuplicatePolicy,
+) -> Result<Vec<Duplicate>, DuplicateError> {
+    let duplicates = find_duplicates(file);
+    if duplicates.is_empty() {
+        return Ok(duplicates);
+    }
+    if policy == DuplicatePolicy::Reject {
+        return Err(DuplicateError { duplicates });
+    }
+    for msg in &mut file.messages {
+        let MessageBody::GetListResponse(glr) = &mut msg.message_body else {
+            continue;
+        };
+        let codes: Vec<_> = glr.val_list.iter().map(|e| e.obis_code()).collect();
+        let mut entry_index = 0;
+        glr.val_list.retain(|_| {
+            let code = codes[entry_index];
+            // entries are removed if the entry that is kept comes earlier (later) in the list
+            let others = match policy {
+                DuplicatePolicy::KeepLast => &codes[entry_index + 1..],
+                _ => &codes[..entry_index],
+            };
+            entry_index += 1;
+            code.is_none() || !others.contains(&code)
+        });
+    }
+    Ok(duplicates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+    const A: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const B: ObisCode = ObisCode::ACTIVE_POWER;
+
+    // returns a transmission with the registers A = 1, B = 2, A = 3, B = 4, A = 5
+    fn transmission() -> Vec<u8> {
+        let mut meter = Meter::new(b"meter");
+        for (code, value) in [(A, 1), (B, 2), (A, 3), (B, 4), (A, 5)] {
+            meter = meter.register(code, None, 0, move |_| value);
+        }
+        decode(meter.next_frame(0)).remove(0).unwrap()
+    }
+
+    fn values(file: &File<'_>) -> Vec<(ObisCode, i64)> {
+        file.list_entries()
+            .map(|(_, e)| (e.obis_code().unwrap(), e.value.as_i64().unwrap()))
+            .collect()
+    }
+
+    fn pos(entry_index: usize) -> EntryPosition {
+        EntryPosition {
+            message_index: 1,
+            entry_index,
+        }
+    }
+
+    #[test]
+    fn find() {
+        let bytes = transmission();
+        let file = parse(&bytes).unwrap();
+        let dup = |obis_code, first, duplicate| Duplicate {
+            obis_code,
+            first: pos(first),
+            duplicate: pos(duplicate),
+        };
+        let expected = [dup(A, 0, 2), dup(B, 1, 3), dup(A, 0, 4)];
+        assert_eq!(find_duplicates(&file), expected);
+
+        let mut rejected = file.clone();
+        assert_eq!(
+            remove_duplicates(&mut rejected, DuplicatePolicy::Reject),
+            Err(DuplicateError {
+                duplicates: expected.into()
+            })
+        );
+        assert_eq!(rejected, file);
+    }
+
+    #[test]
+    fn policies() {
+        let bytes = transmission();
+        let mut file = parse(&bytes).unwrap();
+        assert_eq!(
+            remove_duplicates(&mut file, DuplicatePolicy::KeepFirst).map(|d| d.len()),
+            Ok(3)
+        );
+        assert_eq!(values(&file), [(A, 1), (B, 2)]);
+        assert_eq!(find_duplicates(&file), []);
+
+        let mut file = parse(&bytes).unwrap();
+        remove_duplicates(&mut file, DuplicatePolicy::KeepLast).unwrap();
+        assert_eq!(values(&file), [(B, 4), (A, 5)]);
+        assert_eq!(
+            remove_duplicates(&mut file, DuplicatePolicy::Reject),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+        let bytes = transmission();
+        let err =
+            remove_duplicates(&mut parse(&bytes).unwrap(), DuplicatePolicy::Reject).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "duplicate OBIS codes in SML file: 1-0:1.8.0*255, 1-0:16.7.0*255, 1-0:1.8.0*255"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Detecting meter reboots and counter resets.
+//!
+//! A meter's `SecIndex` and its energy registers only ever increase during normal
+//! operation. If the `SecIndex` decreases, the meter has been restarted. If an energy
+//! register decreases, the register has been reset (e.g. after replacing the meter
+//! or by a firmware bug). Both silently corrupt aggregations such as daily
+//! consumption values. [`EventDetector`] reports them as [`MeterEvent`]s containing
+//! the values before and after the event, which can be stored in audit logs.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{events::{EventDetector, MeterEvent}, quantity::Quantity}, parser::ObisCode};
+//! let wh = |value| Quantity { value, scaler: 0, unit: Some(30) };
+//! let mut detector = EventDetector::<4>::new();
+//! assert_eq!(detector.push_sec_index(1000), None);
+//! assert_eq!(detector.push_register(ObisCode::ENERGY_IMPORT_TOTAL, 1000, wh(5000)), None);
+//!
+//! // the meter restarted and lost its counter
+//! assert_eq!(
+//!     detector.push_sec_index(10),
+//!     Some(MeterEvent::Rebooted { previous_sec_index: 1000, sec_index: 10 })
+//! );
+//! assert_eq!(
+//!     detector.push_register(ObisCode::ENERGY_IMPORT_TOTAL, 10, wh(0)),
+//!     Some(MeterEvent::RegisterReset {
+//!         obis_code: ObisCode::ENERGY_IMPORT_TOTAL,
+//!         previous_sec_index: 1000,
+//!         sec_index: 10,
+//!         previous: wh(5000),
+//!         current: wh(0),
+//!     })
+//! );
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use core::cmp::Ordering;
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{common::ListEntry, ObisCode},
+};
+
+/// Event detected by [`EventDetector`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MeterEvent {
+    /// The `SecIndex` decreased, which indicates that the meter has been restarted
+    Rebooted {
+        /// the last `SecIndex` before the event
+        previous_sec_index: u32,
+        /// the first `SecIndex` after the event
+        sec_index: u32,
+    },
+    /// The value of an energy register decreased
+    RegisterReset {
+        /// the OBIS code of the register
+        obis_code: ObisCode,
+        /// time of the last value before the event
+        previous_sec_index: u32,
+        /// time of the first value after the event
+        sec_index: u32,
+        /// the last value before the event
+        previous: Quantity,
+        /// the first value after the event
+        current: Quantity,
+    },
+}
+
+/// Detects meter reboots and resets of up to `N` energy registers.
+///
+/// The detector has to be fed with all transmissions of a single meter in the
+/// order they have been received. Registers are tracked in the order of their
+/// first occurrence. Once `N` registers are tracked, further registers are ignored.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct EventDetector<const N: usize> {
+    last_sec_index: Option<u32>,
+    registers: [Option<(ObisCode, u32, Quantity)>; N],
+}
+
+impl<const N: usize> Default for EventDetector<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> EventDetector<N> {
+    /// Constructs a detector that hasn't seen any values yet.
+    #[must_use]
+    pub const fn new() -> Self {
+        EventDetector {
+            last_sec_index: None,
+            registers: [None; N],
+        }
+    }
+
+    /// Records the `SecIndex` of a transmission (e.g. the `act_sensor_time` of the `GetListResponse`).
+    ///
+    /// Returns [`MeterEvent::Rebooted`] if it is smaller than the previous one.
+    pub fn push_sec_index(&mut self, sec_index: u32) -> Option<MeterEvent> {
+        let previous_sec_index = self.last_sec_index.replace(sec_index)?;
+        (sec_index < previous_sec_index).then_some(MeterEvent::Rebooted {
+            previous_sec_index,
+            sec_index,
+        })
+    }
+
+    /// Records the value of the energy register `obis_code` observed at `sec_index`.
+    ///
+    /// Returns [`MeterEvent::RegisterReset`] if the value is smaller than the previous
+    /// value of the register. Values with a different unit than the previous value
+    /// aren't compared.
+    pub fn push_register(
+        &mut self,
+        obis_code: ObisCode,
+        sec_index: u32,
+        value: Quantity,
+    ) -> Option<MeterEvent> {
+        let slot = self
+            .registers
+            .iter_mut()
+            .find(|r| r.map_or(true, |(code, ..)| code == obis_code))?;
+        let previous = slot.replace((obis_code, sec_index, value));
+        let (_, previous_sec_index, previous) = previous?;
+        (value.cmp_value(&previous) == Some(Ordering::Less)).then_some(MeterEvent::RegisterReset {
+            obis_code,
+            previous_sec_index,
+            sec_index,
+            previous,
+            current: value,
+        })
+    }
+
+    /// Records the value of `entry` if it is an energy register (unit Wh, VAh or varh).
+    ///
+    /// The value is recorded at [`ListEntry::sec_index_or`]`(sec_index)`, i.e. at its `val_time`
+    /// if present.
+    pub fn record(&mut self, entry: &ListEntry<'_>, sec_index: u32) -> Option<MeterEvent> {
+        if !matches!(entry.unit, Some(30..=32)) {
+            return None;
+        }
+        let obis_code = entry.obis_code()?;
+        let value = Quantity::from_list_entry(entry)?;
+        let time = entry.sec_index_or(sec_index);
+        self.push_register(obis_code, time, value)
+    }
+
+    /// Records all `GetListResponse` messages of `file` and returns the detected events.
+    ///
+    /// Messages without a `SecIndex` in their `act_sensor_time` are ignored.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn check_file(
+        &mut self,
+        file: &crate::parser::complete::File<'_>,
+    ) -> alloc::vec::Vec<MeterEvent> {
+        use crate::parser::complete::MessageBody;
+
+        let mut events = alloc::vec::Vec::new();
+        for msg in &file.messages {
+            let MessageBody::GetListResponse(glr) = &msg.message_body else {
+                continue;
+            };
+            let Some(sec_index) = glr.act_sensor_time.as_ref().and_then(|t| t.sec_index()) else {
+                continue;
+            };
+            events.extend(self.push_sec_index(sec_index));
+            for entry in &glr.val_list {
+                events.extend(self.record(entry, sec_index));
+            }
+        }
+        events
+    }
+
+    /// Forgets all previously recorded values.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENERGY: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const EXPORT: ObisCode = ObisCode::ENERGY_EXPORT_TOTAL;
+
+    fn wh(value: i64, scaler: i8) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit: Some(30),
+        }
+    }
+
+    #[test]
+    fn sec_index() {
+        let mut d = EventDetector::<0>::new();
+        assert_eq!(d.push_sec_index(5), None);
+        assert_eq!(d.push_sec_index(5), None);
+        assert_eq!(d.push_sec_index(6), None);
+        assert_eq!(
+            d.push_sec_index(1),
+            Some(MeterEvent::Rebooted {
+                previous_sec_index: 6,
+                sec_index: 1
+            })
+        );
+        assert_eq!(d.push_sec_index(2), None);
+        d.reset();
+        assert_eq!(d.push_sec_index(1), None);
+    }
+
+    #[test]
+    fn registers() {
+        let mut d = EventDetector::<1>::new();
+        assert_eq!(d.push_register(ENERGY, 1, wh(100, 0)), None);
+        // the scaler is taken into account
+        assert_eq!(d.push_register(ENERGY, 2, wh(1000, -1)), None);
+        assert_eq!(d.push_register(ENERGY, 3, wh(11, 1)), None);
+        // different units aren't compared
+        let other = Quantity {
+            unit: None,
+            ..wh(0, 0)
+        };
+        assert_eq!(d.push_register(ENERGY, 4, other), None);
+        assert_eq!(d.push_register(ENERGY, 5, wh(110, 0)), None);
+        assert_eq!(
+            d.push_register(ENERGY, 6, wh(3, 0)),
+            Some(MeterEvent::RegisterReset {
+                obis_code: ENERGY,
+                previous_sec_index: 5,
+                sec_index: 6,
+                previous: wh(110, 0),
+                current: wh(3, 0),
+            })
+        );
+        // only a single register is tracked
+        assert_eq!(d.push_register(EXPORT, 7, wh(100, 0)), None);
+        assert_eq!(d.push_register(EXPORT, 8, wh(0, 0)), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn check_file() {
+        use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), 0, |s| if s < 100 { 50 } else { 1000 })
+            .register(EXPORT, Some(30), 0, |_| 20)
+            // not an energy register
+            .register(ObisCode::ACTIVE_POWER, Some(27), 0, |s| 1000 - i64::from(s));
+        let mut d = EventDetector::<2>::new();
+        let mut events = alloc::vec::Vec::new();
+        for s in [100, 200, 50] {
+            let bytes = decode(meter.next_frame(s)).remove(0).unwrap();
+            events.extend(d.check_file(&parse(&bytes).unwrap()));
+        }
+        assert_eq!(
+            events,
+            [
+                MeterEvent::Rebooted {
+                    previous_sec_index: 200,
+                    sec_index: 50
+                },
+                MeterEvent::RegisterReset {
+                    obis_code: ENERGY,
+                    previous_sec_index: 200,
+                    sec_index: 50,
+                    previous: wh(1000, 0),
+                    current: wh(50, 0),
+                },
+            ]
+        );
+    }
+}
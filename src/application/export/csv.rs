@@ -0,0 +1,221 @@
+//! Writing list entries as CSV rows.
+//!
+//! [`CsvWriter`] writes one row per list entry of an SML File, which allows
+//! analyzing captures with spreadsheet software. The columns are (in this order):
+//!
+//! | Column      | Content                                                              |
+//! |-------------|----------------------------------------------------------------------|
+//! | `timestamp` | `val_time` of the entry or `act_sensor_time` of the `GetListResponse` |
+//! | `server_id` | server id of the `GetListResponse` as lowercase hex string           |
+//! | `obis`      | OBIS code (e.g. `1-0:1.8.0*255`) or the entry's name as hex string   |
+//! | `value`     | the raw value; octet strings are written as lowercase hex string      |
+//! | `scaler`    | the scaler                                                           |
+//! | `unit`      | the unit code                                                        |
+//! | `status`    | the status word                                                      |
+//!
+//! Missing optional fields are written as empty cells. The column order is part of
+//! the stable output format; new columns will only be appended.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::export::csv::CsvWriter, parser::complete::parse};
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../../sample.bin"));
+//! let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+//!
+//! let mut writer = CsvWriter::new(Vec::new());
+//! writer.write_file(&file).unwrap();
+//! let csv = String::from_utf8(writer.into_inner()).unwrap();
+//! assert!(csv.starts_with("timestamp,server_id,obis,value,scaler,unit,status\n"));
+//! ```
+
+use std::io::{self, Write};
+
+use crate::parser::{
+    common::{ListEntry, ListType, Status, Time, Value},
+    complete::{File, MessageBody},
+};
+
+/// Header row written by [`CsvWriter`].
+pub const HEADER: &str = "timestamp,server_id,obis,value,scaler,unit,status";
+
+/// Writes list entries of SML Files as CSV rows.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct CsvWriter<W: Write> {
+    inner: W,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Constructs a writer writing into `inner`.
+    ///
+    /// The header row is written before the first row.
+    pub fn new(inner: W) -> Self {
+        CsvWriter {
+            inner,
+            header_written: false,
+        }
+    }
+
+    /// Constructs a writer that doesn't write a header row.
+    ///
+    /// This is useful when appending to an existing file.
+    pub fn without_header(inner: W) -> Self {
+        CsvWriter {
+            inner,
+            header_written: true,
+        }
+    }
+
+    /// Writes one row per list entry of `file`.
+    pub fn write_file(&mut self, file: &File<'_>) -> io::Result<()> {
+        for msg in &file.messages {
+            if let MessageBody::GetListResponse(glr) = &msg.message_body {
+                for entry in &glr.val_list {
+                    self.write_entry(
+                        entry.val_time.as_ref().or(glr.act_sensor_time.as_ref()),
+                        glr.server_id,
+                        entry,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a single row.
+    ///
+    /// `time` is written into the `timestamp` column. Use [`write_file`](Self::write_file)
+    /// to fill in the `timestamp` and `server_id` columns from the `GetListResponse`.
+    pub fn write_entry(
+        &mut self,
+        time: Option<&Time>,
+        server_id: &[u8],
+        entry: &ListEntry<'_>,
+    ) -> io::Result<()> {
+        if !self.header_written {
+            writeln!(self.inner, "{HEADER}")?;
+            self.header_written = true;
+        }
+        let obis = match entry.obis_code() {
+            Some(code) => code.to_string(),
+            None => hex(entry.obj_name),
+        };
+        let fields = [
+            time.map(time_to_string).unwrap_or_default(),
+            hex(server_id),
+            obis,
+            value_to_string(&entry.value),
+            opt_to_string(entry.scaler),
+            opt_to_string(entry.unit),
+            entry
+                .status
+                .as_ref()
+                .map(status_to_string)
+                .unwrap_or_default(),
+        ];
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.inner.write_all(b",")?;
+            }
+            write_field(&mut self.inner, field)?;
+        }
+        self.inner.write_all(b"\n")
+    }
+
+    /// Consumes the writer and returns the inner writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+// writes a field, quoting it if it contains separators, quotes or line breaks
+fn write_field(w: &mut impl Write, field: &str) -> io::Result<()> {
+    if field.contains([',', '"', '\n', '\r']) {
+        write!(w, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        w.write_all(field.as_bytes())
+    }
+}
+
+pub(super) fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Bytes(bytes) => hex(bytes),
+        Value::List(ListType::Time(time)) => time_to_string(time),
+        Value::I8(x) => x.to_string(),
+        Value::I16(x) => x.to_string(),
+        Value::I32(x) => x.to_string(),
+        Value::I64(x) => x.to_string(),
+        Value::U8(x) => x.to_string(),
+        Value::U16(x) => x.to_string(),
+        Value::U32(x) => x.to_string(),
+        Value::U64(x) => x.to_string(),
+    }
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn time_to_string(time: &Time) -> String {
+    match time {
+        Time::SecIndex(secs) => secs.to_string(),
+    }
+}
+
+fn status_to_string(status: &Status) -> String {
+    status.as_u64().to_string()
+}
+
+fn opt_to_string(value: Option<impl ToString>) -> String {
+    value.map(|x| x.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::simulator::Meter,
+        parser::{complete::parse, ObisCode},
+        transport::decode,
+    };
+
+    #[test]
+    fn rows() {
+        let mut meter = Meter::new(&[0x0a, 0x01])
+            .register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), -1, |_| 1234)
+            .register(ObisCode::new(1, 0, 16, 7, 0, 255), None, 0, |_| -5);
+        let decoded = decode(meter.next_frame(42));
+        let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+
+        let mut writer = CsvWriter::new(Vec::new());
+        writer.write_file(&file).unwrap();
+        writer.write_file(&file).unwrap();
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+        let row_1 = "42,0a01,1-0:1.8.0*255,1234,-1,30,\n";
+        let row_2 = "42,0a01,1-0:16.7.0*255,-5,0,,\n";
+        assert_eq!(csv, format!("{HEADER}\n{row_1}{row_2}{row_1}{row_2}"));
+    }
+
+    #[test]
+    fn escaping() {
+        let mut out = Vec::new();
+        write_field(&mut out, "a,b").unwrap();
+        write_field(&mut out, "say \"hi\"").unwrap();
+        write_field(&mut out, "plain").unwrap();
+        assert_eq!(out, b"\"a,b\"\"say \"\"hi\"\"\"plain");
+    }
+
+    #[test]
+    fn sample() {
+        let decoded = decode(include_bytes!("../../../sample.bin"));
+        let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+        let mut writer = CsvWriter::without_header(Vec::new());
+        writer.write_file(&file).unwrap();
+        let csv = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(csv.lines().count(), file.list_entries().count());
+        assert!(csv.lines().all(|line| line.split(',').count() == 7));
+    }
+}
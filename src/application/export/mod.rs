@@ -0,0 +1,7 @@
+//! Exporting parsed SML data into other formats.
+//!
+//! *This module is available only if sml-rs is built with the `"std"` feature.*
+
+pub mod csv;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
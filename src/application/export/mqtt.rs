@@ -0,0 +1,272 @@
+//! Publishing list entries to an MQTT broker.
+//!
+//! [`MqttPublisher`] publishes one message per list entry of an SML File using a
+//! [`rumqttc::Client`]. The topics have the form `{prefix}/{server_id}/{obis}`,
+//! where `server_id` is the lowercase hex string of the `GetListResponse`'s server id
+//! and `obis` is the OBIS code (e.g. `1-0:1.8.0*255`) or the entry's name as hex string.
+//!
+//! Integer values are published as decimal numbers with the scaler applied (e.g.
+//! `5430.2`); other values are formatted as in the [`csv`](super::csv) export.
+//!
+//! The availability of the publisher is published on `{prefix}/status`: `online`
+//! after connecting and `offline` as last will when the connection is lost. Both
+//! messages are always retained, independent of [`MqttPublisher::with_retain`], so
+//! that subscribers connecting later see the current state. The broker publishes
+//! the last will whenever the connection drops, so [`MqttPublisher::handle_event`]
+//! has to be called with every event of the connection to republish the `online`
+//! message after `rumqttc` has reconnected.
+//!
+//! *This module is available only if sml-rs is built with the `"mqtt"` feature.*
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use sml_rs::application::export::mqtt::MqttPublisher;
+//! use rumqttc::{Client, MqttOptions, QoS};
+//!
+//! let publisher = MqttPublisher::new("sml")
+//!     .with_qos(QoS::AtLeastOnce)
+//!     .with_retain(true);
+//! let mut options = MqttOptions::new("sml-rs", "localhost", 1883);
+//! publisher.configure(&mut options);
+//! let (client, mut connection) = Client::new(options, 64);
+//!
+//! std::thread::spawn(move || {
+//!     for event in connection.iter() {
+//!         // rumqttc reconnects automatically when polled after an error
+//!         if let Ok(event) = event {
+//!             publisher.handle_event(&client, &event).unwrap();
+//!         }
+//!     }
+//! });
+//! ```
+
+use rumqttc::{Client, ClientError, ConnectReturnCode, Event, LastWill, MqttOptions, Packet, QoS};
+
+use super::csv::{hex, value_to_string};
+use crate::{
+    application::quantity::Quantity,
+    parser::{
+        common::ListEntry,
+        complete::{File, MessageBody},
+    },
+};
+
+/// Payload of the status topic while the publisher is connected.
+pub const ONLINE: &str = "online";
+
+/// Payload of the status topic after the connection has been lost.
+pub const OFFLINE: &str = "offline";
+
+/// A message to be published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MqttMessage {
+    /// the topic
+    pub topic: String,
+    /// the payload
+    pub payload: String,
+    /// the quality of service
+    pub qos: QoS,
+    /// whether the broker retains the message
+    pub retain: bool,
+}
+
+impl MqttMessage {
+    /// Publishes the message using `client`.
+    pub fn publish(self, client: &Client) -> Result<(), ClientError> {
+        client.publish(self.topic, self.qos, self.retain, self.payload)
+    }
+}
+
+/// Publishes list entries of SML Files to an MQTT broker.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct MqttPublisher {
+    prefix: String,
+    qos: QoS,
+    retain: bool,
+}
+
+impl MqttPublisher {
+    /// Constructs a publisher using the given topic prefix.
+    ///
+    /// Values are published with `QoS::AtMostOnce` and aren't retained by default.
+    pub fn new(prefix: impl Into<String>) -> Self {
+        MqttPublisher {
+            prefix: prefix.into(),
+            qos: QoS::AtMostOnce,
+            retain: false,
+        }
+    }
+
+    /// Sets the quality of service used for values and the status topic.
+    #[must_use]
+    pub fn with_qos(self, qos: QoS) -> Self {
+        MqttPublisher { qos, ..self }
+    }
+
+    /// Sets whether the broker retains the published values.
+    #[must_use]
+    pub fn with_retain(self, retain: bool) -> Self {
+        MqttPublisher { retain, ..self }
+    }
+
+    /// Returns the topic on which the availability of the publisher is published.
+    #[must_use]
+    pub fn status_topic(&self) -> String {
+        format!("{}/status", self.prefix)
+    }
+
+    /// Returns the last will publishing [`OFFLINE`] on the status topic.
+    #[must_use]
+    pub fn last_will(&self) -> LastWill {
+        LastWill::new(self.status_topic(), OFFLINE, self.qos, true)
+    }
+
+    /// Sets the last will of `options` to [`last_will`](Self::last_will).
+    pub fn configure(&self, options: &mut MqttOptions) {
+        options.set_last_will(self.last_will());
+    }
+
+    /// Returns the message publishing [`ONLINE`] on the status topic.
+    #[must_use]
+    pub fn online_message(&self) -> MqttMessage {
+        MqttMessage {
+            topic: self.status_topic(),
+            payload: ONLINE.into(),
+            qos: self.qos,
+            retain: true,
+        }
+    }
+
+    /// Returns one message per list entry of `file`.
+    #[must_use]
+    pub fn messages(&self, file: &File<'_>) -> Vec<MqttMessage> {
+        let mut res = Vec::new();
+        for msg in &file.messages {
+            if let MessageBody::GetListResponse(glr) = &msg.message_body {
+                let server_id = hex(glr.server_id);
+                for entry in &glr.val_list {
+                    res.push(self.message(&server_id, entry));
+                }
+            }
+        }
+        res
+    }
+
+    fn message(&self, server_id: &str, entry: &ListEntry<'_>) -> MqttMessage {
+        let obis = match entry.obis_code() {
+            Some(code) => code.to_string(),
+            None => hex(entry.obj_name),
+        };
+        let payload = match Quantity::from_list_entry(entry) {
+            Some(q) => Quantity { unit: None, ..q }.to_string(),
+            None => value_to_string(&entry.value),
+        };
+        MqttMessage {
+            topic: format!("{}/{server_id}/{obis}", self.prefix),
+            payload,
+            qos: self.qos,
+            retain: self.retain,
+        }
+    }
+
+    /// Publishes one message per list entry of `file`.
+    pub fn publish(&self, client: &Client, file: &File<'_>) -> Result<(), ClientError> {
+        self.messages(file)
+            .into_iter()
+            .try_for_each(|msg| msg.publish(client))
+    }
+
+    /// Returns the message to publish in reaction to `event`, if any.
+    ///
+    /// This is the [`online_message`](Self::online_message) after every successful
+    /// (re)connect.
+    #[must_use]
+    pub fn on_event(&self, event: &Event) -> Option<MqttMessage> {
+        match event {
+            Event::Incoming(Packet::ConnAck(ack)) if ack.code == ConnectReturnCode::Success => {
+                Some(self.online_message())
+            }
+            _ => None,
+        }
+    }
+
+    /// Publishes the message returned by [`on_event`](Self::on_event), if any.
+    ///
+    /// Must be called with every event of the connection.
+    pub fn handle_event(&self, client: &Client, event: &Event) -> Result<(), ClientError> {
+        match self.on_event(event) {
+            Some(msg) => msg.publish(client),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rumqttc::{ConnAck, Outgoing};
+
+    use super::*;
+    use crate::{
+        application::simulator::Meter,
+        parser::{complete::parse, ObisCode},
+        transport::decode,
+    };
+
+    #[test]
+    fn messages() {
+        let mut meter = Meter::new(&[0x0a, 0x01])
+            .register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), -1, |_| 1234)
+            .register(ObisCode::new(1, 0, 16, 7, 0, 255), None, 0, |_| -5);
+        let decoded = decode(meter.next_frame(42));
+        let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+
+        let publisher = MqttPublisher::new("home/sml").with_qos(QoS::AtLeastOnce);
+        let msgs = publisher.messages(&file);
+        let topics: Vec<_> = msgs.iter().map(|m| m.topic.as_str()).collect();
+        assert_eq!(
+            topics,
+            [
+                "home/sml/0a01/1-0:1.8.0*255",
+                "home/sml/0a01/1-0:16.7.0*255"
+            ]
+        );
+        let payloads: Vec<_> = msgs.iter().map(|m| m.payload.as_str()).collect();
+        assert_eq!(payloads, ["123.4", "-5"]);
+        assert!(msgs.iter().all(|m| m.qos == QoS::AtLeastOnce && !m.retain));
+
+        let msgs = publisher.with_retain(true).messages(&file);
+        assert!(msgs.iter().all(|m| m.retain));
+    }
+
+    #[test]
+    fn status() {
+        let publisher = MqttPublisher::new("sml");
+        let will = publisher.last_will();
+        assert_eq!(will.topic, "sml/status");
+        assert_eq!(&will.message[..], OFFLINE.as_bytes());
+        assert!(will.retain);
+
+        let online = MqttMessage {
+            topic: "sml/status".into(),
+            payload: ONLINE.into(),
+            qos: QoS::AtMostOnce,
+            retain: true,
+        };
+        let connack = |code| Event::Incoming(Packet::ConnAck(ConnAck::new(code, false)));
+        assert_eq!(
+            publisher.on_event(&connack(ConnectReturnCode::Success)),
+            Some(online)
+        );
+        assert_eq!(
+            publisher.on_event(&connack(ConnectReturnCode::NotAuthorized)),
+            None
+        );
+        assert_eq!(
+            publisher.on_event(&Event::Outgoing(Outgoing::PingReq)),
+            None
+        );
+    }
+}
@@ -0,0 +1,736 @@
+//! Extracting a fixed set of values from transmissions.
+//!
+//! An [`Extractor`] declares the registers an application reads from a meter. Each
+//! [`ExtractField`] can be required or optional, provide a fallback value used when
+//! the register is missing and fix deviations of specific meters (e.g. a missing
+//! scaler or a wrong unit). [`Extractor::extract`] returns one result per field, so
+//! a single absent register doesn't prevent reading the others.
+//!
+//! Meters of some vendors send wrong scalers for specific registers. Such bugs can
+//! be described by [`ScalerCorrection`]s, which are applied by
+//! `Extractor::extract_file` when the vendor of the meter has been identified.
+//! The uncorrected values are still returned by [`Extractor::extract`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{extract::{ExtractField, Extractor}, quantity::Quantity}, parser::ObisCode};
+//! const EXTRACTOR: Extractor<3> = Extractor::new([
+//!     ExtractField::required(ObisCode::ENERGY_IMPORT_TOTAL),
+//!     // the meter doesn't send a scaler for the power, but uses 0.1 W as resolution
+//!     ExtractField::optional(ObisCode::ACTIVE_POWER).with_default_scaler(-1),
+//!     // not sent by the meter
+//!     ExtractField::optional(ObisCode::ENERGY_EXPORT_TOTAL)
+//!         .with_fallback(Quantity { value: 0, scaler: 0, unit: Some(30) }),
+//! ]);
+//!
+//! # #[cfg(feature = "alloc")] {
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../sample.bin"));
+//! let file = sml_rs::parser::complete::parse(decoded[0].as_ref().unwrap()).unwrap();
+//! let [energy, _power, export] = EXTRACTOR.extract_file(&file);
+//! assert_eq!(energy.unwrap().unwrap().quantity.unit, Some(30));
+//! assert_eq!(export.unwrap().unwrap().quantity, Quantity { value: 0, scaler: 0, unit: Some(30) });
+//! # }
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use core::borrow::Borrow;
+
+use crate::{
+    application::{quantity::Quantity, status::Vendor},
+    parser::{
+        common::{ListEntry, Status, Time, Unit, Value},
+        streaming::{MessageBody, ParseEvent, Parser},
+        ObisCode, ParseError,
+    },
+    util::Clock,
+};
+
+/// A register extracted by an [`Extractor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractField {
+    /// the OBIS code of the register
+    pub obis_code: ObisCode,
+    /// whether a transmission without this register is an error (unless `fallback` is set)
+    pub required: bool,
+    /// value used if the register is missing
+    pub fallback: Option<Quantity>,
+    /// scaler used if the list entry doesn't contain a scaler (`0` if `None`)
+    pub default_scaler: Option<i8>,
+    /// unit replacing the unit of the list entry
+    pub unit: Option<Unit>,
+}
+
+impl ExtractField {
+    /// Constructs a field that has to be contained in each transmission.
+    #[must_use]
+    pub const fn required(obis_code: ObisCode) -> Self {
+        ExtractField {
+            obis_code,
+            required: true,
+            fallback: None,
+            default_scaler: None,
+            unit: None,
+        }
+    }
+
+    /// Constructs a field that may be omitted.
+    #[must_use]
+    pub const fn optional(obis_code: ObisCode) -> Self {
+        ExtractField {
+            required: false,
+            ..Self::required(obis_code)
+        }
+    }
+
+    /// Sets the value used if the register is missing.
+    #[must_use]
+    pub const fn with_fallback(mut self, fallback: Quantity) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Sets the scaler used if the list entry doesn't contain a scaler.
+    #[must_use]
+    pub const fn with_default_scaler(mut self, scaler: i8) -> Self {
+        self.default_scaler = Some(scaler);
+        self
+    }
+
+    /// Overrides the unit of the list entry.
+    #[must_use]
+    pub const fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+
+    fn quantity(&self, entry: &ListEntry<'_>) -> Result<Quantity, ExtractError> {
+        let value = entry
+            .value
+            .as_i64()
+            .ok_or(ExtractError::NotNumeric(self.obis_code))?;
+        Ok(Quantity {
+            value,
+            scaler: entry.scaler.or(self.default_scaler).unwrap_or(0),
+            unit: self.unit.or(entry.unit),
+        })
+    }
+}
+
+/// A wrong scaler sent by the meters of a vendor for a register.
+///
+/// The correction is only applied to entries with the wrong scaler, so meters
+/// whose firmware has been fixed aren't affected.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScalerCorrection {
+    /// the vendor of the affected meters
+    pub vendor: Vendor,
+    /// the affected register
+    pub obis_code: ObisCode,
+    /// the scaler sent by the affected meters
+    pub wrong_scaler: i8,
+    /// the scaler replacing `wrong_scaler`
+    pub scaler: i8,
+}
+
+impl ScalerCorrection {
+    /// Constructs a correction replacing `wrong_scaler` by `scaler`.
+    #[must_use]
+    pub const fn new(vendor: Vendor, obis_code: ObisCode, wrong_scaler: i8, scaler: i8) -> Self {
+        ScalerCorrection {
+            vendor,
+            obis_code,
+            wrong_scaler,
+            scaler,
+        }
+    }
+
+    // returns whether the correction applies to `quantity` of the register `obis_code`
+    fn matches(&self, vendor: Vendor, obis_code: ObisCode, quantity: &Quantity) -> bool {
+        self.vendor == vendor && self.obis_code == obis_code && self.wrong_scaler == quantity.scaler
+    }
+}
+
+/// Error returned by [`Extractor::extract`] for a single field.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractError {
+    /// A required register without fallback value is missing
+    Missing(ObisCode),
+    /// The value of the register isn't an integer that fits into an `i64`
+    NotNumeric(ObisCode),
+}
+
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtractError::Missing(obis) => write!(f, "required register {obis} is missing"),
+            ExtractError::NotNumeric(obis) => {
+                write!(
+                    f,
+                    "value of register {obis} isn't an integer that fits into an `i64`"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ExtractError {}
+
+/// A value extracted by an [`Extractor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ExtractedValue {
+    /// the value of the register
+    pub quantity: Quantity,
+    /// the `status` of the list entry widened to `u64` (`None` for fallback values)
+    ///
+    /// Some meters use it to convey e.g. the energy direction or tamper bits, see
+    /// [`MeterStatus`](super::status::MeterStatus) for decoding it.
+    pub status: Option<u64>,
+}
+
+/// A list entry whose value can't be converted into a [`Quantity`].
+///
+/// Returned by [`raw_entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEntry<'i> {
+    /// the OBIS code of the entry
+    pub obis: ObisCode,
+    /// the unconverted value
+    pub value: Value<'i>,
+}
+
+/// Returns the entries whose values can't be converted into a [`Quantity`].
+///
+/// [`Extractor`] only handles numeric values: it reports non-numeric values of its
+/// fields as [`ExtractError::NotNumeric`] and ignores entries of other registers.
+/// This function surfaces the remaining entries (e.g. octet strings such as the
+/// public key of the meter or boolean values), so they can be handled by the
+/// application instead of being lost. Entries without a valid OBIS code are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{application::extract::{raw_entries, RawEntry}, parser::{common::{ListEntry, Value}, ObisCode}};
+/// const PUBLIC_KEY: ObisCode = ObisCode::new(129, 129, 199, 130, 5, 255);
+/// let entry = |obj_name, value| ListEntry {
+///     obj_name,
+///     status: None,
+///     val_time: None,
+///     unit: None,
+///     scaler: None,
+///     value,
+///     value_signature: None,
+/// };
+/// let entries = [
+///     entry(&ObisCode::ENERGY_IMPORT_TOTAL.0, Value::U32(12345)),
+///     entry(&PUBLIC_KEY.0, Value::Bytes(b"\x01\x02")),
+/// ];
+/// let mut raw = raw_entries(&entries);
+/// assert_eq!(raw.next(), Some(RawEntry { obis: PUBLIC_KEY, value: Value::Bytes(b"\x01\x02") }));
+/// assert_eq!(raw.next(), None);
+/// ```
+pub fn raw_entries<'e, 'i: 'e, I>(entries: I) -> impl Iterator<Item = RawEntry<'i>> + 'e
+where
+    I: IntoIterator<Item = &'e ListEntry<'i>>,
+    I::IntoIter: 'e,
+{
+    entries.into_iter().filter_map(|entry| {
+        let obis = entry.obis_code()?;
+        match entry.value.as_i64() {
+            Some(_) => None,
+            None => Some(RawEntry {
+                obis,
+                value: entry.value.clone(),
+            }),
+        }
+    })
+}
+
+/// Result of extracting a single field.
+///
+/// `Ok(None)` means that an optional register without fallback value is missing.
+pub type ExtractResult = Result<Option<ExtractedValue>, ExtractError>;
+
+/// Values extracted from a transmission together with the time they were captured.
+///
+/// Returned by [`Extractor::extract_bytes_with_time_source`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedExtract<const N: usize> {
+    /// the results, ordered as the fields of the extractor
+    pub values: [ExtractResult; N],
+    /// `act_sensor_time` of the first `GetListResponse` containing one (or the
+    /// `ref_time` of the `OpenResponse`), if the meter sends a `SecIndex`
+    pub sec_index: Option<u32>,
+    /// time returned by the time source when the values have been extracted
+    pub timestamp: u64,
+}
+
+/// Extracts the values of `N` registers.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extractor<const N: usize> {
+    fields: [ExtractField; N],
+    corrections: &'static [ScalerCorrection],
+}
+
+impl<const N: usize> Extractor<N> {
+    /// Constructs an extractor from its fields.
+    #[must_use]
+    pub const fn new(fields: [ExtractField; N]) -> Self {
+        Extractor {
+            fields,
+            corrections: &[],
+        }
+    }
+
+    /// Sets the scaler corrections applied to meters of known vendors.
+    #[must_use]
+    pub const fn with_corrections(mut self, corrections: &'static [ScalerCorrection]) -> Self {
+        self.corrections = corrections;
+        self
+    }
+
+    /// Returns the fields.
+    #[must_use]
+    pub const fn fields(&self) -> &[ExtractField; N] {
+        &self.fields
+    }
+
+    /// Returns the scaler corrections.
+    #[must_use]
+    pub const fn corrections(&self) -> &'static [ScalerCorrection] {
+        self.corrections
+    }
+
+    /// Extracts the values of the fields from `entries`.
+    ///
+    /// The results are ordered as the fields. If an OBIS code occurs several
+    /// times, the first entry is used. Entries without a valid OBIS code are ignored.
+    ///
+    /// No scaler corrections are applied, see [`extract_for_vendor`](Extractor::extract_for_vendor).
+    pub fn extract<'e, 'i: 'e>(
+        &self,
+        entries: impl IntoIterator<Item = &'e ListEntry<'i>>,
+    ) -> [ExtractResult; N] {
+        self.extract_for_vendor(entries, None)
+    }
+
+    /// Extracts the values of the fields from `entries` sent by a meter of the given vendor.
+    ///
+    /// Same as [`extract`](Extractor::extract), except that the scaler corrections
+    /// of `vendor` are applied. Fallback values aren't corrected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{application::{extract::{ExtractField, Extractor, ScalerCorrection}, status::Vendor}, parser::{common::{ListEntry, Value}, ObisCode}};
+    /// // hypothetical bug: the meters send the energy in Wh, but with scaler 0 instead of -1
+    /// const CORRECTIONS: &[ScalerCorrection] =
+    ///     &[ScalerCorrection::new(Vendor::Emh, ObisCode::ENERGY_IMPORT_TOTAL, 0, -1)];
+    /// const EXTRACTOR: Extractor<1> =
+    ///     Extractor::new([ExtractField::required(ObisCode::ENERGY_IMPORT_TOTAL)])
+    ///         .with_corrections(CORRECTIONS);
+    ///
+    /// let entry = ListEntry {
+    ///     obj_name: &ObisCode::ENERGY_IMPORT_TOTAL.0,
+    ///     status: None,
+    ///     val_time: None,
+    ///     unit: Some(30),
+    ///     scaler: Some(0),
+    ///     value: Value::U32(12345),
+    ///     value_signature: None,
+    /// };
+    /// let [energy] = EXTRACTOR.extract_for_vendor([&entry], Some(Vendor::Emh));
+    /// assert_eq!(energy.unwrap().unwrap().quantity.scaler, -1);
+    /// // the raw value
+    /// let [energy] = EXTRACTOR.extract([&entry]);
+    /// assert_eq!(energy.unwrap().unwrap().quantity.scaler, 0);
+    /// ```
+    pub fn extract_for_vendor<'e, 'i: 'e>(
+        &self,
+        entries: impl IntoIterator<Item = &'e ListEntry<'i>>,
+        vendor: Option<Vendor>,
+    ) -> [ExtractResult; N] {
+        self.extract_entries(entries, vendor)
+    }
+
+    // same as `extract_for_vendor`, but also accepts owned entries
+    fn extract_entries<'i>(
+        &self,
+        entries: impl IntoIterator<Item = impl Borrow<ListEntry<'i>>>,
+        vendor: Option<Vendor>,
+    ) -> [ExtractResult; N] {
+        let mut found: [Option<ExtractResult>; N] = [None; N];
+        for entry in entries {
+            let entry = entry.borrow();
+            let Some(obis_code) = entry.obis_code() else {
+                continue;
+            };
+            for (field, res) in self.fields.iter().zip(&mut found) {
+                if field.obis_code == obis_code && res.is_none() {
+                    let value = field.quantity(entry).map(|q| ExtractedValue {
+                        quantity: self.correct(vendor, obis_code, q),
+                        status: entry.status.as_ref().map(Status::as_u64),
+                    });
+                    *res = Some(value.map(Some));
+                }
+            }
+        }
+        core::array::from_fn(|i| {
+            let field = &self.fields[i];
+            found[i].unwrap_or(match field.fallback {
+                Some(quantity) => Ok(Some(ExtractedValue {
+                    quantity,
+                    status: None,
+                })),
+                None if field.required => Err(ExtractError::Missing(field.obis_code)),
+                None => Ok(None),
+            })
+        })
+    }
+
+    /// Extracts the values of the fields from the list entries of an SML File.
+    ///
+    /// The vendor of the meter is identified from the `server_id` of the first
+    /// `GetListResponse` (see [`Vendor::from_server_id`]) or from its manufacturer id
+    /// entry (see [`Vendor::from_list_entry`]) and its scaler corrections are applied.
+    /// See [`extract_for_vendor`](Extractor::extract_for_vendor).
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn extract_file(&self, file: &crate::parser::complete::File<'_>) -> [ExtractResult; N] {
+        use crate::parser::complete::MessageBody;
+
+        let server_id = file
+            .messages
+            .iter()
+            .find_map(|msg| match &msg.message_body {
+                MessageBody::GetListResponse(glr) => Some(glr.server_id),
+                _ => None,
+            });
+        let vendor = server_id.and_then(Vendor::from_server_id).or_else(|| {
+            file.list_entries()
+                .find_map(|(_, entry)| Vendor::from_list_entry(entry))
+        });
+        self.extract_for_vendor(file.list_entries().map(|(_, entry)| entry), vendor)
+    }
+
+    /// Extracts the values of the fields from a decoded transmission without allocating.
+    ///
+    /// The transmission is parsed using the [streaming parser](crate::parser::streaming).
+    /// As in `extract_file`, the vendor of the meter is
+    /// identified and its scaler corrections are applied. The values are returned
+    /// together with the `SecIndex` sent by the meter (if any) and the current time
+    /// of `time_source`, which allows timestamping values of meters without a clock.
+    ///
+    /// Returns an error if the transmission can't be parsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{application::extract::{ExtractField, Extractor}, parser::ObisCode, util::ArrayBuf};
+    /// const EXTRACTOR: Extractor<1> = Extractor::new([ExtractField::required(ObisCode::ENERGY_IMPORT_TOTAL)]);
+    ///
+    /// let mut decoder = sml_rs::transport::decode_streaming::<ArrayBuf<512>>(include_bytes!("../../sample.bin"));
+    /// let bytes = decoder.next().unwrap().unwrap();
+    /// // e.g. milliseconds since boot
+    /// let uptime = || 42_000;
+    /// let extracted = EXTRACTOR.extract_bytes_with_time_source(bytes, &uptime).unwrap();
+    /// assert_eq!(extracted.values[0].unwrap().unwrap().quantity.unit, Some(30));
+    /// assert_eq!(extracted.timestamp, 42_000);
+    /// ```
+    pub fn extract_bytes_with_time_source(
+        &self,
+        bytes: &[u8],
+        time_source: &impl Clock,
+    ) -> Result<TimedExtract<N>, ParseError> {
+        let mut server_id = None;
+        let mut manufacturer = None;
+        let (mut ref_time, mut act_sensor_time) = (None, None);
+        for event in Parser::new(bytes) {
+            match event? {
+                ParseEvent::MessageStart(msg) => match msg.message_body {
+                    MessageBody::OpenResponse(o) => {
+                        ref_time = ref_time.or(o.ref_time.as_ref().and_then(Time::sec_index));
+                    }
+                    MessageBody::GetListResponse(glr) => {
+                        server_id = server_id.or(Some(glr.server_id));
+                        act_sensor_time = act_sensor_time
+                            .or(glr.act_sensor_time.as_ref().and_then(Time::sec_index));
+                    }
+                    _ => {}
+                },
+                ParseEvent::ListEntry(entry) => {
+                    manufacturer = manufacturer.or_else(|| Vendor::from_list_entry(&entry));
+                }
+                ParseEvent::GetListResponseEnd(_) => {}
+            }
+        }
+        let vendor = server_id.and_then(Vendor::from_server_id).or(manufacturer);
+        // the transmission has been parsed successfully above
+        let entries = Parser::new(bytes).list_entries().filter_map(Result::ok);
+        Ok(TimedExtract {
+            values: self.extract_entries(entries, vendor),
+            sec_index: act_sensor_time.or(ref_time),
+            timestamp: time_source.now(),
+        })
+    }
+
+    // applies the matching scaler correction to `quantity`
+    fn correct(&self, vendor: Option<Vendor>, obis_code: ObisCode, quantity: Quantity) -> Quantity {
+        let Some(vendor) = vendor else {
+            return quantity;
+        };
+        match self
+            .corrections
+            .iter()
+            .find(|c| c.matches(vendor, obis_code, &quantity))
+        {
+            Some(c) => Quantity {
+                scaler: c.scaler,
+                ..quantity
+            },
+            None => quantity,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+    const ENERGY: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const EXPORT: ObisCode = ObisCode::ENERGY_EXPORT_TOTAL;
+    const POWER: ObisCode = ObisCode::ACTIVE_POWER;
+
+    fn q(value: i64, scaler: i8, unit: Option<Unit>) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit,
+        }
+    }
+
+    fn v(value: i64, scaler: i8, unit: Option<Unit>) -> ExtractedValue {
+        ExtractedValue {
+            quantity: q(value, scaler, unit),
+            status: None,
+        }
+    }
+
+    #[test]
+    fn extract() {
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), -1, |_| 1234)
+            .register(POWER, None, 0, |_| 500)
+            .register(ENERGY, Some(30), 0, |_| 1);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let file = parse(&bytes).unwrap();
+
+        let extractor = Extractor::new([
+            ExtractField::required(ENERGY),
+            ExtractField::required(POWER).with_unit(27),
+            ExtractField::required(EXPORT),
+            ExtractField::optional(EXPORT),
+            ExtractField::optional(EXPORT).with_fallback(q(0, 0, Some(30))),
+            ExtractField::required(EXPORT).with_fallback(q(1, 0, Some(30))),
+        ]);
+        assert_eq!(
+            extractor.extract_file(&file),
+            [
+                // the first entry is used
+                Ok(Some(v(1234, -1, Some(30)))),
+                Ok(Some(v(500, 0, Some(27)))),
+                Err(ExtractError::Missing(EXPORT)),
+                Ok(None),
+                Ok(Some(v(0, 0, Some(30)))),
+                Ok(Some(v(1, 0, Some(30)))),
+            ]
+        );
+    }
+
+    #[test]
+    fn extract_bytes_with_time_source() {
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), -1, |secs| secs.into())
+            .register(POWER, None, 0, |_| 500);
+        let bytes = decode(meter.next_frame(77)).remove(0).unwrap();
+
+        let extractor = Extractor::new([
+            ExtractField::required(ENERGY),
+            ExtractField::optional(EXPORT),
+        ]);
+        let extracted = extractor
+            .extract_bytes_with_time_source(&bytes, &|| 1000)
+            .unwrap();
+        assert_eq!(
+            extracted,
+            TimedExtract {
+                values: extractor.extract_file(&parse(&bytes).unwrap()),
+                sec_index: Some(77),
+                timestamp: 1000,
+            }
+        );
+        assert_eq!(extracted.values[0], Ok(Some(v(77, -1, Some(30)))));
+
+        assert!(extractor
+            .extract_bytes_with_time_source(&bytes[..bytes.len() - 3], &|| 1000)
+            .is_err());
+    }
+
+    #[test]
+    fn status() {
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), 0, |_| 1234)
+            .register(POWER, Some(27), 0, |_| 500);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let file = parse(&bytes).unwrap();
+        let mut entries: Vec<_> = file.list_entries().map(|(_, e)| e.clone()).collect();
+        entries[0].status = Some(Status::Status16(0x0182));
+
+        let extractor = Extractor::new([
+            ExtractField::required(ENERGY),
+            ExtractField::required(POWER),
+            ExtractField::required(EXPORT).with_fallback(q(0, 0, Some(30))),
+        ]);
+        let [energy, power, export] = extractor.extract(&entries);
+        assert_eq!(energy.unwrap().unwrap().status, Some(0x0182));
+        assert_eq!(power.unwrap().unwrap().status, None);
+        // fallback values don't have a status
+        assert_eq!(export.unwrap().unwrap().status, None);
+    }
+
+    #[test]
+    fn raw_entries() {
+        let entries = [
+            ListEntry::new_test(&ENERGY.0, Value::U64(12)),
+            ListEntry::new_test(&POWER.0, Value::Bool(true)),
+            // not an OBIS code
+            ListEntry::new_test(b"name", Value::Bytes(b"value")),
+            ListEntry::new_test(&EXPORT.0, Value::Bytes(b"value")),
+            // doesn't fit into an `i64`
+            ListEntry::new_test(&EXPORT.0, Value::U64(u64::MAX)),
+        ];
+        let raw: Vec<_> = super::raw_entries(&entries).collect();
+        assert_eq!(
+            raw,
+            [
+                RawEntry {
+                    obis: POWER,
+                    value: Value::Bool(true)
+                },
+                RawEntry {
+                    obis: EXPORT,
+                    value: Value::Bytes(b"value")
+                },
+                RawEntry {
+                    obis: EXPORT,
+                    value: Value::U64(u64::MAX)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn default_scaler() {
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), -1, |_| 1234)
+            .register(POWER, Some(27), 0, |_| 500);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let file = parse(&bytes).unwrap();
+        let mut entries: Vec<_> = file.list_entries().map(|(_, e)| e.clone()).collect();
+        entries[1].scaler = None;
+
+        let extractor = Extractor::new([
+            ExtractField::required(ENERGY).with_default_scaler(-2),
+            ExtractField::required(POWER).with_default_scaler(-1),
+        ]);
+        // scalers contained in the entries take precedence
+        assert_eq!(
+            extractor.extract(&entries),
+            [
+                Ok(Some(v(1234, -1, Some(30)))),
+                Ok(Some(v(500, -1, Some(27))))
+            ]
+        );
+
+        entries[0].value = Value::Bytes(b"text");
+        assert_eq!(
+            extractor.extract(&entries)[0],
+            Err(ExtractError::NotNumeric(ENERGY))
+        );
+    }
+
+    #[test]
+    fn corrections() {
+        use crate::parser::{common::Value, complete::MessageBody};
+
+        const CORRECTIONS: &[ScalerCorrection] = &[
+            ScalerCorrection::new(Vendor::Emh, ENERGY, 0, -1),
+            ScalerCorrection::new(Vendor::Iskra, POWER, 0, -2),
+        ];
+        let extractor = Extractor::new([
+            ExtractField::required(ENERGY),
+            ExtractField::required(POWER),
+        ])
+        .with_corrections(CORRECTIONS);
+
+        let mut meter = Meter::new(b"\x0a\x01EMH\x00\x00\x12\x34\x56")
+            .register(ENERGY, Some(30), 0, |_| 1234)
+            .register(POWER, Some(27), 0, |_| 500);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let file = parse(&bytes).unwrap();
+        assert_eq!(
+            extractor.extract_file(&file),
+            [
+                Ok(Some(v(1234, -1, Some(30)))),
+                Ok(Some(v(500, 0, Some(27))))
+            ]
+        );
+        // the uncorrected values
+        let entries = file.list_entries().map(|(_, e)| e);
+        assert_eq!(
+            extractor.extract(entries),
+            [
+                Ok(Some(v(1234, 0, Some(30)))),
+                Ok(Some(v(500, 0, Some(27))))
+            ]
+        );
+
+        // entries with other scalers aren't corrected
+        let mut meter =
+            Meter::new(b"\x0a\x01EMH\x00\x00\x12\x34\x56").register(ENERGY, Some(30), -2, |_| 1234);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let file = parse(&bytes).unwrap();
+        assert_eq!(
+            extractor.extract_file(&file)[0],
+            Ok(Some(v(1234, -2, Some(30))))
+        );
+
+        // the vendor can also be identified using the manufacturer id
+        let mut meter = Meter::new(b"meter")
+            .register(ObisCode::MANUFACTURER_ID, None, 0, |_| 0)
+            .register(POWER, Some(27), 0, |_| 500);
+        let bytes = decode(meter.next_frame(0)).remove(0).unwrap();
+        let mut file = parse(&bytes).unwrap();
+        let MessageBody::GetListResponse(glr) = &mut file.messages[1].message_body else {
+            panic!("expected GetListResponse");
+        };
+        glr.val_list[0].value = Value::Bytes(b"ISK");
+        assert_eq!(
+            extractor.extract_file(&file)[1],
+            Ok(Some(v(500, -2, Some(27))))
+        );
+    }
+}
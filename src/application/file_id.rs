@@ -0,0 +1,273 @@
+//! Detecting missed transmissions using the `req_file_id`.
+//!
+//! Most meters use an incrementing counter as `req_file_id` of their `OpenResponse`.
+//! On lossy links (e.g. optical reading heads), a transmission that couldn't be
+//! decoded leaves a gap in this counter, which makes the `req_file_id` the cheapest
+//! indicator of missed transmissions. [`FileIdTracker`] tracks the `req_file_id`
+//! of each meter (identified by its `server_id`) and reports deviations as
+//! [`FileIdAnomaly`]s.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::application::file_id::{FileIdAnomaly, FileIdTracker};
+//! let mut tracker = FileIdTracker::<2>::new();
+//! assert_eq!(tracker.push(b"meter", 41), None);
+//! assert_eq!(tracker.push(b"meter", 42), None);
+//! // two transmissions have been lost
+//! assert_eq!(
+//!     tracker.push(b"meter", 45),
+//!     Some(FileIdAnomaly::Gap { previous: 42, current: 45 })
+//! );
+//! // meters are tracked independently
+//! assert_eq!(tracker.push(b"other meter", 7), None);
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::parser::common::OpenResponse;
+
+/// Maximum length of the `server_id`s tracked by [`FileIdTracker`].
+pub const MAX_SERVER_ID_LEN: usize = 16;
+
+/// Deviation of a `req_file_id` detected by [`FileIdTracker`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FileIdAnomaly {
+    /// The `req_file_id` didn't change (the transmission has probably been received twice)
+    Repeated {
+        /// the repeated `req_file_id`
+        file_id: u64,
+    },
+    /// The `req_file_id` increased by more than the maximum step
+    ///
+    /// For counters, `current - previous - 1` transmissions have been missed.
+    Gap {
+        /// the previous `req_file_id`
+        previous: u64,
+        /// the current `req_file_id`
+        current: u64,
+    },
+    /// The `req_file_id` decreased (e.g. after a meter reboot or an overflow of the counter)
+    Reset {
+        /// the previous `req_file_id`
+        previous: u64,
+        /// the current `req_file_id`
+        current: u64,
+    },
+}
+
+/// Tracks the `req_file_id`s of up to `N` meters.
+///
+/// Meters are tracked in the order of their first occurrence. Once `N` meters are
+/// tracked, further meters are ignored. Meters whose `server_id` is longer than
+/// [`MAX_SERVER_ID_LEN`] bytes are ignored as well.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct FileIdTracker<const N: usize> {
+    max_step: u64,
+    // (server_id, length of the server_id, last req_file_id)
+    meters: [Option<([u8; MAX_SERVER_ID_LEN], usize, u64)>; N],
+}
+
+impl<const N: usize> Default for FileIdTracker<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FileIdTracker<N> {
+    /// Constructs a tracker for counters incrementing by one per transmission.
+    #[must_use]
+    pub const fn new() -> Self {
+        FileIdTracker {
+            max_step: 1,
+            meters: [None; N],
+        }
+    }
+
+    /// Sets the largest increment that isn't reported as [`FileIdAnomaly::Gap`] (default: `1`).
+    ///
+    /// Use a larger value for meters whose `req_file_id` is a timestamp or that
+    /// increment their counter by more than one per transmission.
+    #[must_use]
+    pub const fn with_max_step(mut self, max_step: u64) -> Self {
+        self.max_step = max_step;
+        self
+    }
+
+    /// Records the `req_file_id` of a transmission of the meter `server_id`.
+    ///
+    /// Returns an anomaly if the `req_file_id` didn't increase by at least one and
+    /// at most the maximum step compared to the previous transmission of the meter.
+    pub fn push(&mut self, server_id: &[u8], file_id: u64) -> Option<FileIdAnomaly> {
+        if server_id.len() > MAX_SERVER_ID_LEN {
+            return None;
+        }
+        let slot = self.meters.iter_mut().find(|m| {
+            m.as_ref()
+                .map_or(true, |(id, len, _)| &id[..*len] == server_id)
+        })?;
+        let previous = match slot {
+            Some((_, _, last)) => core::mem::replace(last, file_id),
+            None => {
+                let mut id = [0; MAX_SERVER_ID_LEN];
+                id[..server_id.len()].copy_from_slice(server_id);
+                *slot = Some((id, server_id.len(), file_id));
+                return None;
+            }
+        };
+        let current = file_id;
+        if current == previous {
+            Some(FileIdAnomaly::Repeated { file_id })
+        } else if current < previous {
+            Some(FileIdAnomaly::Reset { previous, current })
+        } else if current - previous > self.max_step {
+            Some(FileIdAnomaly::Gap { previous, current })
+        } else {
+            None
+        }
+    }
+
+    /// Records the `req_file_id` of an `OpenResponse`.
+    ///
+    /// `req_file_id`s that can't be interpreted as integer (see
+    /// [`OpenResponse::req_file_id_as_u64`]) are ignored.
+    pub fn check(&mut self, open: &OpenResponse<'_>) -> Option<FileIdAnomaly> {
+        let file_id = open.req_file_id_as_u64()?;
+        self.push(open.server_id, file_id)
+    }
+
+    /// Records all `OpenResponse` messages of `file` and returns the detected anomalies.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn check_file(
+        &mut self,
+        file: &crate::parser::complete::File<'_>,
+    ) -> alloc::vec::Vec<FileIdAnomaly> {
+        file.messages
+            .iter()
+            .filter_map(|msg| msg.message_body.as_open_response())
+            .filter_map(|open| self.check(open))
+            .collect()
+    }
+
+    /// Forgets all tracked meters.
+    pub fn reset(&mut self) {
+        self.meters = [None; N];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counter() {
+        let mut t = FileIdTracker::<1>::new();
+        assert_eq!(t.push(b"a", 1), None);
+        assert_eq!(t.push(b"a", 2), None);
+        assert_eq!(
+            t.push(b"a", 2),
+            Some(FileIdAnomaly::Repeated { file_id: 2 })
+        );
+        assert_eq!(
+            t.push(b"a", 4),
+            Some(FileIdAnomaly::Gap {
+                previous: 2,
+                current: 4
+            })
+        );
+        assert_eq!(
+            t.push(b"a", 0),
+            Some(FileIdAnomaly::Reset {
+                previous: 4,
+                current: 0
+            })
+        );
+        assert_eq!(t.push(b"a", 1), None);
+        // only a single meter is tracked
+        assert_eq!(t.push(b"b", 1), None);
+        assert_eq!(t.push(b"b", 1), None);
+        t.reset();
+        assert_eq!(t.push(b"b", 1), None);
+        assert_eq!(
+            t.push(b"b", 1),
+            Some(FileIdAnomaly::Repeated { file_id: 1 })
+        );
+    }
+
+    #[test]
+    fn max_step() {
+        let mut t = FileIdTracker::<2>::new().with_max_step(5);
+        assert_eq!(t.push(b"a", 100), None);
+        assert_eq!(t.push(b"a", 105), None);
+        assert_eq!(
+            t.push(b"a", 111),
+            Some(FileIdAnomaly::Gap {
+                previous: 105,
+                current: 111
+            })
+        );
+        // server ids that are too long are ignored
+        let long = [0; MAX_SERVER_ID_LEN + 1];
+        assert_eq!(t.push(&long, 1), None);
+        assert_eq!(t.push(&long, 1), None);
+        // prefixes of other server ids are different meters
+        assert_eq!(t.push(b"", 1), None);
+        assert_eq!(t.push(b"", 2), None);
+    }
+
+    #[test]
+    fn req_file_id_as_u64() {
+        let open = |req_file_id| OpenResponse {
+            codepage: None,
+            client_id: None,
+            req_file_id,
+            server_id: b"meter",
+            ref_time: None,
+            sml_version: None,
+        };
+        assert_eq!(open(&[0x01, 0x02]).req_file_id_as_u64(), Some(0x0102));
+        assert_eq!(open(&[0xff; 8]).req_file_id_as_u64(), Some(u64::MAX));
+        assert_eq!(open(&[]).req_file_id_as_u64(), None);
+        assert_eq!(open(&[0; 9]).req_file_id_as_u64(), None);
+
+        let mut t = FileIdTracker::<1>::new();
+        assert_eq!(t.check(&open(&[0, 5])), None);
+        assert_eq!(t.check(&open(&[])), None);
+        assert_eq!(
+            t.check(&open(&[0, 5])),
+            Some(FileIdAnomaly::Repeated { file_id: 5 })
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn check_file() {
+        use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+        let mut meter = Meter::new(b"meter");
+        let mut t = FileIdTracker::<1>::new();
+        let mut anomalies = alloc::vec::Vec::new();
+        for i in 0..5 {
+            let frame = meter.next_frame(i);
+            // the third transmission is lost
+            if i != 2 {
+                let bytes = decode(frame).remove(0).unwrap();
+                anomalies.extend(t.check_file(&parse(&bytes).unwrap()));
+            }
+        }
+        assert_eq!(
+            anomalies,
+            [FileIdAnomaly::Gap {
+                previous: 2,
+                current: 4
+            }]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Fixed-size history of the values of a single OBIS code.
+//!
+//! [`History`] records the last `N` values of a configured OBIS code together
+//! with the time they have been observed. It doesn't allocate and can be used
+//! by small devices to display the recent development of a value (e.g. the
+//! current power).
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::history::History, parser::ObisCode};
+//! let mut history = History::<8>::new(ObisCode::new(1, 0, 16, 7, 0, 255));
+//! history.push(100, 320);
+//! history.push(101, 280);
+//! history.push(102, 400);
+//!
+//! assert_eq!(history.min(), Some(280));
+//! assert_eq!(history.max(), Some(400));
+//! assert_eq!(history.avg(), Some(1000 / 3));
+//! assert_eq!(history.latest(), Some((102, 400)));
+//! ```
+
+use crate::parser::{common::ListEntry, ObisCode};
+
+/// Ring buffer holding the last `N` values of an OBIS code.
+///
+/// Each sample consists of the `SecIndex` when the value was observed and the raw
+/// value (without applying the scaler).
+#[derive(Debug, Clone)]
+pub struct History<const N: usize> {
+    obis_code: ObisCode,
+    samples: [(u32, i64); N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> History<N> {
+    /// Constructs an empty history recording values of `obis_code`.
+    #[must_use]
+    pub const fn new(obis_code: ObisCode) -> Self {
+        History {
+            obis_code,
+            samples: [(0, 0); N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the OBIS code recorded by this history.
+    #[must_use]
+    pub const fn obis_code(&self) -> ObisCode {
+        self.obis_code
+    }
+
+    /// Records the value of `entry` if it belongs to the configured OBIS code.
+    ///
+    /// The value is recorded at [`ListEntry::sec_index_or`]`(sec_index)`, i.e. at its `val_time`
+    /// if present.
+    /// Returns `true` if the value has been recorded. Values that aren't integers
+    /// or don't fit into an `i64` are ignored.
+    pub fn record(&mut self, entry: &ListEntry<'_>, sec_index: u32) -> bool {
+        if entry.obis_code() != Some(self.obis_code) {
+            return false;
+        }
+        let Some(value) = entry.value.as_i64() else {
+            return false;
+        };
+        let time = entry.sec_index_or(sec_index);
+        self.push(time, value);
+        true
+    }
+
+    /// Appends a sample, dropping the oldest sample if the history is full.
+    pub fn push(&mut self, sec_index: u32, value: i64) {
+        if N == 0 {
+            return;
+        }
+        if self.len < N {
+            self.samples[(self.start + self.len) % N] = (sec_index, value);
+            self.len += 1;
+        } else {
+            self.samples[self.start] = (sec_index, value);
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Removes all samples.
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// Returns the number of samples.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there are no samples.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the samples, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = (u32, i64)> + '_ {
+        (0..self.len).map(move |i| self.samples[(self.start + i) % N])
+    }
+
+    /// Returns the newest sample.
+    #[must_use]
+    pub fn latest(&self) -> Option<(u32, i64)> {
+        self.iter().last()
+    }
+
+    /// Returns the smallest value.
+    #[must_use]
+    pub fn min(&self) -> Option<i64> {
+        self.iter().map(|(_, v)| v).min()
+    }
+
+    /// Returns the largest value.
+    #[must_use]
+    pub fn max(&self) -> Option<i64> {
+        self.iter().map(|(_, v)| v).max()
+    }
+
+    /// Returns the average of all values, rounded towards zero.
+    #[must_use]
+    pub fn avg(&self) -> Option<i64> {
+        if self.is_empty() {
+            return None;
+        }
+        let sum: i128 = self.iter().map(|(_, v)| i128::from(v)).sum();
+        // the average of `i64` values always fits into an `i64`
+        Some((sum / self.len as i128) as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::common::{Time, Value};
+
+    const POWER: ObisCode = ObisCode::new(1, 0, 16, 7, 0, 255);
+
+    #[test]
+    fn ring_buffer() {
+        let mut h = History::<3>::new(POWER);
+        assert!(h.is_empty());
+        assert_eq!(
+            (h.min(), h.max(), h.avg(), h.latest()),
+            (None, None, None, None)
+        );
+        for i in 0..5 {
+            h.push(i, i64::from(i) * 10);
+        }
+        assert_eq!(h.len(), 3);
+        assert!(h.iter().eq([(2, 20), (3, 30), (4, 40)]));
+        assert_eq!(h.min(), Some(20));
+        assert_eq!(h.max(), Some(40));
+        assert_eq!(h.avg(), Some(30));
+        assert_eq!(h.latest(), Some((4, 40)));
+        h.clear();
+        assert!(h.iter().eq([]));
+    }
+
+    #[test]
+    fn avg_no_overflow() {
+        let mut h = History::<2>::new(POWER);
+        h.push(0, i64::MAX);
+        h.push(1, i64::MAX);
+        assert_eq!(h.avg(), Some(i64::MAX));
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut h = History::<0>::new(POWER);
+        h.push(0, 1);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn record() {
+        let mut h = History::<4>::new(POWER);
+        let name = &POWER.0;
+        assert!(h.record(&ListEntry::new_test(name, Value::I32(-5)), 10));
+        assert!(h.record(
+            &ListEntry::new_test(name, Value::U16(5)).with_val_time(Some(Time::SecIndex(7))),
+            11
+        ));
+        assert!(!h.record(&ListEntry::new_test(name, Value::Bytes(&[1])), 12));
+        assert!(!h.record(&ListEntry::new_test(name, Value::U64(u64::MAX)), 12));
+        assert!(!h.record(
+            &ListEntry::new_test(&[1, 0, 1, 8, 0, 255], Value::I32(1)),
+            12
+        ));
+        assert!(h.iter().eq([(10, -5), (7, 5)]));
+    }
+}
@@ -0,0 +1,388 @@
+//! Merging the values of several meters into one logical meter.
+//!
+//! Some installations use separate meters for import and export (e.g. for PV
+//! feed-in). Energy-management controllers need a single view, such as the net
+//! power of the installation. A [`Merger`] combines the registers of several
+//! meters according to a list of [`MergeRule`]s: each rule copies a register of
+//! one meter into a target register, optionally negating it. Rules with the same
+//! target are summed up.
+//!
+//! As the `SecIndex` values of different meters aren't related, readings are
+//! aligned using the time they have been received (e.g. using a [`Clock`](crate::util::Clock)).
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{merge::{MergeRule, Merger, MeterReading}, quantity::Quantity}, parser::{common::{ListEntry, Value}, ObisCode}};
+//! # fn entry(code: &ObisCode, unit: u8, value: i64) -> ListEntry<'_> {
+//! #     ListEntry { obj_name: &code.0, status: None, val_time: None, unit: Some(unit), scaler: Some(0), value: Value::I64(value), value_signature: None }
+//! # }
+//! const MERGER: Merger<3> = Merger::new(
+//!     [
+//!         MergeRule::new(0, ObisCode::ENERGY_IMPORT_TOTAL, ObisCode::ENERGY_IMPORT_TOTAL),
+//!         // net power: import meter minus export meter
+//!         MergeRule::new(0, ObisCode::ACTIVE_POWER, ObisCode::ACTIVE_POWER),
+//!         MergeRule::new(1, ObisCode::ACTIVE_POWER, ObisCode::ACTIVE_POWER).negated(),
+//!     ],
+//!     // readings have to be received within 2 seconds (in ms)
+//!     2000,
+//! );
+//! let import = [entry(&ObisCode::ENERGY_IMPORT_TOTAL, 30, 5000), entry(&ObisCode::ACTIVE_POWER, 27, 300)];
+//! let export = [entry(&ObisCode::ACTIVE_POWER, 27, 1200)];
+//! let merged = MERGER.merge(&[
+//!     MeterReading { time: 10_000, entries: &import },
+//!     MeterReading { time: 10_500, entries: &export },
+//! ]).unwrap();
+//! assert_eq!(merged.time, 10_500);
+//! assert_eq!(merged.get(ObisCode::ACTIVE_POWER), Some(Quantity { value: -900, scaler: 0, unit: Some(27) }));
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{common::ListEntry, ObisCode},
+};
+
+/// Copies a register of a meter into a register of the merged reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeRule {
+    /// index of the meter in the readings passed to [`Merger::merge`]
+    pub meter: usize,
+    /// the register of the meter
+    pub source: ObisCode,
+    /// the register of the merged reading
+    pub target: ObisCode,
+    /// whether the value is negated
+    pub negate: bool,
+}
+
+impl MergeRule {
+    /// Constructs a rule copying `source` of meter `meter` into `target`.
+    #[must_use]
+    pub const fn new(meter: usize, source: ObisCode, target: ObisCode) -> Self {
+        MergeRule {
+            meter,
+            source,
+            target,
+            negate: false,
+        }
+    }
+
+    /// Negates the value (e.g. to subtract the power of an export meter).
+    #[must_use]
+    pub const fn negated(mut self) -> Self {
+        self.negate = true;
+        self
+    }
+}
+
+/// The values of a single meter passed to [`Merger::merge`].
+#[derive(Debug, Clone, Copy)]
+pub struct MeterReading<'a, 'i> {
+    /// time when the transmission has been received
+    pub time: u64,
+    /// the list entries of the transmission
+    pub entries: &'a [ListEntry<'i>],
+}
+
+/// Error returned by [`Merger::merge`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// A rule references a meter index for which no reading has been passed
+    MissingMeter(usize),
+    /// The readings have been received too far apart
+    OutOfWindow {
+        /// difference between the earliest and the latest reading
+        spread: u64,
+    },
+    /// Values with different units are summed up into the given register
+    UnitMismatch(ObisCode),
+    /// The value of the given register doesn't fit into an `i64`
+    Overflow(ObisCode),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::MissingMeter(idx) => write!(f, "no reading for meter {idx}"),
+            MergeError::OutOfWindow { spread } => {
+                write!(
+                    f,
+                    "readings are {spread} time units apart, which exceeds the merge window"
+                )
+            }
+            MergeError::UnitMismatch(obis) => {
+                write!(f, "values with different units are summed up into {obis}")
+            }
+            MergeError::Overflow(obis) => write!(f, "value of {obis} doesn't fit into an `i64`"),
+        }
+    }
+}
+
+impl core::error::Error for MergeError {}
+
+/// Values of the logical meter returned by [`Merger::merge`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedReading<const N: usize> {
+    /// time of the latest reading
+    pub time: u64,
+    // target registers in the order of their first rule, `None` if a source is missing
+    values: [Option<(ObisCode, Option<Quantity>)>; N],
+}
+
+impl<const N: usize> MergedReading<N> {
+    /// Returns the value of a target register.
+    ///
+    /// Returns `None` if the register isn't a target of any rule or if the source
+    /// register of one of its rules is missing or isn't an integer.
+    #[must_use]
+    pub fn get(&self, obis_code: ObisCode) -> Option<Quantity> {
+        self.iter()
+            .find(|(code, _)| *code == obis_code)
+            .map(|(_, q)| q)
+    }
+
+    /// Returns an iterator over the target registers with their values.
+    ///
+    /// Registers without value (see [`get`](MergedReading::get)) are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (ObisCode, Quantity)> + '_ {
+        self.values
+            .iter()
+            .filter_map(|v| v.and_then(|(code, q)| Some((code, q?))))
+    }
+}
+
+/// Merges the registers of several meters according to `N` rules.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Merger<const N: usize> {
+    rules: [MergeRule; N],
+    window: u64,
+}
+
+impl<const N: usize> Merger<N> {
+    /// Constructs a merger from its rules.
+    ///
+    /// Readings are only merged if they have been received within `window` time units.
+    #[must_use]
+    pub const fn new(rules: [MergeRule; N], window: u64) -> Self {
+        Merger { rules, window }
+    }
+
+    /// Returns the rules.
+    #[must_use]
+    pub const fn rules(&self) -> &[MergeRule; N] {
+        &self.rules
+    }
+
+    /// Merges the readings of the meters, where `readings[i]` is the reading of meter `i`.
+    ///
+    /// Values with different scalers are summed up using the smallest scaler. If an
+    /// OBIS code occurs several times in a reading, the first entry is used.
+    pub fn merge(&self, readings: &[MeterReading<'_, '_>]) -> Result<MergedReading<N>, MergeError> {
+        let times = readings.iter().map(|r| r.time);
+        let (min, max) = (times.clone().min(), times.max());
+        let spread = max.unwrap_or(0) - min.unwrap_or(0);
+        if spread > self.window {
+            return Err(MergeError::OutOfWindow { spread });
+        }
+
+        let mut values: [Option<(ObisCode, Option<Quantity>)>; N] = [None; N];
+        for rule in &self.rules {
+            let reading = readings
+                .get(rule.meter)
+                .ok_or(MergeError::MissingMeter(rule.meter))?;
+            let mut value = reading
+                .entries
+                .iter()
+                .find(|e| e.obis_code() == Some(rule.source))
+                .and_then(Quantity::from_list_entry);
+            if rule.negate {
+                value = match value {
+                    Some(q) => Some(Quantity {
+                        value: q
+                            .value
+                            .checked_neg()
+                            .ok_or(MergeError::Overflow(rule.target))?,
+                        ..q
+                    }),
+                    None => None,
+                };
+            }
+            let slot = values
+                .iter_mut()
+                .find(|v| v.map_or(true, |(code, _)| code == rule.target))
+                .expect("there is a slot for each rule");
+            let sum = match *slot {
+                None => value,
+                Some((_, sum)) => match (sum, value) {
+                    (Some(a), Some(b)) => Some(add(a, b, rule.target)?),
+                    _ => None,
+                },
+            };
+            *slot = Some((rule.target, sum));
+        }
+        Ok(MergedReading {
+            time: max.unwrap_or(0),
+            values,
+        })
+    }
+}
+
+// adds two quantities using the smaller scaler
+fn add(a: Quantity, b: Quantity, target: ObisCode) -> Result<Quantity, MergeError> {
+    if a.unit != b.unit {
+        return Err(MergeError::UnitMismatch(target));
+    }
+    let scaler = a.scaler.min(b.scaler);
+    let rescale = |q: Quantity| {
+        10i64
+            .checked_pow(u32::from(q.scaler.abs_diff(scaler)))?
+            .checked_mul(q.value)
+    };
+    let value = rescale(a)
+        .zip(rescale(b))
+        .and_then(|(a, b)| a.checked_add(b))
+        .ok_or(MergeError::Overflow(target))?;
+    Ok(Quantity {
+        value,
+        scaler,
+        unit: a.unit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::common::Value;
+
+    const IMPORT: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const EXPORT: ObisCode = ObisCode::ENERGY_EXPORT_TOTAL;
+    const POWER: ObisCode = ObisCode::ACTIVE_POWER;
+
+    fn q(value: i64, scaler: i8, unit: u8) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit: Some(unit),
+        }
+    }
+
+    const MERGER: Merger<4> = Merger::new(
+        [
+            MergeRule::new(0, IMPORT, IMPORT),
+            MergeRule::new(1, IMPORT, EXPORT),
+            MergeRule::new(0, POWER, POWER),
+            MergeRule::new(1, POWER, POWER).negated(),
+        ],
+        5,
+    );
+
+    #[test]
+    fn merge() {
+        let a = [
+            ListEntry::new_test(&IMPORT.0, Value::I64(100)).with_unit(Some(30), 0),
+            ListEntry::new_test(&POWER.0, Value::I64(1234)).with_unit(Some(27), -1),
+        ];
+        let b = [
+            ListEntry::new_test(&IMPORT.0, Value::I64(7)).with_unit(Some(30), 1),
+            ListEntry::new_test(&POWER.0, Value::I64(100)).with_unit(Some(27), 0),
+        ];
+        let readings = |t0, t1| {
+            [
+                MeterReading {
+                    time: t0,
+                    entries: &a,
+                },
+                MeterReading {
+                    time: t1,
+                    entries: &b,
+                },
+            ]
+        };
+        let merged = MERGER.merge(&readings(10, 15)).unwrap();
+        assert_eq!(merged.time, 15);
+        assert!(merged.iter().eq([
+            (IMPORT, q(100, 0, 30)),
+            (EXPORT, q(7, 1, 30)),
+            (POWER, q(234, -1, 27)),
+        ]));
+        assert_eq!(MERGER.merge(&readings(15, 10)).map(|m| m.time), Ok(15));
+        assert_eq!(
+            MERGER.merge(&readings(10, 16)),
+            Err(MergeError::OutOfWindow { spread: 6 })
+        );
+        assert_eq!(
+            MERGER.merge(&readings(10, 10)[..1]),
+            Err(MergeError::MissingMeter(1))
+        );
+    }
+
+    #[test]
+    fn missing_values() {
+        let a = [ListEntry::new_test(&IMPORT.0, Value::I64(100)).with_unit(Some(30), 0)];
+        let b = [ListEntry::new_test(&POWER.0, Value::I64(100)).with_unit(Some(27), 0)];
+        let merged = MERGER
+            .merge(&[
+                MeterReading {
+                    time: 0,
+                    entries: &a,
+                },
+                MeterReading {
+                    time: 0,
+                    entries: &b,
+                },
+            ])
+            .unwrap();
+        // the power of meter 0 is missing, so the sum is unknown
+        assert_eq!(merged.get(POWER), None);
+        assert!(merged.iter().eq([(IMPORT, q(100, 0, 30))]));
+    }
+
+    #[test]
+    fn errors() {
+        let merger = Merger::new(
+            [
+                MergeRule::new(0, IMPORT, IMPORT),
+                MergeRule::new(0, POWER, IMPORT),
+            ],
+            0,
+        );
+        let entries = [
+            ListEntry::new_test(&IMPORT.0, Value::I64(1)).with_unit(Some(30), 0),
+            ListEntry::new_test(&POWER.0, Value::I64(1)).with_unit(Some(27), 0),
+        ];
+        let reading = MeterReading {
+            time: 0,
+            entries: &entries,
+        };
+        assert_eq!(
+            merger.merge(&[reading]),
+            Err(MergeError::UnitMismatch(IMPORT))
+        );
+
+        let entries = [
+            ListEntry::new_test(&IMPORT.0, Value::I64(i64::MAX)).with_unit(Some(30), 0),
+            ListEntry::new_test(&POWER.0, Value::I64(1)).with_unit(Some(30), 0),
+        ];
+        let reading = MeterReading {
+            time: 0,
+            entries: &entries,
+        };
+        assert_eq!(merger.merge(&[reading]), Err(MergeError::Overflow(IMPORT)));
+
+        let negated = Merger::new([MergeRule::new(0, IMPORT, IMPORT).negated()], 0);
+        let entries = [ListEntry::new_test(&IMPORT.0, Value::I64(i64::MIN)).with_unit(Some(30), 0)];
+        let reading = MeterReading {
+            time: 0,
+            entries: &entries,
+        };
+        assert_eq!(negated.merge(&[reading]), Err(MergeError::Overflow(IMPORT)));
+    }
+}
@@ -0,0 +1,52 @@
+//! Application-level helpers built on top of the parsed SML data.
+//!
+//! While [`parser`](crate::parser) and [`transport`](crate::transport) implement the
+//! SML specification, the modules in here solve common tasks of applications reading
+//! power meters, for example:
+//!
+//! - interpreting values: [`quantity`], [`status`], [`extract`], `schema`
+//! - tracking values over time: [`cache`], [`history`], [`power`], [`resample`], [`events`], [`file_id`]
+//! - combining and comparing transmissions: [`merge`], `diff`, `duplicates`
+//! - storing and sending values: [`delta`], [`telemetry`], [`config`], [`report`], `export`
+//! - talking to meters and gateways: `session`, `smgw`, `simulator`
+//!
+//! The modules shown without links require the `alloc` or `std` feature, as noted in their
+//! documentation.
+
+pub mod cache;
+pub mod config;
+pub mod delta;
+#[cfg(feature = "alloc")]
+pub mod diff;
+#[cfg(feature = "alloc")]
+pub mod duplicates;
+pub mod events;
+#[cfg(feature = "std")]
+pub mod export;
+pub mod extract;
+pub mod file_id;
+pub mod history;
+pub mod merge;
+pub mod power;
+pub mod quantity;
+pub mod report;
+pub mod resample;
+#[cfg(feature = "alloc")]
+pub mod schema;
+#[cfg(feature = "alloc")]
+pub mod session;
+#[cfg(feature = "alloc")]
+pub mod simulator;
+#[cfg(feature = "std")]
+pub mod smgw;
+pub mod status;
+pub mod telemetry;
+
+pub use cache::ValueCache;
+pub use history::History;
+pub use power::PowerEstimator;
+pub use quantity::{parse_quantity, Quantity};
+pub use report::render_compact;
+pub use resample::Resampler;
+#[cfg(feature = "alloc")]
+pub use session::Session;
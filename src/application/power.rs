@@ -0,0 +1,284 @@
+//! Estimating the power from energy counters.
+//!
+//! Many meters only transmit their energy counters (e.g. `1-0:1.8.0`) without the
+//! instantaneous power. [`PowerEstimator`] derives the average power between two
+//! consecutive transmissions from the difference of the counter values and the
+//! difference of their `SecIndex` times.
+//!
+//! The estimates are labelled using [`ObisCode::ACTIVE_POWER_ESTIMATED`], which
+//! distinguishes them from power values measured by the meter.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::application::{power::PowerEstimator, quantity::Quantity};
+//! let mut estimator = PowerEstimator::new();
+//! // 1000 Wh
+//! assert_eq!(estimator.push(100, Quantity { value: 10_000, scaler: -1, unit: Some(30) }), None);
+//! // 1001 Wh after 10 seconds
+//! let estimate = estimator.push(110, Quantity { value: 10_010, scaler: -1, unit: Some(30) }).unwrap();
+//! assert_eq!(estimate.sec_index, 110);
+//! // 1 Wh in 10 s corresponds to 360 W
+//! assert_eq!(estimate.power.to_string(), "360 W");
+//! ```
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{
+        common::{ListEntry, Unit},
+        ObisCode,
+    },
+};
+
+// scaler of the estimated power values (0.1 W resolution)
+const POWER_SCALER: i8 = -1;
+
+/// Power derived from two consecutive energy counter values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerEstimate {
+    /// always [`ObisCode::ACTIVE_POWER_ESTIMATED`]
+    pub obis_code: ObisCode,
+    /// time of the later counter value
+    pub sec_index: u32,
+    /// average power between the two counter values
+    pub power: Quantity,
+}
+
+/// Derives the average power from consecutive values of an energy counter.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct PowerEstimator {
+    obis_code: ObisCode,
+    counter_modulus: Option<i64>,
+    last: Option<(u32, Quantity)>,
+}
+
+impl Default for PowerEstimator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PowerEstimator {
+    /// Constructs an estimator using the total imported energy (`1-0:1.8.0*255`).
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::for_obis_code(ObisCode::ENERGY_IMPORT_TOTAL)
+    }
+
+    /// Constructs an estimator using the energy counter `obis_code`.
+    #[must_use]
+    pub const fn for_obis_code(obis_code: ObisCode) -> Self {
+        PowerEstimator {
+            obis_code,
+            counter_modulus: None,
+            last: None,
+        }
+    }
+
+    /// Sets the raw value at which the energy counter wraps around to zero.
+    ///
+    /// By default, a decreasing counter value is treated as a reset of the meter
+    /// and no estimate is returned. If a modulus is set, the counter is assumed to
+    /// have wrapped around instead.
+    #[must_use]
+    pub const fn with_counter_modulus(mut self, modulus: i64) -> Self {
+        self.counter_modulus = Some(modulus);
+        self
+    }
+
+    /// Returns the OBIS code of the energy counter.
+    #[must_use]
+    pub const fn obis_code(&self) -> ObisCode {
+        self.obis_code
+    }
+
+    /// Records the value of `entry` if it belongs to the configured OBIS code.
+    ///
+    /// The value is recorded at [`ListEntry::sec_index_or`]`(sec_index)`, i.e. at its `val_time`
+    /// if present.
+    /// Returns the estimated power since the previously recorded value.
+    pub fn record(&mut self, entry: &ListEntry<'_>, sec_index: u32) -> Option<PowerEstimate> {
+        if entry.obis_code() != Some(self.obis_code) {
+            return None;
+        }
+        let energy = Quantity::from_list_entry(entry)?;
+        let time = entry.sec_index_or(sec_index);
+        self.push(time, energy)
+    }
+
+    /// Records an energy counter value observed at `sec_index`.
+    ///
+    /// Returns the estimated power since the previous value. No estimate is
+    /// returned for the first value, if no time has passed, if the scaler or the unit
+    /// changed, if the counter decreased (and no modulus is set) or if the unit isn't
+    /// an energy unit (Wh, VAh or varh).
+    pub fn push(&mut self, sec_index: u32, energy: Quantity) -> Option<PowerEstimate> {
+        let Some((last_time, last_energy)) = self.last else {
+            self.last = Some((sec_index, energy));
+            return None;
+        };
+        // `SecIndex` values wrap around
+        let secs = sec_index.wrapping_sub(last_time);
+        if secs == 0 {
+            return None;
+        }
+        self.last = Some((sec_index, energy));
+        if energy.scaler != last_energy.scaler || energy.unit != last_energy.unit {
+            return None;
+        }
+        let mut delta = i128::from(energy.value) - i128::from(last_energy.value);
+        if delta < 0 {
+            delta += i128::from(self.counter_modulus?);
+        }
+        let power = Quantity {
+            value: average_power(delta, energy.scaler, secs)?,
+            scaler: POWER_SCALER,
+            unit: Some(power_unit(energy.unit?)?),
+        };
+        Some(PowerEstimate {
+            obis_code: ObisCode::ACTIVE_POWER_ESTIMATED,
+            sec_index,
+            power,
+        })
+    }
+
+    /// Forgets the previously recorded value.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+// returns the power unit corresponding to an energy unit
+fn power_unit(energy_unit: Unit) -> Option<Unit> {
+    match energy_unit {
+        // Wh -> W
+        30 => Some(27),
+        // VAh -> VA
+        31 => Some(28),
+        // varh -> var
+        32 => Some(29),
+        _ => None,
+    }
+}
+
+// returns the average power (using `POWER_SCALER`) of the energy `delta * 10^scaler` hours over `secs` seconds
+fn average_power(delta: i128, scaler: i8, secs: u32) -> Option<i64> {
+    let exp = i32::from(scaler) - i32::from(POWER_SCALER);
+    let mut num = delta.checked_mul(3600)?;
+    let mut den = i128::from(secs);
+    if exp >= 0 {
+        num = num.checked_mul(10i128.checked_pow(exp.unsigned_abs())?)?;
+    } else {
+        den = den.checked_mul(10i128.checked_pow(exp.unsigned_abs())?)?;
+    }
+    (num / den).try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::common::Value;
+
+    fn wh(value: i64, scaler: i8) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit: Some(30),
+        }
+    }
+
+    fn w(value: i64) -> Quantity {
+        Quantity {
+            value,
+            scaler: POWER_SCALER,
+            unit: Some(27),
+        }
+    }
+
+    #[test]
+    fn estimates() {
+        let mut e = PowerEstimator::new();
+        assert_eq!(e.push(0, wh(0, 0)), None);
+        let estimate = e.push(3600, wh(500, 0)).unwrap();
+        assert_eq!(estimate.obis_code, ObisCode::ACTIVE_POWER_ESTIMATED);
+        assert_eq!(estimate.power, w(5000));
+        // same time
+        assert_eq!(e.push(3600, wh(600, 0)), None);
+        // kWh counter with 0.1 kWh resolution
+        let mut e = PowerEstimator::new();
+        e.push(0, wh(10, 2));
+        assert_eq!(e.push(60, wh(11, 2)).unwrap().power, w(60_000));
+        // SecIndex wrap
+        let mut e = PowerEstimator::new();
+        e.push(u32::MAX - 4, wh(0, -4));
+        assert_eq!(e.push(5, wh(100, -4)).unwrap().power, w(36));
+    }
+
+    #[test]
+    fn counter_changes() {
+        // counter reset
+        let mut e = PowerEstimator::new();
+        e.push(0, wh(100, 0));
+        assert_eq!(e.push(10, wh(0, 0)), None);
+        assert_eq!(e.push(20, wh(1, 0)).unwrap().power, w(3600));
+        // counter wrap
+        let mut e = PowerEstimator::new().with_counter_modulus(1000);
+        e.push(0, wh(999, 0));
+        assert_eq!(e.push(10, wh(1, 0)).unwrap().power, w(7200));
+        // scaler change
+        e.push(20, wh(10, -1));
+        assert_eq!(e.push(30, wh(10, -1)).unwrap().power, w(0));
+        // unsupported unit
+        let mut e = PowerEstimator::new();
+        e.push(
+            0,
+            Quantity {
+                unit: Some(27),
+                ..wh(0, 0)
+            },
+        );
+        assert_eq!(
+            e.push(
+                1,
+                Quantity {
+                    unit: Some(27),
+                    ..wh(1, 0)
+                }
+            ),
+            None
+        );
+        // overflow
+        let mut e = PowerEstimator::new();
+        e.push(0, wh(0, 20));
+        assert_eq!(e.push(1, wh(i64::MAX, 20)), None);
+    }
+
+    #[test]
+    fn record() {
+        let energy = ObisCode::ENERGY_IMPORT_TOTAL.0;
+        let mut e = PowerEstimator::new();
+        assert_eq!(
+            e.record(
+                &ListEntry::new_test(&energy, Value::I64(0)).with_unit(Some(30), 0),
+                0
+            ),
+            None
+        );
+        assert_eq!(
+            e.record(
+                &ListEntry::new_test(&[1, 2, 3], Value::I64(10)).with_unit(Some(30), 0),
+                10
+            ),
+            None
+        );
+        let estimate = e
+            .record(
+                &ListEntry::new_test(&energy, Value::I64(1)).with_unit(Some(30), 0),
+                36,
+            )
+            .unwrap();
+        assert_eq!((estimate.sec_index, estimate.power), (36, w(1000)));
+    }
+}
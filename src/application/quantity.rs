@@ -0,0 +1,450 @@
+//! Human-readable quantities such as `1.5 kW`.
+//!
+//! Configuration files often express values (e.g. thresholds) in a human-readable
+//! form. [`parse_quantity`] converts such strings into a [`Quantity`], which uses
+//! the same representation as SML list entries: an integer value, a decimal
+//! scaler and a unit code according to the DLMS-Unit-List. `Quantity` implements
+//! `Display`, which allows writing values in the same format. Neither parsing
+//! nor formatting allocates.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::application::quantity::{parse_quantity, Quantity};
+//! let threshold = parse_quantity("1.5 kW").unwrap();
+//! assert_eq!(threshold, Quantity { value: 15, scaler: 2, unit: Some(27) });
+//! # #[cfg(feature = "alloc")]
+//! assert_eq!(threshold.to_string(), "1500 W");
+//!
+//! let power = Quantity { value: 16_000, scaler: -1, unit: Some(27) };
+//! assert!(power.cmp_value(&threshold).unwrap().is_gt());
+//! ```
+
+use core::{cmp::Ordering, fmt};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::parser::common::{ListEntry, Unit};
+
+/// Units with their symbols according to the DLMS-Unit-List (see IEC 62056-62).
+const UNITS: &[(Unit, &str)] = &[
+    (1, "a"),
+    (2, "mo"),
+    (3, "wk"),
+    (4, "d"),
+    (5, "h"),
+    (6, "min"),
+    (7, "s"),
+    (8, "°"),
+    (9, "°C"),
+    (11, "m"),
+    (12, "m/s"),
+    (13, "m³"),
+    (15, "m³/h"),
+    (17, "m³/d"),
+    (19, "l"),
+    (20, "kg"),
+    (21, "N"),
+    (22, "Nm"),
+    (23, "Pa"),
+    (24, "bar"),
+    (25, "J"),
+    (26, "J/h"),
+    (27, "W"),
+    (28, "VA"),
+    (29, "var"),
+    (30, "Wh"),
+    (31, "VAh"),
+    (32, "varh"),
+    (33, "A"),
+    (34, "C"),
+    (35, "V"),
+    (36, "V/m"),
+    (37, "F"),
+    (38, "Ω"),
+    (40, "Wb"),
+    (41, "T"),
+    (42, "A/m"),
+    (43, "H"),
+    (44, "Hz"),
+    (52, "K"),
+    (56, "%"),
+    (57, "Ah"),
+];
+
+/// SI prefixes accepted by [`parse_quantity`] with their decimal exponents.
+const PREFIXES: &[(char, i8)] = &[('m', -3), ('k', 3), ('M', 6), ('G', 9)];
+
+/// Returns the symbol of a unit code (e.g. `"W"` for `27`).
+///
+/// Returns `None` if the unit code is unknown.
+#[must_use]
+pub fn unit_symbol(unit: Unit) -> Option<&'static str> {
+    UNITS.iter().find(|(u, _)| *u == unit).map(|(_, s)| *s)
+}
+
+/// Returns the unit code of a unit symbol (e.g. `27` for `"W"`).
+///
+/// Returns `None` if the symbol is unknown.
+#[must_use]
+pub fn unit_from_symbol(symbol: &str) -> Option<Unit> {
+    UNITS.iter().find(|(_, s)| *s == symbol).map(|(u, _)| *u)
+}
+
+/// A value with a decimal scaler and an optional unit.
+///
+/// The represented value is `value * 10^scaler`, as for SML list entries.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Quantity {
+    /// the raw value
+    pub value: i64,
+    /// decimal exponent applied to `value`
+    pub scaler: i8,
+    /// unit code according to DLMS-Unit-List (see IEC 62056-62)
+    pub unit: Option<Unit>,
+}
+
+impl Quantity {
+    /// Returns the quantity of a list entry.
+    ///
+    /// Returns `None` if the value isn't an integer that fits into an `i64`.
+    #[must_use]
+    pub fn from_list_entry(entry: &ListEntry<'_>) -> Option<Self> {
+        Some(Quantity {
+            value: entry.value.as_i64()?,
+            scaler: entry.scaler.unwrap_or(0),
+            unit: entry.unit,
+        })
+    }
+
+    /// Compares the values of two quantities, taking their scalers into account.
+    ///
+    /// Returns `None` if the quantities have different units or the comparison overflows.
+    #[must_use]
+    pub fn cmp_value(&self, other: &Quantity) -> Option<Ordering> {
+        if self.unit != other.unit {
+            return None;
+        }
+        let scaler = self.scaler.min(other.scaler);
+        let a = rescale(self.value, self.scaler.abs_diff(scaler))?;
+        let b = rescale(other.value, other.scaler.abs_diff(scaler))?;
+        Some(a.cmp(&b))
+    }
+
+    /// Returns `value * 10^scaler` or `None` if the result doesn't fit into an `i64`.
+    ///
+    /// For negative scalers, the fractional part is truncated (rounded towards zero).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::application::quantity::Quantity;
+    /// let q = |value, scaler| Quantity { value, scaler, unit: None };
+    /// assert_eq!(q(15, 2).checked_scaled(), Some(1500));
+    /// assert_eq!(q(-1234, -2).checked_scaled(), Some(-12));
+    /// assert_eq!(q(1, 19).checked_scaled(), None);
+    /// ```
+    #[must_use]
+    pub fn checked_scaled(&self) -> Option<i64> {
+        if self.value == 0 {
+            Some(0)
+        } else if self.scaler >= 0 {
+            rescale(self.value, self.scaler.unsigned_abs())?
+                .try_into()
+                .ok()
+        } else {
+            // the divisor doesn't fit into an `i128` only if the result is 0 anyway
+            let divisor = 10i128.checked_pow(self.scaler.unsigned_abs().into());
+            Some(divisor.map_or(0, |d| (i128::from(self.value) / d) as i64))
+        }
+    }
+
+    /// Returns `value * 10^scaler`, saturating at `i64::MIN` and `i64::MAX`.
+    ///
+    /// For negative scalers, the fractional part is truncated (rounded towards zero).
+    #[must_use]
+    pub fn saturating_scaled(&self) -> i64 {
+        self.checked_scaled()
+            .unwrap_or(if self.value < 0 { i64::MIN } else { i64::MAX })
+    }
+}
+
+// returns `value * 10^exp` if it fits into an `i128`
+fn rescale(value: i64, exp: u8) -> Option<i128> {
+    10i128.checked_pow(exp.into())?.checked_mul(value.into())
+}
+
+impl fmt::Display for Quantity {
+    /// Formats the quantity as a decimal number followed by the unit symbol (e.g. `1.5 W`).
+    ///
+    /// Unknown units are formatted using their code (e.g. `1.5 [unit 200]`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value < 0 {
+            f.write_str("-")?;
+        }
+        // decimal digits of the absolute value, least significant digit first
+        let mut digits = [0u8; 20];
+        let mut len = 0;
+        let mut rest = self.value.unsigned_abs();
+        loop {
+            digits[len] = b'0' + (rest % 10) as u8;
+            rest /= 10;
+            len += 1;
+            if rest == 0 {
+                break;
+            }
+        }
+        let write_digits = |f: &mut fmt::Formatter<'_>, digits: &[u8]| {
+            digits
+                .iter()
+                .rev()
+                .try_for_each(|d| write!(f, "{}", char::from(*d)))
+        };
+        let write_zeros =
+            |f: &mut fmt::Formatter<'_>, n: usize| (0..n).try_for_each(|_| f.write_str("0"));
+
+        if self.scaler >= 0 {
+            write_digits(f, &digits[..len])?;
+            if self.value != 0 {
+                write_zeros(f, self.scaler.unsigned_abs().into())?;
+            }
+        } else {
+            let num_frac = usize::from(self.scaler.unsigned_abs());
+            // trailing zeros of the fractional part are omitted
+            let num_trailing = digits[..len.min(num_frac)]
+                .iter()
+                .take_while(|d| **d == b'0')
+                .count();
+            if len > num_frac {
+                write_digits(f, &digits[num_frac..len])?;
+            } else {
+                f.write_str("0")?;
+            }
+            if num_trailing < num_frac.min(len) {
+                f.write_str(".")?;
+                write_zeros(f, num_frac.saturating_sub(len))?;
+                write_digits(f, &digits[num_trailing..len.min(num_frac)])?;
+            }
+        }
+        match self.unit.map(|u| (u, unit_symbol(u))) {
+            None => Ok(()),
+            Some((_, Some(symbol))) => write!(f, " {symbol}"),
+            Some((u, None)) => write!(f, " [unit {u}]"),
+        }
+    }
+}
+
+/// Error type returned by [`parse_quantity`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantityParseError {
+    /// The string doesn't start with a decimal number
+    InvalidNumber,
+    /// The unit isn't known
+    UnknownUnit,
+    /// The number has too many digits or the scaler doesn't fit into an `i8`
+    Overflow,
+}
+
+impl fmt::Display for QuantityParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantityParseError::InvalidNumber => {
+                f.write_str("quantity doesn't start with a decimal number")
+            }
+            QuantityParseError::UnknownUnit => f.write_str("unknown unit"),
+            QuantityParseError::Overflow => {
+                f.write_str("number has too many digits to be represented as a quantity")
+            }
+        }
+    }
+}
+
+impl core::error::Error for QuantityParseError {}
+
+/// Parses a human-readable quantity such as `"1.5 kW"`, `"-20 Wh"` or `"230V"`.
+///
+/// The number may contain a sign and a decimal point. It is optionally
+/// followed by a unit symbol (see [`unit_symbol`]), which may be preceded by
+/// one of the SI prefixes `m`, `k`, `M` and `G`. Whitespace between the number
+/// and the unit is optional.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::application::quantity::{parse_quantity, Quantity, QuantityParseError};
+/// assert_eq!(parse_quantity("230V"), Ok(Quantity { value: 230, scaler: 0, unit: Some(35) }));
+/// assert_eq!(parse_quantity("-0.25 MWh"), Ok(Quantity { value: -25, scaler: 4, unit: Some(30) }));
+/// assert_eq!(parse_quantity("42"), Ok(Quantity { value: 42, scaler: 0, unit: None }));
+/// assert_eq!(parse_quantity("1 xW"), Err(QuantityParseError::UnknownUnit));
+/// ```
+pub fn parse_quantity(s: &str) -> Result<Quantity, QuantityParseError> {
+    let s = s.trim();
+    let num_len = s
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '-' | '+')))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(num_len);
+    let (value, frac_digits) = parse_decimal(number)?;
+    let (unit, exp) = parse_unit(unit.trim_start())?;
+    let scaler = i8::try_from(i16::from(exp) - i16::from(frac_digits))
+        .map_err(|_| QuantityParseError::Overflow)?;
+    Ok(Quantity {
+        value,
+        scaler,
+        unit,
+    })
+}
+
+// returns the digits as integer and the number of fractional digits
+fn parse_decimal(s: &str) -> Result<(i64, u8), QuantityParseError> {
+    let (negative, digits) = match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    if int.is_empty() && frac.is_empty()
+        || !int.bytes().chain(frac.bytes()).all(|b| b.is_ascii_digit())
+    {
+        return Err(QuantityParseError::InvalidNumber);
+    }
+    let mut value: i64 = 0;
+    for b in int.bytes().chain(frac.bytes()) {
+        let digit = i64::from(b - b'0');
+        value = value
+            .checked_mul(10)
+            .and_then(|v| {
+                if negative {
+                    v.checked_sub(digit)
+                } else {
+                    v.checked_add(digit)
+                }
+            })
+            .ok_or(QuantityParseError::Overflow)?;
+    }
+    let frac_digits = u8::try_from(frac.len()).map_err(|_| QuantityParseError::Overflow)?;
+    Ok((value, frac_digits))
+}
+
+// returns the unit and the exponent of its prefix
+pub(crate) fn parse_unit(s: &str) -> Result<(Option<Unit>, i8), QuantityParseError> {
+    if s.is_empty() {
+        return Ok((None, 0));
+    }
+    if let Some(unit) = unit_from_symbol(s) {
+        return Ok((Some(unit), 0));
+    }
+    let mut chars = s.chars();
+    let prefix = chars
+        .next()
+        .and_then(|c| PREFIXES.iter().find(|(p, _)| *p == c));
+    match (prefix, unit_from_symbol(chars.as_str())) {
+        (Some((_, exp)), Some(unit)) => Ok((Some(unit), *exp)),
+        _ => Err(QuantityParseError::UnknownUnit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(value: i64, scaler: i8, unit: Option<Unit>) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit,
+        }
+    }
+
+    #[test]
+    fn scaled() {
+        assert_eq!(q(0, 127, None).checked_scaled(), Some(0));
+        assert_eq!(q(1, 18, None).checked_scaled(), Some(10i64.pow(18)));
+        assert_eq!(q(10, 18, None).checked_scaled(), None);
+        assert_eq!(q(i64::MAX, 0, None).checked_scaled(), Some(i64::MAX));
+        assert_eq!(q(i64::MIN, 1, None).checked_scaled(), None);
+        assert_eq!(q(i64::MIN, -1, None).checked_scaled(), Some(i64::MIN / 10));
+        assert_eq!(q(i64::MAX, -18, None).checked_scaled(), Some(9));
+        assert_eq!(q(i64::MAX, -19, None).checked_scaled(), Some(0));
+        assert_eq!(q(i64::MIN, -128, None).checked_scaled(), Some(0));
+        assert_eq!(q(1, 127, None).saturating_scaled(), i64::MAX);
+        assert_eq!(q(-1, 127, None).saturating_scaled(), i64::MIN);
+        assert_eq!(q(0, 127, None).saturating_scaled(), 0);
+        assert_eq!(q(-7, -1, None).saturating_scaled(), 0);
+        assert_eq!(q(123, 1, None).saturating_scaled(), 1230);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_quantity("1.5 kW"), Ok(q(15, 2, Some(27))));
+        assert_eq!(parse_quantity(" 1.5kW "), Ok(q(15, 2, Some(27))));
+        assert_eq!(parse_quantity("+.5 mA"), Ok(q(5, -4, Some(33))));
+        assert_eq!(parse_quantity("3. m³"), Ok(q(3, 0, Some(13))));
+        assert_eq!(parse_quantity("-1.25 °C"), Ok(q(-125, -2, Some(9))));
+        assert_eq!(parse_quantity("5 min"), Ok(q(5, 0, Some(6))));
+        assert_eq!(parse_quantity("5 m"), Ok(q(5, 0, Some(11))));
+        assert_eq!(parse_quantity("5 mm"), Ok(q(5, -3, Some(11))));
+        assert_eq!(parse_quantity("0"), Ok(q(0, 0, None)));
+        assert_eq!(
+            parse_quantity("-9223372036854775808"),
+            Ok(q(i64::MIN, 0, None))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        use QuantityParseError::*;
+        assert_eq!(parse_quantity(""), Err(InvalidNumber));
+        assert_eq!(parse_quantity("kW"), Err(InvalidNumber));
+        assert_eq!(parse_quantity("."), Err(InvalidNumber));
+        assert_eq!(parse_quantity("1.2.3 W"), Err(InvalidNumber));
+        assert_eq!(parse_quantity("1-2 W"), Err(InvalidNumber));
+        assert_eq!(parse_quantity("1 kWatt"), Err(UnknownUnit));
+        assert_eq!(parse_quantity("1 k"), Err(UnknownUnit));
+        assert_eq!(parse_quantity("9223372036854775808"), Err(Overflow));
+        assert_eq!(
+            parse_quantity("0.000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000001"),
+            Err(Overflow)
+        );
+    }
+
+    #[test]
+    fn symbols() {
+        for (unit, symbol) in UNITS {
+            assert_eq!(unit_symbol(*unit), Some(*symbol));
+            assert_eq!(unit_from_symbol(symbol), Some(*unit));
+        }
+        assert_eq!(unit_symbol(255), None);
+    }
+
+    #[test]
+    fn cmp_value() {
+        let kw = q(15, 2, Some(27));
+        assert_eq!(kw.cmp_value(&q(1500, 0, Some(27))), Some(Ordering::Equal));
+        assert_eq!(
+            kw.cmp_value(&q(14_999, -1, Some(27))),
+            Some(Ordering::Greater)
+        );
+        assert_eq!(kw.cmp_value(&q(1500, 0, Some(30))), None);
+        assert_eq!(q(1, 127, None).cmp_value(&q(1, -128, None)), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+        assert_eq!(q(15, 2, Some(27)).to_string(), "1500 W");
+        assert_eq!(q(-125, -2, Some(9)).to_string(), "-1.25 °C");
+        assert_eq!(q(5, -4, Some(33)).to_string(), "0.0005 A");
+        assert_eq!(q(1500, -3, None).to_string(), "1.5");
+        assert_eq!(q(0, 5, Some(200)).to_string(), "0 [unit 200]");
+        assert_eq!(q(i64::MIN, -128, None).to_string().len(), 3 + 128);
+        assert_eq!(q(1000, -2, None).to_string(), "10");
+        assert_eq!(q(1010, -3, None).to_string(), "1.01");
+        for s in ["1500 W", "-1.25 °C", "0.0005 A", "1.5", "230 V"] {
+            assert_eq!(parse_quantity(s).unwrap().to_string(), s);
+        }
+    }
+}
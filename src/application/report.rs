@@ -0,0 +1,291 @@
+//! Compact textual reports for small displays.
+//!
+//! Devices with e-ink or LCD displays usually show a few values in a single line.
+//! [`render_compact`] renders such a line from a template referencing OBIS codes.
+//! It writes into any [`core::fmt::Write`] implementation (e.g. `heapless::String`),
+//! doesn't allocate and only uses integer arithmetic, so the float formatting code
+//! of `core::fmt` isn't linked into the binary.
+//!
+//! # Template syntax
+//!
+//! Text is copied verbatim, except for placeholders enclosed in braces (`{{` and
+//! `}}` produce literal braces):
+//!
+//! - `{t}` - the `SecIndex` of the transmission
+//! - `{OBIS}` - the value of the register, followed by the symbol of its unit (e.g. `312W`)
+//! - `{OBIS|UNIT}` - the value converted to a unit with an optional SI prefix (e.g. `{1-0:1.8.0|kWh}`)
+//! - `{OBIS|UNIT|DECIMALS}` - as above, with a fixed number of decimal places (at most 18, rounded)
+//!
+//! OBIS codes can be given in any format accepted by [`ObisCode`]'s `FromStr`
+//! implementation. Values that aren't present, aren't integers or have a different
+//! unit are rendered as `-`.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::report::render_compact, parser::common::{ListEntry, Value}};
+//! let entry = |obj_name, unit, scaler, value| ListEntry {
+//!     obj_name, status: None, val_time: None, unit: Some(unit), scaler: Some(scaler), value, value_signature: None,
+//! };
+//! let entries = [
+//!     entry(&[1, 0, 16, 7, 0, 255], 27, 0, Value::I32(312)),
+//!     entry(&[1, 0, 1, 8, 0, 255], 30, -1, Value::U64(54_302_170)),
+//! ];
+//! # #[cfg(feature = "alloc")] {
+//! let mut out = String::new();
+//! render_compact("P={1-0:16.7.0} E={1-0:1.8.0|kWh|1} t={t}s", &entries, Some(12345), &mut out).unwrap();
+//! assert_eq!(out, "P=312W E=5430.2kWh t=12345s");
+//! # }
+//! ```
+
+use core::fmt::{self, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::quantity::{parse_unit, unit_symbol, Quantity},
+    parser::{common::ListEntry, ObisCode},
+};
+
+/// Error returned by [`render_compact`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderError {
+    /// The template contains an invalid placeholder starting at the given byte offset
+    InvalidTemplate(usize),
+    /// Writing the output failed (e.g. because the output buffer is full)
+    Write,
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::InvalidTemplate(offset) => {
+                write!(f, "invalid placeholder in template at byte offset {offset}")
+            }
+            RenderError::Write => f.write_str("error while writing the report"),
+        }
+    }
+}
+
+impl core::error::Error for RenderError {}
+
+impl From<fmt::Error> for RenderError {
+    fn from(_: fmt::Error) -> Self {
+        RenderError::Write
+    }
+}
+
+/// Renders `template` using the values of `entries` and writes the result to `out`.
+///
+/// `sec_index` is used for the `{t}` placeholder (e.g. the `act_sensor_time` of the
+/// `GetListResponse`). If an OBIS code occurs several times, the first entry is used.
+///
+/// See the [module-level documentation](self) for the template syntax.
+pub fn render_compact<W: Write>(
+    template: &str,
+    entries: &[ListEntry<'_>],
+    sec_index: Option<u32>,
+    out: &mut W,
+) -> Result<(), RenderError> {
+    let mut rest = template;
+    while let Some(idx) = rest.find(['{', '}']) {
+        out.write_str(&rest[..idx])?;
+        let offset = template.len() - rest.len() + idx;
+        let (brace, after) = rest[idx..].split_at(1);
+        if after.starts_with(brace) {
+            // escaped brace
+            out.write_str(brace)?;
+            rest = &after[1..];
+            continue;
+        }
+        if brace == "}" {
+            return Err(RenderError::InvalidTemplate(offset));
+        }
+        let (placeholder, after) = after
+            .split_once('}')
+            .ok_or(RenderError::InvalidTemplate(offset))?;
+        render_placeholder(placeholder, entries, sec_index, out)
+            .ok_or(RenderError::InvalidTemplate(offset))??;
+        rest = after;
+    }
+    out.write_str(rest)?;
+    Ok(())
+}
+
+// returns `None` if the placeholder is invalid
+fn render_placeholder<W: Write>(
+    placeholder: &str,
+    entries: &[ListEntry<'_>],
+    sec_index: Option<u32>,
+    out: &mut W,
+) -> Option<fmt::Result> {
+    if placeholder == "t" {
+        return Some(match sec_index {
+            Some(t) => write!(out, "{t}"),
+            None => out.write_str("-"),
+        });
+    }
+    let mut parts = placeholder.split('|');
+    let obis_code: ObisCode = parts.next()?.parse().ok()?;
+    let target = match parts.next() {
+        Some(symbol) => Some((symbol, parse_unit(symbol).ok()?)),
+        None => None,
+    };
+    let decimals = match parts.next() {
+        Some(d) if d.bytes().all(|b| b.is_ascii_digit()) => Some(d.parse::<u8>().ok()?),
+        Some(_) => return None,
+        None => None,
+    };
+    if parts.next().is_some() || decimals.is_some_and(|d| d > 18) {
+        return None;
+    }
+
+    let value = entries
+        .iter()
+        .find(|e| e.obis_code() == Some(obis_code))
+        .and_then(Quantity::from_list_entry);
+    Some(match (value, target) {
+        (Some(q), None) => {
+            let value = Quantity { unit: None, ..q };
+            let symbol = q.unit.and_then(unit_symbol).unwrap_or("");
+            write_value(out, value, decimals).and_then(|()| out.write_str(symbol))
+        }
+        (Some(q), Some((symbol, (unit, exp))))
+            if unit.is_none() || q.unit.is_none() || unit == q.unit =>
+        {
+            match i8::try_from(i16::from(q.scaler) - i16::from(exp)) {
+                Ok(scaler) => {
+                    let value = Quantity {
+                        value: q.value,
+                        scaler,
+                        unit: None,
+                    };
+                    write_value(out, value, decimals).and_then(|()| out.write_str(symbol))
+                }
+                Err(_) => out.write_str("-"),
+            }
+        }
+        _ => out.write_str("-"),
+    })
+}
+
+// writes the value of `q` (without unit), rounded to `decimals` decimal places if set
+fn write_value<W: Write>(out: &mut W, q: Quantity, decimals: Option<u8>) -> fmt::Result {
+    let Some(decimals) = decimals else {
+        return write!(out, "{q}");
+    };
+    let Some(n) = round_to(q, decimals) else {
+        return out.write_str("-");
+    };
+    if n < 0 {
+        out.write_str("-")?;
+    }
+    let divisor = 10u128.pow(decimals.into());
+    let (int, frac) = (n.unsigned_abs() / divisor, n.unsigned_abs() % divisor);
+    write!(out, "{int}")?;
+    if decimals > 0 {
+        write!(out, ".{frac:0width$}", width = usize::from(decimals))?;
+    }
+    Ok(())
+}
+
+// returns `q` in units of `10^-decimals`, rounded half away from zero
+fn round_to(q: Quantity, decimals: u8) -> Option<i128> {
+    let shift = i16::from(q.scaler) + i16::from(decimals);
+    let value = i128::from(q.value);
+    if shift >= 0 {
+        return 10i128
+            .checked_pow(shift.unsigned_abs().into())?
+            .checked_mul(value);
+    }
+    let Some(divisor) = 10i128.checked_pow(shift.unsigned_abs().into()) else {
+        return Some(0);
+    };
+    let (quotient, remainder) = (value / divisor, value % divisor);
+    Some(if remainder.abs() * 2 >= divisor {
+        quotient + value.signum()
+    } else {
+        quotient
+    })
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::parser::common::Value;
+    use alloc::string::String;
+
+    fn render(template: &str, entries: &[ListEntry<'_>]) -> Result<String, RenderError> {
+        let mut out = String::new();
+        render_compact(template, entries, None, &mut out).map(|()| out)
+    }
+
+    #[test]
+    fn values() {
+        let power = [1, 0, 16, 7, 0, 255];
+        let entries = [
+            ListEntry::new_test(&power, Value::I64(-12345)).with_unit(Some(27), -1),
+            ListEntry::new_test(&power, Value::I64(1)).with_unit(Some(27), 0),
+            ListEntry::new_test(&[1, 0, 1, 8, 0, 255], Value::I64(5)).with_unit(Some(30), 3),
+            ListEntry::new_test(&[1, 0, 96, 1, 0, 255], Value::I64(42)).with_unit(None, 0),
+        ];
+        let render = |template| render(template, &entries).unwrap();
+        assert_eq!(render("{1-0:16.7.0}"), "-1234.5W");
+        assert_eq!(render("{1-0:16.7.0|W|0}"), "-1235W");
+        assert_eq!(render("{1-0:16.7.0|kW}"), "-1.2345kW");
+        assert_eq!(render("{1-0:16.7.0|kW|2}"), "-1.23kW");
+        assert_eq!(render("{1-0:16.7.0|mW|1}"), "-1234500.0mW");
+        assert_eq!(render("{1-0:1.8.0}"), "5000Wh");
+        assert_eq!(render("{1-0:1.8.0|MWh|3}"), "0.005MWh");
+        assert_eq!(render("{1-0:1.8.0|GWh|2}"), "0.00GWh");
+        assert_eq!(render("{1-0:96.1.0}"), "42");
+        // values without unit can be labelled freely
+        assert_eq!(render("{1-0:96.1.0|%}"), "42%");
+        // missing values and mismatching units
+        assert_eq!(render("{1-0:2.8.0} {1-0:1.8.0|W}"), "- -");
+    }
+
+    #[test]
+    fn template() {
+        let mut out = String::new();
+        render_compact("{{t}}={t} {t}", &[], Some(7), &mut out).unwrap();
+        assert_eq!(out, "{t}=7 7");
+        assert_eq!(render("{t}", &[]), Ok("-".into()));
+        for (template, offset) in [
+            ("ab{", 2),
+            ("a}b", 1),
+            ("{}", 0),
+            ("x{1-0:1.8.0|xW}", 1),
+            ("{1-0:1.8.0|W|a}", 0),
+            ("{1-0:1.8.0|W|19}", 0),
+            ("{1-0:1.8.0|W|1|}", 0),
+        ] {
+            assert_eq!(
+                render(template, &[]),
+                Err(RenderError::InvalidTemplate(offset)),
+                "{template}"
+            );
+        }
+    }
+
+    #[test]
+    fn full_buffer() {
+        struct Limited(usize);
+        impl Write for Limited {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                self.0 = self.0.checked_sub(s.len()).ok_or(fmt::Error)?;
+                Ok(())
+            }
+        }
+        assert_eq!(
+            render_compact("t={t}", &[], Some(1234), &mut Limited(5)),
+            Err(RenderError::Write)
+        );
+        assert_eq!(
+            render_compact("t={t}", &[], Some(1234), &mut Limited(6)),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,256 @@
+//! Aligning irregular values to a fixed time grid.
+//!
+//! Meters send transmissions every few seconds, but not at exact intervals.
+//! Time-series databases (e.g. round-robin databases) usually expect values at
+//! fixed points in time. [`Resampler`] converts the values of an OBIS code into
+//! values at each multiple of a configured interval. It doesn't allocate: the
+//! grid values between two observations are computed lazily by the returned
+//! [`Resampled`] iterator.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::resample::{Interpolation, Resampler}, parser::ObisCode};
+//! let mut resampler = Resampler::new(ObisCode::ACTIVE_POWER, 10, Interpolation::Linear);
+//! assert!(resampler.push(7, 100).eq([]));
+//! assert!(resampler.push(12, 200).eq([(10, 160)]));
+//! assert!(resampler.push(33, 410).eq([(20, 280), (30, 380)]));
+//!
+//! let mut resampler = Resampler::new(ObisCode::ACTIVE_POWER, 10, Interpolation::Previous);
+//! resampler.push(7, 100);
+//! assert!(resampler.push(12, 200).eq([(10, 100)]));
+//! assert!(resampler.push(33, 410).eq([(20, 200), (30, 200)]));
+//! ```
+
+use crate::parser::{common::ListEntry, ObisCode};
+
+/// Defines how [`Resampler`] computes the values between two observations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// Use the last observed value (suitable for counters such as energy registers)
+    Previous,
+    /// Interpolate linearly between the observations (suitable for e.g. power values)
+    ///
+    /// Values are rounded towards the earlier observation.
+    Linear,
+}
+
+/// Resamples the values of an OBIS code to multiples of a fixed interval.
+///
+/// A grid value is returned once the first observation after it is known, so the
+/// grid value at time `t` is returned when pushing an observation newer than `t`.
+/// No values are returned for grid points before the first observation.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    obis_code: ObisCode,
+    interval: u32,
+    interpolation: Interpolation,
+    max_gap: Option<u32>,
+    last: Option<(u32, i64)>,
+}
+
+impl Resampler {
+    /// Constructs a resampler aligning the values of `obis_code` to multiples of `interval`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    #[must_use]
+    pub const fn new(obis_code: ObisCode, interval: u32, interpolation: Interpolation) -> Self {
+        assert!(interval > 0, "the interval must not be zero");
+        Resampler {
+            obis_code,
+            interval,
+            interpolation,
+            max_gap: None,
+            last: None,
+        }
+    }
+
+    /// Sets the largest gap between two observations that is filled with grid values.
+    ///
+    /// Grid points within longer gaps (e.g. while the meter was disconnected) are
+    /// skipped. By default, all gaps are filled.
+    #[must_use]
+    pub const fn with_max_gap(mut self, max_gap: u32) -> Self {
+        self.max_gap = Some(max_gap);
+        self
+    }
+
+    /// Returns the OBIS code resampled by this resampler.
+    #[must_use]
+    pub const fn obis_code(&self) -> ObisCode {
+        self.obis_code
+    }
+
+    /// Records the value of `entry` if it belongs to the configured OBIS code.
+    ///
+    /// The value is recorded at [`ListEntry::sec_index_or`]`(sec_index)`, i.e. at its `val_time`
+    /// if present.
+    /// Returns the grid values that are completed by this value. Values that aren't
+    /// integers or don't fit into an `i64` are ignored.
+    pub fn record(&mut self, entry: &ListEntry<'_>, sec_index: u32) -> Resampled {
+        if entry.obis_code() != Some(self.obis_code) {
+            return Resampled::empty();
+        }
+        let Some(value) = entry.value.as_i64() else {
+            return Resampled::empty();
+        };
+        let time = entry.sec_index_or(sec_index);
+        self.push(time, value)
+    }
+
+    /// Records an observation and returns the grid values that are completed by it.
+    ///
+    /// Observations that aren't newer than the previous one are ignored. If `time`
+    /// is smaller than the time of the previous observation (e.g. after a meter
+    /// reboot), resampling starts over.
+    pub fn push(&mut self, time: u32, value: i64) -> Resampled {
+        let Some(last) = self.last else {
+            self.last = Some((time, value));
+            return Resampled::empty();
+        };
+        if time == last.0 {
+            return Resampled::empty();
+        }
+        self.last = Some((time, value));
+        if time < last.0 || self.max_gap.is_some_and(|max| time - last.0 > max) {
+            return Resampled::empty();
+        }
+        let interval = u64::from(self.interval);
+        Resampled {
+            from: last,
+            to: (time, value),
+            next: u64::from(last.0).div_ceil(interval) * interval,
+            interval: self.interval,
+            interpolation: self.interpolation,
+        }
+    }
+
+    /// Forgets the previous observation.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Iterator over the grid values between two observations.
+///
+/// Yields tuples of the grid time and the value at that time. Returned by
+/// [`Resampler::push`] and [`Resampler::record`].
+#[derive(Debug, Clone)]
+pub struct Resampled {
+    from: (u32, i64),
+    to: (u32, i64),
+    next: u64,
+    interval: u32,
+    interpolation: Interpolation,
+}
+
+impl Resampled {
+    const fn empty() -> Self {
+        Resampled {
+            from: (0, 0),
+            to: (0, 0),
+            next: 0,
+            interval: 1,
+            interpolation: Interpolation::Previous,
+        }
+    }
+}
+
+impl Iterator for Resampled {
+    type Item = (u32, i64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= u64::from(self.to.0) {
+            return None;
+        }
+        // `next` is smaller than `to.0` and therefore fits into an `u32`
+        let time = self.next as u32;
+        self.next += u64::from(self.interval);
+        let ((t0, v0), (t1, v1)) = (self.from, self.to);
+        let value = match self.interpolation {
+            Interpolation::Previous => v0,
+            Interpolation::Linear => {
+                let delta =
+                    (i128::from(v1) - i128::from(v0)) * i128::from(time - t0) / i128::from(t1 - t0);
+                // the result lies between `v0` and `v1`
+                (i128::from(v0) + delta) as i64
+            }
+        };
+        Some((time, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::common::{Time, Value};
+
+    const POWER: ObisCode = ObisCode::ACTIVE_POWER;
+
+    #[test]
+    fn grid() {
+        let mut r = Resampler::new(POWER, 5, Interpolation::Previous);
+        // observations on the grid are returned once the next observation is known
+        assert!(r.push(10, 1).eq([]));
+        assert!(r.push(11, 2).eq([(10, 1)]));
+        assert!(r.push(12, 3).eq([]));
+        assert!(r.push(15, 4).eq([]));
+        assert!(r.push(15, 5).eq([]));
+        assert!(r.push(26, 6).eq([(15, 4), (20, 4), (25, 4)]));
+        // time went backwards
+        assert!(r.push(3, 7).eq([]));
+        assert!(r.push(6, 8).eq([(5, 7)]));
+        r.reset();
+        assert!(r.push(20, 9).eq([]));
+    }
+
+    #[test]
+    fn linear() {
+        let mut r = Resampler::new(POWER, 1, Interpolation::Linear);
+        r.push(0, 0);
+        assert!(r.push(4, -10).eq([(0, 0), (1, -2), (2, -5), (3, -7)]));
+        r.push(5, i64::MIN);
+        assert!(r.push(7, i64::MAX).eq([(5, i64::MIN), (6, -1)]));
+        // the largest possible times don't overflow
+        let mut r = Resampler::new(POWER, u32::MAX, Interpolation::Linear);
+        r.push(1, 1);
+        assert!(r.push(u32::MAX, 2).eq([]));
+    }
+
+    #[test]
+    fn max_gap() {
+        let mut r = Resampler::new(POWER, 10, Interpolation::Previous).with_max_gap(30);
+        r.push(5, 1);
+        assert!(r.push(35, 2).eq([(10, 1), (20, 1), (30, 1)]));
+        assert!(r.push(66, 3).eq([]));
+        assert!(r.push(71, 4).eq([(70, 3)]));
+    }
+
+    #[test]
+    fn record() {
+        let mut r = Resampler::new(POWER, 10, Interpolation::Previous);
+        assert!(r
+            .record(&ListEntry::new_test(&POWER.0, Value::I32(5)), 8)
+            .eq([]));
+        // other OBIS codes and non-integer values are ignored
+        let other = ObisCode::ENERGY_IMPORT_TOTAL;
+        assert!(r
+            .record(&ListEntry::new_test(&other.0, Value::I32(6)), 9)
+            .eq([]));
+        assert!(r
+            .record(&ListEntry::new_test(&POWER.0, Value::Bool(true)), 9)
+            .eq([]));
+        // `val_time` takes precedence
+        let val_time = Some(Time::SecIndex(21));
+        assert!(r
+            .record(
+                &ListEntry::new_test(&POWER.0, Value::I32(7)).with_val_time(val_time),
+                15
+            )
+            .eq([(10, 5), (20, 5)]));
+    }
+}
@@ -0,0 +1,241 @@
+//! Validating transmissions against an expected set of OBIS codes.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+//!
+//! A [`Schema`] declares which OBIS codes a meter is expected to send and which
+//! units the values are expected to have. [`Schema::validate`] checks an SML File
+//! against the schema and reports missing and unexpected entries as well as
+//! entries whose unit differs from the expected one. This helps to detect when a
+//! firmware update changes the data emitted by a meter.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::schema::{Schema, SchemaEntry}, parser::{complete::parse, ObisCode}};
+//! const SCHEMA: Schema = Schema::new(&[
+//!     // energy in Wh
+//!     SchemaEntry::required(ObisCode::new(1, 0, 1, 8, 0, 255)).with_unit(30),
+//!     // power in W
+//!     SchemaEntry::optional(ObisCode::new(1, 0, 16, 7, 0, 255)).with_unit(27),
+//! ])
+//! .allow_extra(true);
+//!
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../sample.bin"));
+//! let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+//! let report = SCHEMA.validate(&file);
+//! assert!(report.is_ok(), "{report:?}");
+//! ```
+
+use alloc::vec::Vec;
+
+use crate::parser::{common::Unit, complete::File, ObisCode};
+
+/// An OBIS code expected by a [`Schema`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaEntry {
+    /// the expected OBIS code
+    pub obis_code: ObisCode,
+    /// whether a transmission without this OBIS code is invalid
+    pub required: bool,
+    /// the expected unit or `None` if the unit isn't checked
+    pub unit: Option<Unit>,
+}
+
+impl SchemaEntry {
+    /// Constructs an entry that has to be contained in each transmission.
+    #[must_use]
+    pub const fn required(obis_code: ObisCode) -> Self {
+        SchemaEntry {
+            obis_code,
+            required: true,
+            unit: None,
+        }
+    }
+
+    /// Constructs an entry that may be omitted.
+    #[must_use]
+    pub const fn optional(obis_code: ObisCode) -> Self {
+        SchemaEntry {
+            obis_code,
+            required: false,
+            unit: None,
+        }
+    }
+
+    /// Sets the expected unit.
+    #[must_use]
+    pub const fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+}
+
+/// Expected OBIS codes of the transmissions of a meter.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schema<'s> {
+    entries: &'s [SchemaEntry],
+    allow_extra: bool,
+}
+
+impl<'s> Schema<'s> {
+    /// Constructs a schema from the expected entries.
+    ///
+    /// By default, OBIS codes not contained in `entries` are reported as extra entries.
+    #[must_use]
+    pub const fn new(entries: &'s [SchemaEntry]) -> Self {
+        Schema {
+            entries,
+            allow_extra: false,
+        }
+    }
+
+    /// Doesn't report OBIS codes that aren't part of the schema if set to `true`.
+    #[must_use]
+    pub const fn allow_extra(mut self, allow_extra: bool) -> Self {
+        self.allow_extra = allow_extra;
+        self
+    }
+
+    /// Returns the expected entries.
+    #[must_use]
+    pub const fn entries(&self) -> &'s [SchemaEntry] {
+        self.entries
+    }
+
+    /// Validates the list entries of an SML File against the schema.
+    ///
+    /// Entries without a valid OBIS code are ignored. The results are ordered as the
+    /// entries appear in the schema (missing entries) or in the file (all others).
+    #[must_use]
+    pub fn validate(&self, file: &File<'_>) -> SchemaReport {
+        let mut report = SchemaReport::default();
+        let codes: Vec<_> = file
+            .list_entries()
+            .filter_map(|(_, e)| Some((e.obis_code()?, e.unit)))
+            .collect();
+
+        for expected in self.entries {
+            if expected.required && !codes.iter().any(|(c, _)| *c == expected.obis_code) {
+                report.missing.push(expected.obis_code);
+            }
+        }
+        for &(obis_code, actual) in &codes {
+            match self.entries.iter().find(|e| e.obis_code == obis_code) {
+                None if !self.allow_extra => report.extra.push(obis_code),
+                None => {}
+                Some(SchemaEntry {
+                    unit: Some(expected),
+                    ..
+                }) if actual != Some(*expected) => {
+                    report.unit_mismatches.push(UnitMismatch {
+                        obis_code,
+                        expected: *expected,
+                        actual,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        report
+    }
+}
+
+/// Result of validating an SML File against a [`Schema`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SchemaReport {
+    /// required OBIS codes not contained in the file
+    pub missing: Vec<ObisCode>,
+    /// OBIS codes contained in the file but not in the schema
+    ///
+    /// Always empty if the schema allows extra entries.
+    pub extra: Vec<ObisCode>,
+    /// entries whose unit differs from the expected unit
+    pub unit_mismatches: Vec<UnitMismatch>,
+}
+
+impl SchemaReport {
+    /// Returns `true` if the file matches the schema.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.unit_mismatches.is_empty()
+    }
+}
+
+/// An entry whose unit differs from the unit expected by the schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitMismatch {
+    /// OBIS code of the entry
+    pub obis_code: ObisCode,
+    /// unit expected by the schema
+    pub expected: Unit,
+    /// unit contained in the file
+    pub actual: Option<Unit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::simulator::Meter, parser::complete::parse, transport::decode};
+
+    const ENERGY: ObisCode = ObisCode::new(1, 0, 1, 8, 0, 255);
+    const POWER: ObisCode = ObisCode::new(1, 0, 16, 7, 0, 255);
+    const VOLTAGE: ObisCode = ObisCode::new(1, 0, 32, 7, 0, 255);
+
+    fn validate(schema: Schema<'_>, meter: Meter) -> SchemaReport {
+        let mut meter = meter;
+        let decoded = decode(meter.next_frame(0));
+        let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+        schema.validate(&file)
+    }
+
+    fn meter() -> Meter {
+        Meter::new(b"meter")
+            .register(ENERGY, Some(30), 0, |_| 1)
+            .register(POWER, None, 0, |_| 2)
+            .register(ObisCode::new(1, 0, 96, 1, 0, 255), None, 0, |_| 3)
+    }
+
+    #[test]
+    fn valid() {
+        let entries = [
+            SchemaEntry::required(ENERGY).with_unit(30),
+            SchemaEntry::required(POWER),
+            SchemaEntry::optional(VOLTAGE).with_unit(35),
+        ];
+        let report = validate(Schema::new(&entries).allow_extra(true), meter());
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn invalid() {
+        let entries = [
+            SchemaEntry::required(ENERGY).with_unit(31),
+            SchemaEntry::optional(POWER).with_unit(27),
+            SchemaEntry::required(VOLTAGE),
+        ];
+        let report = validate(Schema::new(&entries), meter());
+        assert_eq!(
+            report,
+            SchemaReport {
+                missing: [VOLTAGE].into(),
+                extra: [ObisCode::new(1, 0, 96, 1, 0, 255)].into(),
+                unit_mismatches: [
+                    UnitMismatch {
+                        obis_code: ENERGY,
+                        expected: 31,
+                        actual: Some(30),
+                    },
+                    UnitMismatch {
+                        obis_code: POWER,
+                        expected: 27,
+                        actual: None,
+                    },
+                ]
+                .into(),
+            }
+        );
+        assert!(!report.is_ok());
+    }
+}
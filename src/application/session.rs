@@ -0,0 +1,329 @@
+//! Client side of an SML dialog with a meter or gateway.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+//!
+//! Most meters push their data periodically. Some registers, however, are only
+//! sent on request, often after authenticating with a PIN. A [`Session`] sends an
+//! `OpenRequest`, one `GetListRequest` per requested list and a `CloseRequest` in
+//! a single transmission and then waits for the response whose `req_file_id`
+//! matches the request. Transmissions that don't belong to the request (e.g. data
+//! pushed by the meter in the meantime) are skipped.
+//!
+//! The session is half-duplex: it writes a request and then reads from the byte
+//! source until the response arrives. The byte source should therefore block
+//! until data is available (or time out with an error).
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{session::{Request, Session}, simulator::Meter}, parser::{complete::MessageBody, ObisCode}};
+//! # use sml_rs::util::{CustomByteSource, CustomReadError};
+//! // a simulated meter stands in for the optical interface here
+//! let mut meter = Meter::new(b"meter").register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), 0, |_| 42);
+//! let response = meter.next_frame(0);
+//! let mut bytes = response.iter();
+//! let source = CustomByteSource::new(|| bytes.next().copied().ok_or(CustomReadError::<()>::Eof));
+//! let sink = |bytes: &[u8]| -> Result<(), ()> {
+//!     // write `bytes` to the optical interface
+//!     Ok(())
+//! };
+//!
+//! let mut session = Session::new(source, sink);
+//! let file = session
+//!     .request(&Request {
+//!         password: Some(b"1234"),
+//!         ..Request::default()
+//!     })
+//!     .unwrap();
+//! assert!(matches!(file.messages[1].message_body, MessageBody::GetListResponse(_)));
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    parser::complete::{parse, File, MessageBody},
+    transport::{encode, Decoder},
+    util::{ByteSource, ByteSourceErr, ErrKind, VecBuf},
+    writer::{write_message, NONE},
+};
+
+/// Parameters of a request sent by a [`Session`].
+///
+/// Fields set to `None` are omitted from the request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Request<'a> {
+    /// identification of the server (i.e. the meter)
+    pub server_id: Option<&'a [u8]>,
+    /// user name used for authentication
+    pub username: Option<&'a [u8]>,
+    /// password (PIN) used for authentication
+    pub password: Option<&'a [u8]>,
+    /// names of the requested lists
+    ///
+    /// If empty, a single `GetListRequest` without list name is sent, which
+    /// requests the default list of the server.
+    pub list_names: &'a [&'a [u8]],
+}
+
+/// Error returned by [`Session::request`]
+#[derive(Debug, PartialEq)]
+pub enum SessionError<ReadErr, WriteErr> {
+    /// Error while writing the request
+    Write(WriteErr),
+    /// Error while reading from the byte source
+    Read(ReadErr),
+    /// No matching response within the maximum number of transmissions
+    NoResponse,
+}
+
+impl<ReadErr, WriteErr> fmt::Display for SessionError<ReadErr, WriteErr>
+where
+    ReadErr: fmt::Debug,
+    WriteErr: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Write(e) => write!(f, "error while writing the request: {e:?}"),
+            SessionError::Read(e) => write!(f, "error while reading: {e:?}"),
+            SessionError::NoResponse => f.write_str("no response to the request"),
+        }
+    }
+}
+
+impl<ReadErr, WriteErr> core::error::Error for SessionError<ReadErr, WriteErr>
+where
+    ReadErr: fmt::Debug,
+    WriteErr: fmt::Debug,
+{
+}
+
+/// Client side of an SML dialog.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct Session<R, W> {
+    reader: R,
+    write: W,
+    decoder: Decoder<VecBuf>,
+    client_id: Vec<u8>,
+    file_id: u32,
+    transaction_id: u32,
+    max_transmissions: usize,
+}
+
+impl<R, W, E> Session<R, W>
+where
+    R: ByteSource,
+    W: FnMut(&[u8]) -> Result<(), E>,
+{
+    /// Constructs a session reading responses from `reader` and writing requests using `write`.
+    ///
+    /// `write` is called once per request with the encoded transmission.
+    pub fn new(reader: R, write: W) -> Self {
+        Session {
+            reader,
+            write,
+            decoder: Decoder::new(),
+            client_id: b"sml-rs".to_vec(),
+            file_id: 0,
+            transaction_id: 0,
+            max_transmissions: 16,
+        }
+    }
+
+    /// Sets the client id sent with each request (default: `b"sml-rs"`).
+    #[must_use]
+    pub fn with_client_id(mut self, client_id: &[u8]) -> Self {
+        self.client_id = client_id.to_vec();
+        self
+    }
+
+    /// Sets how many transmissions are read while waiting for a response (default: 16).
+    #[must_use]
+    pub fn with_max_transmissions(mut self, max_transmissions: usize) -> Self {
+        self.max_transmissions = max_transmissions;
+        self
+    }
+
+    /// Consumes the session and returns the byte source and the sink.
+    pub fn into_parts(self) -> (R, W) {
+        (self.reader, self.write)
+    }
+
+    /// Sends a request and waits for the matching response.
+    ///
+    /// Each request uses a new `req_file_id`. Transmissions whose `OpenResponse`
+    /// carries a different `req_file_id` as well as transmissions that can't be
+    /// decoded or parsed are skipped.
+    pub fn request(
+        &mut self,
+        request: &Request<'_>,
+    ) -> Result<File<'_>, SessionError<R::ReadError, E>> {
+        self.file_id = self.file_id.wrapping_add(1);
+        let file_id = self.file_id.to_be_bytes();
+        let transmission = self.encode_request(request, &file_id);
+        (self.write)(&transmission).map_err(SessionError::Write)?;
+
+        for _ in 0..self.max_transmissions {
+            if self.read_transmission()? && self.is_response(&file_id) {
+                let bytes = self.decoder.completed_message();
+                // `is_response` already parsed the transmission successfully
+                return parse(bytes).map_err(|_| SessionError::NoResponse);
+            }
+        }
+        Err(SessionError::NoResponse)
+    }
+
+    fn encode_request(&mut self, request: &Request<'_>, file_id: &[u8]) -> Vec<u8> {
+        let client_id = &self.client_id;
+        let mut file = Vec::new();
+        // OpenRequest
+        write_message(&mut file, &mut self.transaction_id, 0x0100, |w| {
+            w.list(7);
+            w.push(NONE); // codepage
+            w.octet_string(client_id);
+            w.octet_string(file_id); // req_file_id
+            w.optional_octet_string(request.server_id);
+            w.optional_octet_string(request.username);
+            w.optional_octet_string(request.password);
+            w.push(NONE); // sml_version
+        });
+        // GetListRequest
+        let mut list_names: Vec<_> = request.list_names.iter().map(|name| Some(*name)).collect();
+        if list_names.is_empty() {
+            list_names.push(None);
+        }
+        for list_name in list_names {
+            write_message(&mut file, &mut self.transaction_id, 0x0700, |w| {
+                w.list(5);
+                w.octet_string(client_id);
+                w.optional_octet_string(request.server_id);
+                w.optional_octet_string(request.username);
+                w.optional_octet_string(request.password);
+                w.optional_octet_string(list_name);
+            });
+        }
+        // CloseRequest
+        write_message(&mut file, &mut self.transaction_id, 0x0200, |w| {
+            w.list(1);
+            w.push(NONE); // global_signature
+        });
+
+        // a `Vec` buffer can only fail if allocation fails
+        encode::<VecBuf>(file).unwrap_or_default()
+    }
+
+    // reads until a transmission is complete (`true`) or couldn't be decoded (`false`)
+    fn read_transmission(&mut self) -> Result<bool, SessionError<R::ReadError, E>> {
+        loop {
+            match self.reader.read_byte() {
+                Ok(b) => match self.decoder._push_byte(b) {
+                    Ok(false) => continue,
+                    Ok(true) => return Ok(true),
+                    Err(_) => return Ok(false),
+                },
+                Err(e) => {
+                    if !matches!(e.kind(), ErrKind::WouldBlock) {
+                        self.decoder.reset();
+                    }
+                    return Err(SessionError::Read(e));
+                }
+            }
+        }
+    }
+
+    fn is_response(&self, file_id: &[u8]) -> bool {
+        let Ok(file) = parse(self.decoder.completed_message()) else {
+            return false;
+        };
+        matches!(
+            file.messages.first().map(|m| &m.message_body),
+            Some(MessageBody::OpenResponse(open)) if open.req_file_id == file_id
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        application::simulator::Meter,
+        parser::ObisCode,
+        transport::decode,
+        util::{Eof, SliceByteSource},
+    };
+    use core::convert::Infallible;
+
+    fn meter() -> Meter {
+        Meter::new(b"meter").register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), 0, |s| s.into())
+    }
+
+    #[test]
+    fn request() {
+        let mut meter = meter();
+        let first = meter.next_frame(1);
+        let second = meter.next_frame(2);
+        // the second frame arrives first and is skipped, followed by garbage
+        let mut input = second.clone();
+        input.extend_from_slice(&[0x12, 0x34, 0x56]);
+        input.extend_from_slice(&first);
+
+        let mut sent = Vec::new();
+        let mut session = Session::new(SliceByteSource::new(&input), |bytes: &[u8]| {
+            sent.push(bytes.to_vec());
+            Ok::<_, Infallible>(())
+        });
+        let request = Request {
+            server_id: Some(b"meter"),
+            password: Some(b"1234"),
+            list_names: &[b"a", b"b"],
+            ..Request::default()
+        };
+        let file = session.request(&request).unwrap();
+        assert_eq!(file, parse(&decode(&first)[0].clone().unwrap()).unwrap());
+        // the second request doesn't get a response
+        assert_eq!(
+            session.request(&Request::default()).map(|_| ()),
+            Err(SessionError::Read(Eof))
+        );
+        drop(session);
+
+        assert_eq!(sent.len(), 2);
+        let decoded = decode(&sent[0]);
+        assert_eq!(decoded.len(), 1);
+        let bytes = decoded[0].as_ref().unwrap();
+        let count = |tag: [u8; 3]| bytes.windows(3).filter(|w| *w == tag).count();
+        assert_eq!(count([0x63, 0x01, 0x00]), 1);
+        assert_eq!(count([0x63, 0x07, 0x00]), 2);
+        assert_eq!(count([0x63, 0x02, 0x00]), 1);
+        assert!(bytes
+            .windows(5)
+            .any(|w| w == [0x05, b'1', b'2', b'3', b'4']));
+        assert!(bytes.windows(5).any(|w| w == [0x05, 0, 0, 0, 1]));
+        let decoded = decode(&sent[1]);
+        assert!(decoded[0]
+            .as_ref()
+            .unwrap()
+            .windows(5)
+            .any(|w| w == [0x05, 0, 0, 0, 2]));
+    }
+
+    #[test]
+    fn no_response() {
+        let mut meter = meter();
+        let _ = meter.next_frame(1);
+        let input = meter.next_frame(2).repeat(2);
+        let mut session = Session::new(SliceByteSource::new(&input), |_: &[u8]| Ok::<_, ()>(()))
+            .with_max_transmissions(2);
+        assert_eq!(
+            session.request(&Request::default()).map(|_| ()),
+            Err(SessionError::NoResponse)
+        );
+
+        let mut session = Session::new(SliceByteSource::new(&input), |_: &[u8]| Err("closed"));
+        assert_eq!(
+            session.request(&Request::default()).map(|_| ()),
+            Err(SessionError::Write("closed"))
+        );
+    }
+}
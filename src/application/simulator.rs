@@ -0,0 +1,360 @@
+//! Simulated power meters producing SML transmissions.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+//!
+//! A [`Meter`] is configured with a server id and a set of registers. Each
+//! register has an OBIS code and a function that computes its value from the
+//! current time. [`Meter::next_frame`] produces correctly encoded transmissions
+//! (transport layer v1) containing an `OpenResponse`, a `GetListResponse` with the
+//! values of all registers and a `CloseResponse`, as sent by real meters.
+//!
+//! This is useful for tests and for mocking meters (e.g. on a microcontroller
+//! connected to an SML reader).
+//! `run` sends the transmissions periodically to an `embedded_io_async::Write`
+//! implementation (requires the `"embedded-io-async"` feature).
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::simulator::Meter, parser::{complete, ObisCode, common::Value}, transport};
+//! let mut meter = Meter::new(&[0x0a, 0x01, 0x45, 0x4d, 0x48])
+//!     // energy in Wh (scaler -1, unit Wh)
+//!     .register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), -1, |secs| 1_000_000 + i64::from(secs) * 5)
+//!     // power in W (scaler 0, unit W)
+//!     .register(ObisCode::new(1, 0, 16, 7, 0, 255), Some(27), 0, |secs| 200 + i64::from(secs % 60));
+//!
+//! let frame = meter.next_frame(42);
+//! let decoded = transport::decode(&frame);
+//! let file = complete::parse(decoded[0].as_ref().unwrap()).unwrap();
+//! let (_, power) = file.list_entries().nth(1).unwrap();
+//! assert_eq!(power.value, Value::I16(242));
+//! ```
+
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    parser::{common::Unit, ObisCode},
+    transport::encode,
+    util::VecBuf,
+    writer::{write_message, NONE},
+};
+
+/// A register of a simulated meter.
+struct Register {
+    obis_code: ObisCode,
+    unit: Option<Unit>,
+    scaler: i8,
+    value: Box<dyn FnMut(u32) -> i64>,
+}
+
+/// A simulated power meter.
+///
+/// See the [module-level documentation](self) for more information.
+pub struct Meter {
+    server_id: Vec<u8>,
+    registers: Vec<Register>,
+    transaction_id: u32,
+    file_id: u32,
+}
+
+impl Meter {
+    /// Constructs a meter without registers.
+    ///
+    /// `server_id` is used for the `server_id` fields of the generated messages.
+    #[must_use]
+    pub fn new(server_id: &[u8]) -> Self {
+        Meter {
+            server_id: server_id.to_vec(),
+            registers: Vec::new(),
+            transaction_id: 0,
+            file_id: 0,
+        }
+    }
+
+    /// Adds a register.
+    ///
+    /// `value` computes the raw value of the register from the current time in
+    /// seconds (the `secs` argument of [`Meter::next_frame`]). The value is
+    /// interpreted using `scaler` and `unit` as in SML list entries.
+    #[must_use]
+    pub fn register(
+        mut self,
+        obis_code: ObisCode,
+        unit: Option<Unit>,
+        scaler: i8,
+        value: impl FnMut(u32) -> i64 + 'static,
+    ) -> Self {
+        self.registers.push(Register {
+            obis_code,
+            unit,
+            scaler,
+            value: Box::new(value),
+        });
+        self
+    }
+
+    /// Returns the next encoded transmission containing the register values at time `secs`.
+    ///
+    /// `secs` is also used as the `SecIndex` of the `GetListResponse`.
+    pub fn next_frame(&mut self, secs: u32) -> Vec<u8> {
+        self.file_id = self.file_id.wrapping_add(1);
+        let file_id = self.file_id.to_be_bytes();
+
+        let mut file = Vec::new();
+        // OpenResponse
+        write_message(&mut file, &mut self.transaction_id, 0x0101, |w| {
+            w.list(6);
+            w.push(NONE); // codepage
+            w.push(NONE); // client_id
+            w.octet_string(&file_id); // req_file_id
+            w.octet_string(&self.server_id);
+            w.push(NONE); // ref_time
+            w.push(NONE); // sml_version
+        });
+        // GetListResponse
+        let mut values = Vec::with_capacity(self.registers.len());
+        for reg in &mut self.registers {
+            values.push((reg.value)(secs));
+        }
+        write_message(&mut file, &mut self.transaction_id, 0x0701, |w| {
+            w.list(7);
+            w.push(NONE); // client_id
+            w.octet_string(&self.server_id);
+            w.push(NONE); // list_name
+            w.sec_index(secs); // act_sensor_time
+            w.list(self.registers.len());
+            for (reg, value) in self.registers.iter().zip(values) {
+                w.list(7);
+                w.octet_string(&reg.obis_code.0);
+                w.push(NONE); // status
+                w.push(NONE); // val_time
+                match reg.unit {
+                    Some(unit) => w.unsigned(unit),
+                    None => w.push(NONE),
+                }
+                w.integer(reg.scaler.into());
+                w.integer(value);
+                w.push(NONE); // value_signature
+            }
+            w.push(NONE); // list_signature
+            w.push(NONE); // act_gateway_time
+        });
+        // CloseResponse
+        write_message(&mut file, &mut self.transaction_id, 0x0201, |w| {
+            w.list(1);
+            w.push(NONE); // global_signature
+        });
+
+        // a `Vec` buffer can only fail if allocation fails
+        encode::<VecBuf>(file).unwrap_or_default()
+    }
+
+    /// Returns an infinite iterator over transmissions, starting at time `start` and
+    /// advancing the time by `interval` seconds for each transmission.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{application::simulator::Meter, parser::ObisCode};
+    /// let mut meter = Meter::new(b"meter").register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), 0, |secs| secs.into());
+    /// let frames: Vec<_> = meter.frames(0, 2).take(3).collect();
+    /// assert_eq!(frames.len(), 3);
+    /// ```
+    pub fn frames(&mut self, start: u32, interval: u32) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let mut secs = start;
+        core::iter::from_fn(move || {
+            let frame = self.next_frame(secs);
+            secs = secs.wrapping_add(interval);
+            Some(frame)
+        })
+    }
+}
+
+/// Periodically writes the transmissions of `meter` to `writer`.
+///
+/// The transmissions start at time `start` and advance the time by `interval` seconds.
+/// After each transmission, the writer is flushed and `sleep(interval)` is awaited,
+/// which allows using any timer (e.g. `|secs| embassy_time::Timer::after_secs(secs.into())`).
+///
+/// Never returns unless writing fails.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` and
+/// `"embedded-io-async"` features.*
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "embedded-io-async")] {
+/// # use core::{future::{ready, Future}, pin::pin, task::{Context, Poll}};
+/// # use std::{sync::Arc, task::Wake};
+/// # struct NoopWaker;
+/// # impl Wake for NoopWaker { fn wake(self: Arc<Self>) {} }
+/// # use sml_rs::{application::simulator::{self, Meter}, parser::ObisCode, transport};
+/// let mut meter = Meter::new(b"meter").register(ObisCode::new(1, 0, 1, 8, 0, 255), Some(30), 0, |secs| secs.into());
+/// let mut buf = [0u8; 1024];
+/// let mut writer = &mut buf[..];
+/// let fut = simulator::run(&mut writer, &mut meter, 0, 1, |_| ready(()));
+///
+/// // stops when the buffer is full
+/// # let waker = Arc::new(NoopWaker).into();
+/// let mut cx = Context::from_waker(&waker);
+/// assert!(matches!(pin!(fut).poll(&mut cx), Poll::Ready(Err(_))));
+/// let written = 1024 - writer.len();
+/// assert!(transport::decode(&buf[..written]).iter().all(|res| res.is_ok()));
+/// # }
+/// ```
+#[cfg(feature = "embedded-io-async")]
+pub async fn run<W, F>(
+    mut writer: W,
+    meter: &mut Meter,
+    start: u32,
+    interval: u32,
+    mut sleep: impl FnMut(u32) -> F,
+) -> Result<core::convert::Infallible, W::Error>
+where
+    W: embedded_io_async::Write,
+    F: core::future::Future<Output = ()>,
+{
+    let mut secs = start;
+    loop {
+        writer.write_all(&meter.next_frame(secs)).await?;
+        writer.flush().await?;
+        sleep(interval).await;
+        secs = secs.wrapping_add(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parser::{
+            common::{Time, Value},
+            complete::{parse, MessageBody},
+        },
+        transport::decode,
+    };
+
+    #[test]
+    fn roundtrip() {
+        let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+        let power = ObisCode::new(1, 0, 16, 7, 0, 255);
+        let mut meter = Meter::new(&[0xab; 20])
+            .register(energy, Some(30), -1, |secs| i64::from(secs) * 1_000_000_000)
+            .register(power, None, 0, |secs| -i64::from(secs));
+        for secs in [0, 1, 1000] {
+            let decoded = decode(meter.next_frame(secs));
+            assert_eq!(decoded.len(), 1);
+            let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+            assert_eq!(file.messages.len(), 3);
+            assert!(matches!(
+                &file.messages[0].message_body,
+                MessageBody::OpenResponse(o) if o.server_id == [0xab; 20]
+            ));
+            let MessageBody::GetListResponse(glr) = &file.messages[1].message_body else {
+                panic!("expected GetListResponse");
+            };
+            assert_eq!(glr.act_sensor_time, Some(Time::SecIndex(secs)));
+            let entries: Vec<_> = glr
+                .val_list
+                .iter()
+                .map(|e| (e.obis_code().unwrap(), e.unit, e.scaler, e.value.as_i64()))
+                .collect();
+            assert_eq!(
+                entries,
+                [
+                    (
+                        energy,
+                        Some(30),
+                        Some(-1),
+                        Some(i64::from(secs) * 1_000_000_000)
+                    ),
+                    (power, None, Some(0), Some(-i64::from(secs))),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn increasing_transaction_ids() {
+        use crate::parser::sequence::SequenceChecker;
+        let mut meter = Meter::new(b"meter");
+        let mut checker = SequenceChecker::new();
+        for frame in meter.frames(0, 1).take(300) {
+            let decoded = decode(frame);
+            let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+            assert_eq!(checker.check_file(&file), Ok(()));
+        }
+    }
+
+    #[test]
+    fn integer_sizes() {
+        for (value, exp) in [
+            (0, Value::I8(0)),
+            (-128, Value::I8(-128)),
+            (128, Value::I16(128)),
+            (-32769, Value::I32(-32769)),
+            (i64::MAX, Value::I64(i64::MAX)),
+        ] {
+            let mut meter =
+                Meter::new(&[]).register(ObisCode::new(0, 0, 0, 0, 0, 0), None, 0, move |_| value);
+            let decoded = decode(meter.next_frame(0));
+            let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+            assert_eq!(file.list_entries().next().unwrap().1.value, exp);
+        }
+    }
+
+    #[test]
+    fn long_lists() {
+        let mut meter = Meter::new(&[0x11; 300]);
+        for i in 0..20 {
+            meter = meter.register(ObisCode::new(1, 0, i, 8, 0, 255), None, 0, move |_| {
+                i.into()
+            });
+        }
+        let decoded = decode(meter.next_frame(0));
+        let file = parse(decoded[0].as_ref().unwrap()).unwrap();
+        assert_eq!(file.list_entries().count(), 20);
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn run() {
+        use alloc::vec;
+        use core::{
+            future::{ready, Future},
+            pin::pin,
+            task::{Context, Poll},
+        };
+
+        // all transmissions of a meter without registers have the same length
+        let frame_len = Meter::new(b"meter").next_frame(0).len();
+        let mut meter = Meter::new(b"meter");
+        let mut buf = vec![0u8; 3 * frame_len + 1];
+        let mut writer = &mut buf[..];
+        let mut sleeps = Vec::new();
+        let fut = super::run(&mut writer, &mut meter, 100, 5, |secs| {
+            sleeps.push(secs);
+            ready(())
+        });
+        let waker = crate::util::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        assert!(matches!(pin!(fut).poll(&mut cx), Poll::Ready(Err(_))));
+        assert_eq!(sleeps, [5, 5, 5]);
+
+        let times: Vec<_> = decode(&buf[..3 * frame_len])
+            .iter()
+            .map(|res| {
+                let file = parse(res.as_ref().unwrap()).unwrap();
+                let MessageBody::GetListResponse(glr) = &file.messages[1].message_body else {
+                    panic!("expected GetListResponse");
+                };
+                glr.act_sensor_time.clone()
+            })
+            .collect();
+        assert_eq!(
+            times,
+            [100, 105, 110].map(|secs| Some(Time::SecIndex(secs)))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Helpers for reading SML data delivered over HTTP by smart meter gateways (SMGW).
+//!
+//! *This module is available only if sml-rs is built with the `"std"` feature.*
+//!
+//! The HAN interface of a smart meter gateway delivers SML data as the body of an
+//! HTTP response. The body may contain several concatenated SML transmissions and,
+//! when read incrementally, a transmission may be split across arbitrary chunk
+//! boundaries. The types in this module take care of reassembling the chunks,
+//! decoding the transport layer and parsing the resulting transmissions.
+//!
+//! # Examples
+//!
+//! Decoding a complete body:
+//!
+//! ```
+//! # use sml_rs::application::smgw::decode_body;
+//! let body = include_bytes!("../../sample.bin");
+//! let transmissions = decode_body(body);
+//! for file in transmissions.files() {
+//!     let file = file.expect("error in transmission");
+//!     assert!(!file.messages.is_empty());
+//! }
+//! ```
+//!
+//! Decoding a body that is received in chunks:
+//!
+//! ```
+//! # use sml_rs::application::smgw::BodyDecoder;
+//! let body = include_bytes!("../../sample.bin");
+//! let mut decoder = BodyDecoder::new();
+//! for chunk in body.chunks(100) {
+//!     decoder.push_chunk(chunk);
+//! }
+//! let transmissions = decoder.finish();
+//! assert_eq!(transmissions.len(), 1);
+//! ```
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{
+    parser::{
+        complete::{parse, File},
+        ParseError,
+    },
+    transport::{DecodeErr, Decoder},
+    util::VecBuf,
+};
+
+/// Error type returned when a transmission of an HTTP body cannot be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyError {
+    /// Error while decoding the transport layer
+    Decode(DecodeErr),
+    /// Error while parsing the decoded transmission
+    Parse(ParseError),
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BodyError::Decode(e) => write!(f, "{e}"),
+            BodyError::Parse(e) => write!(f, "error while parsing: {e}"),
+        }
+    }
+}
+
+impl core::error::Error for BodyError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            BodyError::Decode(e) => Some(e),
+            BodyError::Parse(e) => Some(e),
+        }
+    }
+}
+
+impl From<DecodeErr> for BodyError {
+    fn from(value: DecodeErr) -> Self {
+        BodyError::Decode(value)
+    }
+}
+
+impl From<ParseError> for BodyError {
+    fn from(value: ParseError) -> Self {
+        BodyError::Parse(value)
+    }
+}
+
+/// Incrementally decodes an HTTP body that is received in chunks.
+///
+/// Chunk boundaries don't need to be aligned to transmissions in any way.
+pub struct BodyDecoder {
+    decoder: Decoder<VecBuf>,
+    transmissions: Vec<Result<Vec<u8>, DecodeErr>>,
+}
+
+impl Default for BodyDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BodyDecoder {
+    /// Constructs a new `BodyDecoder`.
+    #[must_use]
+    pub fn new() -> Self {
+        BodyDecoder {
+            decoder: Decoder::new(),
+            transmissions: Vec::new(),
+        }
+    }
+
+    /// Pushes the next chunk of the body into the decoder.
+    pub fn push_chunk(&mut self, chunk: &[u8]) {
+        for &b in chunk {
+            match self.decoder.push_byte(b) {
+                Ok(None) => {}
+                Ok(Some(buf)) => self.transmissions.push(Ok(buf.to_vec())),
+                Err(e) => self.transmissions.push(Err(e)),
+            }
+        }
+    }
+
+    /// Signals the end of the body and returns all transmissions read.
+    ///
+    /// If the body ends with an incomplete transmission, the corresponding
+    /// error is included in the result.
+    #[must_use]
+    pub fn finish(mut self) -> Transmissions {
+        if let Some(e) = self.decoder.finalize() {
+            self.transmissions.push(Err(e));
+        }
+        Transmissions {
+            items: self.transmissions,
+        }
+    }
+}
+
+/// Decodes a complete HTTP body into transmissions.
+///
+/// This is a shorthand for using [`BodyDecoder`] with a single chunk.
+#[must_use]
+pub fn decode_body(body: &[u8]) -> Transmissions {
+    let mut decoder = BodyDecoder::new();
+    decoder.push_chunk(body);
+    decoder.finish()
+}
+
+/// The transmissions read from an HTTP body.
+///
+/// Holds the decoded bytes of each transmission, which are parsed on access.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transmissions {
+    items: Vec<Result<Vec<u8>, DecodeErr>>,
+}
+
+impl Transmissions {
+    /// Returns the number of transmissions and transport errors.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if the body didn't contain any transmissions or errors.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the decoded bytes of each transmission or the transport error.
+    pub fn decoded(&self) -> &[Result<Vec<u8>, DecodeErr>] {
+        &self.items
+    }
+
+    /// Returns an iterator over the parsed transmissions.
+    ///
+    /// Transport errors and parsing errors are yielded in order of occurrence.
+    pub fn files(&self) -> impl Iterator<Item = Result<File<'_>, BodyError>> {
+        self.items.iter().map(|item| match item {
+            Ok(bytes) => parse(bytes).map_err(BodyError::from),
+            Err(e) => Err(BodyError::from(e.clone())),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &[u8] = include_bytes!("../../sample.bin");
+
+    #[test]
+    fn concatenated_transmissions() {
+        let mut body = SAMPLE.to_vec();
+        body.extend_from_slice(SAMPLE);
+        let transmissions = decode_body(&body);
+        assert_eq!(transmissions.len(), 2);
+        assert!(transmissions.files().all(|f| f.is_ok()));
+    }
+
+    #[test]
+    fn arbitrary_chunks() {
+        let mut body = SAMPLE.to_vec();
+        body.extend_from_slice(SAMPLE);
+        let expected = decode_body(&body);
+        for chunk_size in [1, 3, 7, 64, 1000] {
+            let mut decoder = BodyDecoder::new();
+            for chunk in body.chunks(chunk_size) {
+                decoder.push_chunk(chunk);
+            }
+            assert_eq!(decoder.finish(), expected);
+        }
+    }
+
+    #[test]
+    fn truncated_body() {
+        let transmissions = decode_body(&SAMPLE[..SAMPLE.len() - 4]);
+        let res: Vec<_> = transmissions.files().collect();
+        assert!(matches!(
+            res.as_slice(),
+            [Err(BodyError::Decode(DecodeErr::DiscardedBytes(_)))]
+        ));
+    }
+
+    #[test]
+    fn empty_body() {
+        assert!(decode_body(&[]).is_empty());
+    }
+}
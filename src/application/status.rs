@@ -0,0 +1,267 @@
+//! Decoding of the meter status word (`1-0:96.5.0*255`).
+//!
+//! The status word is a bit field whose meaning isn't defined by SML and differs
+//! between manufacturers and even between meter models. sml-rs therefore doesn't
+//! ship any bit tables. Instead, [`MeterStatus`] takes a user-supplied table of
+//! `(bit, name)` tuples (usually taken from the manual of the meter) and provides
+//! the set flags with human-readable names. [`Vendor`] can be used to select the
+//! table for the meter at hand: it identifies the manufacturer from the `server_id`
+//! of the meter (see [`Vendor::from_server_id`]) or from the value of
+//! [`ObisCode::MANUFACTURER_ID`].
+//!
+//! Set bits that aren't part of the table are available via [`MeterStatus::unknown_bits`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::application::status::{MeterStatus, Vendor};
+//! // bit names taken from the manual of the meter
+//! const MY_METER_FLAGS: &[(u8, &str)] = &[(8, "magnetic tamper"), (9, "reverse energy")];
+//!
+//! let server_id = b"\x0a\x01EMH\x00\x00\x12\x34\x56";
+//! let flags = match Vendor::from_server_id(server_id) {
+//!     Some(Vendor::Emh) => MY_METER_FLAGS,
+//!     _ => &[],
+//! };
+//!
+//! let status = MeterStatus::new(0x0000_0302).with_flags(flags);
+//! # #[cfg(feature = "alloc")]
+//! assert_eq!(status.to_string(), "magnetic tamper, reverse energy");
+//! assert_eq!(status.unknown_bits(), 0x0000_0002);
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::parser::{
+    common::{ListEntry, Status, Value},
+    ObisCode,
+};
+
+/// Manufacturers that can be identified from a `server_id` or manufacturer id.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Vendor {
+    /// Iskraemeco (FLAG id `ISK`)
+    Iskra,
+    /// EMH metering (FLAG id `EMH`)
+    Emh,
+    /// EasyMeter (FLAG id `ESY`)
+    Easymeter,
+    /// DZG Metering (FLAG id `DZG`)
+    Dzg,
+}
+
+impl Vendor {
+    /// Identifies the vendor by its three-letter FLAG manufacturer id (e.g. `b"ISK"`).
+    ///
+    /// The comparison is case-insensitive. Returns `None` for unsupported vendors.
+    #[must_use]
+    pub fn from_flag_id(flag_id: &[u8]) -> Option<Self> {
+        [Vendor::Iskra, Vendor::Emh, Vendor::Easymeter, Vendor::Dzg]
+            .into_iter()
+            .find(|vendor| vendor.flag_id().as_bytes().eq_ignore_ascii_case(flag_id))
+    }
+
+    /// Identifies the vendor from a `server_id` according to DIN 43863-5.
+    ///
+    /// Such `server_id`s contain the FLAG manufacturer id in bytes 2 to 4 (e.g.
+    /// `0a 01 49 53 4b ...` for `ISK`). Returns `None` if the `server_id` is too
+    /// short or the vendor isn't supported.
+    #[must_use]
+    pub fn from_server_id(server_id: &[u8]) -> Option<Self> {
+        server_id.get(2..5).and_then(Self::from_flag_id)
+    }
+
+    /// Identifies the vendor from a list entry containing the manufacturer id
+    /// ([`ObisCode::MANUFACTURER_ID`]).
+    #[must_use]
+    pub fn from_list_entry(entry: &ListEntry<'_>) -> Option<Self> {
+        if entry.obis_code() != Some(ObisCode::MANUFACTURER_ID) {
+            return None;
+        }
+        entry.value.as_bytes().and_then(Self::from_flag_id)
+    }
+
+    /// Returns the FLAG manufacturer id of the vendor.
+    #[must_use]
+    pub const fn flag_id(self) -> &'static str {
+        match self {
+            Vendor::Iskra => "ISK",
+            Vendor::Emh => "EMH",
+            Vendor::Easymeter => "ESY",
+            Vendor::Dzg => "DZG",
+        }
+    }
+}
+
+impl fmt::Display for Vendor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.flag_id())
+    }
+}
+
+/// A status word together with the names of its bits.
+///
+/// The `Display` implementation lists the names of the set flags separated by
+/// commas. If no named flag is set, the status word is formatted as hex number
+/// instead.
+///
+/// See the [module-level documentation](self) for more information.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeterStatus {
+    /// the raw status word
+    pub word: u64,
+    /// the names of the bits as `(bit, name)` tuples (not serialized)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub flag_names: &'static [(u8, &'static str)],
+}
+
+impl MeterStatus {
+    /// Constructs a `MeterStatus` from a raw status word without any bit names.
+    #[must_use]
+    pub const fn new(word: u64) -> Self {
+        MeterStatus {
+            word,
+            flag_names: &[],
+        }
+    }
+
+    /// Sets the names of the bits as `(bit, name)` tuples, starting at zero for the
+    /// least significant bit.
+    #[must_use]
+    pub const fn with_flags(mut self, flag_names: &'static [(u8, &'static str)]) -> Self {
+        self.flag_names = flag_names;
+        self
+    }
+
+    /// Constructs a `MeterStatus` from the `status` field of a list entry.
+    #[must_use]
+    pub fn from_status(status: &Status) -> Self {
+        Self::new(status.as_u64())
+    }
+
+    /// Constructs a `MeterStatus` from a list entry containing the status word
+    /// ([`ObisCode::STATUS`]).
+    ///
+    /// The value can be an unsigned integer or an octet string of at most eight
+    /// bytes (big endian). Returns `None` for other entries.
+    #[must_use]
+    pub fn from_list_entry(entry: &ListEntry<'_>) -> Option<Self> {
+        if entry.obis_code() != Some(ObisCode::STATUS) {
+            return None;
+        }
+        let word = match entry.value {
+            Value::U8(x) => x.into(),
+            Value::U16(x) => x.into(),
+            Value::U32(x) => x.into(),
+            Value::U64(x) => x,
+            Value::Bytes(bytes) if bytes.len() <= 8 => {
+                bytes.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b))
+            }
+            _ => return None,
+        };
+        Some(Self::new(word))
+    }
+
+    /// Returns whether the given bit (starting at zero for the least significant bit) is set.
+    #[must_use]
+    pub const fn is_set(&self, bit: u8) -> bool {
+        bit < 64 && self.word & (1 << bit) != 0
+    }
+
+    /// Returns the names of the set flags.
+    pub fn flags(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.flag_names
+            .iter()
+            .filter(|(bit, _)| self.is_set(*bit))
+            .map(|(_, name)| *name)
+    }
+
+    /// Returns the set bits without a name.
+    #[must_use]
+    pub fn unknown_bits(&self) -> u64 {
+        let known = self
+            .flag_names
+            .iter()
+            .filter(|(bit, _)| *bit < 64)
+            .fold(0u64, |acc, (bit, _)| acc | (1 << bit));
+        self.word & !known
+    }
+}
+
+impl fmt::Display for MeterStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = self.flags();
+        let Some(first) = flags.next() else {
+            return write!(f, "{:#x}", self.word);
+        };
+        f.write_str(first)?;
+        for flag in flags {
+            write!(f, ", {flag}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn vendor() {
+        assert_eq!(Vendor::from_flag_id(b"ISK"), Some(Vendor::Iskra));
+        assert_eq!(Vendor::from_flag_id(b"dzg"), Some(Vendor::Dzg));
+        assert_eq!(Vendor::from_flag_id(b"ITR"), None);
+        assert_eq!(Vendor::from_flag_id(b"ES"), None);
+        assert_eq!(
+            Vendor::from_server_id(b"\x0a\x01ESY\x11\x03\x04\x05\x06"),
+            Some(Vendor::Easymeter)
+        );
+        assert_eq!(Vendor::from_server_id(b"\x0a\x01IS"), None);
+        assert_eq!(Vendor::Easymeter.to_string(), "ESY");
+
+        let entry = ListEntry::new_test(&ObisCode::MANUFACTURER_ID.0, Value::Bytes(b"ISK"));
+        assert_eq!(Vendor::from_list_entry(&entry), Some(Vendor::Iskra));
+    }
+
+    #[test]
+    fn status() {
+        const FLAGS: &[(u8, &str)] = &[(8, "magnetic tamper"), (9, "reverse energy"), (70, "x")];
+        let entry = |value| ListEntry::new_test(&ObisCode::STATUS.0, value);
+        let status = MeterStatus::from_list_entry(&entry(Value::U32(0x0302)));
+        assert_eq!(status, Some(MeterStatus::new(0x0302)));
+        let status = status.unwrap().with_flags(FLAGS);
+        assert!(status.flags().eq(["magnetic tamper", "reverse energy"]));
+        assert_eq!(status.unknown_bits(), 0x02);
+        assert_eq!(status.to_string(), "magnetic tamper, reverse energy");
+        assert_eq!(MeterStatus::new(0x0302).to_string(), "0x302");
+        assert_eq!(MeterStatus::new(0x0302).unknown_bits(), 0x0302);
+        assert_eq!(
+            MeterStatus::new(0x0002).with_flags(FLAGS).to_string(),
+            "0x2"
+        );
+
+        assert_eq!(
+            MeterStatus::from_list_entry(&entry(Value::Bytes(&[0x01, 0x00]))),
+            Some(MeterStatus::new(0x0100))
+        );
+        assert_eq!(
+            MeterStatus::from_list_entry(&entry(Value::Bytes(&[0; 9]))),
+            None
+        );
+        assert_eq!(MeterStatus::from_list_entry(&entry(Value::I32(1))), None);
+        assert_eq!(
+            MeterStatus::from_status(&Status::Status16(0x0100))
+                .with_flags(FLAGS)
+                .to_string(),
+            "magnetic tamper"
+        );
+        assert!(!status.is_set(64));
+    }
+}
@@ -0,0 +1,518 @@
+//! Compact payloads for telemetry uplinks.
+//!
+//! Radio links such as LoRaWAN only allow payloads of a few dozen bytes. A
+//! [`TelemetrySchema`] lists the values that are transmitted. Both sides of the
+//! link use the same schema, which allows omitting the OBIS codes and units from
+//! the payload. [`TelemetrySchema::encode`] writes a payload and
+//! [`TelemetrySchema::decode`] reads it on the receiving side. Neither allocates.
+//!
+//! With the `serde` feature, [`Reading`] and [`TelemetryField`] can also be
+//! serialized using any serde format, e.g. `postcard`.
+//!
+//! # Format
+//!
+//! Integers are encoded like [CBOR](https://www.rfc-editor.org/rfc/rfc8949) integers:
+//! the upper three bits of the first byte contain the major type (0 for unsigned,
+//! 1 for negative integers) and the lower five bits contain the value itself (0 - 23)
+//! or the number of bytes following it (24: 1, 25: 2, 26: 4, 27: 8 bytes, big endian).
+//! A negative integer `n` is encoded as `-1 - n`.
+//!
+//! A payload consists of:
+//!
+//! - the format version (currently `0x01`) as a single byte
+//! - the time (e.g. the `SecIndex` of the meter) as an unsigned integer
+//! - a bitmap of the transmitted fields as an unsigned integer, where bit `i` is set if
+//!   the `i`-th field of the schema is contained in the payload
+//! - for each transmitted field (in the order of the schema) the scaler and the value
+//!   as integers
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{application::{quantity::Quantity, telemetry::{TelemetryField, TelemetrySchema}}, parser::ObisCode, util::ArrayBuf};
+//! const SCHEMA: TelemetrySchema = TelemetrySchema::new(&[
+//!     TelemetryField::new(ObisCode::ENERGY_IMPORT_TOTAL).with_unit(30),
+//!     TelemetryField::new(ObisCode::ENERGY_EXPORT_TOTAL).with_unit(30),
+//!     TelemetryField::new(ObisCode::ACTIVE_POWER).with_unit(27),
+//! ]);
+//!
+//! let energy = Quantity { value: 123_456_789, scaler: -1, unit: Some(30) };
+//! let power = Quantity { value: 320, scaler: 0, unit: Some(27) };
+//! let mut payload = ArrayBuf::<32>::default();
+//! SCHEMA
+//!     .encode(1000, &[(ObisCode::ENERGY_IMPORT_TOTAL, energy), (ObisCode::ACTIVE_POWER, power)], &mut payload)
+//!     .unwrap();
+//! assert_eq!(payload.len(), 15);
+//!
+//! let decoded = SCHEMA.decode(&payload).unwrap();
+//! assert_eq!(decoded.time(), 1000);
+//! let mut readings = decoded.readings();
+//! assert_eq!(readings.next().unwrap().unwrap().quantity, energy);
+//! assert_eq!(readings.next().unwrap().unwrap().quantity, power);
+//! assert!(readings.next().is_none());
+//! ```
+
+use core::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    application::quantity::Quantity,
+    parser::{common::Unit, ObisCode},
+    util::Buffer,
+};
+
+/// Current version of the payload format
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Maximum number of fields of a [`TelemetrySchema`]
+pub const MAX_FIELDS: usize = 64;
+
+/// Error returned when encoding or decoding telemetry payloads fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryError {
+    /// The output buffer is full
+    OutOfMemory,
+    /// The schema contains more than [`MAX_FIELDS`] fields
+    TooManyFields,
+    /// The unit of a value differs from the unit of its field
+    UnitMismatch(ObisCode),
+    /// The payload uses an unsupported format version
+    UnsupportedVersion(u8),
+    /// The payload ended unexpectedly
+    UnexpectedEof,
+    /// The payload is invalid (e.g. it references fields that aren't part of the schema)
+    InvalidInput,
+}
+
+impl fmt::Display for TelemetryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelemetryError::OutOfMemory => {
+                f.write_str("payload doesn't fit into the output buffer")
+            }
+            TelemetryError::TooManyFields => {
+                write!(f, "schema contains more than {MAX_FIELDS} fields")
+            }
+            TelemetryError::UnitMismatch(obis) => {
+                write!(
+                    f,
+                    "unit of {obis} differs from the unit of its schema field"
+                )
+            }
+            TelemetryError::UnsupportedVersion(v) => {
+                write!(f, "unsupported telemetry payload version {v}")
+            }
+            TelemetryError::UnexpectedEof => f.write_str("telemetry payload is truncated"),
+            TelemetryError::InvalidInput => f.write_str("telemetry payload is invalid"),
+        }
+    }
+}
+
+impl core::error::Error for TelemetryError {}
+
+impl From<crate::util::OutOfMemory> for TelemetryError {
+    fn from(_: crate::util::OutOfMemory) -> Self {
+        TelemetryError::OutOfMemory
+    }
+}
+
+/// A value transmitted using a [`TelemetrySchema`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetryField {
+    /// OBIS code of the value
+    pub obis_code: ObisCode,
+    /// unit of the value
+    pub unit: Option<Unit>,
+}
+
+impl TelemetryField {
+    /// Constructs a field without unit.
+    #[must_use]
+    pub const fn new(obis_code: ObisCode) -> Self {
+        TelemetryField {
+            obis_code,
+            unit: None,
+        }
+    }
+
+    /// Sets the unit of the field.
+    #[must_use]
+    pub const fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = Some(unit);
+        self
+    }
+}
+
+/// A value read from a telemetry payload.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reading {
+    /// OBIS code of the value
+    pub obis_code: ObisCode,
+    /// the value
+    pub quantity: Quantity,
+}
+
+/// Values transmitted in telemetry payloads.
+///
+/// See the [module-level documentation](self) for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TelemetrySchema<'s> {
+    fields: &'s [TelemetryField],
+}
+
+impl<'s> TelemetrySchema<'s> {
+    /// Constructs a schema from its fields.
+    ///
+    /// Encoding and decoding fail if there are more than [`MAX_FIELDS`] fields.
+    #[must_use]
+    pub const fn new(fields: &'s [TelemetryField]) -> Self {
+        TelemetrySchema { fields }
+    }
+
+    /// Returns the fields of the schema.
+    #[must_use]
+    pub const fn fields(&self) -> &'s [TelemetryField] {
+        self.fields
+    }
+
+    /// Appends a payload containing `values` observed at `time` to `out`.
+    ///
+    /// Values whose OBIS code isn't part of the schema are skipped. If an OBIS code
+    /// is contained multiple times, the first value is used. On error, `out` may
+    /// contain a partially written payload.
+    pub fn encode<B: Buffer>(
+        &self,
+        time: u32,
+        values: &[(ObisCode, Quantity)],
+        out: &mut B,
+    ) -> Result<(), TelemetryError> {
+        self.check_len()?;
+        let value = |field: &TelemetryField| {
+            values
+                .iter()
+                .find(|(code, _)| *code == field.obis_code)
+                .map(|(_, q)| q)
+        };
+        let mut bitmap = 0u64;
+        for (i, field) in self.fields.iter().enumerate() {
+            if let Some(q) = value(field) {
+                if q.unit != field.unit {
+                    return Err(TelemetryError::UnitMismatch(field.obis_code));
+                }
+                bitmap |= 1 << i;
+            }
+        }
+
+        out.push(FORMAT_VERSION)?;
+        write_int(out, time.into())?;
+        write_int(out, bitmap.into())?;
+        for q in self.fields.iter().filter_map(value) {
+            write_int(out, q.scaler.into())?;
+            write_int(out, q.value.into())?;
+        }
+        Ok(())
+    }
+
+    /// Reads the header of a payload.
+    ///
+    /// The values are decoded by [`Payload::readings`].
+    pub fn decode<'i>(&self, input: &'i [u8]) -> Result<Payload<'s, 'i>, TelemetryError> {
+        self.check_len()?;
+        let (&version, mut input) = input.split_first().ok_or(TelemetryError::UnexpectedEof)?;
+        if version != FORMAT_VERSION {
+            return Err(TelemetryError::UnsupportedVersion(version));
+        }
+        let time = read_int(&mut input)?
+            .try_into()
+            .map_err(|_| TelemetryError::InvalidInput)?;
+        let bitmap: u64 = read_int(&mut input)?
+            .try_into()
+            .map_err(|_| TelemetryError::InvalidInput)?;
+        if self.fields.len() < MAX_FIELDS && bitmap >> self.fields.len() != 0 {
+            return Err(TelemetryError::InvalidInput);
+        }
+        Ok(Payload {
+            fields: self.fields,
+            time,
+            bitmap,
+            input,
+        })
+    }
+
+    fn check_len(&self) -> Result<(), TelemetryError> {
+        if self.fields.len() > MAX_FIELDS {
+            return Err(TelemetryError::TooManyFields);
+        }
+        Ok(())
+    }
+}
+
+/// A telemetry payload returned by [`TelemetrySchema::decode`].
+#[derive(Debug, Clone)]
+pub struct Payload<'s, 'i> {
+    fields: &'s [TelemetryField],
+    time: u32,
+    bitmap: u64,
+    input: &'i [u8],
+}
+
+impl<'s, 'i> Payload<'s, 'i> {
+    /// Returns the time contained in the payload.
+    #[must_use]
+    pub fn time(&self) -> u32 {
+        self.time
+    }
+
+    /// Returns an iterator over the values contained in the payload.
+    ///
+    /// The iterator stops after returning the first error. An error is also returned
+    /// if the payload contains trailing bytes.
+    #[must_use]
+    pub fn readings(&self) -> Readings<'s, 'i> {
+        Readings {
+            fields: self.fields.iter().enumerate(),
+            bitmap: self.bitmap,
+            input: self.input,
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the values of a [`Payload`].
+#[derive(Debug, Clone)]
+pub struct Readings<'s, 'i> {
+    fields: core::iter::Enumerate<core::slice::Iter<'s, TelemetryField>>,
+    bitmap: u64,
+    input: &'i [u8],
+    done: bool,
+}
+
+impl Readings<'_, '_> {
+    fn read(&mut self, field: &TelemetryField) -> Result<Reading, TelemetryError> {
+        let scaler = read_int(&mut self.input)?;
+        let value = read_int(&mut self.input)?;
+        Ok(Reading {
+            obis_code: field.obis_code,
+            quantity: Quantity {
+                value: value.try_into().map_err(|_| TelemetryError::InvalidInput)?,
+                scaler: scaler
+                    .try_into()
+                    .map_err(|_| TelemetryError::InvalidInput)?,
+                unit: field.unit,
+            },
+        })
+    }
+}
+
+impl Iterator for Readings<'_, '_> {
+    type Item = Result<Reading, TelemetryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = match self.fields.find(|(i, _)| self.bitmap & (1 << i) != 0) {
+            Some((_, field)) => self.read(field),
+            None if self.input.is_empty() => {
+                self.done = true;
+                return None;
+            }
+            None => Err(TelemetryError::InvalidInput),
+        };
+        self.done = res.is_err();
+        Some(res)
+    }
+}
+
+fn write_int<B: Buffer>(out: &mut B, x: i128) -> Result<(), TelemetryError> {
+    let (major, arg) = if x < 0 { (0x20, -1 - x) } else { (0x00, x) };
+    // the callers only pass values that fit into an `i64` or `u64`
+    let arg = arg as u64;
+    match arg {
+        0..=23 => out.push(major | arg as u8)?,
+        24..=0xff => out.extend_from_slice(&[major | 24, arg as u8])?,
+        0x100..=0xffff => {
+            out.push(major | 25)?;
+            out.extend_from_slice(&(arg as u16).to_be_bytes())?;
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(major | 26)?;
+            out.extend_from_slice(&(arg as u32).to_be_bytes())?;
+        }
+        _ => {
+            out.push(major | 27)?;
+            out.extend_from_slice(&arg.to_be_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+fn read_int(input: &mut &[u8]) -> Result<i128, TelemetryError> {
+    let (&head, rest) = input.split_first().ok_or(TelemetryError::UnexpectedEof)?;
+    let len = match head & 0x1f {
+        0..=23 => 0,
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return Err(TelemetryError::InvalidInput),
+    };
+    if rest.len() < len {
+        return Err(TelemetryError::UnexpectedEof);
+    }
+    let (bytes, rest) = rest.split_at(len);
+    *input = rest;
+    let arg = if len == 0 {
+        u64::from(head & 0x1f)
+    } else {
+        bytes.iter().fold(0, |acc, &b| (acc << 8) | u64::from(b))
+    };
+    match head >> 5 {
+        0 => Ok(arg.into()),
+        1 => Ok(-1 - i128::from(arg)),
+        _ => Err(TelemetryError::InvalidInput),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::ArrayBuf;
+
+    const A: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const B: ObisCode = ObisCode::ENERGY_EXPORT_TOTAL;
+    const C: ObisCode = ObisCode::ACTIVE_POWER;
+    const FIELDS: [TelemetryField; 3] = [
+        TelemetryField::new(A).with_unit(30),
+        TelemetryField::new(B).with_unit(30),
+        TelemetryField::new(C),
+    ];
+
+    fn q(value: i64, scaler: i8, unit: Option<Unit>) -> Quantity {
+        Quantity {
+            value,
+            scaler,
+            unit,
+        }
+    }
+
+    fn roundtrip(time: u32, values: &[(ObisCode, Quantity)]) -> ArrayBuf<64> {
+        let schema = TelemetrySchema::new(&FIELDS);
+        let mut buf = ArrayBuf::default();
+        schema.encode(time, values, &mut buf).unwrap();
+        let payload = schema.decode(&buf).unwrap();
+        assert_eq!(payload.time(), time);
+        let readings = payload.readings().map(|r| {
+            let r = r.unwrap();
+            (r.obis_code, r.quantity)
+        });
+        assert!(readings.eq(values.iter().copied()));
+        buf
+    }
+
+    #[test]
+    fn encoding() {
+        let buf = roundtrip(0, &[]);
+        assert_eq!(&buf[..], [0x01, 0x00, 0x00]);
+        let buf = roundtrip(24, &[(C, q(-1, -1, None))]);
+        assert_eq!(&buf[..], [0x01, 0x18, 0x18, 0x04, 0x20, 0x20]);
+        let buf = roundtrip(
+            u32::MAX,
+            &[
+                (A, q(i64::MAX, 0, Some(30))),
+                (B, q(i64::MIN, -128, Some(30))),
+            ],
+        );
+        assert_eq!(buf[1..6], [0x1a, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            buf[7..17],
+            [0x00, 0x1b, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+        );
+        assert_eq!(buf[17..19], [0x38, 0x7f]);
+        assert_eq!(buf[19], 0x3b);
+        assert_eq!(buf.len(), 28);
+        roundtrip(70_000, &[(A, q(256, 3, Some(30))), (C, q(65_536, 0, None))]);
+    }
+
+    #[test]
+    fn encode_errors() {
+        let schema = TelemetrySchema::new(&FIELDS);
+        let mut buf = ArrayBuf::<4>::default();
+        // values without field are skipped
+        schema
+            .encode(0, &[(ObisCode::VOLTAGE_L1, q(1, 0, None))], &mut buf)
+            .unwrap();
+        assert_eq!(&buf[..], [0x01, 0x00, 0x00]);
+        assert_eq!(
+            schema.encode(0, &[(A, q(1, 0, Some(27)))], &mut buf),
+            Err(TelemetryError::UnitMismatch(A))
+        );
+        assert_eq!(
+            schema.encode(0, &[(C, q(1, 0, None))], &mut buf),
+            Err(TelemetryError::OutOfMemory)
+        );
+        let fields = [TelemetryField::new(A); MAX_FIELDS + 1];
+        assert_eq!(
+            TelemetrySchema::new(&fields).encode(0, &[], &mut buf),
+            Err(TelemetryError::TooManyFields)
+        );
+    }
+
+    #[test]
+    fn decode_errors() {
+        let schema = TelemetrySchema::new(&FIELDS);
+        let decode = |bytes: &[u8]| {
+            schema
+                .decode(bytes)
+                .and_then(|p| p.readings().try_for_each(|r| r.map(|_| ())))
+        };
+        assert_eq!(decode(&[]), Err(TelemetryError::UnexpectedEof));
+        assert_eq!(decode(&[0x02]), Err(TelemetryError::UnsupportedVersion(2)));
+        assert_eq!(decode(&[0x01, 0x00]), Err(TelemetryError::UnexpectedEof));
+        assert_eq!(
+            decode(&[0x01, 0x19, 0x00]),
+            Err(TelemetryError::UnexpectedEof)
+        );
+        // time doesn't fit into a `u32`
+        assert_eq!(
+            decode(&[0x01, 0x1b, 0, 0, 0, 1, 0, 0, 0, 0, 0x00]),
+            Err(TelemetryError::InvalidInput)
+        );
+        // negative time
+        assert_eq!(
+            decode(&[0x01, 0x20, 0x00]),
+            Err(TelemetryError::InvalidInput)
+        );
+        // unsupported major type
+        assert_eq!(
+            decode(&[0x01, 0x40, 0x00]),
+            Err(TelemetryError::InvalidInput)
+        );
+        // unknown field
+        assert_eq!(
+            decode(&[0x01, 0x00, 0x08]),
+            Err(TelemetryError::InvalidInput)
+        );
+        // missing value
+        assert_eq!(
+            decode(&[0x01, 0x00, 0x01, 0x00]),
+            Err(TelemetryError::UnexpectedEof)
+        );
+        // scaler out of range
+        assert_eq!(
+            decode(&[0x01, 0x00, 0x01, 0x18, 0x80, 0x00]),
+            Err(TelemetryError::InvalidInput)
+        );
+        // trailing bytes
+        assert_eq!(
+            decode(&[0x01, 0x00, 0x01, 0x00, 0x00, 0x00]),
+            Err(TelemetryError::InvalidInput)
+        );
+        assert_eq!(decode(&[0x01, 0x00, 0x01, 0x00, 0x00]), Ok(()));
+    }
+}
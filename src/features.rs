@@ -0,0 +1,67 @@
+//! Features sml-rs has been built with.
+//!
+//! The constants in this module allow downstream code to check which optional APIs
+//! are available, e.g. to report the capabilities of a firmware build or to select
+//! a code path using `if` instead of duplicating `cfg` attributes of sml-rs. Each
+//! constant is `true` if the corresponding cargo feature is enabled (including features
+//! enabled implicitly by other features, such as `alloc` by `std`).
+//!
+//! # Examples
+//!
+//! ```
+//! use sml_rs::features;
+//!
+//! // `std` implies `alloc`
+//! assert!(!features::HAS_STD || features::HAS_ALLOC);
+//! if features::HAS_ALLOC {
+//!     // e.g. use `parser::complete`
+//! }
+//! ```
+
+/// The `std` feature is enabled.
+pub const HAS_STD: bool = cfg!(feature = "std");
+
+/// The `alloc` feature is enabled.
+pub const HAS_ALLOC: bool = cfg!(feature = "alloc");
+
+/// The `nb` feature is enabled.
+pub const HAS_NB: bool = cfg!(feature = "nb");
+
+/// The `embedded-hal-02` feature is enabled.
+pub const HAS_EMBEDDED_HAL_02: bool = cfg!(feature = "embedded-hal-02");
+
+/// The `serde` feature is enabled.
+pub const HAS_SERDE: bool = cfg!(feature = "serde");
+
+/// The `fmt-debug` feature is enabled.
+pub const HAS_FMT_DEBUG: bool = cfg!(feature = "fmt-debug");
+
+/// The `allocator-api2` feature is enabled.
+pub const HAS_ALLOCATOR_API2: bool = cfg!(feature = "allocator-api2");
+
+/// The `embedded-io` feature is enabled.
+pub const HAS_EMBEDDED_IO: bool = cfg!(feature = "embedded-io");
+
+/// The `embedded-io-async` feature is enabled.
+pub const HAS_EMBEDDED_IO_ASYNC: bool = cfg!(feature = "embedded-io-async");
+
+/// The `futures-io` feature is enabled.
+pub const HAS_FUTURES_IO: bool = cfg!(feature = "futures-io");
+
+/// The `mqtt` feature is enabled.
+pub const HAS_MQTT: bool = cfg!(feature = "mqtt");
+
+/// The `tokio` feature is enabled.
+pub const HAS_TOKIO: bool = cfg!(feature = "tokio");
+
+/// The `embassy-sync` feature is enabled.
+pub const HAS_EMBASSY_SYNC: bool = cfg!(feature = "embassy-sync");
+
+/// The `small-crc` feature is enabled.
+pub const HAS_SMALL_CRC: bool = cfg!(feature = "small-crc");
+
+/// The `rayon` feature is enabled.
+pub const HAS_RAYON: bool = cfg!(feature = "rayon");
+
+/// The `test-util` feature is enabled.
+pub const HAS_TEST_UTIL: bool = cfg!(feature = "test-util");
@@ -5,21 +5,95 @@
 //!
 //! See the [`transport`] module for encoding / decoding the SML transport protocol v1 and the
 //! [`parser`] module for parsing decoded data into SML data structures.
+//! Higher-level helpers for applications (e.g. tracking values over time or exporting
+//! them) are located in the [`application`] module.
 //!
 //! Complete examples of how to use the library can be found on github in the [`examples`](https://github.com/felixwrt/sml-rs/tree/main/examples) folder.
 //!
+//! # Usage without `alloc`
+//!
+//! Everything needed to read and parse transmissions works without dynamic memory
+//! allocations. Build sml-rs with `default-features = false` and use
+//!
+//! - [`SmlReader`] / [`transport::DecoderReader`] with a static buffer ([`util::ArrayBuf`]) or
+//!   [`transport::decode_streaming`] to decode the transport protocol,
+//! - [`parser::streaming::Parser`] to parse the decoded messages,
+//! - [`parser::ObisCode`], [`application::quantity::Quantity`] and [`parser::sequence::SequenceChecker`] to interpret the values and
+//! - [`application::extract::Extractor::extract_bytes_with_time_source`] to extract a fixed set of timestamped values.
+//!
+//! APIs requiring `alloc` are marked in their documentation. The following example
+//! is tested with all supported feature combinations:
+//!
+//! ```
+//! use sml_rs::{application::quantity::Quantity, parser::{streaming::Parser, ObisCode}, SmlReader};
+//!
+//! let mut reader = SmlReader::with_static_buffer::<512>().from_slice(include_bytes!("../sample.bin"));
+//! let parser = reader.read::<Parser>().expect("error while decoding");
+//! let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+//! for entry in parser.list_entries() {
+//!     let entry = entry.expect("error while parsing");
+//!     if entry.obis_code() == Some(energy) {
+//!         let quantity = Quantity::from_list_entry(&entry).unwrap();
+//!         assert_eq!(quantity.unit, Some(30));
+//!     }
+//! }
+//! ```
+//!
+//! # Stability
+//!
+//! The [`stable`] module re-exports the parts of the API covered by semver guarantees.
+//! Other modules may still change between minor versions.
+//!
+//! Enums that are expected to grow (e.g. message bodies, values and error types) are
+//! marked `#[non_exhaustive]`, so adding variants isn't a breaking change. Matches on
+//! these enums need a wildcard arm. Prefer accessors such as
+//! [`MessageBody::as_get_list_response`](parser::common::MessageBodyFields::as_get_list_response),
+//! [`Value::as_i64`](parser::common::Value::as_i64) or [`Time::sec_index`](parser::common::Time::sec_index)
+//! where they suffice.
+//!
 //! # Feature flags
 //! - **`std`** (default) — Remove this feature to make the library `no_std` compatible.
 //! - **`alloc`** (default) — Implementations using allocations (`alloc::Vec` et al.).
-//! - **`embedded-hal-02`** — Allows using pins implementing `embedded_hal::serial::Read` in [`SmlReader`](SmlReader::from_eh_reader).
+//! - **`embedded-hal-02`** — Allows using pins implementing `embedded_hal::serial::Read` in [`SmlReader`] (`SmlReader::from_eh_reader`).
 //! - **`nb`** - Enables non-blocking APIs using the `nb` crate.
 //! - **`serde`** - Implements `Serialize` and `Deserialize` on most error types.
+//! - **`fmt-debug`** (default) — Detailed `Debug` implementations for the parsed data structures. Remove this feature to reduce the binary size; `Debug` then only prints the type name.
+//! - **`allocator-api2`** - Parsing into vectors allocated by custom allocators (`parser::complete_in`).
+//! - **`embedded-io`** - Reading from `embedded_io::Read` implementations (`util::EioByteSource`), including polling `ReadReady` sources (`transport::DecoderReader::poll_read`).
+//! - **`embedded-io-async`** - Sending simulated transmissions to `embedded_io_async::Write` implementations (`application::simulator::run`). Implies `alloc`.
+//! - **`futures-io`** - Reading asynchronously from `futures_io::AsyncRead` implementations (async-std, smol) (`util::FuturesIoByteSource`, [`transport::AsyncDecoderReader`]). Implies `std`.
+//! - **`tokio`** - Reading asynchronously from `tokio::io::AsyncRead` implementations (e.g. `tokio_serial::SerialStream`) (`util::TokioByteSource`, [`transport::AsyncDecoderReader`]). Implies `std`.
+//! - **`mqtt`** - Publishing list entries to an MQTT broker using `rumqttc` (`application::export::mqtt`). Implies `std`.
+//! - **`embassy-sync`** - Reading from `embassy_sync::pipe::Pipe`s (`util::PipeByteSource`, `SmlReader::from_pipe_reader`).
+//! - **`small-crc`** - Computes CRC checksums bitwise instead of using a 512-byte lookup table. Slower, but saves flash on small microcontrollers.
+//! - **`rayon`** - Parallel decoding of large captures (`transport::decode_parallel`).
+//! - **`test-util`** - Helpers for regression testing with real-world captures (see `test_util`).
+//!
+//! The enabled features can be queried using the constants in the [`features`] module.
 //!
 #![cfg_attr(not(feature = "std"), no_std)]
 #![cfg_attr(docsrs, feature(doc_auto_cfg))]
 #![deny(unsafe_code)]
 #![warn(missing_docs)]
 
+// Cargo enables the features implied by other features automatically. These checks
+// give a clear error when building with inconsistent features outside of Cargo.
+#[cfg(all(feature = "std", not(feature = "alloc")))]
+compile_error!("the `std` feature requires the `alloc` feature");
+#[cfg(all(feature = "embedded-hal-02", not(feature = "nb")))]
+compile_error!("the `embedded-hal-02` feature requires the `nb` feature");
+#[cfg(all(feature = "rayon", not(feature = "std")))]
+compile_error!("the `rayon` feature requires the `std` feature");
+#[cfg(all(feature = "allocator-api2", not(feature = "alloc")))]
+compile_error!("the `allocator-api2` feature requires the `alloc` feature");
+#[cfg(all(feature = "embedded-io-async", not(feature = "alloc")))]
+compile_error!("the `embedded-io-async` feature requires the `alloc` feature");
+#[cfg(all(
+    feature = "test-util",
+    not(all(feature = "alloc", feature = "fmt-debug"))
+))]
+compile_error!("the `test-util` feature requires the `alloc` and `fmt-debug` features");
+
 use core::fmt;
 use core::{borrow::Borrow, marker::PhantomData};
 
@@ -30,17 +104,27 @@ use parser::ParseError;
 use transport::{DecodeErr, DecoderReader, ReadDecodedError};
 use util::{ArrayBuf, Buffer};
 
-#[cfg(feature = "alloc")]
+#[cfg(any(feature = "alloc", test))]
 extern crate alloc;
 
+pub mod application;
+pub mod features;
 pub mod parser;
+pub mod stable;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod transport;
 pub mod util;
+#[cfg(feature = "alloc")]
+mod writer;
 
 use util::ByteSource;
+#[cfg(feature = "alloc")]
+use util::ByteSourceErr;
 
 /// Error returned by functions parsing sml data read from a reader
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ReadParsedError<ReadErr>
 where
     ReadErr: core::fmt::Debug,
@@ -81,12 +165,56 @@ where
     ReadErr: core::fmt::Debug,
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(self, f)
+        match self {
+            ReadParsedError::ParseErr(e) => write!(f, "error while parsing: {e}"),
+            ReadParsedError::DecodeErr(e) => write!(f, "{e}"),
+            ReadParsedError::IoErr(e, 0) => write!(f, "error while reading: {e:?}"),
+            ReadParsedError::IoErr(e, n) => {
+                write!(f, "error while reading: {e:?} ({n} bytes discarded)")
+            }
+        }
+    }
+}
+
+impl<ReadErr> core::error::Error for ReadParsedError<ReadErr>
+where
+    ReadErr: core::fmt::Debug,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ReadParsedError::ParseErr(e) => Some(e),
+            ReadParsedError::DecodeErr(e) => Some(e),
+            ReadParsedError::IoErr(..) => None,
+        }
     }
 }
 
-#[cfg(feature = "std")]
-impl<ReadErr> std::error::Error for ReadParsedError<ReadErr> where ReadErr: core::fmt::Debug {}
+/// Event produced by [`SmlReader::next_event`]
+///
+/// Combines the results and errors of all layers (byte source, transport and parser)
+/// into a single type.
+///
+/// *This type is available only if sml-rs is built with the `"alloc"` feature.*
+#[cfg(feature = "alloc")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SmlEvent<'i, ReadErr>
+where
+    ReadErr: core::fmt::Debug,
+{
+    /// A transmission has been decoded and parsed successfully
+    Transmission(File<'i>),
+    /// Error while decoding the data (e.g. checksum mismatch)
+    TransportError(DecodeErr),
+    /// A transmission has been decoded successfully, but parsing it failed
+    ParseError(ParseError),
+    /// The byte source doesn't have data available right now ("would block")
+    LinkIdle,
+    /// Error while reading from the internal byte source
+    ///
+    /// (inner_error, num_discarded_bytes)
+    IoError(ReadErr, usize),
+}
 
 // ===========================================================================
 // ===========================================================================
@@ -121,12 +249,13 @@ impl<ReadErr> std::error::Error for ReadParsedError<ReadErr> where ReadErr: core
 ///
 /// | Constructor (`SmlReader::...`)          | Expected data type | Usage examples |
 /// |-----------------------------------------------------|-----------|------------|
-/// |[`from_reader`](SmlReader::from_reader) **¹**             | `impl std::io::Read` | files, sockets, serial ports (see `serialport-rs` crate) |
-/// |[`from_eh_reader`](SmlReader::from_eh_reader) **²** | `impl embedded_hal::serial::Read<u8>` | microcontroller pins |
+/// |`from_reader` **¹**             | `impl std::io::Read` | files, sockets, serial ports (see `serialport-rs` crate) |
+/// |`from_eh_reader` **²** | `impl embedded_hal::serial::Read<u8>` | microcontroller pins |
 /// |[`from_slice`](SmlReader::from_slice)                | `&[u8]` | arrays, vectors, ... |
 /// |[`from_iterator`](SmlReader::from_iterator)                  | `impl IntoIterator<Item = impl Borrow<u8>>)` | anything that can be turned into an iterator over bytes |
+/// |`from_pipe_reader` **³** | `embassy_sync::pipe::Reader` | bytes forwarded by another embassy task |
 ///
-/// ***¹** requires feature `std` (on by default); **²** requires optional feature `embedded_hal`*
+/// ***¹** requires feature `std` (on by default); **²** requires optional feature `embedded_hal`; **³** requires optional feature `embassy-sync`*
 ///
 /// ### Internal Buffer
 ///
@@ -139,7 +268,7 @@ impl<ReadErr> std::error::Error for ReadParsedError<ReadErr> where ReadErr: core
 /// functions for this purpose:
 ///
 /// - [`SmlReader::with_static_buffer<N>()`](SmlReader::with_static_buffer)
-/// - [`SmlReader::with_vec_buffer()`](SmlReader::with_vec_buffer) *(requires feature `alloc` (on by default))*
+/// - `SmlReader::with_vec_buffer()` *(requires feature `alloc` (on by default))*
 ///
 /// These functions return a builder object ([`SmlReaderBuilder`]) that provides methods to create an [`SmlReader`]
 /// from the different data sources shown above.
@@ -193,7 +322,7 @@ impl<ReadErr> std::error::Error for ReadParsedError<ReadErr> where ReadErr: core
 /// transmissions into several different representations:
 ///
 /// - [`DecodedBytes`]: a slice of bytes containing the decoded message. No parsing is done.
-/// - [`File`]: a struct containing completely parsed sml data. (requires feature `"alloc"`)
+/// - `File`: a struct containing completely parsed sml data. (requires feature `"alloc"`)
 /// - [`Parser`]: an streaming parser for sml data.
 ///
 /// **Examples**
@@ -345,6 +474,56 @@ impl DummySmlReader {
             decoder: DecoderReader::new(util::IterByteSource::new(iter.into_iter())),
         }
     }
+
+    /// Build an `SmlReader` from the reading end of an `embassy_sync::pipe::Pipe`.
+    ///
+    /// See [`PipeByteSource`](util::PipeByteSource) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::SmlReader;
+    /// use embassy_sync::{blocking_mutex::raw::NoopRawMutex, pipe::Pipe};
+    ///
+    /// let mut pipe = Pipe::<NoopRawMutex, 512>::new();
+    /// let (pipe_reader, pipe_writer) = pipe.split();
+    /// // the UART task writes the received bytes to `pipe_writer`
+    /// let reader = SmlReader::from_pipe_reader(pipe_reader);
+    /// ```
+    ///
+    /// *This function is available only if sml-rs is built with the `"embassy-sync"` feature.*
+    #[cfg(feature = "embassy-sync")]
+    pub fn from_pipe_reader<M, const N: usize>(
+        reader: embassy_sync::pipe::Reader<'_, M, N>,
+    ) -> SmlReader<util::PipeByteSource<'_, M, N>, DefaultBuffer>
+    where
+        M: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        SmlReader {
+            decoder: DecoderReader::new(util::PipeByteSource::new(reader)),
+        }
+    }
+
+    /// Build an `SmlReader` from a closure reading single bytes.
+    ///
+    /// See [`CustomByteSource`](util::CustomByteSource) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{SmlReader, util::CustomReadError};
+    /// // e.g. a custom UART driver
+    /// let read_byte = || Err(CustomReadError::<()>::WouldBlock);
+    /// let reader = SmlReader::from_fn(read_byte);
+    /// ```
+    pub fn from_fn<F, E>(read: F) -> SmlReader<util::CustomByteSource<F>, DefaultBuffer>
+    where
+        F: FnMut() -> Result<u8, util::CustomReadError<E>>,
+    {
+        SmlReader {
+            decoder: DecoderReader::new(util::CustomByteSource::new(read)),
+        }
+    }
 }
 
 impl<R, ReadErr, Buf> SmlReader<R, Buf>
@@ -372,9 +551,10 @@ where
     /// When reading from a finite data source (such as a file containing a certain
     /// number of transmissions), it's easier to use [`next`](SmlReader::next) instead,
     /// which returns `None` when an EOF is read when trying to read the next transmission.
-    ///
-    /// See also [`read_nb`](DecoderReader::read_nb), which provides a convenient API for
-    /// non-blocking byte sources.
+    #[cfg_attr(
+        feature = "nb",
+        doc = "\nSee also [`read_nb`](DecoderReader::read_nb), which provides a convenient API for non-blocking byte sources."
+    )]
     pub fn read<'i, T>(&'i mut self) -> Result<T, T::Error>
     where
         T: SmlParse<'i, Result<&'i [u8], ReadDecodedError<ReadErr>>>,
@@ -400,9 +580,10 @@ where
     ///
     /// When reading from a data source that will provide data infinitely (such
     /// as from a serial port), it's easier to use [`read`](SmlReader::read) instead.
-    ///
-    /// See also [`next_nb`](SmlReader::next_nb), which provides a convenient API for
-    /// non-blocking byte sources.
+    #[cfg_attr(
+        feature = "nb",
+        doc = "\nSee also [`next_nb`](SmlReader::next_nb), which provides a convenient API for non-blocking byte sources."
+    )]
     pub fn next<'i, T>(&'i mut self) -> Option<Result<T, T::Error>>
     where
         T: SmlParse<'i, Result<&'i [u8], ReadDecodedError<ReadErr>>>,
@@ -478,6 +659,44 @@ where
         };
         T::parse_from(res).map(Some).map_err(nb::Error::Other)
     }
+
+    /// Reads, decodes and parses sml data and returns the outcome as an [`SmlEvent`].
+    ///
+    /// ```
+    /// # use sml_rs::{SmlReader, SmlEvent};
+    /// let data = include_bytes!("../sample.bin");
+    /// let mut reader = SmlReader::from_slice(data.as_slice());
+    ///
+    /// while let Some(event) = reader.next_event() {
+    ///     match event {
+    ///         SmlEvent::Transmission(file) => println!("{:#?}", file),
+    ///         SmlEvent::LinkIdle => { /* wait for data */ }
+    ///         other => println!("Error: {:?}", other),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// Returns `None` if the byte source returns EOF immediately, just like
+    /// [`next`](SmlReader::next). Everything else (including errors of all layers
+    /// and "would block" conditions of the byte source) is returned as an event,
+    /// which allows handling all cases in a single `match` expression.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn next_event(&mut self) -> Option<SmlEvent<'_, ReadErr>>
+    where
+        ReadErr: ByteSourceErr,
+    {
+        Some(match self.decoder.next()? {
+            Ok(bytes) => match parse(bytes) {
+                Ok(file) => SmlEvent::Transmission(file),
+                Err(e) => SmlEvent::ParseError(e),
+            },
+            Err(ReadDecodedError::DecodeErr(e)) => SmlEvent::TransportError(e),
+            Err(ReadDecodedError::IoErr(e, _)) if e.is_would_block() => SmlEvent::LinkIdle,
+            Err(ReadDecodedError::IoErr(e, num_discarded)) => SmlEvent::IoError(e, num_discarded),
+        })
+    }
 }
 
 type DefaultBuffer = ArrayBuf<{ 8 * 1024 }>;
@@ -586,6 +805,54 @@ impl<Buf: Buffer> SmlReaderBuilder<Buf> {
             decoder: DecoderReader::new(util::IterByteSource::new(iter.into_iter())),
         }
     }
+
+    /// Build an `SmlReader` from the reading end of an `embassy_sync::pipe::Pipe`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::SmlReader;
+    /// use embassy_sync::{blocking_mutex::raw::NoopRawMutex, pipe::Pipe};
+    ///
+    /// let mut pipe = Pipe::<NoopRawMutex, 512>::new();
+    /// let (pipe_reader, _pipe_writer) = pipe.split();
+    /// let reader = SmlReader::with_static_buffer::<1024>().from_pipe_reader(pipe_reader);
+    /// ```
+    ///
+    /// *This function is available only if sml-rs is built with the `"embassy-sync"` feature.*
+    #[cfg(feature = "embassy-sync")]
+    pub fn from_pipe_reader<M, const N: usize>(
+        self,
+        reader: embassy_sync::pipe::Reader<'_, M, N>,
+    ) -> SmlReader<util::PipeByteSource<'_, M, N>, Buf>
+    where
+        M: embassy_sync::blocking_mutex::raw::RawMutex,
+    {
+        SmlReader {
+            decoder: DecoderReader::new(util::PipeByteSource::new(reader)),
+        }
+    }
+
+    /// Build an `SmlReader` from a closure reading single bytes.
+    ///
+    /// See [`CustomByteSource`](util::CustomByteSource) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{SmlReader, util::CustomReadError};
+    /// // e.g. a custom UART driver
+    /// let read_byte = || Err(CustomReadError::<()>::WouldBlock);
+    /// let reader = SmlReader::with_static_buffer::<1024>().from_fn(read_byte);
+    /// ```
+    pub fn from_fn<F, E>(self, read: F) -> SmlReader<util::CustomByteSource<F>, Buf>
+    where
+        F: FnMut() -> Result<u8, util::CustomReadError<E>>,
+    {
+        SmlReader {
+            decoder: DecoderReader::new(util::CustomByteSource::new(read)),
+        }
+    }
 }
 
 /// Helper trait implemented for types that can be built from decoded bytes.
@@ -668,6 +935,122 @@ impl<'i> SmlParse<'i, &'i [u8]> for Parser<'i> {
 
 impl<'i> util::private::Sealed for Parser<'i> {}
 
+/// Decodes and parses all transmissions contained in `bytes`, e.g. a capture of a meter's output.
+///
+/// Decoding happens immediately while parsing happens lazily when iterating over the
+/// returned [`Capture`]. Each item is either a parsed [`File`] or the error that occurred
+/// while decoding or parsing the transmission.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// let capture = sml_rs::parse_capture(include_bytes!("../sample.bin"));
+/// for file in &capture {
+///     let file = file.expect("error while decoding or parsing");
+///     println!("{} messages", file.messages.len());
+/// }
+/// ```
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn parse_capture(bytes: &[u8]) -> Capture {
+    Capture {
+        decoded: transport::decode(bytes),
+    }
+}
+
+/// Decoded transmissions returned by [`parse_capture`].
+///
+/// *This type is available only if sml-rs is built with the `"alloc"` feature.*
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    decoded: alloc::vec::Vec<Result<alloc::vec::Vec<u8>, DecodeErr>>,
+}
+
+#[cfg(feature = "alloc")]
+impl Capture {
+    /// Returns an iterator parsing the decoded transmissions.
+    #[must_use]
+    pub fn files(&self) -> CaptureFiles<'_> {
+        CaptureFiles {
+            iter: self.decoded.iter(),
+        }
+    }
+
+    /// Returns the number of items, i.e. the number of transmissions and decoding errors.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.decoded.len()
+    }
+
+    /// Returns `true` if the capture doesn't contain any transmissions or decoding errors.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.decoded.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a Capture {
+    type Item = Result<File<'a>, ReadParsedError<core::convert::Infallible>>;
+    type IntoIter = CaptureFiles<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.files()
+    }
+}
+
+/// Iterator over the parsed transmissions of a [`Capture`].
+///
+/// This struct is created by [`Capture::files`].
+///
+/// *This type is available only if sml-rs is built with the `"alloc"` feature.*
+#[cfg(feature = "alloc")]
+pub struct CaptureFiles<'a> {
+    iter: core::slice::Iter<'a, Result<alloc::vec::Vec<u8>, DecodeErr>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> Iterator for CaptureFiles<'a> {
+    type Item = Result<File<'a>, ReadParsedError<core::convert::Infallible>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(|res| match res {
+            Ok(bytes) => Ok(parse(bytes)?),
+            Err(e) => Err(ReadParsedError::DecodeErr(e.clone())),
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+#[test]
+#[cfg(feature = "alloc")]
+fn test_parse_capture() {
+    let sample = include_bytes!("../sample.bin");
+    let mut bytes = sample.to_vec();
+    bytes.extend_from_slice(&[0x12, 0x34]);
+    // transmission with a valid checksum that doesn't contain an SML File
+    bytes.extend_from_slice(&transport::encode::<util::VecBuf>([0x12, 0x34]).unwrap());
+    bytes.extend_from_slice(sample);
+
+    let capture = parse_capture(&bytes);
+    assert_eq!(capture.len(), 4);
+    let res: alloc::vec::Vec<_> = capture.files().collect();
+    assert!(matches!(&res[0], Ok(f) if f.messages.len() == 3));
+    assert!(matches!(
+        res[1],
+        Err(ReadParsedError::DecodeErr(DecodeErr::DiscardedBytes(2)))
+    ));
+    assert!(matches!(res[2], Err(ReadParsedError::ParseErr(_))));
+    assert_eq!(res[0].as_ref().ok(), res[3].as_ref().ok());
+    assert!(parse_capture(&[]).is_empty());
+}
+
 #[test]
 fn test_smlreader_construction() {
     let arr = [1, 2, 3, 4, 5];
@@ -720,6 +1103,50 @@ fn test_smlreader_eh_construction() {
     SmlReader::with_vec_buffer().from_eh_reader(Pin);
 }
 
+#[test]
+#[cfg(feature = "embassy-sync")]
+fn test_smlreader_pipe() {
+    use embassy_sync::{blocking_mutex::raw::NoopRawMutex, pipe::Pipe};
+    use util::ByteSourceErr;
+
+    let bytes = include_bytes!("../sample.bin");
+    let mut pipe = Pipe::<NoopRawMutex, 1024>::new();
+    let (pipe_reader, pipe_writer) = pipe.split();
+    let mut reader = SmlReader::with_static_buffer::<1024>().from_pipe_reader(pipe_reader);
+
+    // reading from an empty pipe would block
+    let res = reader.read::<DecodedBytes>();
+    assert!(matches!(res, Err(ReadDecodedError::IoErr(e, 0)) if e.is_would_block()));
+
+    assert_eq!(pipe_writer.try_write(bytes), Ok(bytes.len()));
+    assert!(reader.read::<DecodedBytes>().is_ok());
+}
+
+#[test]
+#[cfg(feature = "embassy-sync")]
+fn test_pipe_byte_source_async() {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll},
+    };
+    use embassy_sync::{blocking_mutex::raw::NoopRawMutex, pipe::Pipe};
+
+    let mut pipe = Pipe::<NoopRawMutex, 16>::new();
+    let (pipe_reader, pipe_writer) = pipe.split();
+    let mut source = util::PipeByteSource::new(pipe_reader);
+    let waker = util::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    {
+        let mut fut = pin!(source.read_byte_async());
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(pipe_writer.try_write(&[0x1b, 0x01]), Ok(2));
+        assert_eq!(fut.as_mut().poll(&mut cx), Poll::Ready(0x1b));
+    }
+    assert_eq!(source.read_byte(), Ok(0x01));
+}
+
 mod read_tests {
     #[test]
     fn test_smlreader_reading() {
@@ -744,6 +1171,104 @@ mod read_tests {
         let _ = reader.next::<Parser>();
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_smlreader_next_event() {
+        use super::{SmlEvent, SmlReader};
+        use crate::transport::DecodeErr;
+        use crate::util::Eof;
+
+        let data = include_bytes!("../sample.bin");
+        let mut bytes = [0u8; 2 * 244 + 10];
+        bytes[..244].copy_from_slice(data);
+        // invalidate the checksum of the second transmission
+        bytes[244..488].copy_from_slice(data);
+        bytes[487] ^= 0xFF;
+        // incomplete transmission at the end
+        bytes[488..].copy_from_slice(&data[..10]);
+
+        let mut reader = SmlReader::from_slice(&bytes);
+        assert!(matches!(
+            reader.next_event(),
+            Some(SmlEvent::Transmission(file)) if file.messages.len() == 3
+        ));
+        assert!(matches!(
+            reader.next_event(),
+            Some(SmlEvent::TransportError(DecodeErr::InvalidMessage { .. }))
+        ));
+        assert!(matches!(
+            reader.next_event(),
+            Some(SmlEvent::IoError(Eof, 10))
+        ));
+        assert!(reader.next_event().is_none());
+
+        // successfully decoded transmission that can't be parsed
+        let bytes = crate::transport::encode::<crate::util::VecBuf>([0x12, 0x34]).unwrap();
+        let mut reader = SmlReader::from_slice(&bytes);
+        assert!(matches!(reader.next_event(), Some(SmlEvent::ParseError(_))));
+        assert!(reader.next_event().is_none());
+    }
+
+    #[test]
+    fn test_smlreader_from_fn() {
+        use super::{DecodedBytes, SmlReader};
+        use crate::transport::ReadDecodedError;
+        use crate::util::CustomReadError;
+
+        let data = include_bytes!("../sample.bin");
+        let mut idx = 0;
+        let mut reader = SmlReader::from_fn(|| {
+            idx += 1;
+            match idx {
+                10 => Err(CustomReadError::WouldBlock),
+                i if i < 10 => Ok(data[i - 1]),
+                i if i <= data.len() + 1 => Ok(data[i - 2]),
+                i => Err(CustomReadError::Other(i)),
+            }
+        });
+        assert!(matches!(
+            reader.read::<DecodedBytes>(),
+            Err(ReadDecodedError::IoErr(CustomReadError::WouldBlock, _))
+        ));
+        assert!(reader.read::<DecodedBytes>().is_ok());
+        assert!(matches!(
+            reader.next::<DecodedBytes>(),
+            Some(Err(ReadDecodedError::IoErr(CustomReadError::Other(_), 0)))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_smlreader_next_event_link_idle() {
+        use super::{SmlEvent, SmlReader};
+
+        // reader that returns "would block" on every other call
+        struct IdleReader<'a>(&'a [u8], bool);
+        impl std::io::Read for IdleReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.1 = !self.1;
+                if self.1 {
+                    return Err(std::io::ErrorKind::WouldBlock.into());
+                }
+                self.0.read(buf)
+            }
+        }
+
+        let data = include_bytes!("../sample.bin");
+        let mut reader = SmlReader::from_reader(IdleReader(data, false));
+        let mut num_idle = 0;
+        let mut num_transmissions = 0;
+        while let Some(event) = reader.next_event() {
+            match event {
+                SmlEvent::LinkIdle => num_idle += 1,
+                SmlEvent::Transmission(_) => num_transmissions += 1,
+                other => panic!("Unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(num_idle, data.len() + 1);
+        assert_eq!(num_transmissions, 1);
+    }
+
     #[test]
     #[cfg(feature = "nb")]
     fn test_smlreader_reading_nb() {
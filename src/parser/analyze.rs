@@ -0,0 +1,310 @@
+//! Summarizing the contents of a capture.
+//!
+//! *This module is available only if sml-rs is built with the `"alloc"` feature.*
+
+use alloc::vec::Vec;
+
+use super::{
+    complete::{parse, EntryPosition, MessageBody},
+    ObisCode, ParseError,
+};
+use crate::{
+    application::quantity::Quantity,
+    parser::common::Unit,
+    transport::{decode, DecodeErr},
+};
+
+/// Summary of a capture returned by [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FileAnalysis {
+    /// number of transmissions found in the capture (including invalid ones)
+    pub transmissions: usize,
+    /// number of transmissions that were decoded and parsed successfully
+    pub valid_transmissions: usize,
+    /// number of messages of each type
+    pub messages: MessageCounts,
+    /// statistics of each OBIS code, in the order of their first occurrence
+    pub obis_codes: Vec<ObisStats>,
+    /// problems encountered while analyzing the capture
+    pub warnings: Vec<AnalysisWarning>,
+}
+
+impl FileAnalysis {
+    /// Returns the statistics of `obis_code` or `None` if it isn't contained in the capture.
+    #[must_use]
+    pub fn get(&self, obis_code: ObisCode) -> Option<&ObisStats> {
+        self.obis_codes.iter().find(|s| s.obis_code == obis_code)
+    }
+}
+
+/// Number of messages of each type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MessageCounts {
+    /// number of `OpenResponse` messages
+    pub open_response: usize,
+    /// number of `GetListResponse` messages
+    pub get_list_response: usize,
+    /// number of `CloseResponse` messages
+    pub close_response: usize,
+}
+
+/// Statistics of the list entries with a single OBIS code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObisStats {
+    /// the OBIS code
+    pub obis_code: ObisCode,
+    /// number of list entries with this OBIS code
+    pub count: usize,
+    /// units used by the entries, in the order of their first occurrence
+    pub units: Vec<Option<Unit>>,
+    /// smallest numeric value
+    ///
+    /// `None` if no entry has a numeric value or if the entries use different units.
+    pub min: Option<Quantity>,
+    /// largest numeric value
+    ///
+    /// `None` if no entry has a numeric value or if the entries use different units.
+    pub max: Option<Quantity>,
+}
+
+impl ObisStats {
+    fn new(obis_code: ObisCode) -> Self {
+        ObisStats {
+            obis_code,
+            count: 0,
+            units: Vec::new(),
+            min: None,
+            max: None,
+        }
+    }
+
+    fn record(&mut self, unit: Option<Unit>, value: Option<Quantity>) {
+        self.count += 1;
+        if !self.units.contains(&unit) {
+            self.units.push(unit);
+        }
+        if self.units.len() > 1 {
+            self.min = None;
+            self.max = None;
+            return;
+        }
+        let Some(value) = value else {
+            return;
+        };
+        let is = |other: Option<Quantity>, ordering| {
+            other.map_or(true, |o| value.cmp_value(&o) == Some(ordering))
+        };
+        if is(self.min, core::cmp::Ordering::Less) {
+            self.min = Some(value);
+        }
+        if is(self.max, core::cmp::Ordering::Greater) {
+            self.max = Some(value);
+        }
+    }
+}
+
+/// A problem encountered by [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisWarning {
+    /// A transmission couldn't be decoded
+    Decode {
+        /// index of the transmission in the capture
+        transmission: usize,
+        /// the decoding error
+        error: DecodeErr,
+    },
+    /// A transmission couldn't be parsed
+    Parse {
+        /// index of the transmission in the capture
+        transmission: usize,
+        /// the parsing error
+        error: ParseError,
+    },
+    /// A list entry doesn't contain a valid OBIS code
+    InvalidObisCode {
+        /// index of the transmission in the capture
+        transmission: usize,
+        /// position of the entry within the transmission
+        position: EntryPosition,
+    },
+}
+
+/// Decodes and parses a capture of transport-encoded SML data and summarizes its contents.
+///
+/// In contrast to [`complete::parse`](super::complete::parse), invalid transmissions
+/// don't abort the analysis. They are reported as warnings instead. This makes the
+/// function useful for comparing meters and documenting the data a device emits.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{analyze, ObisCode};
+/// let analysis = analyze(include_bytes!("../../sample.bin"));
+/// assert!(analysis.warnings.is_empty());
+/// assert_eq!(analysis.messages.get_list_response, analysis.transmissions);
+/// let energy = analysis.get(ObisCode::ENERGY_IMPORT_TOTAL).unwrap();
+/// assert_eq!(energy.count, analysis.transmissions);
+/// ```
+#[must_use]
+pub fn analyze(bytes: &[u8]) -> FileAnalysis {
+    let mut analysis = FileAnalysis::default();
+    for (transmission, res) in decode(bytes).into_iter().enumerate() {
+        analysis.transmissions += 1;
+        let decoded = match res {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                let warning = AnalysisWarning::Decode {
+                    transmission,
+                    error,
+                };
+                analysis.warnings.push(warning);
+                continue;
+            }
+        };
+        let file = match parse(&decoded) {
+            Ok(file) => file,
+            Err(error) => {
+                let warning = AnalysisWarning::Parse {
+                    transmission,
+                    error,
+                };
+                analysis.warnings.push(warning);
+                continue;
+            }
+        };
+        analysis.valid_transmissions += 1;
+
+        for msg in &file.messages {
+            let counter = match msg.message_body {
+                MessageBody::OpenResponse(_) => &mut analysis.messages.open_response,
+                MessageBody::GetListResponse(_) => &mut analysis.messages.get_list_response,
+                MessageBody::CloseResponse(_) => &mut analysis.messages.close_response,
+            };
+            *counter += 1;
+        }
+        for (position, entry) in file.list_entries() {
+            let Some(obis_code) = entry.obis_code() else {
+                let warning = AnalysisWarning::InvalidObisCode {
+                    transmission,
+                    position,
+                };
+                analysis.warnings.push(warning);
+                continue;
+            };
+            let idx = match analysis
+                .obis_codes
+                .iter()
+                .position(|s| s.obis_code == obis_code)
+            {
+                Some(idx) => idx,
+                None => {
+                    analysis.obis_codes.push(ObisStats::new(obis_code));
+                    analysis.obis_codes.len() - 1
+                }
+            };
+            analysis.obis_codes[idx].record(entry.unit, Quantity::from_list_entry(entry));
+        }
+    }
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{application::simulator::Meter, transport::encode, util::VecBuf};
+
+    const ENERGY: ObisCode = ObisCode::ENERGY_IMPORT_TOTAL;
+    const POWER: ObisCode = ObisCode::ACTIVE_POWER;
+
+    #[test]
+    fn statistics() {
+        let mut meter = Meter::new(b"meter")
+            .register(ENERGY, Some(30), -1, |s| (s * 10).into())
+            .register(POWER, Some(27), 0, |s| 500 - i64::from(s));
+        let mut capture = Vec::new();
+        for s in [20, 10, 30] {
+            capture.extend(meter.next_frame(s));
+        }
+
+        let analysis = analyze(&capture);
+        assert_eq!(analysis.transmissions, 3);
+        assert_eq!(analysis.valid_transmissions, 3);
+        assert_eq!(
+            analysis.messages,
+            MessageCounts {
+                open_response: 3,
+                get_list_response: 3,
+                close_response: 3,
+            }
+        );
+        assert!(analysis.warnings.is_empty());
+        let codes: Vec<_> = analysis.obis_codes.iter().map(|s| s.obis_code).collect();
+        assert_eq!(codes, [ENERGY, POWER]);
+
+        let energy = analysis.get(ENERGY).unwrap();
+        assert_eq!(energy.count, 3);
+        assert_eq!(energy.units, [Some(30)]);
+        let wh = |value| Quantity {
+            value,
+            scaler: -1,
+            unit: Some(30),
+        };
+        assert_eq!((energy.min, energy.max), (Some(wh(100)), Some(wh(300))));
+        let power = analysis.get(POWER).unwrap();
+        assert_eq!(power.min.unwrap().value, 470);
+        assert_eq!(power.max.unwrap().value, 490);
+        assert_eq!(analysis.get(ObisCode::VOLTAGE_L1), None);
+    }
+
+    #[test]
+    fn warnings() {
+        let mut meter = Meter::new(b"meter").register(ENERGY, Some(30), 0, |_| 1);
+        let valid = meter.next_frame(0);
+        // a transmission with a corrupted message CRC
+        let mut corrupted = decode(meter.next_frame(1)).remove(0).unwrap();
+        let len = corrupted.len();
+        corrupted[len - 2] ^= 0xFF;
+        let mut capture = encode::<VecBuf>(corrupted).unwrap();
+        capture.extend_from_slice(&valid);
+        capture.extend_from_slice(&valid[..valid.len() - 1]);
+
+        let analysis = analyze(&capture);
+        assert_eq!(analysis.transmissions, 3);
+        assert_eq!(analysis.valid_transmissions, 1);
+        assert_eq!(analysis.get(ENERGY).unwrap().count, 1);
+        assert!(matches!(
+            analysis.warnings[..],
+            [
+                AnalysisWarning::Parse {
+                    transmission: 0,
+                    ..
+                },
+                AnalysisWarning::Decode {
+                    transmission: 2,
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn mixed_units() {
+        let mut stats = ObisStats::new(ENERGY);
+        let q = |value, unit| Quantity {
+            value,
+            scaler: 0,
+            unit,
+        };
+        stats.record(Some(30), Some(q(1, Some(30))));
+        stats.record(Some(30), None);
+        assert_eq!(
+            (stats.min, stats.max),
+            (Some(q(1, Some(30))), Some(q(1, Some(30))))
+        );
+        stats.record(None, Some(q(2, None)));
+        assert_eq!(stats.units, [Some(30), None]);
+        assert_eq!((stats.count, stats.min, stats.max), (3, None, None));
+    }
+}
@@ -0,0 +1,156 @@
+//! Codepages used to encode text contained in octet strings.
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Codepage announced in the `codepage` field of an `OpenResponse`.
+///
+/// SML defaults to ISO 8859-15 if the field is omitted, which is the case for
+/// virtually all meters. The other codepages have been seen in the wild.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::Codepage;
+/// assert_eq!(Codepage::from_name(b"ISO 8859-15"), Some(Codepage::Iso8859_15));
+/// assert_eq!(Codepage::from_name(b"utf-8"), Some(Codepage::Utf8));
+///
+/// assert_eq!(Codepage::Iso8859_15.to_str(b"ABC"), Some("ABC"));
+/// # #[cfg(feature = "alloc")]
+/// assert_eq!(Codepage::Iso8859_15.decode(&[0xA4, 0x31]), "€1");
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Codepage {
+    /// ISO 8859-15 (Latin-9), the default codepage of SML
+    #[default]
+    Iso8859_15,
+    /// ISO 8859-1 (Latin-1)
+    Iso8859_1,
+    /// UTF-8
+    Utf8,
+}
+
+impl Codepage {
+    /// Looks up a codepage by the name used in the `codepage` field of an `OpenResponse`.
+    ///
+    /// The comparison ignores case as well as spaces, hyphens and underscores.
+    /// Returns `None` for unknown codepages.
+    #[must_use]
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        let mut normalized = [0u8; 16];
+        let mut len = 0;
+        for &b in name {
+            if matches!(b, b' ' | b'-' | b'_') {
+                continue;
+            }
+            *normalized.get_mut(len)? = b.to_ascii_uppercase();
+            len += 1;
+        }
+        match &normalized[..len] {
+            b"ISO885915" | b"LATIN9" => Some(Codepage::Iso8859_15),
+            b"ISO88591" | b"LATIN1" => Some(Codepage::Iso8859_1),
+            b"UTF8" => Some(Codepage::Utf8),
+            _ => None,
+        }
+    }
+
+    /// Returns `bytes` as `&str` if this is possible without conversion.
+    ///
+    /// This is the case for valid UTF-8 in the `Utf8` codepage and for ASCII
+    /// text in the other codepages. Returns `None` otherwise.
+    #[must_use]
+    pub fn to_str(self, bytes: &[u8]) -> Option<&str> {
+        match self {
+            Codepage::Utf8 => core::str::from_utf8(bytes).ok(),
+            Codepage::Iso8859_15 | Codepage::Iso8859_1 if bytes.is_ascii() => {
+                core::str::from_utf8(bytes).ok()
+            }
+            Codepage::Iso8859_15 | Codepage::Iso8859_1 => None,
+        }
+    }
+
+    /// Decodes `bytes` into a string.
+    ///
+    /// Borrows from `bytes` if no conversion is necessary. Invalid UTF-8 is
+    /// replaced by `U+FFFD`.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        if let Some(s) = self.to_str(bytes) {
+            return Cow::Borrowed(s);
+        }
+        match self {
+            Codepage::Utf8 => String::from_utf8_lossy(bytes),
+            Codepage::Iso8859_15 => Cow::Owned(bytes.iter().map(|&b| latin9_char(b)).collect()),
+            Codepage::Iso8859_1 => Cow::Owned(bytes.iter().map(|&b| char::from(b)).collect()),
+        }
+    }
+}
+
+// ISO 8859-15 differs from ISO 8859-1 in eight code points
+#[cfg(feature = "alloc")]
+fn latin9_char(b: u8) -> char {
+    match b {
+        0xA4 => '€',
+        0xA6 => 'Š',
+        0xA8 => 'š',
+        0xB4 => 'Ž',
+        0xB8 => 'ž',
+        0xBC => 'Œ',
+        0xBD => 'œ',
+        0xBE => 'Ÿ',
+        b => char::from(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn from_name() {
+        for name in [
+            &b"ISO 8859-15"[..],
+            b"iso-8859-15",
+            b"ISO_8859_15",
+            b"latin9",
+        ] {
+            assert_eq!(Codepage::from_name(name), Some(Codepage::Iso8859_15));
+        }
+        assert_eq!(
+            Codepage::from_name(b"ISO-8859-1"),
+            Some(Codepage::Iso8859_1)
+        );
+        assert_eq!(Codepage::from_name(b"UTF8"), Some(Codepage::Utf8));
+        assert_eq!(Codepage::from_name(b"ISO 8859-2"), None);
+        assert_eq!(Codepage::from_name(b""), None);
+        assert_eq!(Codepage::from_name(&[b'A'; 17]), None);
+    }
+
+    #[test]
+    fn to_str() {
+        assert_eq!(Codepage::Iso8859_15.to_str(b"EMH"), Some("EMH"));
+        assert_eq!(Codepage::Iso8859_15.to_str(&[0xA4]), None);
+        assert_eq!(Codepage::Utf8.to_str("€".as_bytes()), Some("€"));
+        assert_eq!(Codepage::Utf8.to_str(&[0xA4]), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn decode() {
+        let bytes = [b'a', 0xA4, 0xBE, 0xE4];
+        assert_eq!(Codepage::Iso8859_15.decode(&bytes), "a€Ÿä");
+        assert_eq!(Codepage::Iso8859_1.decode(&bytes), "a¤¾ä");
+        assert_eq!(Codepage::Utf8.decode(&bytes), "a\u{FFFD}\u{FFFD}\u{FFFD}");
+        assert!(matches!(
+            Codepage::Iso8859_1.decode(b"abc"),
+            Cow::Borrowed("abc")
+        ));
+    }
+}
@@ -1,11 +1,15 @@
 //! Types used by both parsers.
 
-pub use super::OctetStr;
 use super::{
     map, take, take_byte,
     tlf::{Ty, TypeLengthField},
-    NumberFormatter, OctetStrFormatter, ParseError, ResTy, SmlParse, SmlParseTlf,
+    Codepage, ObisCode, ParseError, ResTy, SmlParse, SmlParseTlf,
 };
+#[cfg(feature = "fmt-debug")]
+use super::{NumberFormatter, OctetStrFormatter};
+pub use super::{OctetStr, OctetStrExt};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Eq, Clone)]
 /// `SML_PublicOpen.Res` message
@@ -48,6 +52,44 @@ impl<'i> SmlParseTlf<'i> for OpenResponse<'i> {
     }
 }
 
+impl OpenResponse<'_> {
+    /// Returns the version of the SML protocol, which defaults to version 1 if it is omitted.
+    #[must_use]
+    pub fn version(&self) -> SmlVersion {
+        self.sml_version.map_or(SmlVersion::V1, SmlVersion::from)
+    }
+
+    /// Returns the `req_file_id` interpreted as big endian unsigned integer.
+    ///
+    /// Most meters use an incrementing counter or a timestamp as `req_file_id`.
+    /// Returns `None` if it is empty or longer than 8 bytes.
+    ///
+    /// See [`file_id`](crate::application::file_id) for detecting missed transmissions.
+    #[must_use]
+    pub fn req_file_id_as_u64(&self) -> Option<u64> {
+        if self.req_file_id.is_empty() || self.req_file_id.len() > 8 {
+            return None;
+        }
+        let mut bytes = [0u8; 8];
+        bytes[8 - self.req_file_id.len()..].copy_from_slice(self.req_file_id);
+        Some(u64::from_be_bytes(bytes))
+    }
+
+    /// Returns the announced codepage or the default codepage (ISO 8859-15) if it is omitted.
+    ///
+    /// Returns `None` if the codepage is unknown.
+    #[must_use]
+    pub fn known_codepage(&self) -> Option<Codepage> {
+        match self.codepage {
+            Some(name) => Codepage::from_name(name),
+            None => Some(Codepage::default()),
+        }
+    }
+}
+
+impl_minimal_debug!(OpenResponse<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for OpenResponse<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("OpenResponse");
@@ -69,6 +111,50 @@ impl<'i> core::fmt::Debug for OpenResponse<'i> {
     }
 }
 
+/// Version of the SML protocol announced in an `OpenResponse`.
+///
+/// This crate implements version 1 of the SML protocol. See
+/// [`ParseOptions::reject_unsupported_versions`](super::ParseOptions::reject_unsupported_versions)
+/// to detect meters using other versions.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SmlVersion {
+    /// SML version 1 (default)
+    V1,
+    /// SML version 2
+    V2,
+    /// Any other version
+    Other(u8),
+}
+
+impl SmlVersion {
+    /// Returns whether the version is supported by this crate.
+    #[must_use]
+    pub const fn is_supported(self) -> bool {
+        matches!(self, SmlVersion::V1)
+    }
+}
+
+impl From<u8> for SmlVersion {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => SmlVersion::V1,
+            2 => SmlVersion::V2,
+            x => SmlVersion::Other(x),
+        }
+    }
+}
+
+impl From<SmlVersion> for u8 {
+    fn from(value: SmlVersion) -> Self {
+        match value {
+            SmlVersion::V1 => 1,
+            SmlVersion::V2 => 2,
+            SmlVersion::Other(x) => x,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Clone)]
 /// SML ListEntry type
 pub struct ListEntry<'i> {
@@ -88,6 +174,56 @@ pub struct ListEntry<'i> {
     pub value_signature: Option<Signature<'i>>,
 }
 
+impl ListEntry<'_> {
+    /// Returns the OBIS code contained in `obj_name`.
+    ///
+    /// Returns `None` if `obj_name` isn't six bytes long.
+    #[must_use]
+    pub fn obis_code(&self) -> Option<ObisCode> {
+        ObisCode::from_bytes(self.obj_name)
+    }
+
+    /// Returns the time at which the value was obtained as a `SecIndex`.
+    ///
+    /// Uses `val_time` if it contains a `SecIndex` and `default` otherwise. Meters
+    /// usually leave `val_time` empty, in which case the `act_sensor_time` of the
+    /// surrounding `GetListResponse` is a good choice for `default`.
+    #[must_use]
+    pub fn sec_index_or(&self, default: u32) -> u32 {
+        self.val_time
+            .as_ref()
+            .and_then(Time::sec_index)
+            .unwrap_or(default)
+    }
+}
+
+#[cfg(test)]
+impl<'i> ListEntry<'i> {
+    // list entry with the given name and value, all optional fields are `None`
+    pub(crate) fn new_test(obj_name: &'i [u8], value: Value<'i>) -> Self {
+        ListEntry {
+            obj_name,
+            status: None,
+            val_time: None,
+            unit: None,
+            scaler: None,
+            value,
+            value_signature: None,
+        }
+    }
+
+    pub(crate) fn with_unit(mut self, unit: Option<Unit>, scaler: i8) -> Self {
+        self.unit = unit;
+        self.scaler = Some(scaler);
+        self
+    }
+
+    pub(crate) fn with_val_time(mut self, val_time: Option<Time>) -> Self {
+        self.val_time = val_time;
+        self
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for ListEntry<'i> {
     fn check_tlf(tlf: &TypeLengthField) -> bool {
         *tlf == TypeLengthField::new(Ty::ListOf, 7usize as u32)
@@ -114,6 +250,29 @@ impl<'i> SmlParseTlf<'i> for ListEntry<'i> {
     }
 }
 
+// minimum length of an encoded `ListEntry` (tlf + 7 fields taking at least one byte each)
+pub(crate) const MIN_LIST_ENTRY_LEN: usize = 8;
+
+impl<'i> ListEntry<'i> {
+    // parses the entry with index `parsed` of a list announced to contain `expected` entries
+    //
+    // Running out of input is reported as `ParseError::TruncatedList`, which allows
+    // distinguishing cut off transmissions from corrupted ones.
+    pub(crate) fn parse_in_list(input: &'i [u8], expected: u32, parsed: u32) -> ResTy<'i, Self> {
+        let truncated = ParseError::TruncatedList { expected, parsed };
+        if input.len() < MIN_LIST_ENTRY_LEN {
+            return Err(truncated);
+        }
+        ListEntry::parse(input).map_err(|e| match e {
+            ParseError::UnexpectedEOF => truncated,
+            e => e,
+        })
+    }
+}
+
+impl_minimal_debug!(ListEntry<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for ListEntry<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("ListEntry");
@@ -141,6 +300,7 @@ impl<'i> core::fmt::Debug for ListEntry<'i> {
 #[derive(PartialEq, Eq, Clone)]
 /// SML value type
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub enum Value<'i> {
     Bool(bool),
     Bytes(OctetStr<'i>),
@@ -155,6 +315,167 @@ pub enum Value<'i> {
     List(ListType),
 }
 
+impl<'i> Value<'i> {
+    /// Returns the value as `i64` if it is an integer that fits into an `i64`.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::I8(x) => Some(x.into()),
+            Value::I16(x) => Some(x.into()),
+            Value::I32(x) => Some(x.into()),
+            Value::I64(x) => Some(x),
+            Value::U8(x) => Some(x.into()),
+            Value::U16(x) => Some(x.into()),
+            Value::U32(x) => Some(x.into()),
+            Value::U64(x) => x.try_into().ok(),
+            Value::Bool(_) | Value::Bytes(_) | Value::List(_) => None,
+        }
+    }
+
+    /// Returns the content of a `Bytes` value.
+    #[must_use]
+    pub fn as_bytes(&self) -> Option<OctetStr<'i>> {
+        match *self {
+            Value::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Returns the content of a `Bool` value.
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Returns the type of the value as sent by the meter.
+    ///
+    /// This allows keeping track of the original type after converting the value
+    /// (e.g. using [`as_i64`](Value::as_i64)). See [`ValueKind::value_from_i64`] for
+    /// the reverse direction.
+    #[must_use]
+    pub fn kind(&self) -> ValueKind {
+        match self {
+            Value::Bool(_) => ValueKind::Bool,
+            Value::Bytes(_) => ValueKind::Bytes,
+            Value::I8(_) => ValueKind::I8,
+            Value::I16(_) => ValueKind::I16,
+            Value::I32(_) => ValueKind::I32,
+            Value::I64(_) => ValueKind::I64,
+            Value::U8(_) => ValueKind::U8,
+            Value::U16(_) => ValueKind::U16,
+            Value::U32(_) => ValueKind::U32,
+            Value::U64(_) => ValueKind::U64,
+            Value::List(_) => ValueKind::List,
+        }
+    }
+
+    /// Interprets a 4-byte `Bytes` value as a big-endian IEEE 754 single-precision float.
+    ///
+    /// SML doesn't define floating point values, but some vendor-specific messages
+    /// transmit them as octet strings. Returns `None` if the value isn't a `Bytes`
+    /// value of length 4. Whether the value actually contains a float can't be
+    /// determined from the value itself and has to be known from the vendor's documentation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::common::Value;
+    /// let value = Value::Bytes(&[0x41, 0x48, 0x00, 0x00]);
+    /// assert_eq!(value.bytes_as_f32_be(), Some(12.5));
+    /// assert_eq!(Value::Bytes(&[0x00, 0x00, 0x48, 0x41]).bytes_as_f32_le(), Some(12.5));
+    /// assert_eq!(value.bytes_as_f64_be(), None);
+    /// assert_eq!(Value::U32(0x41480000).bytes_as_f32_be(), None);
+    /// ```
+    #[must_use]
+    pub fn bytes_as_f32_be(&self) -> Option<f32> {
+        self.fixed_bytes().map(f32::from_be_bytes)
+    }
+
+    /// Interprets a 4-byte `Bytes` value as a little-endian IEEE 754 single-precision float.
+    ///
+    /// See [`bytes_as_f32_be`](Value::bytes_as_f32_be).
+    #[must_use]
+    pub fn bytes_as_f32_le(&self) -> Option<f32> {
+        self.fixed_bytes().map(f32::from_le_bytes)
+    }
+
+    /// Interprets an 8-byte `Bytes` value as a big-endian IEEE 754 double-precision float.
+    ///
+    /// See [`bytes_as_f32_be`](Value::bytes_as_f32_be).
+    #[must_use]
+    pub fn bytes_as_f64_be(&self) -> Option<f64> {
+        self.fixed_bytes().map(f64::from_be_bytes)
+    }
+
+    /// Interprets an 8-byte `Bytes` value as a little-endian IEEE 754 double-precision float.
+    ///
+    /// See [`bytes_as_f32_be`](Value::bytes_as_f32_be).
+    #[must_use]
+    pub fn bytes_as_f64_le(&self) -> Option<f64> {
+        self.fixed_bytes().map(f64::from_le_bytes)
+    }
+
+    // returns the content of a `Bytes` value of length `N`
+    fn fixed_bytes<const N: usize>(&self) -> Option<[u8; N]> {
+        self.as_bytes()?.try_into().ok()
+    }
+
+    /// Decodes a `Bytes` value into text using `codepage`.
+    ///
+    /// Use [`File::codepage`](super::complete::File::codepage) or [`OpenResponse::known_codepage`]
+    /// to obtain the codepage of a transmission. Returns `None` if the value isn't a `Bytes` value.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::{common::Value, Codepage};
+    /// let value = Value::Bytes(&[0x31, 0x30, 0x20, 0xA4]);
+    /// assert_eq!(value.as_text(Codepage::Iso8859_15).unwrap(), "10 €");
+    /// assert_eq!(Value::U8(1).as_text(Codepage::Iso8859_15), None);
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn as_text(&self, codepage: Codepage) -> Option<alloc::borrow::Cow<'i, str>> {
+        match self {
+            Value::Bytes(bytes) => Some(codepage.decode(bytes)),
+            _ => None,
+        }
+    }
+
+    /// Parses the content of a `Bytes` value as an SML File.
+    ///
+    /// Some meters embed SML-encoded data (e.g. signed datasets) inside octet
+    /// strings. Returns `None` if the value isn't a `Bytes` value.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::common::Value;
+    /// // CloseResponse message
+    /// let bytes = [0x76, 0x5, 0xdd, 0x43, 0x44, 0x0, 0x62, 0x0, 0x62, 0x0, 0x72, 0x63, 0x2, 0x1, 0x71, 0x1, 0x63, 0xfd, 0x56, 0x0];
+    /// let value = Value::Bytes(&bytes);
+    /// let file = value.parse_nested().unwrap().unwrap();
+    /// assert_eq!(file.messages.len(), 1);
+    ///
+    /// assert!(Value::U8(1).parse_nested().is_none());
+    /// ```
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn parse_nested(&self) -> Option<Result<super::complete::File<'i>, ParseError>> {
+        match self {
+            Value::Bytes(bytes) => Some(super::complete::parse(bytes)),
+            _ => None,
+        }
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for Value<'i> {
     fn check_tlf(_tlf: &TypeLengthField) -> bool {
         true
@@ -177,11 +498,17 @@ impl<'i> SmlParseTlf<'i> for Value<'i> {
             tlf if <ListType>::check_tlf(tlf) => {
                 map(<ListType>::parse_with_tlf(input, tlf), Self::List)
             }
-            _ => Err(ParseError::TlfMismatch(core::any::type_name::<Self>())),
+            _ => Err(ParseError::TlfMismatch {
+                expected: core::any::type_name::<Self>(),
+                found: *tlf,
+            }),
         }
     }
 }
 
+impl_minimal_debug!(Value<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for Value<'i> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -200,13 +527,97 @@ impl<'i> core::fmt::Debug for Value<'i> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Type of a [`Value`] without its content.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::common::{Value, ValueKind};
+/// let value = Value::U32(1234);
+/// let (kind, x) = (value.kind(), value.as_i64().unwrap());
+/// assert_eq!(kind, ValueKind::U32);
+/// // restore the original value, e.g. for re-encoding
+/// assert_eq!(kind.value_from_i64(x), Some(value));
+/// assert_eq!(kind.value_from_i64(-1), None);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum ValueKind {
+    Bool,
+    Bytes,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    List,
+}
+
+impl ValueKind {
+    /// Returns whether the kind is one of the integer types.
+    #[must_use]
+    pub const fn is_integer(self) -> bool {
+        !matches!(self, ValueKind::Bool | ValueKind::Bytes | ValueKind::List)
+    }
+
+    /// Returns whether the kind is one of the signed integer types.
+    #[must_use]
+    pub const fn is_signed(self) -> bool {
+        matches!(
+            self,
+            ValueKind::I8 | ValueKind::I16 | ValueKind::I32 | ValueKind::I64
+        )
+    }
+
+    /// Returns the size of the integer types in bytes.
+    ///
+    /// Returns `None` if the kind isn't an integer type.
+    #[must_use]
+    pub const fn int_size(self) -> Option<usize> {
+        match self {
+            ValueKind::I8 | ValueKind::U8 => Some(1),
+            ValueKind::I16 | ValueKind::U16 => Some(2),
+            ValueKind::I32 | ValueKind::U32 => Some(4),
+            ValueKind::I64 | ValueKind::U64 => Some(8),
+            ValueKind::Bool | ValueKind::Bytes | ValueKind::List => None,
+        }
+    }
+
+    /// Constructs a value of this kind from `x`.
+    ///
+    /// This is the reverse of [`Value::as_i64`]. Returns `None` if the kind isn't
+    /// an integer type or `x` is out of its range.
+    #[must_use]
+    pub fn value_from_i64(self, x: i64) -> Option<Value<'static>> {
+        Some(match self {
+            ValueKind::I8 => Value::I8(x.try_into().ok()?),
+            ValueKind::I16 => Value::I16(x.try_into().ok()?),
+            ValueKind::I32 => Value::I32(x.try_into().ok()?),
+            ValueKind::I64 => Value::I64(x),
+            ValueKind::U8 => Value::U8(x.try_into().ok()?),
+            ValueKind::U16 => Value::U16(x.try_into().ok()?),
+            ValueKind::U32 => Value::U32(x.try_into().ok()?),
+            ValueKind::U64 => Value::U64(x.try_into().ok()?),
+            ValueKind::Bool | ValueKind::Bytes | ValueKind::List => return None,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
 /// SML ListType type
 pub enum ListType {
     /// variant containing time information
     Time(Time),
 }
 
+impl_minimal_debug!(ListType);
+
 impl<'i> SmlParseTlf<'i> for ListType {
     fn check_tlf(tlf: &TypeLengthField) -> bool {
         tlf.ty == Ty::ListOf && tlf.len == 2
@@ -237,6 +648,19 @@ pub enum Status {
     Status64(u64),
 }
 
+impl Status {
+    /// Returns the status word widened to `u64`.
+    #[must_use]
+    pub fn as_u64(&self) -> u64 {
+        match *self {
+            Status::Status8(x) => x.into(),
+            Status::Status16(x) => x.into(),
+            Status::Status32(x) => x.into(),
+            Status::Status64(x) => x,
+        }
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for Status {
     fn check_tlf(_tlf: &TypeLengthField) -> bool {
         true
@@ -248,11 +672,17 @@ impl<'i> SmlParseTlf<'i> for Status {
             tlf if <u16>::check_tlf(tlf) => map(<u16>::parse_with_tlf(input, tlf), Self::Status16),
             tlf if <u32>::check_tlf(tlf) => map(<u32>::parse_with_tlf(input, tlf), Self::Status32),
             tlf if <u64>::check_tlf(tlf) => map(<u64>::parse_with_tlf(input, tlf), Self::Status64),
-            _ => Err(ParseError::TlfMismatch(core::any::type_name::<Self>())),
+            _ => Err(ParseError::TlfMismatch {
+                expected: core::any::type_name::<Self>(),
+                found: *tlf,
+            }),
         }
     }
 }
 
+impl_minimal_debug!(Status);
+
+#[cfg(feature = "fmt-debug")]
 impl ::core::fmt::Debug for Status {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -286,6 +716,9 @@ impl<'i> SmlParseTlf<'i> for CloseResponse<'i> {
     }
 }
 
+impl_minimal_debug!(CloseResponse<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for CloseResponse<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("CloseResponse");
@@ -300,7 +733,7 @@ impl<'i> core::fmt::Debug for CloseResponse<'i> {
 pub(crate) struct EndOfSmlMessage;
 
 impl<'i> SmlParse<'i> for EndOfSmlMessage {
-    fn parse(input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
         let (input, b) = take_byte(input)?;
         if b != 0x00 {
             return Err(ParseError::MsgEndMismatch);
@@ -311,11 +744,22 @@ impl<'i> SmlParse<'i> for EndOfSmlMessage {
 
 #[derive(PartialEq, Eq, Clone)]
 /// SML Time type
+#[non_exhaustive]
 pub enum Time {
     /// usually the number of seconds since the power meter was installed
     SecIndex(u32),
 }
 
+impl Time {
+    /// Returns the `SecIndex` value or `None` if the time uses a different representation.
+    #[must_use]
+    pub fn sec_index(&self) -> Option<u32> {
+        match *self {
+            Time::SecIndex(t) => Some(t),
+        }
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for Time {
     fn check_tlf(tlf: &TypeLengthField) -> bool {
         (tlf.ty == Ty::ListOf && tlf.len == 2) || *tlf == TypeLengthField::new(Ty::Unsigned, 4)
@@ -343,6 +787,9 @@ impl<'i> SmlParseTlf<'i> for Time {
     }
 }
 
+impl_minimal_debug!(Time);
+
+#[cfg(feature = "fmt-debug")]
 impl ::core::fmt::Debug for Time {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -353,3 +800,99 @@ impl ::core::fmt::Debug for Time {
 
 /// SML signature type
 pub type Signature<'i> = OctetStr<'i>;
+
+/// Fields of the `SML_GetList.Res` message shared by the types of all parsers.
+///
+/// Implemented by `complete::GetListResponse` and
+/// [`streaming::GetListResponseStart`](super::streaming::GetListResponseStart), which
+/// allows writing code that is generic over the parser being used.
+pub trait GetListResponseFields<'i> {
+    /// identification of the client
+    fn client_id(&self) -> Option<OctetStr<'i>>;
+    /// identification of the server
+    fn server_id(&self) -> OctetStr<'i>;
+    /// name of the list
+    fn list_name(&self) -> Option<OctetStr<'i>>;
+    /// optional sensor time information
+    fn act_sensor_time(&self) -> Option<&Time>;
+}
+
+/// Accessors of the message bodies shared by the types of all parsers.
+///
+/// Implemented by `complete::MessageBody` and
+/// [`streaming::MessageBody`](super::streaming::MessageBody).
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{common::{GetListResponseFields, MessageBodyFields}, OctetStr};
+/// // works with the message bodies of both parsers
+/// fn server_id<'i>(body: &impl MessageBodyFields<'i>) -> Option<OctetStr<'i>> {
+///     body.as_get_list_response().map(|glr| glr.server_id())
+/// }
+/// ```
+pub trait MessageBodyFields<'i> {
+    /// type holding the fields of a `GetListResponse`
+    type GetListResponse: GetListResponseFields<'i>;
+
+    /// Returns the `OpenResponse` or `None` if the body is a different message type.
+    fn as_open_response(&self) -> Option<&OpenResponse<'i>>;
+    /// Returns the `CloseResponse` or `None` if the body is a different message type.
+    fn as_close_response(&self) -> Option<&CloseResponse<'i>>;
+    /// Returns the `GetListResponse` or `None` if the body is a different message type.
+    fn as_get_list_response(&self) -> Option<&Self::GetListResponse>;
+}
+
+// implements `GetListResponseFields` and `MessageBodyFields` for the types of a parser
+macro_rules! impl_fields {
+    ($body:ident, $glr:ident $(, $alloc:ident)?) => {
+        impl<'i $(, $alloc: Allocator)?> $crate::parser::common::GetListResponseFields<'i>
+            for $glr<'i $(, $alloc)?>
+        {
+            fn client_id(&self) -> Option<OctetStr<'i>> {
+                self.client_id
+            }
+
+            fn server_id(&self) -> OctetStr<'i> {
+                self.server_id
+            }
+
+            fn list_name(&self) -> Option<OctetStr<'i>> {
+                self.list_name
+            }
+
+            fn act_sensor_time(&self) -> Option<&Time> {
+                self.act_sensor_time.as_ref()
+            }
+        }
+
+        impl<'i $(, $alloc: Allocator)?> $crate::parser::common::MessageBodyFields<'i>
+            for $body<'i $(, $alloc)?>
+        {
+            type GetListResponse = $glr<'i $(, $alloc)?>;
+
+            fn as_open_response(&self) -> Option<&OpenResponse<'i>> {
+                match self {
+                    $body::OpenResponse(o) => Some(o),
+                    _ => None,
+                }
+            }
+
+            fn as_close_response(&self) -> Option<&CloseResponse<'i>> {
+                match self {
+                    $body::CloseResponse(c) => Some(c),
+                    _ => None,
+                }
+            }
+
+            fn as_get_list_response(&self) -> Option<&$glr<'i $(, $alloc)?>> {
+                match self {
+                    $body::GetListResponse(glr) => Some(glr),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_fields;
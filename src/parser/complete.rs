@@ -18,34 +18,69 @@
 //!             abort_on_error: 0,
 //!             message_body: MessageBody::CloseResponse(CloseResponse {
 //!                 global_signature: None
-//!             })
+//!             }),
+//!             crc_valid: true,
+//!             unknown_fields: 0,
 //!         }
 //!     ]
 //! };
 //! assert_eq!(result, Ok(expected))
 //! ```
 
-use alloc::vec::Vec;
-use core::fmt::Debug;
+use alloc::{string::String, vec::Vec};
 
+#[cfg(feature = "fmt-debug")]
+use super::OctetStrFormatter;
 use super::{
-    common::{CloseResponse, EndOfSmlMessage, ListEntry, OpenResponse, Signature, Time},
+    common::{
+        CloseResponse, EndOfSmlMessage, ListEntry, OpenResponse, Signature, Time,
+        MIN_LIST_ENTRY_LEN,
+    },
+    map, parse_extensible, streaming,
     tlf::{Ty, TypeLengthField},
-    OctetStr, OctetStrFormatter, ParseError, ResTy, SmlParse, SmlParseTlf,
+    Codepage, ObisCode, OctetStr, ParseError, ParseOptions, ResTy, SmlParse, SmlParseTlf,
+};
+use crate::{
+    transport::encode,
+    util::{self, VecBuf},
+    writer,
 };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
 /// Top-level SML type. Holds multiple `Messages`.
 pub struct File<'i> {
     /// Vector of `Messsages`
     pub messages: Vec<Message<'i>>,
 }
 
+impl_minimal_debug!(File<'_>);
+
 impl<'i> SmlParse<'i> for File<'i> {
-    fn parse(mut input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
+        File::parse_with_options(input, ParseOptions::new())
+    }
+}
+
+impl<'i> File<'i> {
+    fn parse_with_options(mut input: &'i [u8], options: ParseOptions) -> ResTy<'i, Self> {
         let mut messages = Vec::new();
         while !input.is_empty() {
-            let (new_input, msg) = Message::parse(input)?;
+            if !messages.is_empty() && options.is_ignored_trailer(input) {
+                input = &[];
+                break;
+            }
+            let (new_input, msg) = match Message::parse_with_options(input, options) {
+                Err(ParseError::TruncatedList { .. }) if options.keep_truncated_lists => {
+                    messages.extend(Message::parse_truncated(input, options));
+                    input = &[];
+                    break;
+                }
+                res => res?,
+            };
+            if let MessageBody::OpenResponse(open) = &msg.message_body {
+                options.check_open_response(open)?;
+            }
             messages.push(msg);
             input = new_input;
         }
@@ -54,6 +89,268 @@ impl<'i> SmlParse<'i> for File<'i> {
     }
 }
 
+impl<'i> File<'i> {
+    /// Returns an iterator over the list entries of all `GetListResponse` messages.
+    ///
+    /// Each entry is returned together with its position within the file, which
+    /// allows referencing entries independently of their OBIS codes (e.g. when
+    /// comparing consecutive transmissions).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::complete::EntryPosition;
+    /// # let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = sml_rs::parser::complete::parse(&bytes).unwrap();
+    /// let (pos, entry) = file.list_entries().next().unwrap();
+    /// assert_eq!(pos, EntryPosition { message_index: 1, entry_index: 0 });
+    /// assert_eq!(entry.obj_name, &[1, 0, 96, 50, 1, 1]);
+    /// ```
+    pub fn list_entries(&self) -> impl Iterator<Item = (EntryPosition, &ListEntry<'i>)> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter_map(|(message_index, msg)| match &msg.message_body {
+                MessageBody::GetListResponse(glr) => Some((message_index, glr)),
+                _ => None,
+            })
+            .flat_map(|(message_index, glr)| {
+                glr.val_list
+                    .iter()
+                    .enumerate()
+                    .map(move |(entry_index, entry)| {
+                        let pos = EntryPosition {
+                            message_index,
+                            entry_index,
+                        };
+                        (pos, entry)
+                    })
+            })
+    }
+
+    /// Returns an iterator over the list entries of all `GetListResponse` messages
+    /// together with their OBIS codes.
+    ///
+    /// Entries without a valid OBIS code are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::ObisCode;
+    /// # let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = sml_rs::parser::complete::parse(&bytes).unwrap();
+    /// let (code, entry) = file.values().next().unwrap();
+    /// assert_eq!(code, ObisCode::new(1, 0, 96, 50, 1, 1));
+    /// assert_eq!(entry.value.as_bytes(), Some(&b"ITR"[..]));
+    /// ```
+    pub fn values(&self) -> impl Iterator<Item = (ObisCode, &ListEntry<'i>)> {
+        self.list_entries()
+            .filter_map(|(_, entry)| Some((entry.obis_code()?, entry)))
+    }
+
+    /// Returns the first list entry with the given OBIS code or `None` if the file
+    /// doesn't contain such an entry.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::ObisCode;
+    /// # let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = sml_rs::parser::complete::parse(&bytes).unwrap();
+    /// let energy = file.find_value(ObisCode::ENERGY_IMPORT_TOTAL).unwrap();
+    /// assert_eq!(energy.unit, Some(30));
+    /// assert!(file.find_value(ObisCode::VOLTAGE_L1).is_none());
+    /// ```
+    #[must_use]
+    pub fn find_value(&self, code: ObisCode) -> Option<&ListEntry<'i>> {
+        self.values()
+            .find_map(|(c, entry)| (c == code).then_some(entry))
+    }
+
+    /// Returns the codepage announced by the first `OpenResponse` message.
+    ///
+    /// Returns the default codepage (ISO 8859-15) if the file doesn't contain an
+    /// `OpenResponse` or its `codepage` field is omitted and `None` if the codepage is unknown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::{common::Value, Codepage};
+    /// # let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = sml_rs::parser::complete::parse(&bytes).unwrap();
+    /// let codepage = file.codepage().unwrap();
+    /// assert_eq!(codepage, Codepage::Iso8859_15);
+    /// assert_eq!(Value::Bytes(b"EMH").as_text(codepage).unwrap(), "EMH");
+    /// ```
+    #[must_use]
+    pub fn codepage(&self) -> Option<Codepage> {
+        self.messages
+            .iter()
+            .find_map(|msg| match &msg.message_body {
+                MessageBody::OpenResponse(open) => Some(open.known_codepage()),
+                _ => None,
+            })
+            .unwrap_or(Some(Codepage::default()))
+    }
+
+    /// Returns an iterator over the groups of consecutive messages with the same `group_no`.
+    ///
+    /// SML allows splitting logically related messages (e.g. multi-part profile
+    /// responses) into several messages sharing a group number. Use
+    /// [`check_groups`](File::check_groups) to verify that the groups are consistent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = sml_rs::parser::complete::parse(&bytes).unwrap();
+    /// // all messages of the sample belong to group 0
+    /// let groups: Vec<_> = file.groups().map(|g| (g.group_no, g.messages.len())).collect();
+    /// assert_eq!(groups, [(0, 3)]);
+    /// assert_eq!(file.check_groups(), Ok(()));
+    /// ```
+    pub fn groups(&self) -> impl Iterator<Item = MessageGroup<'_, 'i>> {
+        let mut message_index = 0;
+        self.messages
+            .chunk_by(|a, b| a.group_no == b.group_no)
+            .map(move |messages| {
+                let group = MessageGroup {
+                    group_no: messages[0].group_no,
+                    message_index,
+                    messages,
+                };
+                message_index += messages.len();
+                group
+            })
+    }
+
+    /// Verifies that the messages of each group are consistent.
+    ///
+    /// All messages of a group have to be consecutive and use the same `abort_on_error` value.
+    pub fn check_groups(&self) -> Result<(), GroupError> {
+        for (idx, group) in self.groups().enumerate() {
+            let group_no = group.group_no;
+            if self.groups().take(idx).any(|g| g.group_no == group_no) {
+                return Err(GroupError::NotContiguous {
+                    group_no,
+                    message_index: group.message_index,
+                });
+            }
+            let abort_on_error = group.messages[0].abort_on_error;
+            if let Some(pos) = group
+                .messages
+                .iter()
+                .position(|msg| msg.abort_on_error != abort_on_error)
+            {
+                return Err(GroupError::InconsistentAbortOnError {
+                    group_no,
+                    message_index: group.message_index + pos,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Encodes the File as transmission and returns it in the format of the `.hex`
+    /// files of the libsml-testing corpus (see [`util::to_hex_dump`]).
+    ///
+    /// This allows generating minimal reproductions (e.g. of a single message) from
+    /// parsed data. The encoding is canonical: integers and status words keep their
+    /// size, but type-length fields use the shortest encoding, checksums are
+    /// recomputed and skipped unknown fields are omitted. Therefore, the result
+    /// can differ from the originally received bytes even though it parses to the
+    /// same File.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{parser::complete::{parse, File}, transport::decode, util::parse_hex_stream};
+    /// # let bytes = decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// let file = parse(&bytes).unwrap();
+    /// // only keep the `GetListResponse`
+    /// let reduced = File { messages: file.messages[1..2].to_vec() };
+    /// let hex = reduced.to_hex_dump();
+    /// assert!(hex.starts_with("1B1B1B1B01010101"));
+    ///
+    /// let transmission = parse_hex_stream(&hex).unwrap();
+    /// let decoded = decode(&transmission).remove(0).unwrap();
+    /// assert_eq!(parse(&decoded), Ok(reduced));
+    /// ```
+    #[must_use]
+    pub fn to_hex_dump(&self) -> String {
+        let mut bytes = Vec::new();
+        writer::write_file(&mut bytes, self);
+        // a `Vec` buffer can only fail if allocation fails
+        let transmission = encode::<VecBuf>(bytes).unwrap_or_default();
+        util::to_hex_dump(&transmission)
+    }
+}
+
+/// Consecutive messages of an SML File sharing the same `group_no`
+///
+/// Returned by [`File::groups`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageGroup<'a, 'i> {
+    /// the group number of the messages
+    pub group_no: u8,
+    /// index of the group's first message in `File::messages`
+    pub message_index: usize,
+    /// the messages of the group
+    pub messages: &'a [Message<'i>],
+}
+
+/// Inconsistency detected by [`File::check_groups`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupError {
+    /// A group number is used again after messages of a different group
+    NotContiguous {
+        /// the group number
+        group_no: u8,
+        /// index of the first message using the group number again
+        message_index: usize,
+    },
+    /// A message's `abort_on_error` value differs from the value of the first message of its group
+    InconsistentAbortOnError {
+        /// the group number
+        group_no: u8,
+        /// index of the message
+        message_index: usize,
+    },
+}
+
+impl core::fmt::Display for GroupError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GroupError::NotContiguous {
+                group_no,
+                message_index,
+            } => write!(
+                f,
+                "message {message_index} uses group {group_no} again after messages of a different group"
+            ),
+            GroupError::InconsistentAbortOnError {
+                group_no,
+                message_index,
+            } => write!(
+                f,
+                "`abort_on_error` of message {message_index} differs from the first message of group {group_no}"
+            ),
+        }
+    }
+}
+
+impl core::error::Error for GroupError {}
+
+/// Position of a `ListEntry` within an SML File
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EntryPosition {
+    /// index of the message in `File::messages`
+    pub message_index: usize,
+    /// index of the entry in `GetListResponse::val_list`
+    pub entry_index: usize,
+}
+
 #[derive(PartialEq, Eq, Clone)]
 /// An SML message
 pub struct Message<'i> {
@@ -66,19 +363,43 @@ pub struct Message<'i> {
     pub abort_on_error: u8,
     /// main content of the message
     pub message_body: MessageBody<'i>,
+    /// whether the message's checksum is valid
+    ///
+    /// Always `true` unless checksum verification has been disabled using
+    /// [`ParseOptions::verify_crc`].
+    pub crc_valid: bool,
+    /// number of unknown trailing fields of the message body that have been skipped
+    ///
+    /// Always `0` unless skipping unknown fields has been enabled using
+    /// [`ParseOptions::skip_unknown_fields`].
+    pub unknown_fields: u32,
 }
 
 impl<'i> SmlParse<'i> for Message<'i> {
-    fn parse(input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
+        Message::parse_with_options(input, ParseOptions::new())
+    }
+}
+
+impl<'i> Message<'i> {
+    fn parse_with_options(input: &'i [u8], options: ParseOptions) -> ResTy<'i, Self> {
         let input_orig = input;
         let (input, tlf) = TypeLengthField::parse(input)?;
         if tlf.ty != super::tlf::Ty::ListOf || tlf.len != 6 {
-            return Err(ParseError::TlfMismatch("Message"));
+            return Err(ParseError::TlfMismatch {
+                expected: "Message",
+                found: tlf,
+            });
         }
         let (input, transaction_id) = OctetStr::parse(input)?;
         let (input, group_no) = u8::parse(input)?;
         let (input, abort_on_error) = u8::parse(input)?;
-        let (input, message_body) = MessageBody::parse(input)?;
+        let (input, (mut message_body, unknown_fields)) = if options.skip_unknown_fields {
+            MessageBody::parse_extensible(input)?
+        } else {
+            map(MessageBody::parse(input), |body| (body, 0))?
+        };
+        message_body.apply_options(&options);
 
         let num_bytes_read = input_orig.len() - input.len();
 
@@ -89,27 +410,71 @@ impl<'i> SmlParse<'i> for Message<'i> {
         let digest = crate::util::CRC_X25
             .checksum(&input_orig[0..num_bytes_read])
             .swap_bytes();
-        if digest != crc {
-            return Err(ParseError::CrcMismatch);
-        }
+        let crc_valid = options.check_crc(crc, digest)?;
 
         let val = Message {
             transaction_id,
             group_no,
             abort_on_error,
             message_body,
+            crc_valid,
+            unknown_fields,
         };
         Ok((input, val))
     }
 }
 
-impl<'i> Debug for Message<'i> {
+impl<'i> Message<'i> {
+    // parses a message containing a truncated `GetListResponse`
+    //
+    // The list entries preceding the truncation are kept, the fields after the list are
+    // set to `None`.
+    fn parse_truncated(input: &'i [u8], options: ParseOptions) -> Option<Self> {
+        let mut parser = streaming::Parser::with_options(input, options);
+        let Some(Ok(streaming::ParseEvent::MessageStart(start))) = parser.next() else {
+            return None;
+        };
+        let streaming::MessageBody::GetListResponse(glr) = start.message_body else {
+            return None;
+        };
+        let val_list = parser
+            .map_while(|event| match event {
+                Ok(streaming::ParseEvent::ListEntry(entry)) => Some(entry),
+                _ => None,
+            })
+            .collect();
+        let end = streaming::GetListResponseEnd {
+            list_signature: None,
+            act_gateway_time: None,
+        };
+        // the streaming parser already applied the options
+        Some(Message {
+            transaction_id: start.transaction_id,
+            group_no: start.group_no,
+            abort_on_error: start.abort_on_error,
+            message_body: MessageBody::GetListResponse((glr, val_list, end).into()),
+            crc_valid: false,
+            unknown_fields: 0,
+        })
+    }
+}
+
+impl_minimal_debug!(Message<'_>);
+
+#[cfg(feature = "fmt-debug")]
+impl<'i> core::fmt::Debug for Message<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("Message");
         x.field("transaction_id", &OctetStrFormatter(self.transaction_id));
         x.field("group_no", &self.group_no);
         x.field("abort_on_error", &self.abort_on_error);
         x.field("message_body", &self.message_body);
+        if !self.crc_valid {
+            x.field("crc_valid", &self.crc_valid);
+        }
+        if self.unknown_fields != 0 {
+            x.field("unknown_fields", &self.unknown_fields);
+        }
         x.finish()
     }
 }
@@ -120,6 +485,10 @@ impl<'i> Debug for Message<'i> {
 ///
 /// Hint: this type only implements the message types specified by SML that are
 /// used in real-world power meters.
+///
+/// See [`MessageBodyFields`](super::common::MessageBodyFields) for writing code that
+/// works with the message bodies of both parsers.
+#[non_exhaustive]
 pub enum MessageBody<'i> {
     /// `SML_PublicOpen.Res` message
     OpenResponse(OpenResponse<'i>),
@@ -129,7 +498,55 @@ pub enum MessageBody<'i> {
     GetListResponse(GetListResponse<'i>),
 }
 
+impl_minimal_debug!(MessageBody<'_>);
+
+impl<'i> MessageBody<'i> {
+    // applies `ParseOptions::empty_octet_strings_as_none`
+    fn apply_options(&mut self, options: &ParseOptions) {
+        match self {
+            MessageBody::OpenResponse(open) => options.normalize_open_response(open),
+            MessageBody::CloseResponse(close) => options.normalize(&mut close.global_signature),
+            MessageBody::GetListResponse(glr) => {
+                options.normalize(&mut glr.client_id);
+                options.normalize(&mut glr.list_name);
+                options.normalize(&mut glr.list_signature);
+                for entry in &mut glr.val_list {
+                    options.normalize(&mut entry.value_signature);
+                }
+            }
+        }
+    }
+
+    /// Returns the `OpenResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_open_response(&self) -> Option<&OpenResponse<'i>> {
+        match self {
+            MessageBody::OpenResponse(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Returns the `CloseResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_close_response(&self) -> Option<&CloseResponse<'i>> {
+        match self {
+            MessageBody::CloseResponse(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns the `GetListResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_get_list_response(&self) -> Option<&GetListResponse<'i>> {
+        match self {
+            MessageBody::GetListResponse(glr) => Some(glr),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(feature = "alloc")]
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for MessageBody<'i> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -140,6 +557,33 @@ impl<'i> core::fmt::Debug for MessageBody<'i> {
     }
 }
 
+impl<'i> MessageBody<'i> {
+    // parses a message body that may contain unknown trailing fields
+    // (see `ParseOptions::skip_unknown_fields`) and returns the number of skipped fields
+    fn parse_extensible(input: &'i [u8]) -> ResTy<'i, (Self, u32)> {
+        let (input, tlf) = TypeLengthField::parse(input)?;
+        if !Self::check_tlf(&tlf) {
+            return Err(ParseError::TlfMismatch {
+                expected: "MessageBody",
+                found: tlf,
+            });
+        }
+        let (input, tag) = u32::parse(input)?;
+        match tag {
+            0x00000101 => map(parse_extensible(input, 6), |(x, n)| {
+                (MessageBody::OpenResponse(x), n)
+            }),
+            0x00000201 => map(parse_extensible(input, 1), |(x, n)| {
+                (MessageBody::CloseResponse(x), n)
+            }),
+            0x00000701 => map(parse_extensible(input, 7), |(x, n)| {
+                (MessageBody::GetListResponse(x), n)
+            }),
+            _ => Err(ParseError::UnexpectedVariant),
+        }
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for MessageBody<'i> {
     fn check_tlf(tlf: &TypeLengthField) -> bool {
         tlf.ty == Ty::ListOf && tlf.len == 2
@@ -210,6 +654,9 @@ impl<'i> SmlParseTlf<'i> for GetListResponse<'i> {
     }
 }
 
+impl_minimal_debug!(GetListResponse<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for GetListResponse<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("GetListResponse");
@@ -234,6 +681,93 @@ impl<'i> core::fmt::Debug for GetListResponse<'i> {
     }
 }
 
+super::common::impl_fields!(MessageBody, GetListResponse);
+
+/// Assembles a `GetListResponse` from the events of the streaming parser.
+impl<'i>
+    From<(
+        streaming::GetListResponseStart<'i>,
+        List<'i>,
+        streaming::GetListResponseEnd<'i>,
+    )> for GetListResponse<'i>
+{
+    fn from(
+        (start, val_list, end): (
+            streaming::GetListResponseStart<'i>,
+            List<'i>,
+            streaming::GetListResponseEnd<'i>,
+        ),
+    ) -> Self {
+        GetListResponse {
+            client_id: start.client_id,
+            server_id: start.server_id,
+            list_name: start.list_name,
+            act_sensor_time: start.act_sensor_time,
+            val_list,
+            list_signature: end.list_signature,
+            act_gateway_time: end.act_gateway_time,
+        }
+    }
+}
+
+impl<'i> From<&GetListResponse<'i>> for streaming::GetListResponseStart<'i> {
+    fn from(glr: &GetListResponse<'i>) -> Self {
+        streaming::GetListResponseStart {
+            client_id: glr.client_id,
+            server_id: glr.server_id,
+            list_name: glr.list_name,
+            act_sensor_time: glr.act_sensor_time.clone(),
+            num_vals: u32::try_from(glr.val_list.len()).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+impl<'i> From<&GetListResponse<'i>> for streaming::GetListResponseEnd<'i> {
+    fn from(glr: &GetListResponse<'i>) -> Self {
+        streaming::GetListResponseEnd {
+            list_signature: glr.list_signature,
+            act_gateway_time: glr.act_gateway_time.clone(),
+        }
+    }
+}
+
+impl<'i> From<&MessageBody<'i>> for streaming::MessageBody<'i> {
+    fn from(body: &MessageBody<'i>) -> Self {
+        match body {
+            MessageBody::OpenResponse(o) => streaming::MessageBody::OpenResponse(o.clone()),
+            MessageBody::CloseResponse(c) => streaming::MessageBody::CloseResponse(c.clone()),
+            MessageBody::GetListResponse(glr) => {
+                streaming::MessageBody::GetListResponse(glr.into())
+            }
+        }
+    }
+}
+
+/// Converts message bodies that are complete without further parse events.
+///
+/// The start of a `GetListResponse` is only converted if it doesn't contain any list
+/// entries. Otherwise, the body is returned as the error. Use the `From` implementation
+/// of [`GetListResponse`] to assemble it from the parse events instead.
+impl<'i> TryFrom<streaming::MessageBody<'i>> for MessageBody<'i> {
+    type Error = streaming::MessageBody<'i>;
+
+    fn try_from(body: streaming::MessageBody<'i>) -> Result<Self, Self::Error> {
+        match body {
+            streaming::MessageBody::OpenResponse(o) => Ok(MessageBody::OpenResponse(o)),
+            streaming::MessageBody::CloseResponse(c) => Ok(MessageBody::CloseResponse(c)),
+            streaming::MessageBody::GetListResponse(start) if start.num_vals == 0 => {
+                let end = streaming::GetListResponseEnd {
+                    list_signature: None,
+                    act_gateway_time: None,
+                };
+                let glr = (start, List::new(), end).into();
+                Ok(MessageBody::GetListResponse(glr))
+            }
+            body => Err(body),
+        }
+    }
+}
+
 /// Vector of SML list entries
 pub type List<'i> = Vec<ListEntry<'i>>;
 
@@ -243,9 +777,15 @@ impl<'i> SmlParseTlf<'i> for List<'i> {
     }
 
     fn parse_with_tlf(mut input: &'i [u8], tlf: &TypeLengthField) -> ResTy<'i, Self> {
-        let mut v = Vec::with_capacity(tlf.len as usize);
-        for _ in 0..tlf.len {
-            let (new_input, x) = ListEntry::parse(input)?;
+        // Don't trust the length field when allocating: each list entry takes
+        // at least `MIN_LIST_ENTRY_LEN` bytes of the remaining input.
+        // the capacity is only a hint, each entry has to be contained in the input
+        let capacity = usize::try_from(tlf.len)
+            .unwrap_or(usize::MAX)
+            .min(input.len() / MIN_LIST_ENTRY_LEN);
+        let mut v = Vec::with_capacity(capacity);
+        for parsed in 0..tlf.len {
+            let (new_input, x) = ListEntry::parse_in_list(input, tlf.len, parsed)?;
             v.push(x);
             input = new_input;
         }
@@ -256,6 +796,380 @@ impl<'i> SmlParseTlf<'i> for List<'i> {
 /// Parses a slice of bytes into an SML File.
 ///
 /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
-pub fn parse(input: &[u8]) -> Result<File, ParseError> {
+pub fn parse(input: &[u8]) -> Result<File<'_>, ParseError> {
     File::parse_complete(input)
 }
+
+/// Parses a slice of bytes into an SML File using the provided options.
+///
+/// See [`ParseOptions`] for the available options.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{complete::parse_with_options, ParseError, ParseOptions};
+/// // CloseResponse message followed by two zero bytes
+/// let bytes = [0x76, 0x5, 0xdd, 0x43, 0x44, 0x0, 0x62, 0x0, 0x62, 0x0, 0x72, 0x63, 0x2, 0x1, 0x71, 0x1, 0x63, 0xfd, 0x56, 0x0, 0x0, 0x0];
+///
+/// assert!(parse_with_options(&bytes, ParseOptions::new()).is_err());
+///
+/// let options = ParseOptions::new().ignore_trailing_zeros(true);
+/// let file = parse_with_options(&bytes, options).unwrap();
+/// assert_eq!(file.messages.len(), 1);
+/// ```
+pub fn parse_with_options(input: &[u8], options: ParseOptions) -> Result<File<'_>, ParseError> {
+    let (input, file) = File::parse_with_options(input, options)?;
+    if !input.is_empty() {
+        return Err(ParseError::LeftoverInput);
+    }
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::CrcMismatch;
+    use hex_literal::hex;
+
+    #[test]
+    fn trailing_zeros() {
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5600 000000");
+        let options = ParseOptions::new().ignore_trailing_zeros(true);
+
+        assert!(parse(&bytes).is_err());
+        assert_eq!(
+            parse_with_options(&bytes, options),
+            parse(&bytes[..bytes.len() - 3])
+        );
+        assert_eq!(
+            parse_with_options(&bytes, options).unwrap().messages.len(),
+            1
+        );
+
+        // other trailing data is still rejected
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5600 000001");
+        assert!(parse_with_options(&bytes, options).is_err());
+
+        // zero bytes without a message are rejected
+        assert!(parse_with_options(&[0x00], options).is_err());
+    }
+
+    #[test]
+    fn crc_mismatch() {
+        use alloc::format;
+
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5700");
+        let err = ParseError::CrcMismatch(CrcMismatch {
+            expected: 0x57fd,
+            computed: 0x56fd,
+        });
+        assert_eq!(parse(&bytes), Err(err));
+        assert_eq!(
+            format!(
+                "{}",
+                CrcMismatch {
+                    expected: 0x57fd,
+                    computed: 0x56fd,
+                }
+            ),
+            "CRC mismatch (expected: 0x57fd, computed: 0x56fd)"
+        );
+    }
+
+    #[test]
+    fn skip_crc_verification() {
+        let options = ParseOptions::new().verify_crc(false);
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5700");
+        let file = parse_with_options(&bytes, options).unwrap();
+        assert!(!file.messages[0].crc_valid);
+        #[cfg(feature = "fmt-debug")]
+        assert!(alloc::format!("{file:?}").contains("crc_valid: false"));
+
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5600");
+        let file = parse_with_options(&bytes, options).unwrap();
+        assert!(file.messages[0].crc_valid);
+        assert_eq!(Ok(file), parse(&bytes));
+    }
+
+    #[test]
+    fn unknown_fields() {
+        let options = ParseOptions::new()
+            .verify_crc(false)
+            .skip_unknown_fields(true);
+        // `CloseResponse` followed by a nested list and an octet string
+        let bytes = hex!("7605dd43 44006200 62007263 0201 73 01 72 6201 6202 03 6162 630000 00");
+        assert!(matches!(
+            parse_with_options(&bytes, options.skip_unknown_fields(false)),
+            Err(ParseError::TlfMismatch { .. })
+        ));
+        let file = parse_with_options(&bytes, options).unwrap();
+        let msg = &file.messages[0];
+        assert_eq!(msg.unknown_fields, 2);
+        assert_eq!(
+            msg.message_body,
+            MessageBody::CloseResponse(CloseResponse {
+                global_signature: None
+            })
+        );
+        #[cfg(feature = "fmt-debug")]
+        assert!(alloc::format!("{file:?}").contains("unknown_fields: 2"));
+
+        // messages without unknown fields are parsed as usual
+        let bytes = hex!("7605dd43 44006200 62007263 02017101 63fd5600");
+        assert_eq!(parse_with_options(&bytes, options), parse(&bytes));
+        // fewer fields than known and truncated unknown fields are still errors
+        let bytes = hex!("7605dd43 44006200 62007263 0201 70 630000 00");
+        assert!(parse_with_options(&bytes, options).is_err());
+        let bytes = hex!("7605dd43 44006200 62007263 0201 73 01 72 6201");
+        assert_eq!(
+            parse_with_options(&bytes, options),
+            Err(ParseError::UnexpectedEOF)
+        );
+    }
+
+    #[test]
+    fn list_entries() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin")).remove(0);
+        let file = parse(bytes.as_ref().unwrap()).unwrap();
+        let positions: Vec<_> = file.list_entries().map(|(pos, _)| pos).collect();
+        let num_entries = match &file.messages[1].message_body {
+            MessageBody::GetListResponse(glr) => glr.val_list.len(),
+            _ => panic!("expected GetListResponse"),
+        };
+        assert_eq!(positions.len(), num_entries);
+        for (i, pos) in positions.iter().enumerate() {
+            assert_eq!(
+                *pos,
+                EntryPosition {
+                    message_index: 1,
+                    entry_index: i
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn oversized_list_len() {
+        // GetListResponse with a `val_list` length of 0xFFFFFFFF
+        let bytes = hex!("7601 6200 6200 72 630701 77 01 01 01 01 FF8F8F8F8F8F8F0F");
+        let truncated = |parsed| ParseError::TruncatedList {
+            expected: u32::MAX,
+            parsed,
+        };
+        assert_eq!(parse(&bytes), Err(truncated(0)));
+
+        // followed by a single list entry
+        let mut bytes = bytes.to_vec();
+        bytes.extend_from_slice(&hex!("77 07 0100010800FF 01 01 01 01 5201 01"));
+        assert_eq!(parse(&bytes), Err(truncated(1)));
+    }
+
+    #[test]
+    fn truncated_list() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin")).remove(0);
+        let bytes = bytes.unwrap();
+        let file = parse(&bytes).unwrap();
+        let MessageBody::GetListResponse(glr) = &file.messages[1].message_body else {
+            panic!("expected GetListResponse");
+        };
+
+        // cut off within the third list entry
+        let truncated = &bytes[..160];
+        let err = ParseError::TruncatedList {
+            expected: 4,
+            parsed: 2,
+        };
+        assert_eq!(parse(truncated), Err(err.clone()));
+        // corrupted list entries are still reported as such
+        let mut corrupted = bytes.clone();
+        corrupted[140] = 0x00;
+        assert!(!matches!(
+            parse(&corrupted),
+            Err(ParseError::TruncatedList { .. })
+        ));
+
+        let options = ParseOptions::new().keep_truncated_lists(true);
+        let lenient = parse_with_options(truncated, options).unwrap();
+        assert_eq!(lenient.messages.len(), 2);
+        assert_eq!(lenient.messages[0], file.messages[0]);
+        let msg = &lenient.messages[1];
+        assert!(!msg.crc_valid);
+        let MessageBody::GetListResponse(lenient_glr) = &msg.message_body else {
+            panic!("expected GetListResponse");
+        };
+        assert_eq!(lenient_glr.server_id, glr.server_id);
+        assert_eq!(lenient_glr.val_list[..], glr.val_list[..2]);
+        assert_eq!(lenient_glr.list_signature, None);
+
+        // complete files aren't affected
+        assert_eq!(parse_with_options(&bytes, options), Ok(file));
+    }
+
+    #[test]
+    fn oversized_lens() {
+        // octet string (transaction_id) with a length of 0xFFFFFFFF
+        let bytes = hex!("76 8F8F8F8F8F8F8F0F 00");
+        assert_eq!(parse(&bytes), Err(ParseError::UnexpectedEOF));
+        // message with a list length of 0xFFFFFFFF
+        let bytes = hex!("FF8F8F8F8F8F8F0F 01");
+        assert!(parse(&bytes).is_err());
+        // tlf length that doesn't fit into 32 bits
+        let bytes = hex!("FF8F8F8F8F8F8F8F0F 01");
+        assert_eq!(
+            parse(&bytes),
+            Err(ParseError::InvalidTlf(
+                crate::parser::TlfParseError::TlfLengthOverflow
+            ))
+        );
+    }
+
+    #[test]
+    fn message_body_accessors() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        let file = parse(&bytes).unwrap();
+        let bodies: Vec<_> = file.messages.iter().map(|m| &m.message_body).collect();
+        assert!(bodies[0].as_open_response().is_some());
+        assert!(bodies[0].as_get_list_response().is_none());
+        let glr = bodies[1].as_get_list_response().unwrap();
+        assert!(glr
+            .act_sensor_time
+            .as_ref()
+            .and_then(Time::sec_index)
+            .is_some());
+        assert!(bodies[1].as_close_response().is_none());
+        assert!(bodies[2].as_close_response().is_some());
+    }
+
+    #[test]
+    fn groups() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        let mut file = parse(&bytes).unwrap();
+        assert_eq!(file.groups().count(), 1);
+
+        // the open and get list response form a group
+        file.messages[2].group_no = 2;
+        let groups: Vec<_> = file.groups().collect();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].messages, &file.messages[..2]);
+        assert_eq!((groups[1].group_no, groups[1].message_index), (2, 2));
+        assert_eq!(file.check_groups(), Ok(()));
+
+        file.messages[1].abort_on_error = 1;
+        assert_eq!(
+            file.check_groups(),
+            Err(GroupError::InconsistentAbortOnError {
+                group_no: 0,
+                message_index: 1
+            })
+        );
+
+        file.messages[1].abort_on_error = 0;
+        file.messages[1].group_no = 1;
+        file.messages[2].group_no = 0;
+        assert_eq!(file.groups().count(), 3);
+        assert_eq!(
+            file.check_groups(),
+            Err(GroupError::NotContiguous {
+                group_no: 0,
+                message_index: 2
+            })
+        );
+    }
+
+    #[test]
+    fn values() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        let mut file = parse(&bytes).unwrap();
+        assert_eq!(file.values().count(), file.list_entries().count());
+
+        // entries without a valid OBIS code are skipped
+        let MessageBody::GetListResponse(glr) = &mut file.messages[1].message_body else {
+            panic!("expected GetListResponse");
+        };
+        let first = glr.val_list[0].obis_code().unwrap();
+        glr.val_list[0].obj_name = &[1, 2, 3];
+        assert_eq!(file.values().count(), file.list_entries().count() - 1);
+        assert!(file.values().all(|(code, _)| code != first));
+        assert_eq!(file.find_value(first), None);
+        let (code, entry) = file.values().nth(1).unwrap();
+        assert_eq!(file.find_value(code), Some(entry));
+    }
+
+    #[test]
+    fn streaming_conversions() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        let file = parse(&bytes).unwrap();
+
+        // assemble the file from the events of the streaming parser
+        let mut bodies = Vec::new();
+        let mut pending = None;
+        for event in streaming::Parser::new(&bytes) {
+            match event.unwrap() {
+                streaming::ParseEvent::MessageStart(start) => {
+                    match MessageBody::try_from(start.message_body) {
+                        Ok(body) => bodies.push(body),
+                        Err(streaming::MessageBody::GetListResponse(glr)) => {
+                            pending = Some((glr, Vec::new()));
+                        }
+                        Err(_) => panic!("unexpected message body"),
+                    }
+                }
+                streaming::ParseEvent::ListEntry(entry) => pending.as_mut().unwrap().1.push(entry),
+                streaming::ParseEvent::GetListResponseEnd(end) => {
+                    let (start, entries) = pending.take().unwrap();
+                    bodies.push(MessageBody::GetListResponse((start, entries, end).into()));
+                }
+            }
+        }
+        assert!(bodies
+            .iter()
+            .eq(file.messages.iter().map(|m| &m.message_body)));
+
+        // and back
+        let glr = file.messages[1]
+            .message_body
+            .as_get_list_response()
+            .unwrap();
+        let start = streaming::GetListResponseStart::from(glr);
+        assert_eq!(start.num_vals as usize, glr.val_list.len());
+        let end = streaming::GetListResponseEnd::from(glr);
+        assert_eq!(
+            GetListResponse::from((start, glr.val_list.clone(), end)),
+            *glr
+        );
+    }
+
+    #[test]
+    fn shared_fields() {
+        use crate::parser::common::{GetListResponseFields, MessageBodyFields};
+
+        fn server_id<'i>(body: &impl MessageBodyFields<'i>) -> Option<OctetStr<'i>> {
+            body.as_get_list_response().map(|glr| glr.server_id())
+        }
+
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        let file = parse(&bytes).unwrap();
+        for msg in &file.messages {
+            let body = &msg.message_body;
+            let streaming_body = streaming::MessageBody::from(body);
+            assert_eq!(server_id(body), server_id(&streaming_body));
+            assert_eq!(
+                MessageBodyFields::as_open_response(body),
+                MessageBodyFields::as_open_response(&streaming_body)
+            );
+        }
+        assert!(server_id(&file.messages[1].message_body).is_some());
+    }
+}
@@ -0,0 +1,224 @@
+//! Variant of the [`complete`](super::complete) parser allocating from a custom allocator.
+//!
+//! *This module is available only if sml-rs is built with the `"allocator-api2"` feature.*
+//!
+//! The types in this module mirror the types of the `complete` module, but their vectors
+//! are allocated using an allocator implementing [`allocator_api2::alloc::Allocator`].
+//! This allows firmware with a custom heap (e.g. a static arena) to use the convenient
+//! tree API while controlling where the parsed data is placed.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::parser::complete_in::parse_in;
+//! use allocator_api2::alloc::Global;
+//!
+//! let decoded = sml_rs::transport::decode(include_bytes!("../../sample.bin"));
+//! let file = parse_in(decoded[0].as_ref().unwrap(), Global).unwrap();
+//! assert_eq!(file.messages.len(), 3);
+//! ```
+
+use allocator_api2::{alloc::Allocator, vec::Vec};
+
+use super::{
+    common::{CloseResponse, ListEntry, OpenResponse, Signature, Time},
+    streaming::{self, ParseEvent, Parser},
+    OctetStr, ParseError, ParseOptions,
+};
+
+/// Top-level SML type. Holds multiple `Messages`.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
+pub struct File<'i, A: Allocator> {
+    /// Vector of `Messsages`
+    pub messages: Vec<Message<'i, A>, A>,
+}
+
+/// An SML message
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
+pub struct Message<'i, A: Allocator> {
+    /// transaction identifier
+    pub transaction_id: OctetStr<'i>,
+    /// allows grouping of SML messages
+    pub group_no: u8,
+    /// describes how to handle the Message in case of errors
+    pub abort_on_error: u8,
+    /// main content of the message
+    pub message_body: MessageBody<'i, A>,
+}
+
+/// SML message body
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
+#[non_exhaustive]
+pub enum MessageBody<'i, A: Allocator> {
+    /// `SML_PublicOpen.Res` message
+    OpenResponse(OpenResponse<'i>),
+    /// `SML_PublicClose.Res` message
+    CloseResponse(CloseResponse<'i>),
+    /// `SML_GetList.Res` message
+    GetListResponse(GetListResponse<'i, A>),
+}
+
+/// `SML_GetList.Res` message
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
+pub struct GetListResponse<'i, A: Allocator> {
+    /// identification of the client
+    pub client_id: Option<OctetStr<'i>>,
+    /// identification of the server
+    pub server_id: OctetStr<'i>,
+    /// name of the list
+    pub list_name: Option<OctetStr<'i>>,
+    /// optional sensor time information
+    pub act_sensor_time: Option<Time>,
+    /// list of data values
+    pub val_list: Vec<ListEntry<'i>, A>,
+    /// signature of the list - whatever that means?!
+    pub list_signature: Option<Signature<'i>>,
+    /// optional gateway time information
+    pub act_gateway_time: Option<Time>,
+}
+
+macro_rules! impl_minimal_debug_in {
+    ($($name:ident),*) => {
+        $(
+            #[cfg(not(feature = "fmt-debug"))]
+            impl<A: Allocator> core::fmt::Debug for $name<'_, A> {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.write_str(stringify!($name))
+                }
+            }
+        )*
+    };
+}
+
+impl_minimal_debug_in!(File, Message, MessageBody, GetListResponse);
+
+super::common::impl_fields!(MessageBody, GetListResponse, A);
+
+/// Parses a slice of bytes into an SML File whose vectors are allocated using `alloc`.
+///
+/// *This function is available only if sml-rs is built with the `"allocator-api2"` feature.*
+pub fn parse_in<A: Allocator + Clone>(input: &[u8], alloc: A) -> Result<File<'_, A>, ParseError> {
+    parse_in_with_options(input, alloc, ParseOptions::new())
+}
+
+/// Parses a slice of bytes into an SML File using the provided options and allocator.
+///
+/// See [`ParseOptions`] for the available options.
+///
+/// *This function is available only if sml-rs is built with the `"allocator-api2"` feature.*
+pub fn parse_in_with_options<A: Allocator + Clone>(
+    input: &[u8],
+    alloc: A,
+    options: ParseOptions,
+) -> Result<File<'_, A>, ParseError> {
+    let mut messages = Vec::new_in(alloc.clone());
+    for event in Parser::with_options(input, options) {
+        let event = match event {
+            // the truncated `GetListResponse` is the last message
+            Err(ParseError::TruncatedList { .. }) if options.keep_truncated_lists => break,
+            event => event?,
+        };
+        let msg = match event {
+            ParseEvent::MessageStart(start) => Message {
+                transaction_id: start.transaction_id,
+                group_no: start.group_no,
+                abort_on_error: start.abort_on_error,
+                message_body: match start.message_body {
+                    streaming::MessageBody::OpenResponse(o) => MessageBody::OpenResponse(o),
+                    streaming::MessageBody::CloseResponse(c) => MessageBody::CloseResponse(c),
+                    streaming::MessageBody::GetListResponse(glr) => {
+                        MessageBody::GetListResponse(GetListResponse {
+                            client_id: glr.client_id,
+                            server_id: glr.server_id,
+                            list_name: glr.list_name,
+                            act_sensor_time: glr.act_sensor_time,
+                            val_list: Vec::new_in(alloc.clone()),
+                            list_signature: None,
+                            act_gateway_time: None,
+                        })
+                    }
+                },
+            },
+            ParseEvent::ListEntry(entry) => {
+                if let Some(glr) = last_get_list_response(&mut messages) {
+                    glr.val_list.push(entry);
+                }
+                continue;
+            }
+            ParseEvent::GetListResponseEnd(end) => {
+                if let Some(glr) = last_get_list_response(&mut messages) {
+                    glr.list_signature = end.list_signature;
+                    glr.act_gateway_time = end.act_gateway_time;
+                }
+                continue;
+            }
+        };
+        messages.push(msg);
+    }
+    Ok(File { messages })
+}
+
+// the streaming parser emits list entries right after the start of their `GetListResponse`
+fn last_get_list_response<'a, 'i, A: Allocator>(
+    messages: &'a mut Vec<Message<'i, A>, A>,
+) -> Option<&'a mut GetListResponse<'i, A>> {
+    match messages.last_mut().map(|m| &mut m.message_body) {
+        Some(MessageBody::GetListResponse(glr)) => Some(glr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::complete;
+    use allocator_api2::alloc::Global;
+
+    #[test]
+    fn same_as_complete() {
+        for transmission in crate::transport::decode(include_bytes!("../../sample.bin")) {
+            let bytes = transmission.unwrap();
+            let file = complete::parse(&bytes).unwrap();
+            let file_in = parse_in(&bytes, Global).unwrap();
+
+            assert_eq!(file.messages.len(), file_in.messages.len());
+            for (msg, msg_in) in file.messages.iter().zip(&file_in.messages) {
+                assert_eq!(msg.transaction_id, msg_in.transaction_id);
+                match (&msg.message_body, &msg_in.message_body) {
+                    (complete::MessageBody::OpenResponse(a), MessageBody::OpenResponse(b)) => {
+                        assert_eq!(a, b);
+                    }
+                    (complete::MessageBody::CloseResponse(a), MessageBody::CloseResponse(b)) => {
+                        assert_eq!(a, b);
+                    }
+                    (
+                        complete::MessageBody::GetListResponse(a),
+                        MessageBody::GetListResponse(b),
+                    ) => {
+                        assert_eq!(a.server_id, b.server_id);
+                        assert_eq!(a.act_sensor_time, b.act_sensor_time);
+                        assert_eq!(a.val_list[..], b.val_list[..]);
+                        assert_eq!(a.list_signature, b.list_signature);
+                        assert_eq!(a.act_gateway_time, b.act_gateway_time);
+                    }
+                    _ => panic!("message bodies differ"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let bytes = crate::transport::decode(include_bytes!("../../sample.bin"))
+            .remove(0)
+            .unwrap();
+        assert_eq!(
+            parse_in(&bytes[..bytes.len() - 1], Global).map(|_| ()),
+            complete::parse(&bytes[..bytes.len() - 1]).map(|_| ())
+        );
+    }
+}
@@ -100,33 +100,75 @@
 //! })
 //! ```
 //!
+//! # Untrusted input
 //!
+//! The parsers never panic, regardless of their input. Malformed input results in a
+//! [`ParseError`]. This is enforced by denying the `clippy::panic`, `clippy::unwrap_used`,
+//! `clippy::expect_used` and `clippy::unreachable` lints for this module and by a test
+//! feeding corrupted transmissions into all parsers.
 
-use core::{
-    fmt::{self, Debug},
-    ops::Deref,
-};
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::unreachable
+    )
+)]
+
+use core::fmt::{self, Debug};
+#[cfg(feature = "fmt-debug")]
+use core::ops::Deref;
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use tlf::TypeLengthField;
+use crate::util::CrcMismatch;
 
+// Implements `Debug` by only printing the name of the type. This implementation
+// replaces the detailed `Debug` implementations if the `fmt-debug` feature is disabled.
+macro_rules! impl_minimal_debug {
+    ($name:ident $(<$lt:lifetime>)?) => {
+        #[cfg(not(feature = "fmt-debug"))]
+        impl core::fmt::Debug for $name$(<$lt>)? {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str(stringify!($name))
+            }
+        }
+    };
+}
+
+#[cfg(feature = "alloc")]
+mod analyze;
+mod codepage;
 pub mod common;
 #[cfg(feature = "alloc")]
 pub mod complete;
+#[cfg(feature = "allocator-api2")]
+pub mod complete_in;
 mod num;
+mod obis;
 mod octet_string;
+mod raw;
+pub mod sequence;
 pub mod streaming;
 mod tlf;
 
-pub use tlf::TlfParseError;
+pub use tlf::{TlfParseError, Ty, TypeLengthField};
 
-pub use octet_string::OctetStr;
+#[cfg(feature = "alloc")]
+pub use analyze::{analyze, AnalysisWarning, FileAnalysis, MessageCounts, ObisStats};
+pub use codepage::Codepage;
+pub use num::{parse_signed, parse_unsigned};
+pub use obis::{ObisCode, ObisCodeParseError};
+pub use octet_string::{OctetStr, OctetStrExt};
+pub use raw::{split_messages, RawMessage, SplitMessages};
 
 /// Error type used by the parser
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ParseError {
     /// There are additional bytes in the input while the parser expects EOF
     LeftoverInput,
@@ -135,26 +177,313 @@ pub enum ParseError {
     /// An error occurred while parsing a `TypeLengthField`
     InvalidTlf(TlfParseError),
     /// TLF mismatch while parsing struct / enum
-    TlfMismatch(&'static str),
-    /// CRC mismatch,
-    CrcMismatch,
+    TlfMismatch {
+        /// name of the type that was being parsed
+        expected: &'static str,
+        /// the TLF contained in the input
+        found: TypeLengthField,
+    },
+    /// CRC mismatch
+    CrcMismatch(CrcMismatch),
     /// Expected to find 0x00 as message end marker, got something else
     MsgEndMismatch,
     /// Got a variant id that isn't known. This means it's either invalid or not supported (yet) by the parser
     UnexpectedVariant,
+    /// An `OpenResponse` announced an unsupported version of the SML protocol
+    ///
+    /// Only returned if enabled using [`ParseOptions::reject_unsupported_versions`].
+    UnsupportedVersion(u8),
+    /// A length contained in the input doesn't fit into `usize`
+    ///
+    /// Can only occur on 16-bit targets.
+    LengthOverflow(u32),
+    /// A `GetListResponse` announced more list entries than contained in the input
+    ///
+    /// This usually means that the transmission has been cut off. Use
+    /// [`ParseOptions::keep_truncated_lists`] to keep the entries that have been parsed.
+    TruncatedList {
+        /// number of list entries announced by the `GetListResponse`
+        expected: u32,
+        /// number of list entries that have been parsed successfully
+        parsed: u32,
+    },
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as Debug>::fmt(self, f)
+        match self {
+            ParseError::LeftoverInput => {
+                f.write_str("unexpected bytes after the end of the SML file (see `ParseOptions::ignore_trailing_zeros`)")
+            }
+            ParseError::UnexpectedEOF => {
+                f.write_str("unexpected end of input, the SML file is truncated")
+            }
+            ParseError::InvalidTlf(e) => write!(f, "invalid type-length field: {e}"),
+            ParseError::TlfMismatch { expected, found } => {
+                write!(f, "unexpected {found} while parsing `{expected}`")
+            }
+            ParseError::CrcMismatch(crc) => write!(f, "{crc} in SML message, the message is corrupted"),
+            ParseError::MsgEndMismatch => {
+                f.write_str("expected end of SML message (0x00), the message is corrupted")
+            }
+            ParseError::UnexpectedVariant => f.write_str(
+                "unknown message type or variant (either invalid or not supported by sml-rs)",
+            ),
+            ParseError::UnsupportedVersion(v) => write!(f, "unsupported SML version {v}"),
+            ParseError::LengthOverflow(len) => {
+                write!(f, "length {len} exceeds the address space of the target")
+            }
+            ParseError::TruncatedList { expected, parsed } => write!(
+                f,
+                "`GetListResponse` truncated after {parsed} of {expected} list entries"
+            ),
+        }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for ParseError {}
+impl core::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ParseError::InvalidTlf(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Options that change how the parsers handle input deviating from the SML spec.
+///
+/// The default options parse the input strictly according to the spec.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::ParseOptions;
+/// let options = ParseOptions::new().ignore_trailing_zeros(true);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub(crate) ignore_trailing_zeros: bool,
+    pub(crate) reject_unsupported_versions: bool,
+    pub(crate) skip_crc_verification: bool,
+    pub(crate) empty_octet_strings_as_none: bool,
+    pub(crate) keep_truncated_lists: bool,
+    pub(crate) skip_unknown_fields: bool,
+}
+
+impl ParseOptions {
+    /// Returns the default options.
+    #[must_use]
+    pub const fn new() -> Self {
+        ParseOptions {
+            ignore_trailing_zeros: false,
+            reject_unsupported_versions: false,
+            skip_crc_verification: false,
+            empty_octet_strings_as_none: false,
+            keep_truncated_lists: false,
+            skip_unknown_fields: false,
+        }
+    }
+
+    /// Sets whether `0x00` bytes following the last message are ignored (default: `false`).
+    ///
+    /// Some meters and gateways leave zero bytes after the last message of a
+    /// transmission. By default, these bytes are reported as an error. If set to `true`,
+    /// they are ignored. Other leftover data is still reported as an error.
+    #[must_use]
+    pub const fn ignore_trailing_zeros(mut self, ignore: bool) -> Self {
+        self.ignore_trailing_zeros = ignore;
+        self
+    }
+
+    /// Sets whether `OpenResponse` messages announcing an unsupported SML version are rejected (default: `false`).
+    ///
+    /// This crate implements version 1 of the SML protocol. Newer versions may
+    /// change the meaning of the transmitted data without causing parsing errors.
+    /// If set to `true`, such messages are reported as [`ParseError::UnsupportedVersion`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// # use sml_rs::parser::{complete::parse_with_options, ParseError, ParseOptions};
+    /// // OpenResponse announcing `sml_version` 2 followed by a CloseResponse
+    /// let bytes = [
+    ///     0x76, 0x02, 0x01, 0x62, 0x00, 0x62, 0x00, 0x72, 0x63, 0x01, 0x01, 0x76, 0x01, 0x01, 0x02, 0x41,
+    ///     0x02, 0x42, 0x01, 0x62, 0x02, 0x63, 0x94, 0x0c, 0x00, 0x76, 0x02, 0x01, 0x62, 0x00, 0x62, 0x00,
+    ///     0x72, 0x63, 0x02, 0x01, 0x71, 0x01, 0x63, 0xf7, 0x9e, 0x00,
+    /// ];
+    /// assert!(parse_with_options(&bytes, ParseOptions::new()).is_ok());
+    ///
+    /// let options = ParseOptions::new().reject_unsupported_versions(true);
+    /// assert_eq!(parse_with_options(&bytes, options), Err(ParseError::UnsupportedVersion(2)));
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn reject_unsupported_versions(mut self, reject: bool) -> Self {
+        self.reject_unsupported_versions = reject;
+        self
+    }
+
+    /// Sets whether message checksum mismatches are reported as errors (default: `true`).
+    ///
+    /// When analyzing corrupted captures, it can be useful to obtain the parsed data
+    /// even if the checksums don't match. If set to `false`, checksum mismatches don't
+    /// cause [`ParseError::CrcMismatch`] errors. Instead, the complete parser records
+    /// the result of the check in `Message::crc_valid`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// # use sml_rs::parser::{complete::parse_with_options, ParseOptions};
+    /// // CloseResponse with a corrupted checksum
+    /// let bytes = [0x76, 0x5, 0xdd, 0x43, 0x44, 0x0, 0x62, 0x0, 0x62, 0x0, 0x72, 0x63, 0x2, 0x1, 0x71, 0x1, 0x63, 0xfd, 0x57, 0x0];
+    /// assert!(parse_with_options(&bytes, ParseOptions::new()).is_err());
+    ///
+    /// let file = parse_with_options(&bytes, ParseOptions::new().verify_crc(false)).unwrap();
+    /// assert!(!file.messages[0].crc_valid);
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn verify_crc(mut self, verify: bool) -> Self {
+        self.skip_crc_verification = !verify;
+        self
+    }
+
+    /// Sets whether empty octet strings in optional fields are treated as absent (default: `false`).
+    ///
+    /// Some meters send empty octet strings instead of omitting optional fields
+    /// (e.g. `client_id`, `list_name` or signatures). If set to `true`, such fields are
+    /// parsed as `None` instead of `Some(&[])`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// # use sml_rs::parser::{complete::{parse_with_options, MessageBody}, ParseOptions};
+    /// // CloseResponse with an empty global signature
+    /// let bytes = [0x76, 0x5, 0xdd, 0x43, 0x44, 0x0, 0x62, 0x0, 0x62, 0x0, 0x72, 0x63, 0x2, 0x1, 0x71, 0x80, 0x02, 0x63, 0x42, 0x6a, 0x0];
+    /// let signature = |options| match parse_with_options(&bytes, options).unwrap().messages[0].message_body {
+    ///     MessageBody::CloseResponse(ref close) => close.global_signature,
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!(signature(ParseOptions::new()), Some([].as_slice()));
+    /// assert_eq!(signature(ParseOptions::new().empty_octet_strings_as_none(true)), None);
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn empty_octet_strings_as_none(mut self, enabled: bool) -> Self {
+        self.empty_octet_strings_as_none = enabled;
+        self
+    }
+
+    /// Sets whether list entries of a truncated `GetListResponse` are kept (default: `false`).
+    ///
+    /// If a transmission is cut off while transmitting the entries of a `GetListResponse`,
+    /// the parsers return [`ParseError::TruncatedList`]. If set to `true`, the complete
+    /// parsers instead return the messages parsed so far, including the truncated
+    /// `GetListResponse` with the list entries it contains. Its checksum can't be
+    /// verified, so `Message::crc_valid` is `false`.
+    /// The streaming parser always emits the list entries preceding the error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// # use sml_rs::parser::{complete::{parse_with_options, MessageBody}, ParseError, ParseOptions};
+    /// let bytes = sml_rs::transport::decode(include_bytes!("../../sample.bin")).remove(0).unwrap();
+    /// // cut off the transmission within the list entries
+    /// let truncated = &bytes[..180];
+    /// assert!(matches!(
+    ///     parse_with_options(truncated, ParseOptions::new()),
+    ///     Err(ParseError::TruncatedList { parsed: 3, .. })
+    /// ));
+    ///
+    /// let file = parse_with_options(truncated, ParseOptions::new().keep_truncated_lists(true)).unwrap();
+    /// let msg = file.messages.last().unwrap();
+    /// assert!(!msg.crc_valid);
+    /// assert!(matches!(&msg.message_body, MessageBody::GetListResponse(glr) if glr.val_list.len() == 3));
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn keep_truncated_lists(mut self, keep: bool) -> Self {
+        self.keep_truncated_lists = keep;
+        self
+    }
+
+    /// Sets whether unknown trailing fields of message bodies are skipped (default: `false`).
+    ///
+    /// Future revisions of SML may append optional fields to message bodies. By default,
+    /// message bodies with more fields than defined by SML version 1 are reported as
+    /// [`ParseError::TlfMismatch`]. If set to `true`, the known fields are parsed and the
+    /// remaining ones are skipped. The number of skipped fields is recorded in
+    /// `Message::unknown_fields`.
+    ///
+    /// This option is only supported by the parser in the `complete` module.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "alloc")] {
+    /// # use sml_rs::parser::{complete::parse_with_options, ParseOptions};
+    /// // a `CloseResponse` with an additional field (`0x62 0x2a`)
+    /// let bytes = [0x76, 0x05, 0xdd, 0x43, 0x44, 0x00, 0x62, 0x00, 0x62, 0x00, 0x72, 0x63, 0x02, 0x01, 0x72, 0x01, 0x62, 0x2a, 0x63, 0xf4, 0x09, 0x00];
+    /// assert!(parse_with_options(&bytes, ParseOptions::new()).is_err());
+    ///
+    /// let file = parse_with_options(&bytes, ParseOptions::new().skip_unknown_fields(true)).unwrap();
+    /// assert_eq!(file.messages[0].unknown_fields, 1);
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn skip_unknown_fields(mut self, skip: bool) -> Self {
+        self.skip_unknown_fields = skip;
+        self
+    }
+
+    fn check_open_response(&self, open: &common::OpenResponse<'_>) -> Result<(), ParseError> {
+        let version = open.version();
+        if self.reject_unsupported_versions && !version.is_supported() {
+            return Err(ParseError::UnsupportedVersion(version.into()));
+        }
+        Ok(())
+    }
+
+    // compares the checksum read from a message with the computed one (both in transmission byte order)
+    //
+    // Returns whether the checksums match if verification is skipped.
+    fn check_crc(&self, read: u16, computed: u16) -> Result<bool, ParseError> {
+        if read == computed {
+            Ok(true)
+        } else if self.skip_crc_verification {
+            Ok(false)
+        } else {
+            Err(ParseError::CrcMismatch(CrcMismatch {
+                expected: read.swap_bytes(),
+                computed: computed.swap_bytes(),
+            }))
+        }
+    }
+
+    // replaces an empty octet string by `None` if enabled
+    fn normalize(&self, field: &mut Option<OctetStr<'_>>) {
+        if self.empty_octet_strings_as_none && field.is_some_and(<[u8]>::is_empty) {
+            *field = None;
+        }
+    }
+
+    fn normalize_open_response(&self, open: &mut common::OpenResponse<'_>) {
+        self.normalize(&mut open.codepage);
+        self.normalize(&mut open.client_id);
+    }
+
+    // returns whether `input` only consists of zero bytes that should be ignored
+    fn is_ignored_trailer(&self, input: &[u8]) -> bool {
+        self.ignore_trailing_zeros && input.iter().all(|b| *b == 0x00)
+    }
+}
 
 type ResTy<'i, O> = Result<(&'i [u8], O), ParseError>;
-#[allow(dead_code)]
+#[cfg(any(feature = "alloc", test))]
 type ResTyComplete<'i, O> = Result<O, ParseError>;
 
 /// SmlParse is the main trait used to parse bytes into SML data structures.
@@ -165,12 +494,13 @@ where
     /// Tries to parse an instance of `Self` from a byte slice.
     ///
     /// On success, returns the remaining input and the parsed instance of `Self`.
-    fn parse(input: &'i [u8]) -> ResTy<Self>;
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self>;
 
     /// Tries to parse an instance of `Self` from a byte slice and returns an error if there are leftover bytes.
     ///
     /// On success, returns the parsed instance of `Self`.
-    fn parse_complete(input: &'i [u8]) -> ResTyComplete<Self> {
+    #[cfg(any(feature = "alloc", test))]
+    fn parse_complete(input: &'i [u8]) -> ResTyComplete<'i, Self> {
         let (input, x) = Self::parse(input)?;
         if !input.is_empty() {
             return Err(ParseError::LeftoverInput);
@@ -189,17 +519,20 @@ where
 }
 
 impl<'i, T: SmlParseTlf<'i>> SmlParse<'i> for T {
-    fn parse(input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
         let (input, tlf) = TypeLengthField::parse(input)?;
         if !Self::check_tlf(&tlf) {
-            return Err(ParseError::TlfMismatch(core::any::type_name::<Self>()));
+            return Err(ParseError::TlfMismatch {
+                expected: core::any::type_name::<Self>(),
+                found: tlf,
+            });
         }
         Self::parse_with_tlf(input, &tlf)
     }
 }
 
 impl<'i, T: SmlParse<'i>> SmlParse<'i> for Option<T> {
-    fn parse(input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
         if let Some(0x01u8) = input.first() {
             Ok((&input[1..], None))
         } else {
@@ -209,42 +542,76 @@ impl<'i, T: SmlParse<'i>> SmlParse<'i> for Option<T> {
     }
 }
 
-fn take_byte(input: &[u8]) -> ResTy<u8> {
+fn take_byte(input: &[u8]) -> ResTy<'_, u8> {
     if input.is_empty() {
         return Err(ParseError::UnexpectedEOF);
     }
     Ok((&input[1..], input[0]))
 }
 
-fn take<const N: usize>(input: &[u8]) -> ResTy<&[u8; N]> {
-    if input.len() < N {
-        return Err(ParseError::UnexpectedEOF);
+fn take<const N: usize>(input: &[u8]) -> ResTy<'_, &[u8; N]> {
+    match input.split_first_chunk::<N>() {
+        Some((bytes, input)) => Ok((input, bytes)),
+        None => Err(ParseError::UnexpectedEOF),
     }
-    Ok((&input[N..], input[..N].try_into().unwrap()))
 }
 
-fn take_n(input: &[u8], n: usize) -> ResTy<&[u8]> {
+// converts a length read from the input into `usize`, which may be 16 bits wide
+fn to_usize(len: u32) -> Result<usize, ParseError> {
+    usize::try_from(len).map_err(|_| ParseError::LengthOverflow(len))
+}
+
+fn take_n(input: &[u8], n: usize) -> ResTy<'_, &[u8]> {
     if input.len() < n {
         return Err(ParseError::UnexpectedEOF);
     }
     Ok((&input[n..], &input[..n]))
 }
 
-fn map<O1, O2>(val: ResTy<O1>, mut f: impl FnMut(O1) -> O2) -> ResTy<O2> {
+// parses a list whose first `num_known` fields are parsed by `T` and skips the remaining
+// fields (see `ParseOptions::skip_unknown_fields`). Returns the number of skipped fields.
+#[cfg(feature = "alloc")]
+fn parse_extensible<'i, T: SmlParseTlf<'i>>(
+    input: &'i [u8],
+    num_known: u32,
+) -> ResTy<'i, (T, u32)> {
+    let (input, tlf) = TypeLengthField::parse(input)?;
+    let known = TypeLengthField::new(Ty::ListOf, num_known);
+    if tlf.ty != Ty::ListOf || tlf.len <= num_known || !T::check_tlf(&known) {
+        if !T::check_tlf(&tlf) {
+            return Err(ParseError::TlfMismatch {
+                expected: core::any::type_name::<T>(),
+                found: tlf,
+            });
+        }
+        return map(T::parse_with_tlf(input, &tlf), |x| (x, 0));
+    }
+    let (mut input, val) = T::parse_with_tlf(input, &known)?;
+    for _ in num_known..tlf.len {
+        input = raw::skip_value(input)?;
+    }
+    Ok((input, (val, tlf.len - num_known)))
+}
+
+fn map<'i, O1, O2>(val: ResTy<'i, O1>, mut f: impl FnMut(O1) -> O2) -> ResTy<'i, O2> {
     val.map(|(input, x)| (input, f(x)))
 }
 
+#[cfg(feature = "fmt-debug")]
 struct OctetStrFormatter<'i>(&'i [u8]);
 
 // formats a slice using the compact single-line output even when the parent element should be formatted using "{:#?}"
+#[cfg(feature = "fmt-debug")]
 impl<'i> Debug for OctetStrFormatter<'i> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:?}", self.0)
     }
 }
 
+#[cfg(feature = "fmt-debug")]
 struct NumberFormatter<T: Debug, U: Deref<Target = T>>(U);
 
+#[cfg(feature = "fmt-debug")]
 impl<T: Debug, U: Deref<Target = T>> Debug for NumberFormatter<T, U> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{:?}{}", self.0.deref(), core::any::type_name::<T>())
@@ -3,15 +3,72 @@
 use super::{
     map, take_byte, take_n,
     tlf::{Ty, TypeLengthField},
-    ResTy, SmlParseTlf,
+    to_usize, ParseError, ResTy, SmlParseTlf,
 };
 
+/// Parses an unsigned integer whose `TypeLengthField` has already been read.
+///
+/// SML encodes integers using as few bytes as needed, so e.g. an `Unsigned64` value
+/// may be transmitted using a single byte. This function accepts all unsigned
+/// integers of one to eight bytes and widens them to `u64`, the same way the parsers
+/// of this crate do. On success, returns the remaining input and the value.
+///
+/// Returns [`ParseError::TlfMismatch`] if `tlf` doesn't describe an unsigned integer.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{parse_unsigned, TypeLengthField};
+/// let (rest, tlf) = TypeLengthField::read(&[0x63, 0x01, 0x01, 0xff]).unwrap();
+/// assert_eq!(parse_unsigned(rest, &tlf), Ok((&[0xff][..], 257)));
+/// ```
+pub fn parse_unsigned<'i>(
+    input: &'i [u8],
+    tlf: &TypeLengthField,
+) -> Result<(&'i [u8], u64), ParseError> {
+    if !u64::check_tlf(tlf) {
+        return Err(ParseError::TlfMismatch {
+            expected: "unsigned integer",
+            found: *tlf,
+        });
+    }
+    u64::parse_with_tlf(input, tlf)
+}
+
+/// Parses a signed integer whose `TypeLengthField` has already been read.
+///
+/// Same as [`parse_unsigned`], except that the value is sign-extended to `i64`
+/// (e.g. the single byte `0xff` is parsed as `-1`).
+///
+/// Returns [`ParseError::TlfMismatch`] if `tlf` doesn't describe a signed integer.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{parse_signed, TypeLengthField};
+/// let (rest, tlf) = TypeLengthField::read(&[0x53, 0xec, 0x78]).unwrap();
+/// assert_eq!(parse_signed(rest, &tlf), Ok((&[][..], -5000)));
+/// ```
+pub fn parse_signed<'i>(
+    input: &'i [u8],
+    tlf: &TypeLengthField,
+) -> Result<(&'i [u8], i64), ParseError> {
+    if !i64::check_tlf(tlf) {
+        return Err(ParseError::TlfMismatch {
+            expected: "signed integer",
+            found: *tlf,
+        });
+    }
+    i64::parse_with_tlf(input, tlf)
+}
+
 fn parse_num<'i, const SIZE: usize, const IS_SIGNED: bool>(
     input: &'i [u8],
     tlf: &TypeLengthField,
 ) -> ResTy<'i, [u8; SIZE]> {
     // read bytes
-    let (input, bytes) = take_n(input, tlf.len as usize)?;
+    let len = to_usize(tlf.len)?;
+    let (input, bytes) = take_n(input, len)?;
 
     // determine fill bytes depending on the type and sign of the number
     let fill_byte = if IS_SIGNED {
@@ -29,7 +86,7 @@ fn parse_num<'i, const SIZE: usize, const IS_SIGNED: bool>(
     let mut buffer = [fill_byte; SIZE];
 
     // copy read bytes into the buffer
-    let num_skipped_bytes = SIZE - tlf.len as usize;
+    let num_skipped_bytes = SIZE - len;
     buffer[num_skipped_bytes..].copy_from_slice(bytes);
 
     Ok((input, buffer))
@@ -48,7 +105,7 @@ macro_rules! impl_num {
                     // size of the number type (in bytes)
                     const SIZE: usize = core::mem::size_of::<$t>();
 
-                    tlf.ty == $int_ty && tlf.len as usize <= SIZE && tlf.len != 0
+                    tlf.ty == $int_ty && usize::try_from(tlf.len).is_ok_and(|len| len <= SIZE) && tlf.len != 0
                 }
 
                 fn parse_with_tlf(input: &'i [u8], tlf: &TypeLengthField) -> ResTy<'i, Self> {
@@ -124,6 +181,34 @@ mod test {
         assert_eq!(i16::parse_complete(&[0x52, 0x01]), Ok(1))
     }
 
+    #[test]
+    fn public_helpers() {
+        use super::{parse_signed, parse_unsigned};
+        use crate::parser::{ParseError, Ty, TypeLengthField};
+
+        let tlf = TypeLengthField::new(Ty::Unsigned, 3);
+        assert_eq!(
+            parse_unsigned(&[0x01, 0x00, 0x01], &tlf),
+            Ok((&[][..], 65537))
+        );
+        let tlf = TypeLengthField::new(Ty::Integer, 1);
+        assert_eq!(parse_signed(&[0x80, 0x42], &tlf), Ok((&[0x42][..], -128)));
+        assert_eq!(parse_signed(&[], &tlf), Err(ParseError::UnexpectedEOF));
+
+        for tlf in [
+            TypeLengthField::new(Ty::Unsigned, 0),
+            TypeLengthField::new(Ty::Unsigned, 9),
+            TypeLengthField::new(Ty::Integer, 1),
+        ] {
+            assert!(matches!(
+                parse_unsigned(&[0; 9], &tlf),
+                Err(ParseError::TlfMismatch { .. })
+            ));
+        }
+        let tlf = TypeLengthField::new(Ty::Unsigned, 1);
+        assert!(parse_signed(&[0], &tlf).is_err());
+    }
+
     #[test]
     fn parse_optional_num() {
         assert_eq!(Option::<u8>::parse_complete(&[0x01]), Ok(None));
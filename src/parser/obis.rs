@@ -0,0 +1,280 @@
+//! OBIS codes identify the values contained in an SML `ListEntry` (see IEC 62056-61).
+
+use core::{fmt, str::FromStr};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// An OBIS code consisting of the six value groups A to F.
+///
+/// # Formatting
+///
+/// - `{}` uses the common textual representation, e.g. `1-0:1.8.0*255`
+/// - `{:#}` prints the six groups as hex bytes, e.g. `01 00 01 08 00 FF`
+///
+/// # Parsing
+///
+/// [`ObisCode`] implements `FromStr` and accepts both representations. The
+/// final group can be omitted from the textual representation, in which case
+/// it defaults to `255`.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::ObisCode;
+/// let code: ObisCode = "1-0:1.8.0".parse().unwrap();
+/// assert_eq!(code, ObisCode::new(1, 0, 1, 8, 0, 255));
+/// assert_eq!(format!("{}", code), "1-0:1.8.0*255");
+/// assert_eq!(format!("{:#}", code), "01 00 01 08 00 FF");
+/// assert_eq!("01 00 01 08 00 FF".parse(), Ok(code));
+/// ```
+///
+/// Commonly used OBIS codes are available as associated constants:
+///
+/// ```
+/// # use sml_rs::parser::ObisCode;
+/// assert_eq!(ObisCode::ENERGY_IMPORT_TOTAL, "1-0:1.8.0".parse().unwrap());
+/// assert_eq!(ObisCode::ACTIVE_POWER.to_string(), "1-0:16.7.0*255");
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ObisCode(pub [u8; 6]);
+
+impl ObisCode {
+    /// Positive active energy (import from the grid), total (`1-0:1.8.0*255`)
+    pub const ENERGY_IMPORT_TOTAL: ObisCode = ObisCode::new(1, 0, 1, 8, 0, 255);
+    /// Positive active energy (import from the grid), tariff 1 (`1-0:1.8.1*255`)
+    pub const ENERGY_IMPORT_TARIFF_1: ObisCode = ObisCode::new(1, 0, 1, 8, 1, 255);
+    /// Positive active energy (import from the grid), tariff 2 (`1-0:1.8.2*255`)
+    pub const ENERGY_IMPORT_TARIFF_2: ObisCode = ObisCode::new(1, 0, 1, 8, 2, 255);
+    /// Negative active energy (export to the grid), total (`1-0:2.8.0*255`)
+    pub const ENERGY_EXPORT_TOTAL: ObisCode = ObisCode::new(1, 0, 2, 8, 0, 255);
+    /// Negative active energy (export to the grid), tariff 1 (`1-0:2.8.1*255`)
+    pub const ENERGY_EXPORT_TARIFF_1: ObisCode = ObisCode::new(1, 0, 2, 8, 1, 255);
+    /// Negative active energy (export to the grid), tariff 2 (`1-0:2.8.2*255`)
+    pub const ENERGY_EXPORT_TARIFF_2: ObisCode = ObisCode::new(1, 0, 2, 8, 2, 255);
+    /// Instantaneous active power, sum over all phases (`1-0:16.7.0*255`)
+    pub const ACTIVE_POWER: ObisCode = ObisCode::new(1, 0, 16, 7, 0, 255);
+    /// Active power estimated from energy counters (`1-0:16.7.0*128`, see [`crate::application::power`])
+    ///
+    /// Not transmitted by meters. The value group F uses a manufacturer-specific value
+    /// to distinguish the estimates from measured values.
+    pub const ACTIVE_POWER_ESTIMATED: ObisCode = ObisCode::new(1, 0, 16, 7, 0, 128);
+    /// Instantaneous active power of phase L1 (`1-0:36.7.0*255`)
+    pub const ACTIVE_POWER_L1: ObisCode = ObisCode::new(1, 0, 36, 7, 0, 255);
+    /// Instantaneous active power of phase L2 (`1-0:56.7.0*255`)
+    pub const ACTIVE_POWER_L2: ObisCode = ObisCode::new(1, 0, 56, 7, 0, 255);
+    /// Instantaneous active power of phase L3 (`1-0:76.7.0*255`)
+    pub const ACTIVE_POWER_L3: ObisCode = ObisCode::new(1, 0, 76, 7, 0, 255);
+    /// Instantaneous voltage of phase L1 (`1-0:32.7.0*255`)
+    pub const VOLTAGE_L1: ObisCode = ObisCode::new(1, 0, 32, 7, 0, 255);
+    /// Instantaneous voltage of phase L2 (`1-0:52.7.0*255`)
+    pub const VOLTAGE_L2: ObisCode = ObisCode::new(1, 0, 52, 7, 0, 255);
+    /// Instantaneous voltage of phase L3 (`1-0:72.7.0*255`)
+    pub const VOLTAGE_L3: ObisCode = ObisCode::new(1, 0, 72, 7, 0, 255);
+    /// Instantaneous current of phase L1 (`1-0:31.7.0*255`)
+    pub const CURRENT_L1: ObisCode = ObisCode::new(1, 0, 31, 7, 0, 255);
+    /// Instantaneous current of phase L2 (`1-0:51.7.0*255`)
+    pub const CURRENT_L2: ObisCode = ObisCode::new(1, 0, 51, 7, 0, 255);
+    /// Instantaneous current of phase L3 (`1-0:71.7.0*255`)
+    pub const CURRENT_L3: ObisCode = ObisCode::new(1, 0, 71, 7, 0, 255);
+    /// Grid frequency (`1-0:14.7.0*255`)
+    pub const FREQUENCY: ObisCode = ObisCode::new(1, 0, 14, 7, 0, 255);
+    /// Device identification (server id) of the meter (`1-0:0.0.9*255`)
+    pub const DEVICE_ID: ObisCode = ObisCode::new(1, 0, 0, 0, 9, 255);
+    /// Manufacturer identification (`1-0:96.50.1*1`)
+    pub const MANUFACTURER_ID: ObisCode = ObisCode::new(1, 0, 96, 50, 1, 1);
+    /// Status word of the meter (`1-0:96.5.0*255`)
+    pub const STATUS: ObisCode = ObisCode::new(1, 0, 96, 5, 0, 255);
+
+    /// Constructs an `ObisCode` from its value groups.
+    #[must_use]
+    pub const fn new(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8) -> Self {
+        ObisCode([a, b, c, d, e, f])
+    }
+
+    /// Converts the `obj_name` of a `ListEntry` into an `ObisCode`.
+    ///
+    /// Returns `None` if `bytes` doesn't have a length of six bytes.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(ObisCode)
+    }
+}
+
+impl fmt::Display for ObisCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        if f.alternate() {
+            write!(f, "{a:02X} {b:02X} {c:02X} {d:02X} {e:02X} {g:02X}")
+        } else {
+            write!(f, "{a}-{b}:{c}.{d}.{e}*{g}")
+        }
+    }
+}
+
+/// Error returned when parsing an [`ObisCode`] from a string fails.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObisCodeParseError {
+    /// The string doesn't match any of the supported representations
+    InvalidFormat,
+    /// A value group isn't a number in the range 0 to 255
+    InvalidValue,
+}
+
+impl fmt::Display for ObisCodeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObisCodeParseError::InvalidFormat => {
+                f.write_str("invalid OBIS code format (expected e.g. `1-0:1.8.0*255` or `1.8.0`)")
+            }
+            ObisCodeParseError::InvalidValue => {
+                f.write_str("OBIS code value group is not a number between 0 and 255")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ObisCodeParseError {}
+
+impl FromStr for ObisCode {
+    type Err = ObisCodeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains(['-', ':']) {
+            parse_textual(s)
+        } else {
+            parse_hex(s)
+        }
+    }
+}
+
+// parses the `A-B:C.D.E*F` representation
+fn parse_textual(s: &str) -> Result<ObisCode, ObisCodeParseError> {
+    let (a, rest) = s.split_once('-').ok_or(ObisCodeParseError::InvalidFormat)?;
+    let (b, rest) = rest
+        .split_once(':')
+        .ok_or(ObisCodeParseError::InvalidFormat)?;
+    let (cde, f) = match rest.split_once('*') {
+        Some((cde, f)) => (cde, Some(f)),
+        None => (rest, None),
+    };
+    let mut cde = cde.split('.');
+    let mut groups = [0u8; 6];
+    for (group, part) in groups[..2].iter_mut().zip([a, b]) {
+        *group = parse_dec(part)?;
+    }
+    for group in &mut groups[2..5] {
+        *group = parse_dec(cde.next().ok_or(ObisCodeParseError::InvalidFormat)?)?;
+    }
+    if cde.next().is_some() {
+        return Err(ObisCodeParseError::InvalidFormat);
+    }
+    groups[5] = match f {
+        Some(f) => parse_dec(f)?,
+        None => 255,
+    };
+    Ok(ObisCode(groups))
+}
+
+fn parse_dec(s: &str) -> Result<u8, ObisCodeParseError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(ObisCodeParseError::InvalidFormat);
+    }
+    s.parse().map_err(|_| ObisCodeParseError::InvalidValue)
+}
+
+// parses the hex representation with optional whitespace between bytes (e.g. `01 00 01 08 00 FF`)
+fn parse_hex(s: &str) -> Result<ObisCode, ObisCodeParseError> {
+    let mut groups = [0u8; 6];
+    let mut digits = s.chars().filter(|c| !c.is_whitespace());
+    for group in &mut groups {
+        let mut value = 0;
+        for _ in 0..2 {
+            let digit = digits
+                .next()
+                .and_then(|c| c.to_digit(16))
+                .ok_or(ObisCodeParseError::InvalidFormat)?;
+            value = value * 16 + digit as u8;
+        }
+        *group = value;
+    }
+    if digits.next().is_some() {
+        return Err(ObisCodeParseError::InvalidFormat);
+    }
+    Ok(ObisCode(groups))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_textual() {
+        let exp = Ok(ObisCode::new(1, 0, 1, 8, 0, 255));
+        assert_eq!("1-0:1.8.0*255".parse(), exp);
+        assert_eq!("1-0:1.8.0".parse(), exp);
+        assert_eq!(" 1-0:1.8.0 ".parse(), exp);
+        assert_eq!(
+            "129-129:199.130.3*255".parse(),
+            Ok(ObisCode::new(129, 129, 199, 130, 3, 255))
+        );
+        assert_eq!(
+            "1-0:1.8*255".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+        assert_eq!(
+            "1-0:1.8.0.0".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+        assert_eq!(
+            "1-0:1.8.+0".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+        assert_eq!(
+            "1-0:1.8.256".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn parse_hex() {
+        let exp = Ok(ObisCode::new(1, 0, 1, 8, 0, 255));
+        assert_eq!("01 00 01 08 00 FF".parse(), exp);
+        assert_eq!("0100010800ff".parse(), exp);
+        assert_eq!(
+            "01 00 01 08 00".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+        assert_eq!(
+            "01 00 01 08 00 FF 00".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+        assert_eq!(
+            "01 00 01 08 00 FG".parse::<ObisCode>(),
+            Err(ObisCodeParseError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn from_bytes() {
+        assert_eq!(
+            ObisCode::from_bytes(&[1, 0, 1, 8, 0, 255]),
+            Some(ObisCode::new(1, 0, 1, 8, 0, 255))
+        );
+        assert_eq!(ObisCode::from_bytes(&[1, 0, 1, 8, 0]), None);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::format;
+        let code = ObisCode::new(1, 0, 16, 7, 0, 255);
+        assert_eq!(format!("{}", code), "1-0:16.7.0*255");
+        assert_eq!(format!("{:#}", code), "01 00 10 07 00 FF");
+        assert_eq!(format!("{}", code).parse(), Ok(code));
+        assert_eq!(format!("{:#}", code).parse(), Ok(code));
+    }
+}
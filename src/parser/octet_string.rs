@@ -3,7 +3,7 @@
 use super::{
     take_n,
     tlf::{Ty, TypeLengthField},
-    ResTy, SmlParseTlf,
+    to_usize, ResTy, SmlParseTlf,
 };
 
 // #[cfg(feature = "alloc")]
@@ -25,13 +25,47 @@ use super::{
 /// OctetStr is the borrowed version of a sequence of bytes.
 pub type OctetStr<'i> = &'i [u8];
 
+/// Extension methods for comparing octet strings such as `server_id`s without short-circuiting.
+///
+/// Use these methods instead of `==` or `starts_with` when the compared value is
+/// secret, e.g. when authorizing meters by their `server_id`. They are best-effort
+/// and not a cryptographic guarantee, see [`util::eq_ct`](crate::util::eq_ct) and
+/// [`util::starts_with_ct`](crate::util::starts_with_ct).
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::common::{OctetStr, OctetStrExt};
+/// let server_id: OctetStr = b"\x0a\x01ISK\x00\x04\x7a\x55\x44";
+/// assert!(server_id.starts_with_ct(b"\x0a\x01ISK"));
+/// assert!(!server_id.eq_ct(b"\x0a\x01ISK"));
+/// ```
+pub trait OctetStrExt {
+    /// Compares `self` with `other` without short-circuiting (see [`util::eq_ct`](crate::util::eq_ct)).
+    fn eq_ct(&self, other: &[u8]) -> bool;
+
+    /// Checks without short-circuiting whether `self` starts with `prefix` (see
+    /// [`util::starts_with_ct`](crate::util::starts_with_ct)).
+    fn starts_with_ct(&self, prefix: &[u8]) -> bool;
+}
+
+impl OctetStrExt for [u8] {
+    fn eq_ct(&self, other: &[u8]) -> bool {
+        crate::util::eq_ct(self, other)
+    }
+
+    fn starts_with_ct(&self, prefix: &[u8]) -> bool {
+        crate::util::starts_with_ct(self, prefix)
+    }
+}
+
 impl<'i> SmlParseTlf<'i> for OctetStr<'i> {
     fn check_tlf(tlf: &TypeLengthField) -> bool {
         matches!(tlf.ty, Ty::OctetString)
     }
 
     fn parse_with_tlf(input: &'i [u8], tlf: &TypeLengthField) -> ResTy<'i, Self> {
-        take_n(input, tlf.len as usize)
+        take_n(input, to_usize(tlf.len)?)
     }
 }
 
@@ -62,4 +96,14 @@ mod test {
             None
         );
     }
+
+    #[test]
+    fn test_octet_str_length_overflow() {
+        // announces 65536 bytes, which doesn't fit into `usize` on 16-bit targets
+        let res = OctetStr::parse_complete(&hex!("8180808005 00"));
+        #[cfg(target_pointer_width = "16")]
+        assert_eq!(res, Err(crate::parser::ParseError::LengthOverflow(0x10000)));
+        #[cfg(not(target_pointer_width = "16"))]
+        assert_eq!(res, Err(crate::parser::ParseError::UnexpectedEOF));
+    }
 }
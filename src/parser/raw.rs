@@ -0,0 +1,247 @@
+//! Splitting SML Files into messages without parsing their bodies.
+
+use super::{
+    common::EndOfSmlMessage, streaming, take_n, to_usize, OctetStr, ParseError, ParseOptions,
+    ResTy, SmlParse, Ty, TypeLengthField,
+};
+
+/// An SML Message whose body hasn't been parsed.
+///
+/// Returned by [`split_messages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawMessage<'i> {
+    /// all bytes of the message (including the checksum and the end of message marker)
+    pub bytes: &'i [u8],
+    /// transaction identifier
+    pub transaction_id: OctetStr<'i>,
+    /// allows grouping of SML messages
+    pub group_no: u8,
+    /// describes how to handle the Message in case of errors
+    pub abort_on_error: u8,
+    /// tag of the message body (e.g. [`RawMessage::GET_LIST_RESPONSE`])
+    pub tag: u32,
+    /// the unparsed message body (without its tag)
+    pub body: &'i [u8],
+}
+
+impl<'i> RawMessage<'i> {
+    /// Tag of `SML_PublicOpen.Res` messages
+    pub const OPEN_RESPONSE: u32 = 0x0101;
+    /// Tag of `SML_PublicClose.Res` messages
+    pub const CLOSE_RESPONSE: u32 = 0x0201;
+    /// Tag of `SML_GetList.Res` messages
+    pub const GET_LIST_RESPONSE: u32 = 0x0701;
+
+    /// Returns a streaming parser parsing the message.
+    #[must_use]
+    pub fn parser(&self) -> streaming::Parser<'i> {
+        streaming::Parser::new(self.bytes)
+    }
+
+    /// Parses the message.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    pub fn parse(&self) -> Result<super::complete::Message<'i>, ParseError> {
+        let mut file = super::complete::parse(self.bytes)?;
+        Ok(file.messages.remove(0))
+    }
+
+    /// Returns the bytes of the message in the format of the `.hex` files of the
+    /// libsml-testing corpus (see [`util::to_hex_dump`](crate::util::to_hex_dump)).
+    ///
+    /// Unlike [`File::to_hex_dump`](super::complete::File::to_hex_dump), the
+    /// message is returned exactly as received and isn't encoded as transmission.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn hex(&self) -> alloc::string::String {
+        crate::util::to_hex_dump(self.bytes)
+    }
+}
+
+/// Splits an SML File into its messages without parsing the message bodies.
+///
+/// The structure of each message and its checksum are verified, but the body is
+/// only skipped. This allows routing or filtering messages cheaply and only parsing
+/// the selected ones using [`RawMessage::parser`] or `RawMessage::parse`. The
+/// iterator stops after the first error.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::parser::{split_messages, RawMessage};
+/// # let mut decoder = sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<512>>(include_bytes!("../../sample.bin"));
+/// # let bytes = decoder.next().unwrap().unwrap();
+/// let tags: Vec<_> = split_messages(bytes).map(|msg| msg.unwrap().tag).collect();
+/// assert_eq!(tags, [RawMessage::OPEN_RESPONSE, RawMessage::GET_LIST_RESPONSE, RawMessage::CLOSE_RESPONSE]);
+///
+/// // only parse the `GetListResponse`
+/// let msg = split_messages(bytes).find(|msg| msg.as_ref().is_ok_and(|msg| msg.tag == RawMessage::GET_LIST_RESPONSE));
+/// let num_entries = msg.unwrap().unwrap().parser().list_entries().count();
+/// assert!(num_entries > 0);
+/// ```
+#[must_use]
+pub fn split_messages(bytes: &[u8]) -> SplitMessages<'_> {
+    SplitMessages { input: bytes }
+}
+
+/// Iterator over the messages of an SML File.
+///
+/// Created by [`split_messages`].
+#[derive(Debug, Clone)]
+pub struct SplitMessages<'i> {
+    input: &'i [u8],
+}
+
+impl<'i> Iterator for SplitMessages<'i> {
+    type Item = Result<RawMessage<'i>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+        match parse_raw_message(self.input) {
+            Ok((input, msg)) => {
+                self.input = input;
+                Some(Ok(msg))
+            }
+            Err(e) => {
+                // stop after the first error
+                self.input = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn parse_raw_message(input_orig: &[u8]) -> ResTy<'_, RawMessage<'_>> {
+    let (input, tlf) = TypeLengthField::parse(input_orig)?;
+    if tlf.ty != Ty::ListOf || tlf.len != 6 {
+        return Err(ParseError::TlfMismatch {
+            expected: "Message",
+            found: tlf,
+        });
+    }
+    let (input, transaction_id) = OctetStr::parse(input)?;
+    let (input, group_no) = u8::parse(input)?;
+    let (input, abort_on_error) = u8::parse(input)?;
+    let (input, tlf) = TypeLengthField::parse(input)?;
+    if tlf.ty != Ty::ListOf || tlf.len != 2 {
+        return Err(ParseError::TlfMismatch {
+            expected: "MessageBody",
+            found: tlf,
+        });
+    }
+    let (body, tag) = u32::parse(input)?;
+    let input = skip_value(body)?;
+    let body = &body[..body.len() - input.len()];
+
+    let num_bytes_read = input_orig.len() - input.len();
+    let (input, crc) = u16::parse(input)?;
+    let (input, _) = EndOfSmlMessage::parse(input)?;
+
+    let digest = crate::util::CRC_X25
+        .checksum(&input_orig[..num_bytes_read])
+        .swap_bytes();
+    ParseOptions::new().check_crc(crc, digest)?;
+
+    let msg = RawMessage {
+        bytes: &input_orig[..input_orig.len() - input.len()],
+        transaction_id,
+        group_no,
+        abort_on_error,
+        tag,
+        body,
+    };
+    Ok((input, msg))
+}
+
+// skips a single value (including nested lists) and returns the remaining input
+pub(super) fn skip_value(mut input: &[u8]) -> Result<&[u8], ParseError> {
+    // lists are handled iteratively, so deeply nested input can't overflow the stack
+    let mut remaining: u64 = 1;
+    while remaining > 0 {
+        remaining -= 1;
+        let (rest, tlf) = TypeLengthField::parse(input)?;
+        input = rest;
+        if tlf.ty == Ty::ListOf {
+            remaining += u64::from(tlf.len);
+        } else {
+            input = take_n(input, to_usize(tlf.len)?)?.0;
+        }
+    }
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    const CLOSE_RESPONSE: [u8; 20] = hex!("7605dd43 44006200 62007263 02017101 63fd5600");
+
+    #[test]
+    fn split() {
+        let mut bytes = [0u8; 40];
+        bytes[..20].copy_from_slice(&CLOSE_RESPONSE);
+        bytes[20..].copy_from_slice(&CLOSE_RESPONSE);
+        let mut msgs = split_messages(&bytes);
+        assert_eq!(
+            msgs.next().unwrap().unwrap(),
+            RawMessage {
+                bytes: &CLOSE_RESPONSE,
+                transaction_id: &hex!("dd434400"),
+                group_no: 0,
+                abort_on_error: 0,
+                tag: RawMessage::CLOSE_RESPONSE,
+                body: &hex!("7101"),
+            }
+        );
+        let msg = msgs.next().unwrap().unwrap();
+        assert_eq!(msg.bytes, &bytes[20..]);
+        #[cfg(feature = "alloc")]
+        assert_eq!(msg.hex(), "7605DD4344006200620072630201710163FD5600");
+        assert_eq!(msgs.next(), None);
+        assert_eq!(split_messages(&[]).count(), 0);
+    }
+
+    #[test]
+    fn errors() {
+        let mut bytes = CLOSE_RESPONSE;
+        bytes[17] = 0x57;
+        let mut iter = split_messages(&bytes);
+        assert!(matches!(iter.next(), Some(Err(ParseError::CrcMismatch(_)))));
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(
+            split_messages(&CLOSE_RESPONSE[..15]).next(),
+            Some(Err(ParseError::UnexpectedEOF))
+        );
+        // deeply nested lists don't overflow the stack
+        let mut nested = [0x71; 100_000];
+        nested[..12].copy_from_slice(&CLOSE_RESPONSE[..12]);
+        assert_eq!(
+            split_messages(&nested).next(),
+            Some(Err(ParseError::UnexpectedEOF))
+        );
+    }
+
+    #[test]
+    fn sample() {
+        let mut decoder = crate::transport::decode_streaming::<crate::util::ArrayBuf<512>>(
+            include_bytes!("../../sample.bin"),
+        );
+        while let Some(bytes) = decoder.next() {
+            let bytes = bytes.unwrap();
+            let mut len = 0;
+            for msg in split_messages(bytes) {
+                let msg = msg.unwrap();
+                len += msg.bytes.len();
+                assert!(msg.parser().all(|event| event.is_ok()));
+            }
+            assert_eq!(len, bytes.len());
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Plausibility checks for the sequence of received SML messages.
+//!
+//! Real-world meters number their messages using increasing transaction
+//! identifiers. On lossy links, a transmission can be assembled from parts of
+//! different transmissions or be received twice while still having a valid
+//! checksum. [`SequenceChecker`] detects such anomalies by verifying that the
+//! transaction identifiers increase and that each SML File is framed by an
+//! `OpenResponse` and a `CloseResponse` message.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[cfg(feature = "alloc")] {
+//! # use sml_rs::parser::{complete, sequence::{SequenceChecker, SequenceAnomaly}};
+//! let bytes = include_bytes!("../../sample.bin");
+//! let decoded = sml_rs::transport::decode(bytes);
+//! let file = complete::parse(decoded[0].as_ref().unwrap()).unwrap();
+//!
+//! let mut checker = SequenceChecker::new();
+//! assert_eq!(checker.check_file(&file), Ok(()));
+//! // receiving the same file again is detected
+//! assert_eq!(checker.check_file(&file), Err(SequenceAnomaly::TransactionIdNotIncreasing));
+//! # }
+//! ```
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use super::complete::{File, MessageBody};
+
+/// Maximum length of transaction identifiers checked by [`SequenceChecker`].
+///
+/// Longer transaction identifiers are not checked.
+pub const MAX_TRANSACTION_ID_LEN: usize = 16;
+
+/// Anomaly detected by [`SequenceChecker`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceAnomaly {
+    /// A transaction identifier is not larger than the previous one
+    TransactionIdNotIncreasing,
+    /// The SML File doesn't start with an `OpenResponse` message
+    MissingOpenResponse,
+    /// The SML File doesn't end with a `CloseResponse` message
+    MissingCloseResponse,
+}
+
+impl fmt::Display for SequenceAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceAnomaly::TransactionIdNotIncreasing => {
+                f.write_str("transaction id is not larger than the previous one")
+            }
+            SequenceAnomaly::MissingOpenResponse => {
+                f.write_str("SML file doesn't start with an `OpenResponse` message")
+            }
+            SequenceAnomaly::MissingCloseResponse => {
+                f.write_str("SML file doesn't end with a `CloseResponse` message")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SequenceAnomaly {}
+
+/// Byte order used to interpret transaction identifiers as numbers.
+///
+/// Meters differ in how they encode their transaction identifiers. For example,
+/// meters by EMH use big endian counters while meters by DZG use little endian counters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TransactionIdOrder {
+    /// Accept a transaction identifier if it is larger in either byte order (default)
+    #[default]
+    Any,
+    /// Most significant byte first
+    BigEndian,
+    /// Least significant byte first
+    LittleEndian,
+}
+
+impl TransactionIdOrder {
+    fn is_increasing(self, prev: &[u8], next: &[u8]) -> bool {
+        let be = || next > prev;
+        let le = || next.iter().rev().gt(prev.iter().rev());
+        match self {
+            TransactionIdOrder::Any => be() || le(),
+            TransactionIdOrder::BigEndian => be(),
+            TransactionIdOrder::LittleEndian => le(),
+        }
+    }
+}
+
+/// Checks that received SML messages form a plausible sequence.
+///
+/// The checker keeps the last transaction identifier seen and therefore needs to
+/// be fed with all messages in the order they have been received. Transaction
+/// identifiers are only compared if they have the same length, which means that
+/// a change of length (e.g. after a firmware update) is never reported.
+///
+/// By default, transaction identifiers have to increase across SML Files. Some
+/// meters (e.g. by Holley) restart numbering in each SML File, which can be
+/// handled using [`SequenceChecker::with_per_file_ids`].
+#[derive(Debug, Clone)]
+pub struct SequenceChecker {
+    order: TransactionIdOrder,
+    per_file_ids: bool,
+    last_id: [u8; MAX_TRANSACTION_ID_LEN],
+    last_id_len: Option<usize>,
+}
+
+impl Default for SequenceChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SequenceChecker {
+    /// Constructs a new `SequenceChecker` accepting transaction identifiers in any byte order.
+    #[must_use]
+    pub const fn new() -> Self {
+        SequenceChecker {
+            order: TransactionIdOrder::Any,
+            per_file_ids: false,
+            last_id: [0; MAX_TRANSACTION_ID_LEN],
+            last_id_len: None,
+        }
+    }
+
+    /// Sets the byte order used to compare transaction identifiers.
+    #[must_use]
+    pub const fn with_order(mut self, order: TransactionIdOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Only compares transaction identifiers within the same SML File if set to `true`.
+    ///
+    /// This only affects `SequenceChecker::check_file`.
+    #[must_use]
+    pub const fn with_per_file_ids(mut self, per_file_ids: bool) -> Self {
+        self.per_file_ids = per_file_ids;
+        self
+    }
+
+    /// Checks the transaction identifier of the next message.
+    ///
+    /// The transaction identifier is stored as reference for the next call, even if an anomaly is detected.
+    pub fn check_transaction_id(&mut self, transaction_id: &[u8]) -> Result<(), SequenceAnomaly> {
+        let res = match self.last_id_len {
+            Some(len) if len == transaction_id.len() => {
+                if self
+                    .order
+                    .is_increasing(&self.last_id[..len], transaction_id)
+                {
+                    Ok(())
+                } else {
+                    Err(SequenceAnomaly::TransactionIdNotIncreasing)
+                }
+            }
+            _ => Ok(()),
+        };
+        if transaction_id.len() <= MAX_TRANSACTION_ID_LEN {
+            self.last_id[..transaction_id.len()].copy_from_slice(transaction_id);
+            self.last_id_len = Some(transaction_id.len());
+        } else {
+            self.last_id_len = None;
+        }
+        res
+    }
+
+    /// Checks all messages of an SML File.
+    ///
+    /// All messages are taken into account, even if an anomaly is detected. If
+    /// there are several anomalies, the first one is returned.
+    #[cfg(feature = "alloc")]
+    pub fn check_file(&mut self, file: &File<'_>) -> Result<(), SequenceAnomaly> {
+        if self.per_file_ids {
+            self.reset();
+        }
+        let mut res = Ok(());
+        for msg in &file.messages {
+            let r = self.check_transaction_id(msg.transaction_id);
+            res = res.and(r);
+        }
+        if !matches!(
+            file.messages.first().map(|m| &m.message_body),
+            Some(MessageBody::OpenResponse(_))
+        ) {
+            res = res.and(Err(SequenceAnomaly::MissingOpenResponse));
+        }
+        if !matches!(
+            file.messages.last().map(|m| &m.message_body),
+            Some(MessageBody::CloseResponse(_))
+        ) {
+            res = res.and(Err(SequenceAnomaly::MissingCloseResponse));
+        }
+        res
+    }
+
+    /// Forgets the last transaction identifier, e.g. after the meter has been reconnected.
+    pub fn reset(&mut self) {
+        self.last_id_len = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn big_endian() {
+        let mut c = SequenceChecker::new().with_order(TransactionIdOrder::BigEndian);
+        assert_eq!(c.check_transaction_id(&[0, 0, 4, 254]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0, 0, 4, 255]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0, 0, 5, 0]), Ok(()));
+        assert_eq!(
+            c.check_transaction_id(&[0, 0, 5, 0]),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+        assert_eq!(
+            c.check_transaction_id(&[0, 0, 4, 0]),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+        assert_eq!(c.check_transaction_id(&[0, 0, 7, 0]), Ok(()));
+    }
+
+    #[test]
+    fn little_endian() {
+        let mut c = SequenceChecker::new().with_order(TransactionIdOrder::LittleEndian);
+        assert_eq!(c.check_transaction_id(&[254, 44, 173, 7]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[255, 44, 173, 7]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0, 45, 173, 7]), Ok(()));
+        assert_eq!(
+            c.check_transaction_id(&[255, 44, 173, 7]),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+    }
+
+    #[test]
+    fn any_order() {
+        let mut c = SequenceChecker::new();
+        assert_eq!(c.check_transaction_id(&[255, 44, 173, 7]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0, 45, 173, 7]), Ok(()));
+        assert_eq!(
+            c.check_transaction_id(&[0, 0, 0, 1]),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+        assert_eq!(c.check_transaction_id(&[0, 0, 1, 0]), Ok(()));
+        assert_eq!(
+            c.check_transaction_id(&[0, 0, 1, 0]),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+    }
+
+    #[test]
+    fn length_change_and_reset() {
+        let mut c = SequenceChecker::new();
+        assert_eq!(c.check_transaction_id(&[5, 5]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0, 0, 1]), Ok(()));
+        c.reset();
+        assert_eq!(c.check_transaction_id(&[0, 0, 1]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0; 17]), Ok(()));
+        assert_eq!(c.check_transaction_id(&[0; 17]), Ok(()));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn check_file() {
+        use crate::parser::{
+            common::CloseResponse,
+            complete::{File, Message, MessageBody},
+        };
+        use alloc::vec;
+        let close = Message {
+            transaction_id: &[1],
+            group_no: 0,
+            abort_on_error: 0,
+            message_body: MessageBody::CloseResponse(CloseResponse {
+                global_signature: None,
+            }),
+            crc_valid: true,
+            unknown_fields: 0,
+        };
+        let mut c = SequenceChecker::new().with_per_file_ids(true);
+        let file = File {
+            messages: vec![close.clone()],
+        };
+        // ids aren't compared across files
+        for _ in 0..2 {
+            assert_eq!(
+                c.check_file(&file),
+                Err(SequenceAnomaly::MissingOpenResponse)
+            );
+        }
+        let file = File {
+            messages: vec![close.clone(), close],
+        };
+        assert_eq!(
+            c.check_file(&file),
+            Err(SequenceAnomaly::TransactionIdNotIncreasing)
+        );
+        let file = File { messages: vec![] };
+        assert_eq!(
+            c.check_file(&file),
+            Err(SequenceAnomaly::MissingOpenResponse)
+        );
+    }
+}
@@ -4,11 +4,13 @@
 
 use crate::util::CRC_X25;
 
+#[cfg(feature = "fmt-debug")]
+use super::OctetStrFormatter;
 use super::{
-    common::{CloseResponse, EndOfSmlMessage, ListEntry, OpenResponse, Signature, Time},
+    common::{CloseResponse, EndOfSmlMessage, ListEntry, OpenResponse, Signature, Time, Value},
     octet_string::OctetStr,
     tlf::{self, Ty, TypeLengthField},
-    OctetStrFormatter, ParseError, ResTy, SmlParse, SmlParseTlf,
+    ObisCode, ParseError, ParseOptions, ResTy, SmlParse, SmlParseTlf,
 };
 
 /// Incremental parser for SML messages.
@@ -17,20 +19,107 @@ use super::{
 pub struct Parser<'i> {
     input: &'i [u8],
     msg_input: &'i [u8],
-    pending_list_entries: u32,
+    pending_list_entries: u64,
+    // number of list entries announced by the current `GetListResponse`
+    num_vals: u32,
+    options: ParseOptions,
 }
 
 impl<'i> Parser<'i> {
     /// Create a new Parser from a slice of bytes.
     pub fn new(input: &'i [u8]) -> Self {
+        Self::with_options(input, ParseOptions::new())
+    }
+
+    /// Create a new Parser from a slice of bytes using the provided options.
+    ///
+    /// See [`ParseOptions`] for the available options.
+    pub fn with_options(input: &'i [u8], options: ParseOptions) -> Self {
         Parser {
             input,
             msg_input: &[],
             pending_list_entries: 0,
+            num_vals: 0,
+            options,
+        }
+    }
+
+    /// Returns an iterator that only yields the `ListEntry` events of the parser.
+    ///
+    /// Errors are passed through.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::streaming::Parser;
+    /// # let mut decoder = sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<512>>(include_bytes!("../../sample.bin"));
+    /// # let bytes = decoder.next().unwrap().unwrap();
+    /// for entry in Parser::new(bytes).list_entries() {
+    ///     let entry = entry.expect("error while parsing");
+    ///     println!("{:?}: {:?}", entry.obj_name, entry.value);
+    /// }
+    /// ```
+    pub fn list_entries(self) -> ListEntries<'i> {
+        ListEntries { parser: self }
+    }
+
+    /// Returns an iterator that yields the OBIS code and value of all list entries whose OBIS code is contained in `codes`.
+    ///
+    /// Errors are passed through.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::{streaming::Parser, ObisCode, common::Value};
+    /// # let mut decoder = sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<512>>(include_bytes!("../../sample.bin"));
+    /// # let bytes = decoder.next().unwrap().unwrap();
+    /// let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+    /// let codes = [energy];
+    /// let mut values = Parser::new(bytes).values_for(&codes);
+    /// assert!(matches!(values.next(), Some(Ok((code, Value::U64(_)))) if code == energy));
+    /// assert!(values.next().is_none());
+    /// ```
+    pub fn values_for<'c>(self, codes: &'c [ObisCode]) -> ValuesFor<'i, 'c> {
+        ValuesFor {
+            entries: self.list_entries(),
+            codes,
+        }
+    }
+
+    /// Like [`Parser::values_for`], but also returns the time each value has been captured.
+    ///
+    /// The time is taken from the `val_time` field of the list entry. If it's missing,
+    /// the `act_sensor_time` of the `GetListResponse` or the `ref_time` of the
+    /// `OpenResponse` is used instead, if present.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::{streaming::Parser, ObisCode, common::Time};
+    /// # let mut decoder = sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<512>>(include_bytes!("../../sample.bin"));
+    /// # let bytes = decoder.next().unwrap().unwrap();
+    /// let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+    /// let codes = [energy];
+    /// let value = Parser::new(bytes).timed_values_for(&codes).next().unwrap().unwrap();
+    /// assert_eq!(value.obis_code, energy);
+    /// assert!(matches!(value.time, Some(Time::SecIndex(_))));
+    /// ```
+    pub fn timed_values_for<'c>(self, codes: &'c [ObisCode]) -> TimedValuesFor<'i, 'c> {
+        TimedValuesFor {
+            parser: self,
+            codes,
+            transmission_time: None,
         }
     }
 
     fn parse_next(&mut self) -> Result<Option<ParseEvent<'i>>, ParseError> {
+        // `msg_input` is non-empty once the first message has been read
+        if self.pending_list_entries == 0
+            && !self.msg_input.is_empty()
+            && self.options.is_ignored_trailer(self.input)
+        {
+            self.input = &[];
+        }
         if self.input.is_empty() && self.pending_list_entries == 0 {
             return Ok(None);
         }
@@ -38,12 +127,24 @@ impl<'i> Parser<'i> {
         Ok(Some(match self.pending_list_entries {
             0 => {
                 self.msg_input = self.input;
-                let (input, msg) = MessageStart::parse(self.input)?;
+                let (input, mut msg) = MessageStart::parse(self.input)?;
                 self.input = input;
-                if let MessageBody::GetListResponse(glr) = &msg.message_body {
-                    self.pending_list_entries = glr.num_vals + 2;
-                } else {
-                    self.pending_list_entries = 1;
+                match &mut msg.message_body {
+                    MessageBody::GetListResponse(glr) => {
+                        self.options.normalize(&mut glr.client_id);
+                        self.options.normalize(&mut glr.list_name);
+                        self.num_vals = glr.num_vals;
+                        self.pending_list_entries = u64::from(glr.num_vals) + 2;
+                    }
+                    MessageBody::OpenResponse(open) => {
+                        self.options.normalize_open_response(open);
+                        self.options.check_open_response(open)?;
+                        self.pending_list_entries = 1;
+                    }
+                    MessageBody::CloseResponse(close) => {
+                        self.options.normalize(&mut close.global_signature);
+                        self.pending_list_entries = 1;
+                    }
                 }
                 ParseEvent::MessageStart(msg)
             }
@@ -58,21 +159,23 @@ impl<'i> Parser<'i> {
                 let digest = CRC_X25
                     .checksum(&self.msg_input[0..num_bytes_read])
                     .swap_bytes();
-                if digest != crc {
-                    return Err(ParseError::CrcMismatch);
-                }
+                self.options.check_crc(crc, digest)?;
 
                 self.pending_list_entries = 0;
                 return self.parse_next();
             }
             2 => {
-                let (input, glre) = GetListResponseEnd::parse(self.input)?;
+                let (input, mut glre) = GetListResponseEnd::parse(self.input)?;
+                self.options.normalize(&mut glre.list_signature);
                 self.input = input;
                 self.pending_list_entries = 1;
                 ParseEvent::GetListResponseEnd(glre)
             }
             x => {
-                let (input, le) = ListEntry::parse(self.input)?;
+                // `x - 2` entries are pending, which is at most `num_vals`
+                let parsed = self.num_vals - (x - 2) as u32;
+                let (input, mut le) = ListEntry::parse_in_list(self.input, self.num_vals, parsed)?;
+                self.options.normalize(&mut le.value_signature);
                 self.input = input;
                 self.pending_list_entries = x - 1;
                 ParseEvent::ListEntry(le)
@@ -87,7 +190,9 @@ impl<'i> Iterator for Parser<'i> {
     fn next(&mut self) -> Option<Self::Item> {
         let res = self.parse_next();
         if res.is_err() {
+            // stop parsing after the first error
             self.input = &[];
+            self.pending_list_entries = 0;
         }
         match res {
             Ok(None) => None,
@@ -97,8 +202,115 @@ impl<'i> Iterator for Parser<'i> {
     }
 }
 
+/// Iterator over the `ListEntry` events of a [`Parser`].
+///
+/// This struct is created by [`Parser::list_entries`].
+pub struct ListEntries<'i> {
+    parser: Parser<'i>,
+}
+
+impl<'i> Iterator for ListEntries<'i> {
+    type Item = Result<ListEntry<'i>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.parser.next()? {
+                Ok(ParseEvent::ListEntry(entry)) => return Some(Ok(entry)),
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Iterator over the values of selected OBIS codes.
+///
+/// This struct is created by [`Parser::values_for`].
+pub struct ValuesFor<'i, 'c> {
+    entries: ListEntries<'i>,
+    codes: &'c [ObisCode],
+}
+
+impl<'i> Iterator for ValuesFor<'i, '_> {
+    type Item = Result<(ObisCode, Value<'i>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next()? {
+                Ok(entry) => match entry.obis_code() {
+                    Some(code) if self.codes.contains(&code) => {
+                        return Some(Ok((code, entry.value)));
+                    }
+                    _ => {}
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Value of a list entry together with the time it has been captured.
+///
+/// Returned by [`Parser::timed_values_for`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TimedValue<'i> {
+    /// OBIS code of the entry
+    pub obis_code: ObisCode,
+    /// value of the entry
+    pub value: Value<'i>,
+    /// time of the entry or, if missing, of the transmission
+    pub time: Option<Time>,
+}
+
+/// Iterator over the values and times of selected OBIS codes.
+///
+/// This struct is created by [`Parser::timed_values_for`].
+pub struct TimedValuesFor<'i, 'c> {
+    parser: Parser<'i>,
+    codes: &'c [ObisCode],
+    transmission_time: Option<Time>,
+}
+
+impl<'i> Iterator for TimedValuesFor<'i, '_> {
+    type Item = Result<TimedValue<'i>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.parser.next()? {
+                Ok(ParseEvent::MessageStart(MessageStart {
+                    message_body: MessageBody::OpenResponse(o),
+                    ..
+                })) => {
+                    self.transmission_time = o.ref_time;
+                }
+                Ok(ParseEvent::MessageStart(MessageStart {
+                    message_body: MessageBody::GetListResponse(glr),
+                    ..
+                })) => {
+                    if glr.act_sensor_time.is_some() {
+                        self.transmission_time = glr.act_sensor_time;
+                    }
+                }
+                Ok(ParseEvent::ListEntry(entry)) => match entry.obis_code() {
+                    Some(obis_code) if self.codes.contains(&obis_code) => {
+                        return Some(Ok(TimedValue {
+                            obis_code,
+                            value: entry.value,
+                            time: entry.val_time.or_else(|| self.transmission_time.clone()),
+                        }));
+                    }
+                    _ => {}
+                },
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
 /// Event data structure produced by the streaming parser.
-#[derive(Debug)]
+#[cfg_attr(feature = "fmt-debug", derive(Debug))]
+#[non_exhaustive]
 pub enum ParseEvent<'i> {
     /// Start of an SML Message.
     MessageStart(MessageStart<'i>),
@@ -108,6 +320,8 @@ pub enum ParseEvent<'i> {
     ListEntry(ListEntry<'i>),
 }
 
+impl_minimal_debug!(ParseEvent<'_>);
+
 /// Contains the start of an SML message.
 ///
 /// For message types that have a known size (e.g. `OpenResponse`), the `MessageStart` type
@@ -131,10 +345,13 @@ pub struct MessageStart<'i> {
 }
 
 impl<'i> SmlParse<'i> for MessageStart<'i> {
-    fn parse(input: &'i [u8]) -> ResTy<Self> {
+    fn parse(input: &'i [u8]) -> ResTy<'i, Self> {
         let (input, tlf) = TypeLengthField::parse(input)?;
         if tlf.ty != Ty::ListOf || tlf.len != 6 {
-            return Err(ParseError::TlfMismatch("Message"));
+            return Err(ParseError::TlfMismatch {
+                expected: "Message",
+                found: tlf,
+            });
         }
         let (input, transaction_id) = OctetStr::parse(input)?;
         let (input, group_no) = u8::parse(input)?;
@@ -151,6 +368,9 @@ impl<'i> SmlParse<'i> for MessageStart<'i> {
     }
 }
 
+impl_minimal_debug!(MessageStart<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for MessageStart<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("MessageStart");
@@ -167,6 +387,7 @@ impl<'i> core::fmt::Debug for MessageStart<'i> {
 ///
 /// Hint: this type only implements the message types specified by SML that are
 /// used in real-world power meters.
+#[non_exhaustive]
 pub enum MessageBody<'i> {
     /// `SML_PublicOpen.Res` message
     OpenResponse(OpenResponse<'i>),
@@ -176,6 +397,40 @@ pub enum MessageBody<'i> {
     GetListResponse(GetListResponseStart<'i>),
 }
 
+super::common::impl_fields!(MessageBody, GetListResponseStart);
+
+impl_minimal_debug!(MessageBody<'_>);
+
+impl<'i> MessageBody<'i> {
+    /// Returns the `OpenResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_open_response(&self) -> Option<&OpenResponse<'i>> {
+        match self {
+            MessageBody::OpenResponse(o) => Some(o),
+            _ => None,
+        }
+    }
+
+    /// Returns the `CloseResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_close_response(&self) -> Option<&CloseResponse<'i>> {
+        match self {
+            MessageBody::CloseResponse(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Returns the start of the `GetListResponse` or `None` if the body is a different message type.
+    #[must_use]
+    pub fn as_get_list_response(&self) -> Option<&GetListResponseStart<'i>> {
+        match self {
+            MessageBody::GetListResponse(glr) => Some(glr),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for MessageBody<'i> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -240,7 +495,10 @@ impl<'i> crate::parser::SmlParseTlf<'i> for GetListResponseStart<'i> {
         let (input, act_sensor_time) = <Option<Time>>::parse(input)?;
         let (input, tlf) = TypeLengthField::parse(input)?;
         if !matches!(tlf.ty, Ty::ListOf) {
-            return Err(ParseError::TlfMismatch(core::any::type_name::<Self>()));
+            return Err(ParseError::TlfMismatch {
+                expected: core::any::type_name::<Self>(),
+                found: tlf,
+            });
         }
         let val = GetListResponseStart {
             client_id,
@@ -253,6 +511,9 @@ impl<'i> crate::parser::SmlParseTlf<'i> for GetListResponseStart<'i> {
     }
 }
 
+impl_minimal_debug!(GetListResponseStart<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for GetListResponseStart<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("GetListResponseStart");
@@ -292,6 +553,9 @@ impl<'i> crate::parser::SmlParse<'i> for GetListResponseEnd<'i> {
     }
 }
 
+impl_minimal_debug!(GetListResponseEnd<'_>);
+
+#[cfg(feature = "fmt-debug")]
 impl<'i> core::fmt::Debug for GetListResponseEnd<'i> {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let mut x = f.debug_struct("GetListResponseEnd");
@@ -304,3 +568,193 @@ impl<'i> core::fmt::Debug for GetListResponseEnd<'i> {
         x.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    const CLOSE_RESPONSE: [u8; 20] = hex!("7605dd43 44006200 62007263 02017101 63fd5600");
+
+    fn count_events(input: &[u8], options: ParseOptions) -> Result<usize, ParseError> {
+        Parser::with_options(input, options).try_fold(0, |n, event| event.map(|_| n + 1))
+    }
+
+    #[test]
+    fn trailing_zeros() {
+        let mut bytes = [0u8; 23];
+        bytes[..20].copy_from_slice(&CLOSE_RESPONSE);
+        let options = ParseOptions::new().ignore_trailing_zeros(true);
+
+        assert_eq!(count_events(&bytes[..20], ParseOptions::new()), Ok(1));
+        assert!(count_events(&bytes, ParseOptions::new()).is_err());
+        assert_eq!(count_events(&bytes[..21], options), Ok(1));
+        assert_eq!(count_events(&bytes, options), Ok(1));
+
+        // other trailing data is still rejected
+        bytes[22] = 0x01;
+        assert!(count_events(&bytes, options).is_err());
+
+        // zero bytes without a message are rejected
+        assert!(count_events(&[0x00], options).is_err());
+    }
+
+    #[test]
+    fn skip_crc_verification() {
+        let mut bytes = CLOSE_RESPONSE;
+        bytes[18] = 0x57;
+        assert!(matches!(
+            count_events(&bytes, ParseOptions::new()),
+            Err(ParseError::CrcMismatch(_))
+        ));
+        assert_eq!(
+            count_events(&bytes, ParseOptions::new().verify_crc(false)),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn unsupported_version() {
+        // OpenResponse announcing `sml_version` 2 followed by a CloseResponse
+        let mut bytes = hex!("76020162 00620072 63010176 01010241 02420162 0263940c 00760201 62006200 72630201 710163f7 9e00");
+        let options = ParseOptions::new().reject_unsupported_versions(true);
+        assert_eq!(count_events(&bytes, ParseOptions::new()), Ok(2));
+        assert_eq!(
+            count_events(&bytes, options),
+            Err(ParseError::UnsupportedVersion(2))
+        );
+
+        // version 1
+        bytes[20] = 0x01;
+        bytes[22..24].copy_from_slice(&[0x0f, 0x3e]);
+        assert_eq!(count_events(&bytes, options), Ok(2));
+    }
+
+    #[test]
+    fn empty_octet_strings() {
+        // CloseResponse with an empty global signature
+        let bytes = hex!("7605dd43 44006200 62007263 02017180 0263426a 00");
+        let signature = |options| match Parser::with_options(&bytes, options).next() {
+            Some(Ok(ParseEvent::MessageStart(MessageStart {
+                message_body: MessageBody::CloseResponse(close),
+                ..
+            }))) => close.global_signature,
+            other => panic!("unexpected event: {other:?}"),
+        };
+        assert_eq!(signature(ParseOptions::new()), Some([].as_slice()));
+        let options = ParseOptions::new().empty_octet_strings_as_none(true);
+        assert_eq!(signature(options), None);
+        assert_eq!(count_events(&bytes, options), Ok(1));
+    }
+
+    #[test]
+    fn truncated_list() {
+        let mut decoder = crate::transport::decode_streaming::<crate::util::ArrayBuf<512>>(
+            include_bytes!("../../sample.bin"),
+        );
+        let bytes = decoder.next().unwrap().unwrap();
+        // cut off within the third list entry
+        let mut entries = Parser::new(&bytes[..160]).list_entries();
+        assert!(entries.next().unwrap().is_ok());
+        assert!(entries.next().unwrap().is_ok());
+        assert!(matches!(
+            entries.next(),
+            Some(Err(ParseError::TruncatedList {
+                expected: 4,
+                parsed: 2
+            }))
+        ));
+        assert!(entries.next().is_none());
+    }
+
+    #[test]
+    fn adapters() {
+        let mut decoder = crate::transport::decode_streaming::<crate::util::ArrayBuf<512>>(
+            include_bytes!("../../sample.bin"),
+        );
+        let bytes = decoder.next().unwrap().unwrap();
+        let len = bytes.len();
+
+        let num_entries = Parser::new(bytes)
+            .filter(|e| matches!(e, Ok(ParseEvent::ListEntry(_))))
+            .count();
+        assert_eq!(Parser::new(bytes).list_entries().count(), num_entries);
+        assert!(Parser::new(bytes).list_entries().all(|e| e.is_ok()));
+
+        let codes = [
+            ObisCode::new(1, 0, 1, 8, 0, 255),
+            ObisCode::new(1, 0, 16, 7, 0, 255),
+        ];
+        let found: usize = Parser::new(bytes)
+            .values_for(&codes)
+            .map(|x| {
+                let code = x.unwrap().0;
+                codes.iter().position(|c| *c == code).unwrap() + 1
+            })
+            .sum();
+        // both codes are found exactly once
+        assert_eq!(found, 3);
+        assert_eq!(Parser::new(bytes).values_for(&[]).count(), 0);
+
+        // errors are passed through
+        let mut entries = Parser::new(&bytes[..len - 1]).list_entries();
+        assert!(entries.any(|e| e.is_err()));
+    }
+
+    #[test]
+    fn oversized_list_len() {
+        // GetListResponse with a `val_list` length of 0xFFFFFFFF followed by a single list entry
+        let bytes = hex!("7601 6200 6200 72 630701 77 01 01 01 01 FF8F8F8F8F8F8F0F 77 07 0100010800FF 01 01 01 01 5201 01");
+        let mut parser = Parser::new(&bytes);
+        assert!(matches!(
+            parser.next(),
+            Some(Ok(ParseEvent::MessageStart(MessageStart {
+                message_body: MessageBody::GetListResponse(GetListResponseStart {
+                    num_vals: 0xFFFFFFFF,
+                    ..
+                }),
+                ..
+            })))
+        ));
+        assert!(matches!(parser.next(), Some(Ok(ParseEvent::ListEntry(_)))));
+        assert!(matches!(
+            parser.next(),
+            Some(Err(ParseError::TruncatedList {
+                expected: 0xFFFFFFFF,
+                parsed: 1
+            }))
+        ));
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn timed_values() {
+        // GetListResponse (act_sensor_time: 100) containing an entry with val_time 90 and one without
+        let input = hex!(
+            "76020162 00620072 63070177 01024201 72620165 00000064 72770701 00010800
+             ff017262 01650000 005a621e 52005205 01770701 00100700 ff010162 1b520052
+             07010101 63389c00"
+        );
+        let energy = ObisCode::new(1, 0, 1, 8, 0, 255);
+        let power = ObisCode::new(1, 0, 16, 7, 0, 255);
+        let codes = [energy, power];
+        let mut values = Parser::new(&input).timed_values_for(&codes);
+        assert_eq!(
+            values.next(),
+            Some(Ok(TimedValue {
+                obis_code: energy,
+                value: Value::I8(5),
+                time: Some(Time::SecIndex(90)),
+            }))
+        );
+        assert_eq!(
+            values.next(),
+            Some(Ok(TimedValue {
+                obis_code: power,
+                value: Value::I8(7),
+                time: Some(Time::SecIndex(100)),
+            }))
+        );
+        assert_eq!(values.next(), None);
+    }
+}
@@ -14,6 +14,7 @@ use super::ResTy;
 /// Error type used when parsing a `TypeLengthField`
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum TlfParseError {
     /// The length field of a TLF overflowed
     TlfLengthOverflow,
@@ -35,28 +36,69 @@ impl From<TlfParseError> for ParseError {
 
 impl fmt::Display for TlfParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(self, f)
+        f.write_str(match self {
+            TlfParseError::TlfLengthOverflow => "length field overflowed",
+            TlfParseError::TlfReserved => "uses values reserved for future usage",
+            TlfParseError::TlfLengthUnderflow => "length field underflowed",
+            TlfParseError::TlfNextByteTypeMismatch => {
+                "type field of a continuation byte isn't `000`"
+            }
+            TlfParseError::TlfInvalidTy => "invalid type field",
+        })
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for TlfParseError {}
+impl core::error::Error for TlfParseError {}
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub(crate) struct TypeLengthField {
+/// Type-Length-Field preceding each SML value.
+///
+/// Contained in [`ParseError::TlfMismatch`] to describe what has been found in the input.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TypeLengthField {
+    /// type of the value
     pub ty: Ty,
+    /// number of bytes of the value (excluding the TLF) or number of list elements
     pub len: u32,
 }
 
 impl TypeLengthField {
-    #[allow(unused)]
-    pub(crate) fn new(ty: Ty, len: u32) -> TypeLengthField {
+    /// Constructs a `TypeLengthField`.
+    #[must_use]
+    pub const fn new(ty: Ty, len: u32) -> TypeLengthField {
         TypeLengthField { ty, len }
     }
+
+    /// Reads a `TypeLengthField` from the start of `input`.
+    ///
+    /// On success, returns the remaining input and the `TypeLengthField`. Together
+    /// with [`parse_unsigned`](super::parse_unsigned) and [`parse_signed`](super::parse_signed),
+    /// this allows writing parsers for vendor-specific message bodies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::parser::{Ty, TypeLengthField};
+    /// let (rest, tlf) = TypeLengthField::read(&[0x63, 0x01, 0x01]).unwrap();
+    /// assert_eq!(tlf, TypeLengthField::new(Ty::Unsigned, 2));
+    /// assert_eq!(rest, &[0x01, 0x01]);
+    /// ```
+    pub fn read(input: &[u8]) -> Result<(&[u8], TypeLengthField), ParseError> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for TypeLengthField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ty {
+            Ty::ListOf => write!(f, "list of {} elements", self.len),
+            ty => write!(f, "{ty} of {} bytes", self.len),
+        }
+    }
 }
 
 impl<'i> SmlParse<'i> for TypeLengthField {
-    fn parse(input: &[u8]) -> ResTy<Self> {
+    fn parse(input: &[u8]) -> ResTy<'_, Self> {
         let (mut input, (mut has_more_bytes, ty, mut len)) = tlf_first_byte(input)?;
         let mut tlf_len = 1;
 
@@ -72,13 +114,11 @@ impl<'i> SmlParse<'i> for TypeLengthField {
             input = input_new;
             has_more_bytes = has_more_bytes_new;
 
-            len = match len.checked_shl(4) {
-                Some(l) => l,
-                None => {
-                    return Err(TlfParseError::TlfLengthOverflow.into());
-                }
-            };
-            len += len_new & 0b1111;
+            // the length has to fit into 32 bits after shifting in the next nibble
+            if len > u32::MAX >> 4 {
+                return Err(TlfParseError::TlfLengthOverflow.into());
+            }
+            len = (len << 4) + (len_new & 0b1111);
         }
 
         // For some reason, the length of the tlf is part of `len` for primitive types.
@@ -96,7 +136,7 @@ impl<'i> SmlParse<'i> for TypeLengthField {
     }
 }
 
-fn tlf_byte(input: &[u8]) -> ResTy<(bool, u8, u32)> {
+fn tlf_byte(input: &[u8]) -> ResTy<'_, (bool, u8, u32)> {
     let (input, b) = take_byte(input)?;
     let len = b & 0x0F;
     let ty = (b >> 4) & 0x07;
@@ -104,13 +144,13 @@ fn tlf_byte(input: &[u8]) -> ResTy<(bool, u8, u32)> {
     Ok((input, (has_more_bytes, ty, len as u32)))
 }
 
-fn tlf_first_byte(input: &[u8]) -> ResTy<(bool, Ty, u32)> {
+fn tlf_first_byte(input: &[u8]) -> ResTy<'_, (bool, Ty, u32)> {
     let (input, (has_more_bytes, ty, len)) = tlf_byte(input)?;
     let ty = Ty::from_byte(ty)?;
     Ok((input, (has_more_bytes, ty, len)))
 }
 
-fn tlf_next_byte(input: &[u8]) -> ResTy<(bool, u32)> {
+fn tlf_next_byte(input: &[u8]) -> ResTy<'_, (bool, u32)> {
     let (input, (has_more_bytes, ty, len)) = tlf_byte(input)?;
     if ty != 0x00 {
         return Err(TlfParseError::TlfNextByteTypeMismatch.into());
@@ -118,15 +158,34 @@ fn tlf_next_byte(input: &[u8]) -> ResTy<(bool, u32)> {
     Ok((input, (has_more_bytes, len)))
 }
 
+/// Type of an SML value.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub(crate) enum Ty {
+pub enum Ty {
+    /// octet string
     OctetString,
+    /// boolean
     Boolean,
+    /// signed integer
     Integer,
+    /// unsigned integer
     Unsigned,
+    /// list of values
     ListOf,
 }
 
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Ty::OctetString => "octet string",
+            Ty::Boolean => "boolean",
+            Ty::Integer => "integer",
+            Ty::Unsigned => "unsigned integer",
+            Ty::ListOf => "list",
+        })
+    }
+}
+
 impl Ty {
     fn from_byte(ty_num: u8) -> Result<Ty, ParseError> {
         Ok(match ty_num {
@@ -164,6 +223,80 @@ mod tests {
         });
     }
 
+    // encodes a TLF using as few bytes as possible
+    fn encode(tlf: &TypeLengthField) -> Option<([u8; 8], usize)> {
+        let ty_bits = match tlf.ty {
+            Ty::OctetString => 0b000,
+            Ty::Boolean => 0b100,
+            Ty::Integer => 0b101,
+            Ty::Unsigned => 0b110,
+            Ty::ListOf => 0b111,
+        };
+        for num_bytes in 1..=8 {
+            // the length of primitive types includes the TLF itself
+            let len = match tlf.ty {
+                Ty::ListOf => u64::from(tlf.len),
+                _ => u64::from(tlf.len) + num_bytes as u64,
+            };
+            if len >= 1 << (4 * num_bytes) {
+                continue;
+            }
+            let mut bytes = [0; 8];
+            for (i, b) in bytes[..num_bytes].iter_mut().enumerate() {
+                let more = if i + 1 < num_bytes { 0x80 } else { 0 };
+                let ty = if i == 0 { ty_bits << 4 } else { 0 };
+                *b = more | ty | ((len >> (4 * (num_bytes - 1 - i))) & 0x0f) as u8;
+            }
+            return Some((bytes, num_bytes));
+        }
+        None
+    }
+
+    #[test]
+    fn roundtrip() {
+        let types = [
+            Ty::OctetString,
+            Ty::Boolean,
+            Ty::Integer,
+            Ty::Unsigned,
+            Ty::ListOf,
+        ];
+        let lens = (0..=0x1100).chain((8..32).flat_map(|shift| {
+            let x = 1u32 << shift;
+            [x - 9, x - 8, x - 1, x, x + 1]
+        }));
+        for len in lens.chain([u32::MAX - 8, u32::MAX]) {
+            for ty in types {
+                let tlf = TypeLengthField::new(ty, len);
+                let Some((bytes, num_bytes)) = encode(&tlf) else {
+                    continue;
+                };
+                let res = TypeLengthField::parse_complete(&bytes[..num_bytes]);
+                if ty == Ty::Boolean && num_bytes > 1 {
+                    assert_eq!(res, Err(TlfParseError::TlfReserved.into()));
+                } else {
+                    assert_eq!(res, Ok(tlf), "{:02x?}", &bytes[..num_bytes]);
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn mismatch_display() {
+        use alloc::string::ToString;
+        let err = ParseError::TlfMismatch {
+            expected: "Message",
+            found: TypeLengthField::new(Ty::ListOf, 5),
+        };
+        assert_eq!(
+            err.to_string(),
+            "unexpected list of 5 elements while parsing `Message`"
+        );
+        let found = TypeLengthField::new(Ty::Unsigned, 2);
+        assert_eq!(found.to_string(), "unsigned integer of 2 bytes");
+    }
+
     #[test]
     fn reserved() {
         // single-byte
@@ -184,6 +317,19 @@ mod tests {
         assert!(TypeLengthField::parse(&[0b1000_0010, 0b0111_0000]).is_err());
     }
 
+    #[test]
+    fn error_source() {
+        use core::error::Error;
+
+        let err = TypeLengthField::parse(&[0b1100_0000]).unwrap_err();
+        assert_eq!(err, ParseError::InvalidTlf(TlfParseError::TlfReserved));
+        let source = err.source().expect("missing source");
+        assert_eq!(
+            source.downcast_ref::<TlfParseError>(),
+            Some(&TlfParseError::TlfReserved)
+        );
+    }
+
     #[test]
     fn len_single_byte() {
         // for primitive data types, the tlf length is part of the length field.
@@ -0,0 +1,55 @@
+//! Stable subset of the API.
+//!
+//! This module re-exports the parts of sml-rs that are covered by semver guarantees:
+//! they will only change in incompatible ways with a new major version. Newer
+//! APIs are added here once they have settled. Projects with long lifecycles
+//! (e.g. firmware) can import everything from this module to make sure they don't
+//! depend on APIs that may still change between minor versions.
+//!
+//! The module structure mirrors the crate root.
+//!
+//! # Examples
+//!
+//! ```
+//! use sml_rs::stable::{parser::streaming::Parser, util::ArrayBuf, SmlReader};
+//!
+//! let mut reader = SmlReader::with_static_buffer::<512>().from_slice(include_bytes!("../sample.bin"));
+//! let parser = reader.read::<Parser>().expect("error while decoding");
+//! assert!(parser.list_entries().all(|entry| entry.is_ok()));
+//! ```
+
+pub use crate::{DecodedBytes, ReadParsedError, SmlParse, SmlReader, SmlReaderBuilder};
+
+/// Stable subset of [`crate::transport`].
+pub mod transport {
+    pub use crate::transport::{
+        decode_streaming, encode, encode_streaming, DecodeErr, DecodeIterator, Decoder,
+        DecoderReader, Encoder, ReadDecodedError,
+    };
+
+    #[cfg(feature = "alloc")]
+    pub use crate::transport::decode;
+}
+
+/// Stable subset of [`crate::parser`].
+pub mod parser {
+    pub use crate::parser::{common, streaming, ObisCode, OctetStr, ParseError, TlfParseError};
+
+    #[cfg(feature = "alloc")]
+    pub use crate::parser::complete;
+}
+
+/// Stable subset of [`crate::util`].
+pub mod util {
+    pub use crate::util::{
+        ArrayBuf, Buffer, ByteSource, ByteSourceErr, Eof, ErrKind, IterByteSource, OutOfMemory,
+        SliceByteSource,
+    };
+
+    #[cfg(feature = "embedded-hal-02")]
+    pub use crate::util::EhByteSource;
+    #[cfg(feature = "std")]
+    pub use crate::util::IoByteSource;
+    #[cfg(feature = "alloc")]
+    pub use crate::util::VecBuf;
+}
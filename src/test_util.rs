@@ -0,0 +1,104 @@
+//! Helpers for regression testing code built on top of `sml-rs`.
+//!
+//! *This module is available only if sml-rs is built with the `"test-util"` feature.*
+//!
+//! The functions in this module produce the same canonical textual output that
+//! `sml-rs` uses for its own snapshot tests (see `tests/libsml-testing.rs`). This
+//! allows downstream projects to snapshot their own captures (e.g. using `insta`)
+//! and detect changes in the parsing behaviour of `sml-rs` when upgrading.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::test_util::dump;
+//! let bytes = include_bytes!("../sample.bin");
+//! let s = dump(bytes);
+//! assert!(s.starts_with("Ok(\n    [\n        Message {"));
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::{Debug, Write};
+
+use crate::{
+    parser::complete::parse,
+    transport::{decode_streaming, encode},
+    util::{VecBuf, CRC_X25},
+};
+
+/// Decodes and parses raw capture bytes and returns a canonical textual dump of the result.
+///
+/// The input is decoded using the SML transport protocol v1. For each decoded
+/// transmission, the output contains the parsed messages (`Ok([...])`). Decoding
+/// and parsing errors are included in the output as `Err(...)`. Every element is
+/// formatted using `{:#?}` and terminated by a newline.
+pub fn dump(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    let mut decoder = decode_streaming::<VecBuf>(bytes);
+    while let Some(result) = decoder.next() {
+        match result {
+            Ok(x) => match parse(x) {
+                Ok(file) => write_result(&mut s, Ok::<_, ()>(file.messages)),
+                Err(e) => write_result(&mut s, Err::<(), _>(e)),
+            },
+            Err(e) => write_result(&mut s, Err::<(), _>(e)),
+        }
+    }
+    s
+}
+
+/// Encodes `bytes` using the SML transport protocol v1, filling the padding bytes with `padding`.
+///
+/// The specification requires padding bytes to be zero. This function allows generating
+/// transmissions deviating from the specification (e.g. for fuzzing or round-trip tests
+/// of [`Decoder::with_lenient_padding`](crate::transport::Decoder::with_lenient_padding)).
+/// The checksum is calculated over the modified padding bytes. For `padding == 0`, the
+/// result equals the result of [`encode`].
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::test_util::encode_with_padding;
+/// let encoded = encode_with_padding(&[0x12, 0x34, 0x56], 0xff);
+/// assert_eq!(&encoded[8..12], &[0x12, 0x34, 0x56, 0xff]);
+/// ```
+#[must_use]
+pub fn encode_with_padding(bytes: &[u8], padding: u8) -> Vec<u8> {
+    let mut res = encode::<VecBuf>(bytes).expect("`VecBuf` doesn't run out of memory");
+    // layout of the end: [padding, 1b1b1b1b, 1a, num_padding_bytes, crc, crc]
+    let end = res.len() - 8;
+    let num_padding_bytes = usize::from(res[end + 5]);
+    res[end - num_padding_bytes..end].fill(padding);
+    let crc = CRC_X25.checksum(&res[..res.len() - 2]);
+    let len = res.len();
+    res[len - 2..].copy_from_slice(&crc.to_le_bytes());
+    res
+}
+
+fn write_result<T: Debug, E: Debug>(s: &mut String, res: Result<T, E>) {
+    // writing into a `String` cannot fail
+    let _ = writeln!(s, "{:#?}", res);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Decoder;
+
+    #[test]
+    fn padding_roundtrip() {
+        for padding in [0x00, 0x01, 0x1a, 0xff] {
+            for len in 0..8u8 {
+                let data: Vec<u8> = (1..=len).collect();
+                let encoded = encode_with_padding(&data, padding);
+                if padding == 0 {
+                    assert_eq!(encoded, encode::<VecBuf>(&data).unwrap());
+                }
+                let mut decoder = Decoder::<VecBuf>::new().with_lenient_padding(true);
+                let mut decoded = decoder.push_bytes(&encoded);
+                assert_eq!(decoded.next_message(), Some(Ok(data.as_slice())));
+                let tolerated = padding != 0 && len % 4 != 0;
+                assert_eq!(decoder.num_tolerated_padding(), u32::from(tolerated));
+            }
+        }
+    }
+}
@@ -0,0 +1,246 @@
+//! module containing the `AsyncDecoderReader`
+
+use core::{future::poll_fn, pin::Pin};
+
+use super::{DecodeErr, Decoder, FrameInfo, ReadDecodedError};
+use crate::util::{AsyncByteSource, Buffer, ByteSourceErr, ErrKind};
+
+// number of bytes requested from the byte source per read
+const CHUNK_LEN: usize = 32;
+
+/// Decode transmissions read asynchronously from a byte source
+///
+/// This is the async counterpart of [`DecoderReader`](super::DecoderReader). It reads
+/// from any [`AsyncByteSource`], e.g. `FuturesIoByteSource` for the async-std and smol
+/// ecosystems or `TokioByteSource` for tokio (see the `futures-io` and `tokio` features).
+///
+/// Use [`AsyncDecoderReader::new`] to create a reader with default settings or
+/// [`DecoderReaderBuilder::build_async`](super::DecoderReaderBuilder::build_async) to
+/// configure the buffer and decoder options.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "futures-io")] {
+/// # use sml_rs::{transport::AsyncDecoderReader, util::{ArrayBuf, FuturesIoByteSource}};
+/// async fn read_all(port: impl futures_io::AsyncRead + Unpin) {
+///     let source = FuturesIoByteSource::new(port);
+///     let mut reader = AsyncDecoderReader::<ArrayBuf<1024>, _>::new(source);
+///     while let Some(res) = reader.next().await {
+///         match res {
+///             Ok(bytes) => println!("{bytes:02x?}"),
+///             Err(e) => eprintln!("{e}"),
+///         }
+///     }
+/// }
+/// # }
+/// ```
+///
+/// The same works for tokio (e.g. with a `tokio_serial::SerialStream`):
+///
+/// ```
+/// # #[cfg(feature = "tokio")] {
+/// # use sml_rs::{transport::AsyncDecoderReader, util::{ArrayBuf, TokioByteSource}};
+/// async fn read_all(port: impl tokio::io::AsyncRead + Unpin) {
+///     let source = TokioByteSource::new(port);
+///     let mut reader = AsyncDecoderReader::<ArrayBuf<1024>, _>::new(source);
+///     while let Some(res) = reader.next().await {
+///         match res {
+///             Ok(bytes) => println!("{bytes:02x?}"),
+///             Err(e) => eprintln!("{e}"),
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct AsyncDecoderReader<B, R>
+where
+    B: Buffer,
+    R: AsyncByteSource + Unpin,
+{
+    decoder: Decoder<B>,
+    reader: R,
+    chunk: [u8; CHUNK_LEN],
+    pos: usize,
+    len: usize,
+}
+
+impl<B, R> AsyncDecoderReader<B, R>
+where
+    B: Buffer,
+    R: AsyncByteSource + Unpin,
+{
+    /// Create a new decoder wrapping the provided reader.
+    pub fn new(reader: R) -> Self {
+        Self::from_decoder(Decoder::new(), reader)
+    }
+
+    pub(super) fn from_decoder(decoder: Decoder<B>, reader: R) -> Self {
+        AsyncDecoderReader {
+            decoder,
+            reader,
+            chunk: [0; CHUNK_LEN],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    /// Consumes the `AsyncDecoderReader` and returns the internal buffer and the byte source.
+    ///
+    /// Bytes that have been read from the byte source but not decoded yet are lost.
+    pub fn into_parts(self) -> (B, R) {
+        (self.decoder.into_buf(), self.reader)
+    }
+
+    /// Reads and decodes a transmission
+    ///
+    /// Same as [`DecoderReader::read`](super::DecoderReader::read), except that it
+    /// waits asynchronously for data to become available.
+    ///
+    /// The returned future is cancel-safe: if it is dropped before completion, no
+    /// data is lost and a partially received transmission is continued by the next call.
+    pub async fn read(&mut self) -> Result<&[u8], ReadDecodedError<R::ReadError>> {
+        loop {
+            if self.decode_chunk().map_err(ReadDecodedError::DecodeErr)? {
+                break;
+            }
+            let reader = &mut self.reader;
+            let chunk = &mut self.chunk;
+            match poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, chunk)).await {
+                Ok(n) => (self.pos, self.len) = (0, n.min(CHUNK_LEN)),
+                Err(e) => return Err(self.io_err(e)),
+            }
+        }
+        Ok(self.decoder.completed_message())
+    }
+
+    /// Tries to read and decode a transmission
+    ///
+    /// Same as [`read`](AsyncDecoderReader::read), except that `None` is returned if
+    /// the byte source reaches its end between two transmissions.
+    pub async fn next(&mut self) -> Option<Result<&[u8], ReadDecodedError<R::ReadError>>> {
+        match self.read().await {
+            Err(ReadDecodedError::IoErr(e, 0)) if e.is_eof() => None,
+            x => Some(x),
+        }
+    }
+
+    /// Returns metadata of the transmission returned by the last successful read.
+    ///
+    /// See [`Decoder::frame_info`] for details.
+    #[must_use]
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.decoder.frame_info()
+    }
+
+    /// Returns the number of transmissions with nonzero padding bytes that have been accepted.
+    ///
+    /// See [`DecoderReaderBuilder::with_lenient_padding`](super::DecoderReaderBuilder::with_lenient_padding).
+    #[must_use]
+    pub fn num_tolerated_padding(&self) -> u32 {
+        self.decoder.num_tolerated_padding()
+    }
+
+    // pushes the buffered bytes into the decoder until a transmission has been completed
+    fn decode_chunk(&mut self) -> Result<bool, DecodeErr> {
+        while self.pos < self.len {
+            let b = self.chunk[self.pos];
+            self.pos += 1;
+            if self.decoder._push_byte(b)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn io_err(&mut self, e: R::ReadError) -> ReadDecodedError<R::ReadError> {
+        let discarded_bytes = match e.kind() {
+            // reset the decoder and return how many bytes were discarded
+            ErrKind::Eof | ErrKind::Other => self.decoder.reset(),
+            ErrKind::WouldBlock => 0,
+        };
+        ReadDecodedError::IoErr(e, discarded_bytes)
+    }
+}
+
+#[cfg(all(test, any(feature = "futures-io", feature = "tokio")))]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll},
+    };
+
+    use super::*;
+    use crate::util::ArrayBuf;
+    #[cfg(feature = "futures-io")]
+    use crate::util::FuturesIoByteSource;
+    #[cfg(feature = "tokio")]
+    use crate::util::TokioByteSource;
+
+    // polls a future whose byte source never returns `Pending`
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let waker = crate::util::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        match pin!(fut).poll(&mut cx) {
+            Poll::Ready(x) => x,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn sample() {
+        let bytes: &[u8] = include_bytes!("../../sample.bin");
+        let mut reader =
+            AsyncDecoderReader::<ArrayBuf<512>, _>::new(FuturesIoByteSource::new(bytes));
+        let mut expected = crate::transport::decode(bytes).into_iter();
+        while let Some(res) = block_on(reader.next()) {
+            assert_eq!(res.ok(), expected.next().unwrap().ok().as_deref());
+        }
+        assert!(expected.next().is_none());
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn errors() {
+        let bytes: &[u8] = include_bytes!("../../sample.bin");
+        let mut reader = crate::transport::DecoderReader::builder()
+            .with_static_buffer::<16>()
+            .build_async(FuturesIoByteSource::new(bytes));
+        assert!(matches!(
+            block_on(reader.read()),
+            Err(ReadDecodedError::DecodeErr(DecodeErr::OutOfMemory))
+        ));
+
+        // truncated transmissions are discarded at EOF
+        let source = FuturesIoByteSource::new(&bytes[..100]);
+        let mut reader = AsyncDecoderReader::<ArrayBuf<512>, _>::new(source);
+        assert!(matches!(
+            block_on(reader.next()),
+            Some(Err(ReadDecodedError::IoErr(e, 100))) if e.is_eof()
+        ));
+        assert!(block_on(reader.next()).is_none());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn tokio() {
+        let bytes: &[u8] = include_bytes!("../../sample.bin");
+        let mut reader = AsyncDecoderReader::<ArrayBuf<512>, _>::new(TokioByteSource::new(bytes));
+        let mut expected = crate::transport::decode(bytes).into_iter();
+        while let Some(res) = block_on(reader.next()) {
+            assert_eq!(res.ok(), expected.next().unwrap().ok().as_deref());
+        }
+        assert!(expected.next().is_none());
+
+        // truncated transmissions are discarded at EOF
+        let source = TokioByteSource::new(&bytes[..100]);
+        let mut reader = AsyncDecoderReader::<ArrayBuf<512>, _>::new(source);
+        assert!(matches!(
+            block_on(reader.next()),
+            Some(Err(ReadDecodedError::IoErr(e, 100))) if e.is_eof()
+        ));
+        assert!(block_on(reader.next()).is_none());
+    }
+}
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 
 use core::{borrow::Borrow, fmt};
 
-use crate::util::{Buffer, CRC_X25};
+use crate::util::{Buffer, CrcMismatch};
+
+use super::sans_io::{Event, FrameInfo, Machine};
 
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
@@ -11,6 +13,7 @@ use alloc::vec::Vec;
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// An error which can be returned when decoding an sml message.
+#[non_exhaustive]
 pub enum DecodeErr {
     /// Some bytes could not be parsed and were discarded
     DiscardedBytes(usize),
@@ -20,8 +23,8 @@ pub enum DecodeErr {
     OutOfMemory,
     /// The decoded message is invalid.
     InvalidMessage {
-        /// (expected, found) checksums
-        checksum_mismatch: (u16, u16),
+        /// the checksum mismatch or `None` if the checksum is valid
+        checksum_mismatch: Option<CrcMismatch>,
         /// whether the end escape sequence wasn't aligned to a 4-byte boundary
         end_esc_misaligned: bool,
         /// the number of padding bytes.
@@ -33,30 +36,66 @@ pub enum DecodeErr {
 
 impl fmt::Display for DecodeErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(self, f)
+        match self {
+            DecodeErr::DiscardedBytes(n) => write!(
+                f,
+                "discarded {n} bytes not belonging to a transmission (expected after connecting to a meter, \
+                otherwise check the baud rate and the alignment of the IR head)"
+            ),
+            DecodeErr::InvalidEsc([a, b, c, d]) => write!(
+                f,
+                "invalid escape sequence 1b1b1b1b {a:02x}{b:02x}{c:02x}{d:02x}, transmission discarded \
+                (check the baud rate and the alignment of the IR head)"
+            ),
+            DecodeErr::OutOfMemory => write!(
+                f,
+                "transmission doesn't fit into the buffer and has been discarded (use a larger buffer, \
+                see `transport::recommended_buffer_size`)"
+            ),
+            DecodeErr::InvalidMessage {
+                checksum_mismatch,
+                end_esc_misaligned,
+                num_padding_bytes,
+                invalid_padding_bytes,
+            } => {
+                f.write_str("invalid transmission (")?;
+                let mut sep = "";
+                if let Some(crc) = checksum_mismatch {
+                    write!(f, "{crc}")?;
+                    sep = ", ";
+                }
+                if *end_esc_misaligned {
+                    write!(f, "{sep}end escape sequence not aligned to 4 bytes")?;
+                    sep = ", ";
+                }
+                if *num_padding_bytes > 3 {
+                    write!(f, "{sep}invalid number of padding bytes: {num_padding_bytes}")?;
+                    sep = ", ";
+                }
+                if *invalid_padding_bytes {
+                    write!(f, "{sep}padding bytes not equal to zero")?;
+                }
+                f.write_str(
+                    "), transmission discarded (check the baud rate and the alignment of the IR head)",
+                )
+            }
+        }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for DecodeErr {}
-
-#[derive(Debug)]
-enum DecodeState {
-    LookingForMessageStart {
-        num_discarded_bytes: u16,
-        num_init_seq_bytes: u8,
-    },
-    ParsingNormal,
-    ParsingEscChars(u8),
-    ParsingEscPayload {
-        step: u8,
-        payload: [u8; 4],
-    },
-    Done,
-}
+impl core::error::Error for DecodeErr {}
 
 /// Decoder for sml transport v1.
 ///
+/// # Usage in async code
+///
+/// The decoder keeps all state between calls to [`push_byte`](Decoder::push_byte), so
+/// async readers feeding it stay cancel-safe as long as a byte is pushed as soon as it has
+/// been read: cancelling a pending read (e.g. using a timeout) doesn't lose any data.
+/// Meter links stall frequently; after a timeout, call [`reset`](Decoder::reset) to discard the
+/// incomplete transmission instead of combining it with data received later. See the
+/// `esp32c3-sml-reader-async` example for a reader task using a timeout.
+///
 /// # Examples
 ///
 /// ```
@@ -80,7 +119,7 @@ enum DecodeState {
 /// ```
 pub struct Decoder<B: Buffer> {
     buf: B,
-    decoder: NonOwningDecoder,
+    decoder: Machine,
 }
 
 impl<B: Buffer> Default for Decoder<B> {
@@ -89,6 +128,28 @@ impl<B: Buffer> Default for Decoder<B> {
     }
 }
 
+#[cfg(feature = "alloc")]
+impl Decoder<crate::util::VecBuf> {
+    /// Constructs a new decoder whose buffer can hold `capacity` bytes without reallocating.
+    ///
+    /// The buffer keeps its capacity when the decoder is reset, so choosing a capacity
+    /// larger than the expected transmissions avoids any reallocation in long-running services.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_buf(Vec::with_capacity(capacity))
+    }
+
+    /// Returns the number of bytes the decoder's buffer can hold without reallocating.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+}
+
 impl<B: Buffer> Decoder<B> {
     /// Constructs a new decoder.
     #[must_use]
@@ -97,356 +158,221 @@ impl<B: Buffer> Decoder<B> {
     }
 
     /// Constructs a new decoder using an existing buffer `buf`.
-    pub fn from_buf(mut buf: B) -> Self {
+    pub fn from_buf(buf: B) -> Self {
+        Self::with_machine(buf, Machine::new())
+    }
+
+    // constructs a decoder using the state machine `decoder` (used by `DecoderV2`)
+    pub(super) fn with_machine(mut buf: B, decoder: Machine) -> Self {
         buf.clear();
-        Decoder {
-            buf,
-            decoder: NonOwningDecoder::new(),
-        }
+        Decoder { buf, decoder }
     }
 
-    /// Pushes a byte `b` into the decoder, advances the parser state and possibly returns
-    /// a transmission or an decoder error.
-    pub fn push_byte(&mut self, b: u8) -> Result<Option<&[u8]>, DecodeErr> {
-        self._push_byte(b)
-            .map(|b| if b { Some(self.borrow_buf()) } else { None })
+    pub(super) fn machine(&self) -> &Machine {
+        &self.decoder
     }
 
-    /// Resets the `Decoder` and returns an error if it contained an incomplete message.
-    pub fn finalize(&mut self) -> Option<DecodeErr> {
-        self.decoder.finalize(&mut self.buf)
+    /// Consumes the decoder and returns its buffer.
+    ///
+    /// This can be used to return a buffer leased from a [`BufferPool`](crate::util::BufferPool).
+    #[must_use]
+    pub fn into_buf(self) -> B {
+        self.buf
     }
 
-    /// Main function of the parser.
+    /// Enables or disables fuzzy resynchronization (disabled by default).
     ///
-    /// Returns
-    /// - `Ok(true)` if a complete message is ready.
-    /// - `Ok(false)` when more bytes are necessary to complete parsing a message.
-    /// - `Err(_)` if an error occurred during parsing
-    pub(crate) fn _push_byte(&mut self, b: u8) -> Result<bool, DecodeErr> {
-        self.decoder.push_byte(&mut self.buf, b)
+    /// Noisy optical links sometimes corrupt single bytes of the start sequence
+    /// (`1b1b1b1b 01010101`), which causes the whole following transmission to be
+    /// discarded. When fuzzy resynchronization is enabled, the decoder also accepts
+    /// start sequences in which a single byte has been corrupted. The message
+    /// is still validated using its CRC checksum, which is calculated over the correct
+    /// start sequence.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{util::ArrayBuf, transport::Decoder};
+    /// // the second byte of the start sequence has been corrupted
+    /// let bytes = [0x1b, 0x0b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b];
+    ///
+    /// let mut decoder = Decoder::<ArrayBuf<20>>::new().with_fuzzy_resync(true);
+    /// let mut num_decoded = 0;
+    /// for b in bytes {
+    ///     if let Ok(Some(decoded)) = decoder.push_byte(b) {
+    ///         assert_eq!(decoded, [0x12, 0x34, 0x56, 0x78]);
+    ///         num_decoded += 1;
+    ///     }
+    /// }
+    /// assert_eq!(num_decoded, 1);
+    /// ```
+    #[must_use]
+    pub fn with_fuzzy_resync(mut self, enabled: bool) -> Self {
+        self.decoder = self.decoder.with_fuzzy_resync(enabled);
+        self
     }
 
-    pub(crate) fn borrow_buf(&self) -> &[u8] {
-        if !self.decoder.is_done() {
-            panic!("Reading from the internal buffer is only allowed when a complete message is present (DecodeState::Done).");
-        }
-        &self.buf[..self.buf.len()]
+    /// Enables or disables accepting nonzero padding bytes (disabled by default).
+    ///
+    /// The specification requires padding bytes to be zero, but some devices fill
+    /// them with arbitrary values. When lenient padding is enabled, such transmissions
+    /// are accepted (if their checksum is valid) and the padding bytes are removed.
+    /// The number of accepted transmissions with nonzero padding bytes is returned
+    /// by [`num_tolerated_padding`](Decoder::num_tolerated_padding).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{util::ArrayBuf, transport::Decoder};
+    /// // a transmission with a single padding byte `0xff`
+    /// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0xff, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x01, 0x34, 0x2b];
+    ///
+    /// let mut decoder = Decoder::<ArrayBuf<20>>::new().with_lenient_padding(true);
+    /// let mut decoded = decoder.push_bytes(&bytes);
+    /// assert_eq!(decoded.next_message(), Some(Ok([0x12, 0x34, 0x56].as_slice())));
+    /// assert_eq!(decoder.num_tolerated_padding(), 1);
+    /// ```
+    #[must_use]
+    pub fn with_lenient_padding(mut self, enabled: bool) -> Self {
+        self.decoder = self.decoder.with_lenient_padding(enabled);
+        self
     }
 
-    /// Resets the `Decoder` and returns the number of bytes that were discarded
-    pub fn reset(&mut self) -> usize {
-        self.decoder.reset(&mut self.buf)
+    /// Returns the number of transmissions with nonzero padding bytes that have been accepted.
+    ///
+    /// See [`with_lenient_padding`](Decoder::with_lenient_padding).
+    #[must_use]
+    pub fn num_tolerated_padding(&self) -> u32 {
+        self.decoder.num_tolerated_padding()
     }
-}
 
-pub(crate) struct NonOwningDecoder {
-    // the number of bytes that were read out of the byte source
-    raw_msg_len: usize,
-    crc: crc::Digest<'static, u16>,
-    state: DecodeState,
-    // the number of zero bytes that weren't written into the buffer
-    // immediately because they could be padding bytes
-    zero_cache: u8,
-}
+    /// Pushes a byte `b` into the decoder, advances the parser state and possibly returns
+    /// a transmission or an decoder error.
+    pub fn push_byte(&mut self, b: u8) -> Result<Option<&[u8]>, DecodeErr> {
+        self._push_byte(b)
+            .map(|complete| complete.then(|| self.completed_message()))
+    }
 
-impl Default for NonOwningDecoder {
-    fn default() -> Self {
-        Self {
-            raw_msg_len: Default::default(),
-            crc: CRC_X25.digest(),
-            state: DecodeState::LookingForMessageStart {
-                num_discarded_bytes: 0,
-                num_init_seq_bytes: 0,
-            },
-            zero_cache: 0,
+    /// Pushes a chunk of bytes (e.g. a DMA half-buffer) into the decoder.
+    ///
+    /// Returns a [`PushBytes`] which pushes the bytes one by one and yields every
+    /// complete message and decoding error using [`PushBytes::next_message`]. This
+    /// handles chunks containing several messages. Bytes that haven't been pushed
+    /// when the `PushBytes` is dropped can be obtained using [`PushBytes::remaining`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{util::ArrayBuf, transport::Decoder};
+    /// // chunk containing two messages
+    /// let chunk = [
+    ///     0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b,
+    ///     0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x13, 0x24, 0x35, 0x46, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb1, 0xa1,
+    /// ];
+    ///
+    /// let mut decoder = Decoder::<ArrayBuf<20>>::new();
+    /// let mut messages = decoder.push_bytes(&chunk);
+    /// assert_eq!(messages.next_message(), Some(Ok([0x12, 0x34, 0x56, 0x78].as_slice())));
+    /// assert_eq!(messages.next_message(), Some(Ok([0x13, 0x24, 0x35, 0x46].as_slice())));
+    /// assert_eq!(messages.next_message(), None);
+    /// ```
+    pub fn push_bytes<'d, 'b>(&'d mut self, bytes: &'b [u8]) -> PushBytes<'d, 'b, B> {
+        PushBytes {
+            decoder: self,
+            bytes,
         }
     }
-}
-
-impl NonOwningDecoder {
-    pub fn new() -> Self {
-        Default::default()
-    }
-
-    /// Returns:
-    /// - Ok(true) Full message was written into buf
-    /// - Ok(false) Needs more input
-    /// - Error There was an error
-    pub fn push_byte(&mut self, buf: &mut impl Buffer, b: u8) -> Result<bool, DecodeErr> {
-        use DecodeState::*;
-        self.raw_msg_len += 1;
-        match self.state {
-            LookingForMessageStart {
-                ref mut num_discarded_bytes,
-                ref mut num_init_seq_bytes,
-            } => {
-                if (b == 0x1b && *num_init_seq_bytes < 4) || (b == 0x01 && *num_init_seq_bytes >= 4)
-                {
-                    *num_init_seq_bytes += 1;
-                } else {
-                    *num_discarded_bytes += 1 + u16::from(*num_init_seq_bytes);
-                    *num_init_seq_bytes = 0;
-                }
-                if *num_init_seq_bytes == 8 {
-                    let num_discarded_bytes = *num_discarded_bytes;
-                    self.state = ParsingNormal;
-                    self.raw_msg_len = 8;
-                    self.crc = CRC_X25.digest();
-                    self.crc
-                        .update(&[0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01]);
-                    if num_discarded_bytes > 0 {
-                        return Err(DecodeErr::DiscardedBytes(num_discarded_bytes as usize));
-                    }
-                }
-            }
-            ParsingNormal => {
-                self.crc.update(&[b]);
-                if b == 0x1b {
-                    // this could be the first byte of an escape sequence
-                    self.state = ParsingEscChars(1);
-                } else {
-                    // regular data
-                    self.push(buf, b)?;
-                }
-            }
-            ParsingEscChars(n) => {
-                self.crc.update(&[b]);
-                if b != 0x1b {
-                    // push previous 0x1b bytes as they didn't belong to an escape sequence
-                    for _ in 0..n {
-                        self.push(buf, 0x1b)?;
-                    }
-                    // push current byte
-                    self.push(buf, b)?;
-                    // continue in regular parsing state
-                    self.state = ParsingNormal;
-                } else if n == 3 {
-                    // this is the fourth 0x1b byte, so we're seeing an escape sequence.
-                    // continue by parsing the escape sequence's payload.
-
-                    self.state = ParsingEscPayload {
-                        step: 0,
-                        payload: Default::default(),
-                    };
-                } else {
-                    self.state = ParsingEscChars(n + 1);
-                }
-            }
-            ParsingEscPayload { step, mut payload } => {
-                payload[step as usize] = b;
-                if step < 3 {
-                    self.state = ParsingEscPayload {
-                        step: step + 1,
-                        payload,
-                    };
-                } else {
-                    // last 4 elements in self.buf are the escape sequence payload
-                    if payload == [0x1b, 0x1b, 0x1b, 0x1b] {
-                        // escape sequence in user data
-
-                        self.crc.update(&payload);
-
-                        // push escape sequence bytes
-                        for b in payload {
-                            self.push(buf, b)?;
-                        }
 
-                        // nothing to do here as the input has already been added to the buffer (see above)
-                        self.state = ParsingNormal;
-                    } else if payload == [0x01, 0x01, 0x01, 0x01] {
-                        // another transmission start
-
-                        // ignore everything that has previously been read and start reading a new transmission
-                        let ignored_bytes = self.raw_msg_len - 8;
-                        self.raw_msg_len = 8;
-                        self.zero_cache = 0;
-                        buf.clear();
-                        self.crc = CRC_X25.digest();
-                        self.crc
-                            .update(&[0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01]);
-                        self.state = ParsingNormal;
-                        return Err(DecodeErr::DiscardedBytes(ignored_bytes));
-                    } else if payload[0] == 0x1a {
-                        // end sequence (layout: [0x1a, num_padding_bytes, crc, crc])
-
-                        // check number of padding bytes
-                        let num_padding_bytes = payload[1];
-
-                        // compute and compare checksum
-                        let read_crc = u16::from_le_bytes([payload[2], payload[3]]);
-                        // update the crc, but exclude the last two bytes (which contain the crc itself)
-                        self.crc.update(&[payload[0], payload[1]]);
-                        // get the calculated crc and reset it afterwards
-                        let calculated_crc = {
-                            let mut crc = CRC_X25.digest();
-                            core::mem::swap(&mut crc, &mut self.crc);
-                            crc.finalize()
-                        };
-
-                        // check alignment (end marker needs to have 4-byte alignment)
-                        let misaligned = self.raw_msg_len % 4 != 0;
-
-                        // check if padding is larger than the message length
-                        let padding_too_large = num_padding_bytes > 3;
-                        // hint: start esc + end esc = 16 bytes
-                        let padding_larger_than_msg_size =
-                            self.raw_msg_len < (num_padding_bytes as usize) + 16;
-
-                        // check whether padding bytes are correct
-                        let invalid_padding_bytes = num_padding_bytes > self.zero_cache;
-
-                        if read_crc != calculated_crc
-                            || misaligned
-                            || padding_too_large
-                            || padding_larger_than_msg_size
-                            || invalid_padding_bytes
-                        {
-                            self.reset(buf);
-                            return Err(DecodeErr::InvalidMessage {
-                                checksum_mismatch: (read_crc, calculated_crc),
-                                end_esc_misaligned: misaligned,
-                                num_padding_bytes,
-                                invalid_padding_bytes,
-                            });
-                        }
-
-                        // remove padding bytes
-                        self.zero_cache -= num_padding_bytes;
-
-                        self.flush(buf)?;
-
-                        self.set_done();
-
-                        return Ok(true);
-                    } else {
-                        // special case of message ending with incomplete escape sequence
-                        // Explanation:
-                        // when a message ends with 1-3 0x1b bytes and there's no padding bytes,
-                        // we end up in this branch because there's four consecutive 0x1b bytes
-                        // that aren't followed by a known escape sequence. The problem is that
-                        // the first 1-3 0x1b bytes belong to the message, not to the end escape
-                        // code.
-                        // Example:
-                        //                  detected as escape sequence
-                        //                  vvvv vvvv
-                        // Message: ... 12341b1b 1b1b1b1b 1a00abcd
-                        //                       ^^^^^^^^
-                        //                       real escape sequence
-                        //
-                        // The solution for this issue is to check whether the read escape code
-                        // isn't aligned to a 4-byte boundary and followed by an aligned end
-                        // escape sequence (`1b1b1b1b 1a...`).
-                        // If that's the case, simply reset the parser state by 1-3 steps. This
-                        // will parse the 0x1b bytes in the message as regular bytes and check
-                        // for the end escape code at the right position.
-                        let bytes_until_alignment = (4 - (self.raw_msg_len % 4)) % 4;
-                        if bytes_until_alignment > 0
-                            && payload[..bytes_until_alignment].iter().all(|x| *x == 0x1b)
-                            && payload[bytes_until_alignment] == 0x1a
-                        {
-                            self.crc.update(&payload[..bytes_until_alignment]);
-                            // push bytes that are in payload but belong to the message
-                            for _ in 0..bytes_until_alignment {
-                                self.push(buf, 0x1b)?;
-                            }
-                            // shift the remaining bytes to the beginning of `payload`
-                            payload.copy_within(bytes_until_alignment.., 0);
-                            self.state = ParsingEscPayload {
-                                step: 4 - bytes_until_alignment as u8,
-                                payload,
-                            };
-                            return Ok(false);
-                        }
-
-                        // invalid escape sequence
-
-                        self.reset(buf);
-                        return Err(DecodeErr::InvalidEsc(payload));
-                    }
-                }
-            }
-            Done => {
-                // reset and let's go again
-                self.reset(buf);
-                return self.push_byte(buf, b);
-            }
+    /// Returns the decoded message if the `Decoder` currently holds a complete message.
+    ///
+    /// A message is available after [`push_byte`](Decoder::push_byte) returned `Ok(Some(_))`
+    /// and until the next byte is pushed or the `Decoder` is reset. Returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{util::ArrayBuf, transport::Decoder};
+    /// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b];
+    ///
+    /// let mut decoder = Decoder::<ArrayBuf<20>>::new();
+    /// assert_eq!(decoder.try_message(), None);
+    /// for b in bytes {
+    ///     let _ = decoder.push_byte(b);
+    /// }
+    /// assert_eq!(decoder.try_message(), Some([0x12, 0x34, 0x56, 0x78].as_slice()));
+    /// ```
+    #[must_use]
+    pub fn try_message(&self) -> Option<&[u8]> {
+        if self.decoder.is_done() {
+            Some(&self.buf)
+        } else {
+            None
         }
-        Ok(false)
     }
 
-    /// Resets the `Decoder` and returns an error if it contained an incomplete message.
-    pub fn finalize(&mut self, buf: &mut impl Buffer) -> Option<DecodeErr> {
-        use DecodeState::*;
-        let res = match self.state {
-            LookingForMessageStart {
-                num_discarded_bytes: 0,
-                num_init_seq_bytes: 0,
-            } => None,
-            Done => None,
-            _ => Some(DecodeErr::DiscardedBytes(self.raw_msg_len)),
-        };
-        self.reset(buf);
-        res
+    /// Returns metadata (length, escape sequences, padding) of the decoded message.
+    ///
+    /// Available under the same conditions as [`try_message`](Decoder::try_message).
+    /// See [`Machine::frame_info`] for details.
+    #[must_use]
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.decoder.frame_info()
     }
 
-    /// Resets the `Decoder` and returns the number of bytes that were discarded
-    pub fn reset(&mut self, buf: &mut impl Buffer) -> usize {
-        let num_discarded = match self.state {
-            DecodeState::Done => 0,
-            _ => self.raw_msg_len,
-        };
-        self.state = DecodeState::LookingForMessageStart {
-            num_discarded_bytes: 0,
-            num_init_seq_bytes: 0,
-        };
-        buf.clear();
-        self.raw_msg_len = 0;
-        self.zero_cache = 0;
-        num_discarded
+    /// Resets the `Decoder` and returns an error if it contained an incomplete message.
+    pub fn finalize(&mut self) -> Option<DecodeErr> {
+        self.decoder.finalize(&mut self.buf)
     }
 
-    // pushes bytes from the `zero_cache` into the output buffer
-    fn flush(&mut self, buf: &mut impl Buffer) -> Result<(), DecodeErr> {
-        for _ in 0..self.zero_cache {
-            self.push_inner(buf, 0)?;
-        }
-        self.zero_cache = 0;
-        Ok(())
-    }
-
-    fn push(&mut self, buf: &mut impl Buffer, b: u8) -> Result<(), DecodeErr> {
-        if b == 0 {
-            if self.zero_cache <= 3 {
-                self.zero_cache += 1;
-            } else {
-                // directly push into the output buffer if there are already 3
-                // zero bytes in the cache. Padding cannot be larger than three
-                // and this makes sure that the zero_cache cannot grow infinitely.
-                self.push_inner(buf, b)?;
-            }
-        } else {
-            self.flush(buf)?;
-            self.push_inner(buf, b)?;
-        }
-        Ok(())
+    /// Main function of the parser.
+    ///
+    /// Returns
+    /// - `Ok(true)` if a complete message is ready.
+    /// - `Ok(false)` when more bytes are necessary to complete parsing a message.
+    /// - `Err(_)` if an error occurred during parsing
+    pub(crate) fn _push_byte(&mut self, b: u8) -> Result<bool, DecodeErr> {
+        self.decoder
+            .push_byte(&mut self.buf, b)
+            .map(|event| event == Event::MessageComplete)
     }
 
-    fn push_inner(&mut self, buf: &mut impl Buffer, b: u8) -> Result<(), DecodeErr> {
-        if buf.push(b).is_err() {
-            self.reset(buf);
-            return Err(DecodeErr::OutOfMemory);
-        }
-        Ok(())
+    // returns the message after `_push_byte` returned `Ok(true)`
+    pub(crate) fn completed_message(&self) -> &[u8] {
+        debug_assert!(self.decoder.is_done(), "no complete message available");
+        &self.buf
     }
 
-    fn set_done(&mut self) {
-        self.state = DecodeState::Done;
+    /// Resets the `Decoder` and returns the number of bytes that were discarded
+    pub fn reset(&mut self) -> usize {
+        self.decoder.reset(&mut self.buf)
     }
+}
 
-    fn is_done(&self) -> bool {
-        matches!(self.state, DecodeState::Done)
-    }
+/// Returns the buffer size a [`Decoder`] needs for transmissions containing up to
+/// `max_payload` bytes.
+///
+/// The decoder's buffer holds the decoded data of a transmission without the start
+/// and end sequences. Three additional bytes are needed for nonzero padding bytes,
+/// which are stored temporarily if [lenient padding](Decoder::with_lenient_padding)
+/// is enabled. Transmissions that don't fit are reported as [`DecodeErr::OutOfMemory`].
+///
+/// The payload of a transmission is at most 16 bytes shorter than the raw
+/// transmission (see [`FrameInfo::raw_len`]).
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{transport::{recommended_buffer_size, Decoder}, util::ArrayBuf};
+/// // the longest transmission of the meter contains 1000 bytes
+/// const BUF_LEN: usize = recommended_buffer_size(1000);
+/// let decoder = Decoder::<ArrayBuf<BUF_LEN>>::new();
+///
+/// // checking an existing buffer size at compile time
+/// const _: () = assert!(1024 >= recommended_buffer_size(1000));
+/// ```
+#[must_use]
+pub const fn recommended_buffer_size(max_payload: usize) -> usize {
+    max_payload.saturating_add(3)
 }
 
 /// Decode a given slice of bytes and returns a vector of messages / errors.
@@ -464,19 +390,146 @@ impl NonOwningDecoder {
 #[cfg(feature = "alloc")]
 #[must_use]
 pub fn decode(iter: impl IntoIterator<Item = impl Borrow<u8>>) -> Vec<Result<Vec<u8>, DecodeErr>> {
-    let mut decoder: Decoder<Vec<u8>> = Decoder::new();
     let mut res = Vec::new();
+    decode_ref(iter, |item: Result<&[u8], DecodeErr>| {
+        res.push(item.map(<[u8]>::to_vec));
+    });
+    res
+}
+
+/// Decodes a slice of bytes and passes the messages / errors to a callback.
+///
+/// Same as [`decode`], except that the decoded messages are passed to `f` as borrowed
+/// slices instead of being collected. A single internal buffer is reused for all
+/// messages, so no allocations are made per message. This allows scanning large
+/// captures quickly.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+/// ```
+/// # use sml_rs::transport::decode_ref;
+/// let bytes = include_bytes!("../../sample.bin").repeat(10);
+/// let (mut num_messages, mut num_bytes) = (0, 0);
+/// decode_ref(&bytes, |res| {
+///     num_messages += 1;
+///     num_bytes += res.unwrap().len();
+/// });
+/// assert_eq!((num_messages, num_bytes), (10, 2260));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_ref(
+    iter: impl IntoIterator<Item = impl Borrow<u8>>,
+    mut f: impl FnMut(Result<&[u8], DecodeErr>),
+) {
+    let mut decoder: Decoder<Vec<u8>> = Decoder::new();
     for b in iter.into_iter() {
         match decoder.push_byte(*b.borrow()) {
             Ok(None) => {}
-            Ok(Some(buf)) => res.push(Ok(buf.to_vec())),
-            Err(e) => res.push(Err(e)),
+            Ok(Some(buf)) => f(Ok(buf)),
+            Err(e) => f(Err(e)),
         }
     }
     if let Some(e) = decoder.finalize() {
-        res.push(Err(e));
+        f(Err(e));
     }
-    res
+}
+
+/// Parses a textual hex capture and decodes the contained transmissions.
+///
+/// The capture is parsed using [`parse_hex_stream`](crate::util::parse_hex_stream) (whitespace
+/// and comments are ignored) and then decoded using [`decode`].
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+/// ```
+/// # use sml_rs::transport::decode_hex;
+/// let decoded = decode_hex("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b").unwrap();
+/// assert_eq!(decoded, vec!(Ok(vec![0x12, 0x34, 0x56, 0x78])));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_hex(s: &str) -> Result<Vec<Result<Vec<u8>, DecodeErr>>, crate::util::HexParseError> {
+    crate::util::parse_hex_stream(s).map(decode)
+}
+
+/// Parses a base64-encoded capture and decodes the contained transmissions.
+///
+/// The capture is parsed using [`parse_base64`](crate::util::parse_base64) (whitespace
+/// is ignored) and then decoded using [`decode`].
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+/// ```
+/// # use sml_rs::transport::decode_base64;
+/// let decoded = decode_base64("GxsbGwEBAQESNFZ4GxsbGxoAuHs=").unwrap();
+/// assert_eq!(decoded, vec!(Ok(vec![0x12, 0x34, 0x56, 0x78])));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn decode_base64(
+    s: &str,
+) -> Result<Vec<Result<Vec<u8>, DecodeErr>>, crate::util::Base64ParseError> {
+    crate::util::parse_base64(s).map(decode)
+}
+
+/// Decodes a large capture in parallel and returns a vector of messages / errors.
+///
+/// The input is split into chunks at positions where a transmission ends and the
+/// next one starts directly afterwards. The chunks are decoded in parallel using
+/// `rayon` and the results are merged in order. For well-formed captures, the
+/// result is equal to the result of [`decode`]. Corrupted sections that happen to
+/// look like the end of a transmission may be reported differently.
+///
+/// *This function is available only if sml-rs is built with the `"rayon"` feature.*
+///
+/// # Examples
+/// ```
+/// # use sml_rs::transport::{decode, decode_parallel};
+/// let bytes = include_bytes!("../../sample.bin").repeat(100);
+/// let decoded = decode_parallel(&bytes);
+/// assert_eq!(decoded.len(), 100);
+/// assert_eq!(decoded, decode(&bytes));
+/// ```
+#[cfg(feature = "rayon")]
+#[must_use]
+pub fn decode_parallel(bytes: &[u8]) -> Vec<Result<Vec<u8>, DecodeErr>> {
+    use rayon::prelude::*;
+
+    // chunks smaller than this aren't worth the overhead of parallelization
+    const MIN_CHUNK_LEN: usize = 64 * 1024;
+
+    let num_chunks = (bytes.len() / MIN_CHUNK_LEN).min(rayon::current_num_threads() * 4);
+    let mut split_points = Vec::with_capacity(num_chunks + 1);
+    split_points.push(0);
+    for i in 1..num_chunks {
+        let from = (bytes.len() * i / num_chunks).max(*split_points.last().unwrap_or(&0) + 1);
+        match find_split_point(bytes, from) {
+            Some(p) => split_points.push(p),
+            None => break,
+        }
+    }
+    split_points.push(bytes.len());
+
+    let chunks: Vec<_> = split_points
+        .par_windows(2)
+        .map(|w| decode(&bytes[w[0]..w[1]]))
+        .collect();
+    chunks.into_iter().flatten().collect()
+}
+
+// returns the first position `p >= from` where an end sequence directly precedes a start sequence
+#[cfg(feature = "rayon")]
+fn find_split_point(bytes: &[u8], from: usize) -> Option<usize> {
+    const ESC: [u8; 4] = [0x1b; 4];
+    const START: [u8; 8] = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01];
+    (from.max(12)..bytes.len().saturating_sub(START.len() - 1)).find(|&p| {
+        bytes[p..].starts_with(&START)
+            && bytes[p - 8..p - 4] == ESC
+            && bytes[p - 4] == 0x1a
+            // the end sequence could be the second half of an escape sequence otherwise
+            && bytes[p - 12..p - 8] != ESC
+    })
 }
 
 /// Iterator over decoded messages / errors.
@@ -488,8 +541,12 @@ pub struct DecodeIterator<B: Buffer, I: Iterator<Item = u8>> {
 
 impl<B: Buffer, I: Iterator<Item = u8>> DecodeIterator<B, I> {
     fn new(bytes: I) -> Self {
+        Self::from_decoder(Decoder::new(), bytes)
+    }
+
+    pub(super) fn from_decoder(decoder: Decoder<B>, bytes: I) -> Self {
         DecodeIterator {
-            decoder: Decoder::new(),
+            decoder,
             bytes,
             done: false,
         }
@@ -505,7 +562,7 @@ impl<B: Buffer, I: Iterator<Item = u8>> DecodeIterator<B, I> {
             match self.bytes.next() {
                 Some(b) => {
                     match self.decoder._push_byte(b) {
-                        Ok(true) => return Some(Ok(self.decoder.borrow_buf())),
+                        Ok(true) => return Some(Ok(self.decoder.completed_message())),
                         Err(e) => {
                             return Some(Err(e));
                         }
@@ -523,6 +580,37 @@ impl<B: Buffer, I: Iterator<Item = u8>> DecodeIterator<B, I> {
     }
 }
 
+/// Pushes a chunk of bytes into a [`Decoder`].
+///
+/// This struct is created by [`Decoder::push_bytes`].
+pub struct PushBytes<'d, 'b, B: Buffer> {
+    decoder: &'d mut Decoder<B>,
+    bytes: &'b [u8],
+}
+
+impl<B: Buffer> PushBytes<'_, '_, B> {
+    /// Pushes bytes until a message is complete or an error occurs.
+    ///
+    /// Returns `None` once all bytes have been pushed.
+    pub fn next_message(&mut self) -> Option<Result<&[u8], DecodeErr>> {
+        while let Some((&b, rest)) = self.bytes.split_first() {
+            self.bytes = rest;
+            match self.decoder._push_byte(b) {
+                Ok(false) => {}
+                Ok(true) => return Some(Ok(&self.decoder.buf)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        None
+    }
+
+    /// Returns the bytes that haven't been pushed into the decoder yet.
+    #[must_use]
+    pub fn remaining(&self) -> &[u8] {
+        self.bytes
+    }
+}
+
 /// Takes an iterator over bytes and returns an iterator that yields decoded messages / decoding errors.
 ///
 /// # Examples
@@ -548,6 +636,7 @@ pub fn decode_streaming<B: Buffer>(
 #[cfg(test)]
 mod decode_tests {
     use super::*;
+    use crate::transport::vectors;
     use crate::util::ArrayBuf;
     use hex_literal::hex;
     use DecodeErr::*;
@@ -594,12 +683,22 @@ mod decode_tests {
         }
     }
 
+    fn test_vector<B: Buffer>(vector: &vectors::Vector) {
+        test_parse_input::<B>(vector.input, vector.expected);
+    }
+
     #[test]
-    fn basic() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
-        let exp = &[Ok(hex!("12345678").as_slice())];
+    fn all_vectors() {
+        for vector in vectors::ALL {
+            test_vector::<ArrayBuf<128>>(vector);
+            #[cfg(feature = "alloc")]
+            test_vector::<Vec<u8>>(vector);
+        }
+    }
 
-        test_parse_input::<ArrayBuf<4>>(&bytes, exp);
+    #[test]
+    fn basic() {
+        test_vector::<ArrayBuf<4>>(&vectors::BASIC);
     }
 
     #[test]
@@ -615,146 +714,129 @@ mod decode_tests {
 
     #[test]
     fn invalid_crc() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b8FF");
-        let exp = &[Err(InvalidMessage {
-            checksum_mismatch: (0xFFb8, 0x7bb8),
-            end_esc_misaligned: false,
-            num_padding_bytes: 0,
-            invalid_padding_bytes: false,
-        })];
-
-        test_parse_input::<ArrayBuf<4>>(&bytes, exp);
+        test_vector::<ArrayBuf<4>>(&vectors::INVALID_CRC);
     }
 
     #[test]
     fn msg_end_misaligned() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 FF 1b1b1b1b 1a0013b6");
-        let exp = &[Err(InvalidMessage {
-            checksum_mismatch: (0xb613, 0xb613),
-            end_esc_misaligned: true,
-            num_padding_bytes: 0,
-            invalid_padding_bytes: false,
-        })];
-
-        test_parse_input::<ArrayBuf<16>>(&bytes, exp);
+        test_vector::<ArrayBuf<16>>(&vectors::MSG_END_MISALIGNED);
     }
 
     #[test]
     fn padding_too_large() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 12345678 1b1b1b1b 1a04f950");
-        let exp = &[Err(InvalidMessage {
-            checksum_mismatch: (0x50f9, 0x50f9),
-            end_esc_misaligned: false,
-            num_padding_bytes: 4,
-            invalid_padding_bytes: true,
-        })];
-
-        test_parse_input::<ArrayBuf<16>>(&bytes, exp);
+        test_vector::<ArrayBuf<16>>(&vectors::PADDING_TOO_LARGE);
     }
 
     #[test]
     fn empty_msg_with_padding() {
-        let bytes = hex!("1b1b1b1b 01010101 1b1b1b1b 1a014FF4");
-        let exp = &[Err(InvalidMessage {
-            checksum_mismatch: (0xf44f, 0xf44f),
-            end_esc_misaligned: false,
-            num_padding_bytes: 1,
-            invalid_padding_bytes: true,
-        })];
-
-        test_parse_input::<ArrayBuf<16>>(&bytes, exp);
+        test_vector::<ArrayBuf<16>>(&vectors::EMPTY_MSG_WITH_PADDING);
     }
 
     #[test]
     fn additional_bytes() {
-        let bytes = hex!("000102 1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b 1234");
-        let exp = &[
-            Err(DiscardedBytes(3)),
-            Ok(hex!("12345678").as_slice()),
-            Err(DiscardedBytes(2)),
-        ];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::ADDITIONAL_BYTES);
     }
 
     #[test]
     fn incomplete_message() {
-        let bytes = hex!("1b1b1b1b 01010101 123456");
-        let exp = &[Err(DiscardedBytes(11))];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::INCOMPLETE_MESSAGE);
     }
 
     #[test]
     fn invalid_esc_sequence() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1c000000 12345678 1b1b1b1b 1a03be25");
-        let exp = &[
-            Err(InvalidEsc([0x1c, 0x0, 0x0, 0x0])),
-            Err(DiscardedBytes(12)),
-        ];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::INVALID_ESC_SEQUENCE);
     }
 
     #[test]
     fn incomplete_esc_sequence() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b00 12345678 1b1b1b1b 1a009135");
-        let exp = &[Ok(hex!("12345678 1b1b1b00 12345678").as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::INCOMPLETE_ESC_SEQUENCE);
     }
 
     #[test]
     fn double_msg_start() {
-        let bytes =
-            hex!("1b1b1b1b 01010101 09 87654321 1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
-        let exp = &[Err(DiscardedBytes(13)), Ok(hex!("12345678").as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::DOUBLE_MSG_START);
     }
 
     #[test]
     fn padding() {
-        let bytes = hex!("1b1b1b1b 01010101 12345600 1b1b1b1b 1a0191a5");
-        let exp_bytes = hex!("123456");
-        let exp = &[Ok(exp_bytes.as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::PADDING);
     }
 
     #[test]
     fn escape_in_user_data() {
-        let bytes = hex!("1b1b1b1b 01010101 12 1b1b1b1b 1b1b1b1b 000000 1b1b1b1b 1a03be25");
-        let exp = &[Ok(hex!("121b1b1b1b").as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::ESCAPE_IN_USER_DATA);
     }
 
     #[test]
     fn ending_with_1b_no_padding_1() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 1234561b 1b1b1b1b 1a00361a");
-        let exp_bytes = hex!("12345678 1234561b");
-        let exp = &[Ok(exp_bytes.as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::ENDING_WITH_1B_NO_PADDING_1);
     }
 
     #[test]
     fn ending_with_1b_no_padding_2() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 12341b1b 1b1b1b1b 1a001ac5");
-        let exp_bytes = hex!("12345678 12341b1b");
-        let exp = &[Ok(exp_bytes.as_slice())];
-
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+        test_vector::<ArrayBuf<128>>(&vectors::ENDING_WITH_1B_NO_PADDING_2);
     }
 
     #[test]
     fn ending_with_1b_no_padding_3() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 121b1b1b 1b1b1b1b 1a000ba4");
-        let exp_bytes = hex!("12345678 121b1b1b");
-        let exp = &[Ok(exp_bytes.as_slice())];
+        test_vector::<ArrayBuf<128>>(&vectors::ENDING_WITH_1B_NO_PADDING_3);
+    }
 
-        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+    #[test]
+    fn frame_info() {
+        // data containing two escape sequences, requiring two padding bytes
+        let data = hex!("1b1b1b1b 1b1b1b1b 12");
+        let encoded = crate::transport::encode::<ArrayBuf<64>>(data).unwrap();
+        let mut decoder = Decoder::<ArrayBuf<64>>::new();
+        for b in &encoded[..encoded.len() - 1] {
+            assert_eq!(decoder.push_byte(*b), Ok(None));
+            assert_eq!(decoder.frame_info(), None);
+        }
+        assert_eq!(
+            decoder.push_byte(encoded[encoded.len() - 1]),
+            Ok(Some(&data[..]))
+        );
+        let info = FrameInfo {
+            raw_len: encoded.len(),
+            num_escape_sequences: 2,
+            num_padding_bytes: 3,
+        };
+        assert_eq!(decoder.frame_info(), Some(info));
+        // the metadata is cleared when the next message starts
+        assert_eq!(decoder.push_byte(0x1b), Ok(None));
+        assert_eq!(decoder.frame_info(), None);
+    }
+
+    #[test]
+    fn lenient_padding() {
+        let bytes = hex!(
+            "1b1b1b1b 01010101 123456ff 1b1b1b1b 1a01342b"
+            "1b1b1b1b 01010101 1200ff00 1b1b1b1b 1a03da60"
+            "1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b"
+        );
+        let mut decoder = Decoder::<ArrayBuf<8>>::new();
+        let mut decoded = decoder.push_bytes(&bytes);
+        assert!(matches!(
+            decoded.next_message(),
+            Some(Err(DecodeErr::InvalidMessage {
+                invalid_padding_bytes: true,
+                ..
+            }))
+        ));
+
+        let mut decoder = Decoder::<ArrayBuf<8>>::new().with_lenient_padding(true);
+        let mut decoded = decoder.push_bytes(&bytes);
+        assert_eq!(decoded.next_message(), Some(Ok(hex!("123456").as_slice())));
+        assert_eq!(decoded.next_message(), Some(Ok(hex!("12").as_slice())));
+        assert_eq!(
+            decoded.next_message(),
+            Some(Ok(hex!("12345678").as_slice()))
+        );
+        assert_eq!(decoded.next_message(), None);
+        assert_eq!(decoder.num_tolerated_padding(), 2);
+        // the counter isn't reset
+        decoder.reset();
+        assert_eq!(decoder.num_tolerated_padding(), 2);
     }
 
     #[cfg(feature = "alloc")]
@@ -777,77 +859,37 @@ mod decode_tests {
 
     #[test]
     fn invalid_padding_bytes_1() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 12345601 1b1b1b1b 1a012157");
-        let exp = &[Err(DecodeErr::InvalidMessage {
-            checksum_mismatch: (0x5721, 0x5721),
-            end_esc_misaligned: false,
-            num_padding_bytes: 1,
-            invalid_padding_bytes: true,
-        })];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::INVALID_PADDING_BYTES_1);
     }
 
     #[test]
     fn invalid_padding_bytes_2() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 12000100 1b1b1b1b 1a03297e");
-        let exp = &[Err(DecodeErr::InvalidMessage {
-            checksum_mismatch: (0x7e29, 0x7e29),
-            end_esc_misaligned: false,
-            num_padding_bytes: 3,
-            invalid_padding_bytes: true,
-        })];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::INVALID_PADDING_BYTES_2);
     }
 
     #[test]
     fn invalid_padding_bytes_3() {
-        let bytes = hex!("1b1b1b1b 01010101 12345678 12ff0000 1b1b1b1b 1a03a743");
-        let exp = &[Err(DecodeErr::InvalidMessage {
-            checksum_mismatch: (0x43a7, 0x43a7),
-            end_esc_misaligned: false,
-            num_padding_bytes: 3,
-            invalid_padding_bytes: true,
-        })];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::INVALID_PADDING_BYTES_3);
     }
 
     #[test]
     fn another_msg_start_after_padding_1() {
-        let bytes = hex!("1b1b1b1b 01010101 120000 1b1b1b1b 01010101 87654321 1b1b1b1b 1a00423c");
-        let exp = &[
-            Err(DecodeErr::DiscardedBytes(11)),
-            Ok(hex!("87654321").as_slice()),
-        ];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::ANOTHER_MSG_START_AFTER_PADDING_1);
     }
 
     #[test]
     fn another_msg_start_after_padding_2() {
-        let bytes = hex!("1b1b1b1b 01010101 120000 1b1b1b1b 01010101 1b1b1b1b 1a00c6e5");
-        let exp = &[Err(DecodeErr::DiscardedBytes(11)), Ok(hex!("").as_slice())];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::ANOTHER_MSG_START_AFTER_PADDING_2);
     }
 
     #[test]
     fn another_msg_start_after_padding_3() {
-        let bytes = hex!("1b1b1b1b 01010101 120000 1b1b1b1b 01010101 1b1b1b1b 1a014ff4");
-        let exp = &[
-            Err(DecodeErr::DiscardedBytes(11)),
-            Err(InvalidMessage {
-                checksum_mismatch: (0xf44f, 0xf44f),
-                end_esc_misaligned: false,
-                num_padding_bytes: 1,
-                invalid_padding_bytes: true,
-            }),
-        ];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::ANOTHER_MSG_START_AFTER_PADDING_3);
     }
 
     #[test]
     fn msg_end_with_zeroes_and_padding() {
-        let bytes = hex!("1b1b1b1b 01010101 12000000 1b1b1b1b 1a01e1b1");
-        let exp = &[Ok(hex!("120000").as_slice())];
-        test_parse_input::<ArrayBuf<12>>(&bytes, exp);
+        test_vector::<ArrayBuf<12>>(&vectors::MSG_END_WITH_ZEROES_AND_PADDING);
     }
 
     #[test]
@@ -879,10 +921,281 @@ mod decode_tests {
         test_parse_input::<ArrayBuf<1024>>(&bytes, exp);
     }
 
+    fn test_fuzzy_resync(bytes: &[u8], exp: &[Result<&[u8], DecodeErr>]) {
+        let mut exp_iter = exp.iter();
+        let mut decoder = Decoder::<ArrayBuf<128>>::new().with_fuzzy_resync(true);
+        for b in bytes {
+            let res = match decoder.push_byte(*b) {
+                Ok(None) => continue,
+                Ok(Some(x)) => Ok(x),
+                Err(e) => Err(e),
+            };
+            assert_eq!(Some(&res), exp_iter.next());
+        }
+        if let Some(e) = decoder.finalize() {
+            assert_eq!(Some(&Err(e)), exp_iter.next());
+        }
+        assert_eq!(exp_iter.next(), None);
+    }
+
+    #[test]
+    fn fuzzy_resync_exact() {
+        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let exp = &[Ok(hex!("12345678").as_slice())];
+        test_fuzzy_resync(&bytes, exp);
+    }
+
+    #[test]
+    fn fuzzy_resync_corrupted_byte() {
+        let exp = &[Ok(hex!("12345678").as_slice())];
+        test_fuzzy_resync(&hex!("0b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b"), exp);
+        test_fuzzy_resync(&hex!("1b1b1bff 01010101 12345678 1b1b1b1b 1a00b87b"), exp);
+        test_fuzzy_resync(&hex!("1b1b1b1b 01000101 12345678 1b1b1b1b 1a00b87b"), exp);
+        test_fuzzy_resync(&hex!("1b1b1b1b 01010181 12345678 1b1b1b1b 1a00b87b"), exp);
+    }
+
+    #[test]
+    fn fuzzy_resync_two_corrupted_bytes() {
+        let bytes = hex!("1b001b1b 01010001 12345678 1b1b1b1b 1a00b87b");
+        let exp = &[Err(DiscardedBytes(20))];
+        test_fuzzy_resync(&bytes, exp);
+    }
+
+    #[test]
+    fn fuzzy_resync_additional_bytes() {
+        // an additional 0x1b byte before the start sequence must not shift the message
+        let bytes = hex!("1b 1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let exp = &[Err(DiscardedBytes(1)), Ok(hex!("12345678").as_slice())];
+        test_fuzzy_resync(&bytes, exp);
+
+        let bytes = hex!("000102 1b1b1b1b 01010201 12345678 1b1b1b1b 1a00b87b 1234");
+        let exp = &[
+            Err(DiscardedBytes(3)),
+            Ok(hex!("12345678").as_slice()),
+            Err(DiscardedBytes(2)),
+        ];
+        test_fuzzy_resync(&bytes, exp);
+    }
+
+    #[test]
+    fn fuzzy_resync_crc_mismatch() {
+        // a corrupted start sequence followed by a broken message is still rejected
+        let bytes = hex!("1b1b1b1b 01010102 12345678 1b1b1b1b 1a00b8ff");
+        let exp = &[Err(InvalidMessage {
+            checksum_mismatch: Some(CrcMismatch {
+                expected: 0xffb8,
+                computed: 0x7bb8,
+            }),
+            end_esc_misaligned: false,
+            num_padding_bytes: 0,
+            invalid_padding_bytes: false,
+        })];
+        test_fuzzy_resync(&bytes, exp);
+    }
+
+    #[test]
+    fn fuzzy_resync_disabled() {
+        let bytes = hex!("1b1b1b1b 01000101 12345678 1b1b1b1b 1a00b87b");
+        let exp = &[Err(DiscardedBytes(20))];
+        test_parse_input::<ArrayBuf<128>>(&bytes, exp);
+    }
+
+    #[test]
+    fn long_junk() {
+        // more junk bytes than fit into a `u16`
+        const N: usize = 70_000;
+        let msg = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        for fuzzy_resync in [false, true] {
+            let mut decoder = Decoder::<ArrayBuf<8>>::new().with_fuzzy_resync(fuzzy_resync);
+            for _ in 0..N {
+                assert_eq!(decoder.push_byte(0x42), Ok(None));
+            }
+            let (&last, rest) = msg.split_last().unwrap();
+            let mut err = None;
+            for &b in rest {
+                if let Err(e) = decoder.push_byte(b) {
+                    err = Some(e);
+                }
+            }
+            assert_eq!(err, Some(DiscardedBytes(N)));
+            assert_eq!(
+                decoder.push_byte(last),
+                Ok(Some(hex!("12345678").as_slice()))
+            );
+        }
+
+        let bytes = core::iter::repeat(0x42).take(N).chain(msg);
+        let mut iter = DecodeIterator::<ArrayBuf<8>, _>::new(bytes);
+        assert_eq!(iter.next(), Some(Err(DiscardedBytes(N))));
+        assert_eq!(iter.next(), Some(Ok(hex!("12345678").as_slice())));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn try_message() {
+        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let mut decoder = Decoder::<ArrayBuf<128>>::new();
+        assert_eq!(decoder.try_message(), None);
+        for b in &bytes[..bytes.len() - 1] {
+            assert_eq!(decoder.push_byte(*b), Ok(None));
+            assert_eq!(decoder.try_message(), None);
+        }
+        assert!(decoder.push_byte(bytes[bytes.len() - 1]).is_ok());
+        assert_eq!(decoder.try_message(), Some(hex!("12345678").as_slice()));
+        // pushing another byte starts a new message
+        assert_eq!(decoder.push_byte(0x1b), Ok(None));
+        assert_eq!(decoder.try_message(), None);
+        decoder.reset();
+        assert_eq!(decoder.try_message(), None);
+    }
+
     #[test]
     fn eof_after_zero() {
         let bytes = hex!("1b1b1b1b 01010101 12340000");
         let exp = &[Err(DecodeErr::DiscardedBytes(12))];
         test_parse_input::<ArrayBuf<12>>(&bytes, exp);
     }
+
+    #[test]
+    fn push_bytes() {
+        let msg = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let mut bytes = [0u8; 3 * 20 + 2];
+        bytes[..20].copy_from_slice(&msg);
+        bytes[20..22].copy_from_slice(&[0x01, 0x02]);
+        bytes[22..42].copy_from_slice(&msg);
+        bytes[42..].copy_from_slice(&msg);
+
+        let mut decoder = Decoder::<ArrayBuf<20>>::new();
+        // the first chunk ends in the middle of the last message
+        let (chunk1, chunk2) = bytes.split_at(50);
+        let mut messages = decoder.push_bytes(chunk1);
+        assert_eq!(messages.next_message(), Some(Ok(&hex!("12345678")[..])));
+        assert_eq!(messages.next_message(), Some(Err(DiscardedBytes(2))));
+        assert_eq!(messages.next_message(), Some(Ok(&hex!("12345678")[..])));
+        assert_eq!(messages.next_message(), None);
+        assert_eq!(messages.remaining(), &[]);
+
+        let mut messages = decoder.push_bytes(chunk2);
+        assert_eq!(messages.next_message(), Some(Ok(&hex!("12345678")[..])));
+        assert_eq!(messages.next_message(), None);
+
+        // stopping early leaves the remaining bytes
+        let mut messages = decoder.push_bytes(&bytes);
+        assert!(messages.next_message().is_some());
+        assert_eq!(messages.remaining(), &bytes[20..]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+        let err = InvalidMessage {
+            checksum_mismatch: Some(CrcMismatch {
+                expected: 0x7bb8,
+                computed: 0xffb8,
+            }),
+            end_esc_misaligned: false,
+            num_padding_bytes: 0,
+            invalid_padding_bytes: true,
+        };
+        assert_eq!(
+            err.to_string(),
+            "invalid transmission (CRC mismatch (expected: 0x7bb8, computed: 0xffb8), \
+            padding bytes not equal to zero), transmission discarded \
+            (check the baud rate and the alignment of the IR head)"
+        );
+        assert_eq!(
+            InvalidEsc([0x1b, 0x1b, 0x1b, 0x01]).to_string(),
+            "invalid escape sequence 1b1b1b1b 1b1b1b01, transmission discarded \
+            (check the baud rate and the alignment of the IR head)"
+        );
+        assert_eq!(
+            crate::parser::ParseError::from(crate::parser::TlfParseError::TlfInvalidTy).to_string(),
+            "invalid type-length field: invalid type field"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn vec_capacity() {
+        let sample = include_bytes!("../../sample.bin");
+        let mut decoder = Decoder::<Vec<u8>>::with_capacity(4096);
+        for _ in 0..3 {
+            for &b in &sample[..sample.len() - 1] {
+                assert!(decoder.push_byte(b).is_ok());
+            }
+            assert!(matches!(
+                decoder.push_byte(sample[sample.len() - 1]),
+                Ok(Some(_))
+            ));
+            assert_eq!(decoder.capacity(), 4096);
+        }
+        decoder.reset();
+        assert_eq!(decoder.capacity(), 4096);
+
+        // without an explicit capacity, the buffer starts with at least 1 KiB
+        let mut decoder = Decoder::<Vec<u8>>::new();
+        assert_eq!(decoder.capacity(), 0);
+        for &b in &sample[..9] {
+            decoder.push_byte(b).unwrap();
+        }
+        assert!(decoder.capacity() >= 1024);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn text_captures() {
+        use crate::util::{Base64ParseError, HexParseError};
+        use alloc::string::String;
+        use core::fmt::Write;
+
+        let sample = include_bytes!("../../sample.bin");
+        let mut hex = String::new();
+        for line in sample.chunks(16) {
+            for b in line {
+                write!(hex, "{b:02x} ").unwrap();
+            }
+            hex.push('\n');
+        }
+        assert_eq!(super::decode_hex(&hex), Ok(decode(sample)));
+        assert_eq!(super::decode_hex("1b1b1"), Err(HexParseError::OddLength));
+
+        let base64 = "GxsbGwEBAQESNFZ4\r\nGxsbGxoAuHs=\r\n";
+        assert_eq!(
+            super::decode_base64(base64),
+            Ok(Vec::from([Ok(hex!("12345678").to_vec())]))
+        );
+        // a truncated transmission is reported by the transport decoder
+        assert_eq!(
+            super::decode_base64("GxsbGwEBAQESNFZ4"),
+            Ok(Vec::from([Err(DecodeErr::DiscardedBytes(12))]))
+        );
+        assert_eq!(
+            super::decode_base64("GxsbGw.="),
+            Err(Base64ParseError::InvalidChar('.', 6))
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decode_parallel() {
+        use crate::transport::encode;
+        let sample = include_bytes!("../../sample.bin");
+        // message containing an escaped escape sequence followed by something looking like an end and a start sequence
+        let tricky = encode::<Vec<u8>>(hex!("1b1b1b1b 1a000000 1b1b1b1b 01010101")).unwrap();
+        let mut bytes = Vec::new();
+        for i in 0..3000 {
+            match i % 7 {
+                0 => bytes.extend_from_slice(&tricky),
+                3 => bytes.extend_from_slice(&sample[..100]),
+                5 => bytes.extend_from_slice(&[0x1b; 9]),
+                _ => bytes.extend_from_slice(sample),
+            }
+        }
+        assert!(bytes.len() > 4 * 64 * 1024);
+        assert!(find_split_point(&bytes, 1).is_some());
+        assert_eq!(find_split_point(&tricky, 0), None);
+        assert_eq!(super::decode_parallel(&bytes), decode(&bytes));
+        assert_eq!(super::decode_parallel(&[]), vec![]);
+    }
 }
@@ -5,12 +5,15 @@ use core::fmt;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use super::{DecodeErr, Decoder};
-use crate::util::{Buffer, ByteSource, ByteSourceErr, ErrKind};
+use super::{AsyncDecoderReader, DecodeErr, Decoder, FrameInfo};
+use crate::util::{
+    ArrayBuf, AsyncByteSource, Buffer, ByteSource, ByteSourceErr, Clock, ErrKind, SliceByteSource,
+};
 
 /// Error type used by the `DecoderReader`
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ReadDecodedError<IoErr> {
     /// Error while decoding the data (e.g. checksum mismatch)
     DecodeErr(DecodeErr),
@@ -20,23 +23,115 @@ pub enum ReadDecodedError<IoErr> {
     IoErr(IoErr, usize),
 }
 
-impl fmt::Display for ReadDecodedError<fmt::Error> {
+impl<IoErr> fmt::Display for ReadDecodedError<IoErr>
+where
+    IoErr: fmt::Debug,
+{
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        <Self as fmt::Debug>::fmt(self, f)
+        match self {
+            ReadDecodedError::DecodeErr(e) => write!(f, "{e}"),
+            ReadDecodedError::IoErr(e, 0) => write!(f, "error while reading: {e:?}"),
+            ReadDecodedError::IoErr(e, n) => {
+                write!(f, "error while reading: {e:?} ({n} bytes discarded)")
+            }
+        }
+    }
+}
+
+impl<IoErr> core::error::Error for ReadDecodedError<IoErr>
+where
+    IoErr: fmt::Debug,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ReadDecodedError::DecodeErr(e) => Some(e),
+            ReadDecodedError::IoErr(..) => None,
+        }
     }
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for ReadDecodedError<fmt::Error> {}
+/// Statistics about the reads of a [`DecoderReader`]
+///
+/// A *burst* is the sequence of bytes read between two occurrences of `WouldBlock`
+/// (or between two calls of `poll_read` that found no more
+/// data). The distribution of burst lengths shows how many bytes are available per poll,
+/// which helps tuning poll intervals and UART FIFO thresholds. Many empty bursts mean
+/// that the source is polled more often than necessary, long bursts indicate that
+/// data may be lost due to FIFO overruns.
+///
+/// Returned by [`DecoderReader::stats`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ReaderStats {
+    /// number of bytes read from the byte source
+    pub bytes_read: u64,
+    /// number of times the byte source reported that no data is available
+    pub would_block: u32,
+    /// number of bursts by length
+    ///
+    /// Index `0` counts empty bursts, index `i` counts bursts with a length
+    /// between `2^(i-1)` and `2^i - 1` and the last index counts bursts of 64 bytes
+    /// or more.
+    pub bursts: [u32; 8],
+    // number of bytes read since the last `WouldBlock`
+    current_burst: u32,
+}
+
+impl ReaderStats {
+    fn record_byte(&mut self) {
+        self.bytes_read = self.bytes_read.saturating_add(1);
+        self.current_burst = self.current_burst.saturating_add(1);
+    }
+
+    fn record_would_block(&mut self) {
+        self.would_block = self.would_block.saturating_add(1);
+        let idx = (u32::BITS - self.current_burst.leading_zeros()).min(7) as usize;
+        self.bursts[idx] = self.bursts[idx].saturating_add(1);
+        self.current_burst = 0;
+    }
+}
+
+type TimestampedRes<'i, ReadErr> = Result<(u64, &'i [u8]), ReadDecodedError<ReadErr>>;
 
 /// Decode transmissions read from a byte source
-pub struct DecoderReader<B, R>
+///
+/// Use [`DecoderReader::new`] to create a reader with default settings or
+/// [`DecoderReader::builder`] to configure the buffer and decoder options.
+///
+/// The type parameter `C` is the [`Clock`] set using [`with_clock`](DecoderReader::with_clock)
+/// (`()` if no clock is used).
+pub struct DecoderReader<B, R, C = ()>
 where
     B: Buffer,
     R: ByteSource,
 {
     decoder: Decoder<B>,
     reader: R,
+    clock: C,
+    stats: ReaderStats,
+    // error that ended a batch after some transmissions had been read
+    pending_err: Option<ReadDecodedError<R::ReadError>>,
+}
+
+impl DecoderReader<ArrayBuf<0>, SliceByteSource<'static>> {
+    /// Returns a builder used to configure a `DecoderReader`.
+    ///
+    /// The builder uses a static internal buffer of 8 KiB by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::transport::DecoderReader;
+    /// # let source = sml_rs::util::CustomByteSource::new(|| Err(sml_rs::util::CustomReadError::<()>::Eof));
+    /// let reader = DecoderReader::builder()
+    ///     .with_static_buffer::<1024>()
+    ///     .with_fuzzy_resync(true)
+    ///     .build(source);
+    /// ```
+    #[must_use]
+    pub fn builder() -> DecoderReaderBuilder<crate::DefaultBuffer> {
+        DecoderReaderBuilder::new()
+    }
 }
 
 impl<B, R> DecoderReader<B, R>
@@ -49,9 +144,50 @@ where
         DecoderReader {
             decoder: Default::default(),
             reader,
+            clock: (),
+            stats: ReaderStats::default(),
+            pending_err: None,
+        }
+    }
+}
+
+impl<B, R, C> DecoderReader<B, R, C>
+where
+    B: Buffer,
+    R: ByteSource,
+{
+    /// Uses `clock` to timestamp received transmissions.
+    ///
+    /// The timestamps are returned by [`read_timestamped`](DecoderReader::read_timestamped)
+    /// and [`next_timestamped`](DecoderReader::next_timestamped).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, CustomByteSource, CustomReadError}};
+    /// let mut data = include_bytes!("../../sample.bin").iter().copied();
+    /// let source = CustomByteSource::new(|| data.next().ok_or(CustomReadError::<()>::Eof));
+    /// let mut reader = DecoderReader::<ArrayBuf<1024>, _>::new(source)
+    ///     .with_clock(|| 1_700_000_000);
+    /// let (timestamp, bytes) = reader.next_timestamped().unwrap().unwrap();
+    /// assert_eq!(timestamp, 1_700_000_000);
+    /// assert_eq!(bytes.len(), 226);
+    /// ```
+    pub fn with_clock<C2: Clock>(self, clock: C2) -> DecoderReader<B, R, C2> {
+        DecoderReader {
+            decoder: self.decoder,
+            reader: self.reader,
+            clock,
+            stats: self.stats,
+            pending_err: self.pending_err,
         }
     }
 
+    /// Consumes the `DecoderReader` and returns the internal buffer and the byte source.
+    pub fn into_parts(self) -> (B, R) {
+        (self.decoder.into_buf(), self.reader)
+    }
+
     /// Reads and decodes a transmission
     ///
     /// On success, returns the decoded transmission (`Ok(bytes)`). Otherwise, returns errors
@@ -60,30 +196,95 @@ where
     /// When reading from a finite data source (such as a file containing a certain
     /// number of transmissions), it's easier to use [`next`](DecoderReader::next) instead,
     /// which handles EOFs correctly.
-    ///
-    /// See also [`read_nb`](DecoderReader::read_nb), which provides a convenient API for
-    /// non-blocking byte sources.
+    #[cfg_attr(
+        feature = "nb",
+        doc = "\nSee also [`read_nb`](DecoderReader::read_nb), which provides a convenient API for non-blocking byte sources."
+    )]
     pub fn read(&mut self) -> Result<&[u8], ReadDecodedError<R::ReadError>> {
-        loop {
-            match self.reader.read_byte() {
-                Ok(b) => match self.decoder._push_byte(b) {
-                    Ok(false) => continue,
-                    Ok(true) => return Ok(self.decoder.borrow_buf()),
-                    Err(e) => return Err(ReadDecodedError::DecodeErr(e)),
-                },
+        if let Some(e) = self.pending_err.take() {
+            return Err(e);
+        }
+        while !self.read_step()? {}
+        Ok(self.decoder.completed_message())
+    }
+
+    /// Reads and decodes the transmissions that are available without blocking
+    ///
+    /// Reads up to `max` transmissions and appends them to `out`. The batch ends
+    /// early when the byte source returns `WouldBlock` after at least one
+    /// transmission has been read, which allows draining all frames that arrived in
+    /// a single TCP segment at once. Returns the number of transmissions appended to `out`.
+    ///
+    /// Errors are returned immediately if no transmission has been read yet.
+    /// Otherwise, the batch ends and the error is returned by the next call of
+    /// `read_batch`, [`read`](DecoderReader::read) or `poll_read`.
+    ///
+    /// The byte source should be non-blocking (e.g. a `TcpStream` with
+    /// `set_nonblocking(true)`). Otherwise, this method blocks until `max`
+    /// transmissions have been read.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, CustomByteSource, CustomReadError}};
+    /// let mut data = include_bytes!("../../sample.bin").iter().copied();
+    /// let source = CustomByteSource::new(|| data.next().ok_or(CustomReadError::<()>::Eof));
+    /// let mut reader = DecoderReader::<ArrayBuf<1024>, _>::new(source);
+    /// let mut batch = Vec::new();
+    /// assert_eq!(reader.read_batch(10, &mut batch).unwrap(), 1);
+    /// assert_eq!(batch[0].len(), 226);
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn read_batch(
+        &mut self,
+        max: usize,
+        out: &mut impl Extend<alloc::vec::Vec<u8>>,
+    ) -> Result<usize, ReadDecodedError<R::ReadError>> {
+        let mut n = 0;
+        while n < max {
+            match self.read() {
+                Ok(bytes) => {
+                    out.extend(core::iter::once(bytes.to_vec()));
+                    n += 1;
+                }
+                Err(e) if n == 0 => return Err(e),
+                Err(ReadDecodedError::IoErr(e, _)) if e.is_would_block() => break,
                 Err(e) => {
-                    let discarded_bytes = match e.kind() {
-                        ErrKind::Eof | ErrKind::Other => {
-                            // reset the decoder and return how many bytes were discarded
-                            self.decoder.reset()
-                        }
-                        ErrKind::WouldBlock => 0,
-                    };
-                    // return the error
-                    return Err(ReadDecodedError::IoErr(e, discarded_bytes));
+                    self.pending_err = Some(e);
+                    break;
                 }
             }
         }
+        Ok(n)
+    }
+
+    // reads a single byte and returns whether a transmission has been completed
+    fn read_step(&mut self) -> Result<bool, ReadDecodedError<R::ReadError>> {
+        match self.reader.read_byte() {
+            Ok(b) => {
+                self.stats.record_byte();
+                self.decoder
+                    ._push_byte(b)
+                    .map_err(ReadDecodedError::DecodeErr)
+            }
+            Err(e) => Err(self.io_err(e)),
+        }
+    }
+
+    fn io_err(&mut self, e: R::ReadError) -> ReadDecodedError<R::ReadError> {
+        let discarded_bytes = match e.kind() {
+            ErrKind::Eof | ErrKind::Other => {
+                // reset the decoder and return how many bytes were discarded
+                self.decoder.reset()
+            }
+            ErrKind::WouldBlock => {
+                self.stats.record_would_block();
+                0
+            }
+        };
+        ReadDecodedError::IoErr(e, discarded_bytes)
     }
 
     /// Tries to read and decode a transmission
@@ -94,9 +295,10 @@ where
     ///
     /// When reading from a data source that will provide data infinitely (such
     /// as from a serial port), it's easier to use [`read`](DecoderReader::read) instead.
-    ///
-    /// See also [`next_nb`](DecoderReader::next_nb), which provides a convenient API for
-    /// non-blocking byte sources.
+    #[cfg_attr(
+        feature = "nb",
+        doc = "\nSee also [`next_nb`](DecoderReader::next_nb), which provides a convenient API for non-blocking byte sources."
+    )]
     #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<Result<&[u8], ReadDecodedError<R::ReadError>>> {
         match self.read() {
@@ -105,6 +307,56 @@ where
         }
     }
 
+    /// Returns statistics about the bytes read so far.
+    ///
+    /// See [`ReaderStats`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, CustomByteSource, CustomReadError}};
+    /// // a source that provides three bytes per poll
+    /// let mut n = 0;
+    /// let source = CustomByteSource::new(|| {
+    ///     n += 1;
+    ///     if n % 4 == 0 { Err(CustomReadError::<()>::WouldBlock) } else { Ok(0) }
+    /// });
+    /// let mut reader = DecoderReader::<ArrayBuf<16>, _>::new(source);
+    /// for _ in 0..10 {
+    ///     let _ = reader.read();
+    /// }
+    /// let stats = reader.stats();
+    /// assert_eq!(stats.would_block, 10);
+    /// assert_eq!(stats.bytes_read, 30);
+    /// // bursts of 2 to 3 bytes
+    /// assert_eq!(stats.bursts[2], 10);
+    /// ```
+    #[must_use]
+    pub fn stats(&self) -> &ReaderStats {
+        &self.stats
+    }
+
+    /// Resets the statistics returned by [`stats`](DecoderReader::stats).
+    pub fn reset_stats(&mut self) {
+        self.stats = ReaderStats::default();
+    }
+
+    /// Returns metadata of the transmission returned by the last successful read.
+    ///
+    /// See [`Decoder::frame_info`] for details.
+    #[must_use]
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.decoder.frame_info()
+    }
+
+    /// Returns the number of transmissions with nonzero padding bytes that have been accepted.
+    ///
+    /// See [`DecoderReaderBuilder::with_lenient_padding`].
+    #[must_use]
+    pub fn num_tolerated_padding(&self) -> u32 {
+        self.decoder.num_tolerated_padding()
+    }
+
     /// Reads and decodes a transmission (non-blocking)
     ///
     /// Same as [`read`](DecoderReader::read) except that it returns `nb::Result`.
@@ -141,6 +393,189 @@ where
     }
 }
 
+impl<B, R, C> DecoderReader<B, R, C>
+where
+    B: Buffer,
+    R: ByteSource,
+    C: Clock,
+{
+    /// Reads and decodes a transmission and returns it together with its reception time
+    ///
+    /// Same as [`read`](DecoderReader::read) except that the timestamp returned by the clock
+    /// when the last byte of the transmission has been read is returned alongside the bytes.
+    pub fn read_timestamped(&mut self) -> TimestampedRes<'_, R::ReadError> {
+        // the returned slice borrows `self`, so the message is fetched again after reading the clock
+        self.read()?;
+        let timestamp = self.clock.now();
+        Ok((timestamp, self.decoder.completed_message()))
+    }
+
+    /// Tries to read and decode a transmission and returns it together with its reception time
+    ///
+    /// Same as [`next`](DecoderReader::next) except that the timestamp returned by the clock
+    /// when the last byte of the transmission has been read is returned alongside the bytes.
+    pub fn next_timestamped(&mut self) -> Option<TimestampedRes<'_, R::ReadError>> {
+        match self.read_timestamped() {
+            Err(ReadDecodedError::IoErr(e, 0)) if e.is_eof() => None,
+            x => Some(x),
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<B, R, C> DecoderReader<B, crate::util::EioByteSource<R>, C>
+where
+    B: Buffer,
+    R: embedded_io::Read + embedded_io::ReadReady,
+{
+    /// Reads and decodes the bytes that are available without blocking
+    ///
+    /// Uses `ReadReady` to check whether data is available before reading each byte.
+    /// Returns `Ok(Some(bytes))` as soon as a transmission has been completed and
+    /// `Ok(None)` if no more bytes are available. Partially received transmissions are
+    /// kept, so this method can be called periodically from a main loop.
+    ///
+    /// *This function is available only if sml-rs is built with the `"embedded-io"` feature.*
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, EioByteSource}};
+    /// # fn handle(_: &[u8]) {}
+    /// fn main_loop(uart: impl embedded_io::Read + embedded_io::ReadReady) -> ! {
+    ///     let mut reader = DecoderReader::<ArrayBuf<1024>, _>::new(EioByteSource::new(uart));
+    ///     loop {
+    ///         match reader.poll_read() {
+    ///             Ok(Some(bytes)) => handle(bytes),
+    ///             Ok(None) => { /* no data available, do other work */ }
+    ///             Err(_) => { /* handle the error */ }
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn poll_read(
+        &mut self,
+    ) -> Result<Option<&[u8]>, ReadDecodedError<embedded_io::ReadExactError<R::Error>>> {
+        if let Some(e) = self.pending_err.take() {
+            return Err(e);
+        }
+        loop {
+            match self.reader.inner.read_ready() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.stats.record_would_block();
+                    return Ok(None);
+                }
+                Err(e) => return Err(self.io_err(embedded_io::ReadExactError::Other(e))),
+            }
+            if self.read_step()? {
+                return Ok(Some(self.decoder.completed_message()));
+            }
+        }
+    }
+}
+
+/// Builder used to configure a [`DecoderReader`].
+///
+/// Created using [`DecoderReader::builder`].
+///
+/// The builder doesn't register observer callbacks. Instead, the reader's
+/// [`stats`](DecoderReader::stats) and [`frame_info`](DecoderReader::frame_info) can be
+/// inspected after each read, which keeps `DecoderReader` free of a callback type parameter.
+#[derive(Debug, Clone)]
+pub struct DecoderReaderBuilder<B: Buffer> {
+    buf: B,
+    fuzzy_resync: bool,
+    lenient_padding: bool,
+}
+
+impl<B: Buffer> Default for DecoderReaderBuilder<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Buffer> DecoderReaderBuilder<B> {
+    /// Constructs a builder using the default value of the buffer type `B`.
+    #[must_use]
+    pub fn new() -> Self {
+        DecoderReaderBuilder {
+            buf: B::default(),
+            fuzzy_resync: false,
+            lenient_padding: false,
+        }
+    }
+
+    /// Uses a static internal buffer of size `N`.
+    ///
+    /// Transmissions longer than `N` bytes are reported as `DecodeErr::OutOfMemory`.
+    #[must_use]
+    pub fn with_static_buffer<const N: usize>(self) -> DecoderReaderBuilder<ArrayBuf<N>> {
+        self.with_buffer(ArrayBuf::default())
+    }
+
+    /// Uses a dynamically-sized internal buffer.
+    ///
+    /// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+    #[cfg(feature = "alloc")]
+    #[must_use]
+    pub fn with_vec_buffer(self) -> DecoderReaderBuilder<crate::util::VecBuf> {
+        self.with_buffer(alloc::vec::Vec::new())
+    }
+
+    /// Uses the provided buffer instance (e.g. a buffer leased from a [`BufferPool`](crate::util::BufferPool)).
+    ///
+    /// The buffer is cleared before use.
+    #[must_use]
+    pub fn with_buffer<B2: Buffer>(self, buf: B2) -> DecoderReaderBuilder<B2> {
+        DecoderReaderBuilder {
+            buf,
+            fuzzy_resync: self.fuzzy_resync,
+            lenient_padding: self.lenient_padding,
+        }
+    }
+
+    /// Enables or disables fuzzy resynchronization (see [`Decoder::with_fuzzy_resync`]).
+    #[must_use]
+    pub fn with_fuzzy_resync(mut self, enabled: bool) -> Self {
+        self.fuzzy_resync = enabled;
+        self
+    }
+
+    /// Enables or disables accepting nonzero padding bytes (see [`Decoder::with_lenient_padding`]).
+    #[must_use]
+    pub fn with_lenient_padding(mut self, enabled: bool) -> Self {
+        self.lenient_padding = enabled;
+        self
+    }
+
+    fn decoder(self) -> Decoder<B> {
+        Decoder::from_buf(self.buf)
+            .with_fuzzy_resync(self.fuzzy_resync)
+            .with_lenient_padding(self.lenient_padding)
+    }
+
+    /// Builds a `DecoderReader` reading from `reader`.
+    pub fn build<R: ByteSource>(self, reader: R) -> DecoderReader<B, R> {
+        DecoderReader {
+            decoder: self.decoder(),
+            reader,
+            clock: (),
+            stats: ReaderStats::default(),
+            pending_err: None,
+        }
+    }
+
+    /// Builds an [`AsyncDecoderReader`] reading from `reader`.
+    pub fn build_async<R>(self, reader: R) -> AsyncDecoderReader<B, R>
+    where
+        R: AsyncByteSource + Unpin,
+    {
+        AsyncDecoderReader::from_decoder(self.decoder(), reader)
+    }
+}
+
 #[cfg(test)]
 mod decoder_reader_tests {
     use core::iter::once;
@@ -199,6 +634,9 @@ mod decoder_reader_tests {
         DecoderReader {
             decoder: Default::default(),
             reader: TestReader { iter },
+            clock: (),
+            stats: ReaderStats::default(),
+            pending_err: None,
         }
     }
 
@@ -278,6 +716,37 @@ mod decoder_reader_tests {
         assert_eq!(dr.next(), None);
     }
 
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn read_batch() {
+        use alloc::{vec, vec::Vec};
+
+        let msg = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let all_data = msg
+            .into_iter()
+            .chain(msg)
+            .chain(msg)
+            .map(Ok)
+            .chain(once(Err(TestReaderErr::WouldBlock)))
+            .chain(msg.into_iter().map(Ok))
+            .chain(hex!("1b1b1b1b 01010101 12").into_iter().map(Ok));
+        let mut dr = decoder_from(all_data);
+        let mut out = Vec::new();
+        assert_eq!(dr.read_batch(2, &mut out), Ok(2));
+        // `WouldBlock` ends the batch
+        assert_eq!(dr.read_batch(5, &mut out), Ok(1));
+        assert_eq!(out, vec![hex!("12345678").to_vec(); 3]);
+        // the EOF is reported by the next call
+        assert_eq!(dr.read_batch(5, &mut out), Ok(1));
+        assert_eq!(
+            dr.read_batch(5, &mut out),
+            Err(ReadDecodedError::IoErr(TestReaderErr::Eof, 9))
+        );
+        assert_eq!(dr.read_batch(0, &mut out), Ok(0));
+        assert_eq!(dr.next(), None);
+        assert_eq!(out.len(), 4);
+    }
+
     #[test]
     fn immediate_err() {
         let all_data = once(Err(TestReaderErr::Other));
@@ -374,4 +843,206 @@ mod decoder_reader_tests {
             Err(ReadDecodedError::IoErr(TestReaderErr::Eof, 0))
         );
     }
+
+    #[test]
+    fn builder() {
+        // the second byte of the start sequence has been corrupted
+        let data = hex!("1b0b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let reader = || TestReader {
+            iter: data.into_iter().map(Ok),
+        };
+
+        let mut dr = DecoderReader::builder().build(reader());
+        // the transmission isn't detected without fuzzy resynchronization
+        assert_eq!(
+            dr.next(),
+            Some(Err(ReadDecodedError::IoErr(TestReaderErr::Eof, 20)))
+        );
+
+        let mut dr = DecoderReader::builder()
+            .with_fuzzy_resync(true)
+            .with_static_buffer::<4>()
+            .build(reader());
+        assert_eq!(dr.next(), Some(Ok(hex!("12345678").as_slice())));
+        assert_eq!(dr.next(), None);
+
+        let mut dr = DecoderReader::builder()
+            .with_fuzzy_resync(true)
+            .with_buffer(ArrayBuf::<3>::default())
+            .build(reader());
+        assert_eq!(
+            dr.next(),
+            Some(Err(ReadDecodedError::DecodeErr(DecodeErr::OutOfMemory)))
+        );
+        let (buf, _) = dr.into_parts();
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn builder_lenient_padding() {
+        // the transmission contains a single padding byte `0xff`
+        let data = hex!("1b1b1b1b 01010101 123456ff 1b1b1b1b 1a01342b");
+        let reader = || TestReader {
+            iter: data.into_iter().map(Ok),
+        };
+
+        let mut dr = DecoderReader::builder().build(reader());
+        assert!(matches!(
+            dr.next(),
+            Some(Err(ReadDecodedError::DecodeErr(
+                DecodeErr::InvalidMessage { .. }
+            )))
+        ));
+        assert_eq!(dr.num_tolerated_padding(), 0);
+
+        let mut dr = DecoderReader::builder()
+            .with_lenient_padding(true)
+            .build(reader());
+        assert_eq!(dr.next(), Some(Ok(hex!("123456").as_slice())));
+        assert_eq!(dr.next(), None);
+        assert_eq!(dr.num_tolerated_padding(), 1);
+    }
+
+    // provides `ready` bytes at once, then reports that no data is available
+    #[cfg(feature = "embedded-io")]
+    struct Uart {
+        data: &'static [u8],
+        ready: usize,
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ErrorType for Uart {
+        type Error = embedded_io::ErrorKind;
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::Read for Uart {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            assert!(self.ready > 0, "read would block");
+            let n = buf.len().min(self.data.len()).min(self.ready);
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            self.ready -= n;
+            Ok(n)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    impl embedded_io::ReadReady for Uart {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            if self.data.is_empty() {
+                return Err(embedded_io::ErrorKind::NotConnected);
+            }
+            Ok(self.ready > 0)
+        }
+    }
+
+    #[cfg(feature = "embedded-io")]
+    #[test]
+    fn poll_read() {
+        use crate::util::EioByteSource;
+        use embedded_io::ReadExactError;
+
+        static DATA: [u8; 25] = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b 1b1b1b1b 01");
+        let uart = Uart {
+            data: &DATA,
+            ready: 0,
+        };
+        let mut dr = DecoderReader::<ArrayBuf<64>, _>::new(EioByteSource::new(uart));
+        assert_eq!(dr.poll_read(), Ok(None));
+        dr.reader.inner.ready = 10;
+        assert_eq!(dr.poll_read(), Ok(None));
+        dr.reader.inner.ready = 13;
+        assert_eq!(dr.poll_read(), Ok(Some(hex!("12345678").as_slice())));
+        // the remaining bytes are read by the next call
+        assert_eq!(dr.poll_read(), Ok(None));
+        dr.reader.inner.ready = 2;
+        // the source fails after providing the last bytes
+        assert_eq!(
+            dr.poll_read(),
+            Err(ReadDecodedError::IoErr(
+                ReadExactError::Other(embedded_io::ErrorKind::NotConnected),
+                5
+            ))
+        );
+    }
+
+    #[cfg(all(feature = "embedded-io", feature = "alloc"))]
+    #[test]
+    fn poll_read_pending_err() {
+        use crate::util::EioByteSource;
+        use embedded_io::ReadExactError;
+
+        static DATA: [u8; 25] = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b 1b1b1b1b 01");
+        let uart = Uart {
+            data: &DATA,
+            ready: 100,
+        };
+        let mut dr = DecoderReader::<ArrayBuf<64>, _>::new(EioByteSource::new(uart));
+        let mut out = Vec::new();
+        assert_eq!(dr.read_batch(5, &mut out), Ok(1));
+        // the error that ended the batch is returned first
+        assert_eq!(
+            dr.poll_read(),
+            Err(ReadDecodedError::IoErr(ReadExactError::UnexpectedEof, 5))
+        );
+    }
+
+    #[test]
+    fn stats() {
+        let data = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let would_block = || once(Err(TestReaderErr::WouldBlock));
+        let all_data = would_block()
+            .chain(data[..1].iter().copied().map(Ok))
+            .chain(would_block())
+            .chain(data[1..].iter().copied().map(Ok))
+            .chain(would_block())
+            .chain(would_block());
+        let mut dr = decoder_from(all_data);
+        while dr.next().is_some() {}
+        let stats = dr.stats();
+        assert_eq!(stats.bytes_read, 20);
+        assert_eq!(stats.would_block, 4);
+        // two empty bursts, one burst of a single byte and one burst of 19 bytes
+        assert_eq!(stats.bursts, [2, 1, 0, 0, 0, 1, 0, 0]);
+        dr.reset_stats();
+        assert_eq!(*dr.stats(), ReaderStats::default());
+
+        let mut stats = ReaderStats::default();
+        for _ in 0..1000 {
+            stats.record_byte();
+        }
+        stats.record_would_block();
+        assert_eq!(stats.bursts[7], 1);
+    }
+
+    #[test]
+    fn timestamps() {
+        let data = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let all_data = data
+            .into_iter()
+            .map(Ok)
+            .chain(once(Err(TestReaderErr::WouldBlock)))
+            .chain(data.into_iter().map(Ok));
+        let time = core::cell::Cell::new(0);
+        let mut dr = decoder_from(all_data).with_clock(|| {
+            time.set(time.get() + 1);
+            time.get()
+        });
+        assert_eq!(
+            dr.next_timestamped(),
+            Some(Ok((1, hex!("12345678").as_slice())))
+        );
+        // the clock isn't read for errors
+        assert_eq!(
+            dr.next_timestamped(),
+            Some(Err(ReadDecodedError::IoErr(TestReaderErr::WouldBlock, 0)))
+        );
+        assert_eq!(
+            dr.next_timestamped(),
+            Some(Ok((2, hex!("12345678").as_slice())))
+        );
+        assert_eq!(dr.next_timestamped(), None);
+        assert_eq!(time.get(), 2);
+    }
 }
@@ -1,6 +1,6 @@
 use core::borrow::Borrow;
 
-use crate::util::{Buffer, OutOfMemory, CRC_X25};
+use crate::util::{Buffer, CrcDigest, OutOfMemory, CRC_X25};
 
 struct Padding(u8);
 
@@ -26,15 +26,21 @@ enum EncoderState {
     End(i8),
 }
 
+// maximum number of bytes passed to the sink in `Encoder::write_to`
+const WRITE_CHUNK_SIZE: usize = 16;
+
 /// An iterator that encodes the bytes of an underlying iterator using the SML Transport Protocol v1.
 pub struct Encoder<I>
 where
     I: Iterator<Item = u8>,
 {
     state: EncoderState,
-    crc: crc::Digest<'static, u16>,
+    crc: CrcDigest,
     padding: Padding,
     iter: I,
+    // bytes that have been rejected by the sink in `write_to`
+    pending: [u8; WRITE_CHUNK_SIZE],
+    pending_len: u8,
 }
 
 impl<I> Encoder<I>
@@ -50,6 +56,65 @@ where
             crc,
             padding: Padding::new(),
             iter,
+            pending: [0; WRITE_CHUNK_SIZE],
+            pending_len: 0,
+        }
+    }
+
+    /// Writes the remaining encoded bytes to a fallible sink.
+    ///
+    /// The encoded bytes are passed to `sink` in chunks of at most 16 bytes. If
+    /// the sink returns an error (e.g. because a transmit FIFO is full), the error
+    /// is returned and the chunk is kept. Calling `write_to` again resumes
+    /// writing by passing the same chunk to the sink again. Therefore, the sink
+    /// must either accept a chunk entirely or reject it without side effects.
+    ///
+    /// Returns `Ok(())` once the whole encoded message has been written.
+    ///
+    /// Bytes already taken from the encoder using its `Iterator` implementation aren't written again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::transport::encode_streaming;
+    /// let bytes = [0x12, 0x34, 0x56, 0x78];
+    /// let expected = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b];
+    ///
+    /// let mut encoder = encode_streaming(bytes);
+    /// let mut out = Vec::new();
+    /// let mut fifo_full = true;
+    /// let mut sink = |chunk: &[u8]| {
+    ///     // simulate a sink that is busy every other time
+    ///     fifo_full = !fifo_full;
+    ///     if fifo_full {
+    ///         return Err("busy");
+    ///     }
+    ///     out.extend_from_slice(chunk);
+    ///     Ok(())
+    /// };
+    /// while encoder.write_to(&mut sink).is_err() {
+    ///     // wait for the sink to become ready
+    /// }
+    /// assert_eq!(out, expected);
+    /// ```
+    pub fn write_to<E>(&mut self, sink: &mut impl FnMut(&[u8]) -> Result<(), E>) -> Result<(), E> {
+        loop {
+            if self.pending_len == 0 {
+                while (self.pending_len as usize) < WRITE_CHUNK_SIZE {
+                    match self.next() {
+                        Some(b) => {
+                            self.pending[self.pending_len as usize] = b;
+                            self.pending_len += 1;
+                        }
+                        None => break,
+                    }
+                }
+                if self.pending_len == 0 {
+                    return Ok(());
+                }
+            }
+            sink(&self.pending[..self.pending_len as usize])?;
+            self.pending_len = 0;
         }
     }
 
@@ -120,10 +185,10 @@ where
                         let crc_bytes = self.crc.clone().finalize().to_le_bytes();
                         crc_bytes[(n - 6) as usize]
                     }
-                    8 => {
+                    // the end sequence is complete (`n == 8`)
+                    _ => {
                         return None;
                     }
-                    _ => unreachable!(),
                 };
                 (Some(out), End(n + 1))
             }
@@ -133,6 +198,31 @@ where
     }
 }
 
+/// Returns the maximum length of the transmission produced by [`encode`] for
+/// `payload_len` bytes of data.
+///
+/// Besides the start and end sequences (16 bytes) and up to three padding bytes,
+/// every `1b1b1b1b` sequence in the data is escaped by four additional bytes. Use
+/// this function to size the buffer passed to `encode`. Version 2 transmissions (see
+/// [`encode_v2`](super::encode_v2)) need 12 additional bytes per parameter.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{transport::{encode, max_encoded_len}, util::ArrayBuf};
+/// let data = [0x1b; 8];
+/// let encoded = encode::<ArrayBuf<{ max_encoded_len(8) }>>(&data).unwrap();
+/// assert_eq!(encoded.len(), 32);
+/// ```
+#[must_use]
+pub const fn max_encoded_len(payload_len: usize) -> usize {
+    let num_escapes = payload_len / 4;
+    16usize
+        .saturating_add(payload_len)
+        .saturating_add(num_escapes.saturating_mul(4))
+        .saturating_add(3)
+}
+
 /// Takes a slice of bytes as input and returns a buffer containing the encoded message.
 ///
 /// Returns `Err(())` when the buffer can't be grown to hold the entire output.
@@ -168,18 +258,26 @@ where
 /// assert!(encoded.is_ok());
 /// assert_eq!(&*encoded.unwrap(), &expected);
 ///
-/// // encoding returns `Err(())` if the encoded message does not fit into the vector
+/// // encoding returns `Err(OutOfMemory)` if the encoded message does not fit into the vector
 /// let encoded = encode::<ArrayBuf<19>>(&bytes);
-/// assert_eq!(encoded, Err(OutOfMemory));
+/// assert_eq!(encoded, Err(OutOfMemory { needed: 20, available: 19 }));
 /// ```
 ///
 pub fn encode<B: Buffer>(
     iter: impl IntoIterator<Item = impl Borrow<u8>>,
+) -> Result<B, OutOfMemory> {
+    encode_with_header(&[0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01], iter)
+}
+
+// encodes `iter` after the given start and parameter sequences (length must be a multiple of 4)
+pub(super) fn encode_with_header<B: Buffer>(
+    header: &[u8],
+    iter: impl IntoIterator<Item = impl Borrow<u8>>,
 ) -> Result<B, OutOfMemory> {
     let mut res: B = Default::default();
 
     // start escape sequence
-    res.extend_from_slice(&[0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01])?;
+    res.extend_from_slice(header)?;
 
     // encode data
     let mut num_1b = 0;
@@ -230,6 +328,7 @@ pub fn encode_streaming(
 
 #[cfg(test)]
 mod tests {
+    #[cfg(feature = "alloc")]
     use crate::transport::decode;
 
     use super::*;
@@ -315,6 +414,39 @@ mod tests {
         );
     }
 
+    fn test_write_to(bytes: &[u8], busy_pattern: &[bool]) {
+        let expected = encode_streaming(bytes).collect::<crate::util::ArrayBuf<256>>();
+        let mut out = crate::util::ArrayBuf::<256>::default();
+        let mut busy = busy_pattern.iter().copied().cycle();
+        let mut sink = |chunk: &[u8]| {
+            assert!(!chunk.is_empty() && chunk.len() <= WRITE_CHUNK_SIZE);
+            if busy.next().unwrap() {
+                return Err(());
+            }
+            out.extend_from_slice(chunk).map_err(|_| ())
+        };
+        let mut encoder = encode_streaming(bytes);
+        while encoder.write_to(&mut sink).is_err() {}
+        // writing again after completion doesn't produce any output
+        assert_eq!(encoder.write_to(&mut |_: &[u8]| Err(())), Ok(()));
+        compare_encoded_bytes(&expected, &out);
+    }
+
+    #[test]
+    fn write_to() {
+        let escapes_across_chunks = hex!("12345678 12345678 1b1b1b1b 1b1b1b1b 1b1b1b");
+        for data in [
+            &hex!("")[..],
+            &hex!("12345678"),
+            &hex!("121b1b1b1b"),
+            &escapes_across_chunks,
+        ] {
+            test_write_to(data, &[false]);
+            test_write_to(data, &[true, false]);
+            test_write_to(data, &[true, true, false, false]);
+        }
+    }
+
     #[test]
     fn ending_with_1b_no_padding() {
         test_encoding(
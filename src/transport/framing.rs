@@ -0,0 +1,217 @@
+//! Support for outer framing layers wrapping the SML transport protocol.
+//!
+//! Some gateways don't send the SML transport protocol directly but embed it into
+//! another framing layer (e.g. a proprietary byte-stuffing or length-prefixed
+//! packets). Such layers can be removed by implementing [`FrameLayer`] and using a
+//! [`FramedDecoder`], which passes the de-framed bytes to the SML transport decoder.
+//!
+//! [`LengthPrefixed`] is an implementation for a simple length-prefixed framing.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{transport::framing::{FramedDecoder, LengthPrefixed}, util::ArrayBuf};
+//! // an SML transmission split into two length-prefixed frames
+//! let bytes = [
+//!     0x00, 0x08, 0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01,
+//!     0x00, 0x0c, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b,
+//! ];
+//!
+//! let mut decoder = FramedDecoder::<_, ArrayBuf<20>>::new(LengthPrefixed::new());
+//! let mut num_decoded = 0;
+//! for b in bytes {
+//!     if let Ok(Some(decoded)) = decoder.push_byte(b) {
+//!         assert_eq!(decoded, [0x12, 0x34, 0x56, 0x78]);
+//!         num_decoded += 1;
+//!     }
+//! }
+//! assert_eq!(num_decoded, 1);
+//! ```
+
+use core::{convert::Infallible, fmt};
+
+use super::{DecodeErr, Decoder};
+use crate::util::Buffer;
+
+/// An outer framing layer that is removed before the SML transport protocol is decoded.
+pub trait FrameLayer {
+    /// Error type returned when the framing is invalid
+    type Error;
+
+    /// Pushes a raw byte into the layer.
+    ///
+    /// Returns `Ok(Some(b))` if the byte yields a de-framed byte `b` and `Ok(None)`
+    /// if the byte belongs to the framing itself (e.g. headers or escape bytes).
+    fn push_byte(&mut self, b: u8) -> Result<Option<u8>, Self::Error>;
+
+    /// Resets the layer to its initial state.
+    fn reset(&mut self);
+}
+
+/// Error returned by [`FramedDecoder::push_byte`]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FramedDecodeErr<E> {
+    /// Error of the outer framing layer
+    Framing(E),
+    /// Error while decoding the SML transport protocol
+    Decode(DecodeErr),
+}
+
+impl<E: fmt::Debug> fmt::Display for FramedDecodeErr<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramedDecodeErr::Framing(e) => write!(f, "invalid framing: {e:?}"),
+            FramedDecodeErr::Decode(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> core::error::Error for FramedDecodeErr<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            FramedDecodeErr::Framing(_) => None,
+            FramedDecodeErr::Decode(e) => Some(e),
+        }
+    }
+}
+
+/// Decoder removing an outer framing layer before decoding the SML transport protocol.
+///
+/// See the [module documentation](self) for more information.
+pub struct FramedDecoder<L: FrameLayer, B: Buffer> {
+    layer: L,
+    decoder: Decoder<B>,
+}
+
+impl<L: FrameLayer, B: Buffer> FramedDecoder<L, B> {
+    /// Constructs a new decoder using the framing layer `layer`.
+    pub fn new(layer: L) -> Self {
+        Self::from_parts(layer, Decoder::new())
+    }
+
+    /// Constructs a new decoder from a framing layer and an SML transport decoder.
+    ///
+    /// This allows configuring the transport decoder (e.g. using a custom buffer).
+    pub fn from_parts(layer: L, decoder: Decoder<B>) -> Self {
+        FramedDecoder { layer, decoder }
+    }
+
+    /// Consumes the decoder and returns the framing layer and the transport decoder.
+    pub fn into_parts(self) -> (L, Decoder<B>) {
+        (self.layer, self.decoder)
+    }
+
+    /// Pushes a raw byte `b` into the decoder and possibly returns a transmission or an error.
+    ///
+    /// If the framing layer returns an error, the transport decoder is reset.
+    pub fn push_byte(&mut self, b: u8) -> Result<Option<&[u8]>, FramedDecodeErr<L::Error>> {
+        match self.layer.push_byte(b) {
+            Ok(Some(b)) => self.decoder.push_byte(b).map_err(FramedDecodeErr::Decode),
+            Ok(None) => Ok(None),
+            Err(e) => {
+                self.decoder.reset();
+                Err(FramedDecodeErr::Framing(e))
+            }
+        }
+    }
+
+    /// Resets the decoder and returns an error if it contained an incomplete message.
+    pub fn finalize(&mut self) -> Option<DecodeErr> {
+        self.layer.reset();
+        self.decoder.finalize()
+    }
+}
+
+/// Framing consisting of frames with a 16-bit big-endian length header.
+///
+/// Each frame starts with the number of payload bytes followed by the payload.
+/// SML transmissions can be split across frames arbitrarily.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LengthPrefixed {
+    // number of header bytes read for the current frame (2 while reading the payload)
+    header_len: u8,
+    // remaining payload bytes of the current frame
+    remaining: u16,
+}
+
+impl LengthPrefixed {
+    /// Constructs a new length-prefixed framing layer.
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl FrameLayer for LengthPrefixed {
+    type Error = Infallible;
+
+    fn push_byte(&mut self, b: u8) -> Result<Option<u8>, Self::Error> {
+        match self.header_len {
+            0 => {
+                self.remaining = u16::from(b) << 8;
+                self.header_len = 1;
+                Ok(None)
+            }
+            1 => {
+                self.remaining |= u16::from(b);
+                // empty frames don't have a payload
+                self.header_len = if self.remaining == 0 { 0 } else { 2 };
+                Ok(None)
+            }
+            _ => {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.header_len = 0;
+                }
+                Ok(Some(b))
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::util::ArrayBuf;
+
+    fn frame(chunk_sizes: impl Iterator<Item = usize>, bytes: &[u8]) -> alloc::vec::Vec<u8> {
+        let mut res = alloc::vec::Vec::new();
+        let mut rest = bytes;
+        for size in chunk_sizes {
+            let size = size.min(rest.len());
+            res.extend_from_slice(&(size as u16).to_be_bytes());
+            res.extend_from_slice(&rest[..size]);
+            rest = &rest[size..];
+            if rest.is_empty() {
+                break;
+            }
+        }
+        res
+    }
+
+    #[test]
+    fn length_prefixed() {
+        let bytes = include_bytes!("../../sample.bin");
+        let expected = crate::transport::decode(bytes);
+        for sizes in [[1, 2, 3], [0, 7, 300], [1000, 1000, 1000]] {
+            let framed = frame(sizes.into_iter().cycle(), bytes);
+            let mut decoder = FramedDecoder::<_, ArrayBuf<1024>>::new(LengthPrefixed::new());
+            let mut decoded = alloc::vec::Vec::new();
+            for b in framed {
+                match decoder.push_byte(b) {
+                    Ok(None) => {}
+                    Ok(Some(msg)) => decoded.push(Ok(msg.to_vec())),
+                    Err(FramedDecodeErr::Decode(e)) => decoded.push(Err(e)),
+                    Err(FramedDecodeErr::Framing(e)) => match e {},
+                }
+            }
+            assert_eq!(decoder.finalize(), None);
+            assert_eq!(decoded, expected);
+        }
+    }
+}
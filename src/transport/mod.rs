@@ -1,7 +1,6 @@
 //! SML transport protocol (version 1).
 //!
-//! *Hint: This crate currently only implements version 1 of the SML transport
-//! protocol. If you need support for version 2, let me know!*
+//! *Hint: Version 2 of the SML transport protocol is implemented in the [`v2`] module.*
 //!
 //! # SML Transport Protocol - Version 1
 //!
@@ -25,15 +24,46 @@
 //! ## Decoding
 //!
 //! - `decode`: takes a sequence of bytes and decodes them into a vector of messages / errors. Requires feature "alloc".
+//! - `decode_ref`: same as `decode`, but passes borrowed messages / errors to a callback instead of collecting them. Requires feature "alloc".
+//! - `decode_parallel`: decodes large captures in parallel using `rayon`. Requires feature "rayon".
 //! - `decode_streaming`: takes a sequence of bytes and returns an iterator over the decoded messages / errors.
 //! - using `Decoder` directly: instantiate a `Decoder` manually, call `push_byte()` on it when data becomes available. Call `finalize()` when all data has been pushed.
+//! - using [`framing::FramedDecoder`]: removes an additional outer framing layer before decoding.
+//! - using [`sans_io::Machine`]: the state machine underlying `Decoder` that writes decoded bytes into a caller-provided sink.
+//!
+//! As the parsers in [`parser`](crate::parser), the decoders never panic, regardless
+//! of their input. Malformed input results in a [`DecodeErr`].
+
+#![cfg_attr(
+    not(test),
+    deny(
+        clippy::panic,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::unreachable
+    )
+)]
 
+mod async_reader;
 mod decode;
 mod decoder_reader;
 mod encode;
+pub mod framing;
+pub mod sans_io;
+pub mod v2;
+pub mod vectors;
 
+pub use async_reader::AsyncDecoderReader;
+#[cfg(feature = "rayon")]
+pub use decode::decode_parallel;
+#[cfg(feature = "alloc")]
+pub use decode::{decode, decode_base64, decode_hex, decode_ref};
+pub use decode::{
+    decode_streaming, recommended_buffer_size, DecodeErr, DecodeIterator, Decoder, PushBytes,
+};
+pub use decoder_reader::{DecoderReader, DecoderReaderBuilder, ReadDecodedError, ReaderStats};
+pub use encode::{encode, encode_streaming, max_encoded_len, Encoder};
+pub use sans_io::FrameInfo;
 #[cfg(feature = "alloc")]
-pub use decode::decode;
-pub use decode::{decode_streaming, DecodeErr, DecodeIterator, Decoder};
-pub use decoder_reader::{DecoderReader, ReadDecodedError};
-pub use encode::{encode, encode_streaming, Encoder};
+pub use v2::decode_v2;
+pub use v2::{decode_streaming_v2, encode_v2, DecoderV2};
@@ -0,0 +1,1027 @@
+//! Sans-IO core of the transport protocol decoder.
+//!
+//! [`Machine`] is the state machine used by [`Decoder`](super::Decoder),
+//! [`DecoderV2`](super::DecoderV2) and [`DecoderReader`](super::DecoderReader). It doesn't perform any IO and doesn't own
+//! the memory that decoded bytes are written into. Instead, the caller pushes bytes
+//! into the machine and provides a [`Sink`] that receives the decoded bytes. This
+//! allows integrations that don't fit the owned-buffer model of `Decoder`, e.g.
+//! switching between DMA buffers or pushing bytes from an interrupt handler.
+//!
+//! The machine only ever appends bytes to the sink or clears it. After
+//! [`Machine::push_byte`] returned [`Event::MessageComplete`], the sink contains
+//! exactly the decoded message. The sink is cleared before the next message is
+//! written into it and whenever an error occurs.
+//!
+//! # Snapshots
+//!
+//! The state of a machine can be exported using [`Machine::snapshot`] and restored
+//! using [`Machine::restore`]. Together with a sink located in memory that survives a
+//! reset (e.g. external battery-backed RAM), this allows continuing a partially
+//! received transmission after a reboot. Snapshots can be taken between any two
+//! calls to [`Machine::push_byte`].
+//!
+//! Limitations:
+//! - The snapshot doesn't contain the sink. After restoring, the sink must contain
+//!   exactly the bytes it contained when the snapshot was taken.
+//! - Only the transport layer state is covered. The parsers work on complete
+//!   transmissions and don't need to be restored.
+//! - Bytes transmitted by the meter while the device wasn't running are missing. In
+//!   this case, the transmission is discarded with a checksum error as usual.
+//! - The snapshot format is versioned. Snapshots taken by a different version of
+//!   sml-rs may be rejected with [`SnapshotError::UnsupportedVersion`].
+
+use core::fmt;
+
+use crate::util::{
+    crc_from_state, crc_state, Buffer, CrcDigest, CrcMismatch, OutOfMemory, CRC_X25,
+};
+
+use super::{
+    v2::{self, Params},
+    DecodeErr,
+};
+
+/// Destination of the bytes decoded by [`Machine`].
+///
+/// This trait is implemented for all [`Buffer`] types and can be implemented for
+/// custom types as well.
+pub trait Sink {
+    /// Appends a decoded byte.
+    ///
+    /// Returns `Err` if the byte could not be stored. The machine then reports
+    /// [`DecodeErr::OutOfMemory`] and discards the current message.
+    fn push(&mut self, b: u8) -> Result<(), OutOfMemory>;
+
+    /// Removes all bytes of the current message.
+    fn clear(&mut self);
+
+    /// Removes the last `n` bytes of the current message.
+    ///
+    /// Only used for removing nonzero padding bytes (see [`Machine::with_lenient_padding`]).
+    /// Returns `false` if the bytes couldn't be removed. The default implementation
+    /// always returns `false`, so such messages are rejected.
+    fn remove_last(&mut self, n: usize) -> bool {
+        let _ = n;
+        false
+    }
+}
+
+impl<B: Buffer> Sink for B {
+    fn push(&mut self, b: u8) -> Result<(), OutOfMemory> {
+        Buffer::push(self, b)
+    }
+
+    fn clear(&mut self) {
+        Buffer::clear(self)
+    }
+
+    fn remove_last(&mut self, n: usize) -> bool {
+        let Some(len) = self.len().checked_sub(n) else {
+            return false;
+        };
+        self.truncate(len);
+        true
+    }
+}
+
+/// Event returned by [`Machine::push_byte`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// The byte has been consumed. More bytes are necessary to complete a message.
+    NeedMoreBytes,
+    /// A complete and valid message has been written into the sink.
+    MessageComplete,
+}
+
+/// Metadata of a decoded transmission.
+///
+/// Returned by [`Machine::frame_info`] after a transmission has been decoded successfully.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// length of the encoded transmission including the start and end sequences
+    pub raw_len: usize,
+    /// number of escaped `1b1b1b1b` sequences contained in the data
+    pub num_escape_sequences: u32,
+    /// number of padding bytes
+    pub num_padding_bytes: u8,
+}
+
+/// Error returned by [`Machine::restore`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot doesn't have the expected length
+    InvalidLength,
+    /// The snapshot was taken by an incompatible version of sml-rs
+    UnsupportedVersion(u8),
+    /// The snapshot doesn't describe a valid state
+    InvalidState,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidLength => f.write_str("decoder snapshot has an invalid length"),
+            SnapshotError::UnsupportedVersion(v) => {
+                write!(f, "unsupported decoder snapshot version {v}")
+            }
+            SnapshotError::InvalidState => {
+                f.write_str("decoder snapshot doesn't describe a valid state")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SnapshotError {}
+
+const SNAPSHOT_VERSION: u8 = 4;
+
+const START_SEQ: [u8; 8] = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01];
+
+#[derive(Debug)]
+enum DecodeState {
+    LookingForMessageStart {
+        num_discarded_bytes: usize,
+        num_init_seq_bytes: u8,
+    },
+    ParsingNormal,
+    ParsingEscChars(u8),
+    ParsingEscPayload {
+        step: u8,
+        payload: [u8; 4],
+    },
+    // value of a v2 parameter (`kind` is the payload byte of its escape sequence)
+    ParsingParam {
+        kind: u8,
+        step: u8,
+        value: [u8; 4],
+    },
+    Done,
+}
+
+/// State machine decoding the SML transport protocol.
+///
+/// [`Machine::new`] decodes version 1, [`Machine::new_v2`] decodes
+/// [version 2](super::v2) of the transport protocol. See the
+/// [module documentation](self) for more information.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::transport::sans_io::{Event, Machine};
+/// # use sml_rs::util::ArrayBuf;
+/// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b];
+///
+/// let mut machine = Machine::new();
+/// let mut buf = ArrayBuf::<8>::default();
+/// for b in bytes {
+///     if let Ok(Event::MessageComplete) = machine.push_byte(&mut buf, b) {
+///         assert_eq!(&*buf, &[0x12, 0x34, 0x56, 0x78]);
+///     }
+/// }
+/// assert_eq!(machine.finalize(&mut buf), None);
+/// ```
+pub struct Machine {
+    // the number of bytes that were read out of the byte source
+    raw_msg_len: usize,
+    // the number of escape sequences in the data of the current transmission
+    num_escape_sequences: u32,
+    // the number of padding bytes of the last transmission (only valid in the `Done` state)
+    num_padding_bytes: u8,
+    crc: CrcDigest,
+    state: DecodeState,
+    // the number of zero bytes that weren't written into the buffer
+    // immediately because they could be padding bytes
+    zero_cache: u8,
+    // whether start sequences containing a single corrupted byte are accepted
+    fuzzy_resync: bool,
+    // the last eight bytes read while looking for a start sequence (fuzzy resync only)
+    start_window: u64,
+    // whether the bytes in `start_window` (excluding the latest byte) form a start
+    // sequence with a single corrupted byte (fuzzy resync only)
+    fuzzy_start_pending: bool,
+    // whether transmissions with nonzero padding bytes are accepted
+    lenient_padding: bool,
+    // the number of transmissions with nonzero padding bytes that have been accepted
+    num_tolerated_padding: u32,
+    // whether version 2 of the transport protocol is decoded
+    v2: bool,
+    // the transmission parameters of the current transmission (v2 only)
+    params: Params,
+    // whether the next escape sequence may be a parameter sequence (v2 only)
+    params_allowed: bool,
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Self {
+            raw_msg_len: Default::default(),
+            num_escape_sequences: 0,
+            num_padding_bytes: 0,
+            crc: CRC_X25.digest(),
+            state: DecodeState::LookingForMessageStart {
+                num_discarded_bytes: 0,
+                num_init_seq_bytes: 0,
+            },
+            zero_cache: 0,
+            fuzzy_resync: false,
+            start_window: 0,
+            fuzzy_start_pending: false,
+            lenient_padding: false,
+            num_tolerated_padding: 0,
+            v2: false,
+            params: Params::default(),
+            params_allowed: false,
+        }
+    }
+}
+
+impl Machine {
+    /// Constructs a new state machine.
+    #[must_use]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Constructs a new state machine decoding version 2 of the transport protocol.
+    ///
+    /// Version 1 transmissions are discarded. The transmission parameters of a decoded
+    /// transmission are returned by [`params`](Self::params).
+    #[must_use]
+    pub fn new_v2() -> Self {
+        Machine {
+            v2: true,
+            ..Default::default()
+        }
+    }
+
+    /// Enables or disables fuzzy resynchronization (disabled by default).
+    ///
+    /// See [`Decoder::with_fuzzy_resync`](crate::transport::Decoder::with_fuzzy_resync).
+    #[must_use]
+    pub fn with_fuzzy_resync(mut self, enabled: bool) -> Self {
+        self.fuzzy_resync = enabled;
+        self
+    }
+
+    /// Enables or disables accepting nonzero padding bytes (disabled by default).
+    ///
+    /// See [`Decoder::with_lenient_padding`](crate::transport::Decoder::with_lenient_padding).
+    #[must_use]
+    pub fn with_lenient_padding(mut self, enabled: bool) -> Self {
+        self.lenient_padding = enabled;
+        self
+    }
+
+    /// Returns the number of transmissions with nonzero padding bytes that have been accepted.
+    ///
+    /// The counter is only incremented if lenient padding is enabled. It isn't reset
+    /// by [`reset`](Self::reset) and isn't part of snapshots.
+    #[must_use]
+    pub fn num_tolerated_padding(&self) -> u32 {
+        self.num_tolerated_padding
+    }
+
+    /// Pushes a byte `b` into the state machine and advances its state.
+    ///
+    /// Decoded bytes are written into `sink`. Returns
+    /// - `Ok(Event::MessageComplete)` if a complete and valid message has been written into `sink`.
+    /// - `Ok(Event::NeedMoreBytes)` if more bytes are necessary to complete a message.
+    /// - `Err(_)` if an error occurred. In this case, `sink` has been cleared.
+    pub fn push_byte(&mut self, sink: &mut impl Sink, b: u8) -> Result<Event, DecodeErr> {
+        use DecodeState::*;
+        // saturating, as junk before a start sequence can exceed `usize::MAX` bytes on small targets
+        self.raw_msg_len = self.raw_msg_len.saturating_add(1);
+        let start_seq = self.start_seq();
+        match self.state {
+            LookingForMessageStart { .. } if self.fuzzy_resync => {
+                return self.push_byte_fuzzy_start(sink, b);
+            }
+            LookingForMessageStart {
+                ref mut num_discarded_bytes,
+                ref mut num_init_seq_bytes,
+            } => {
+                if b == start_seq[usize::from(*num_init_seq_bytes)] {
+                    *num_init_seq_bytes += 1;
+                } else {
+                    *num_discarded_bytes =
+                        num_discarded_bytes.saturating_add(1 + usize::from(*num_init_seq_bytes));
+                    *num_init_seq_bytes = 0;
+                }
+                if *num_init_seq_bytes == 8 {
+                    let num_discarded_bytes = *num_discarded_bytes;
+                    self.start_message();
+                    if num_discarded_bytes > 0 {
+                        return Err(DecodeErr::DiscardedBytes(num_discarded_bytes));
+                    }
+                }
+            }
+            ParsingNormal => {
+                self.crc.update(&[b]);
+                if b == 0x1b {
+                    // this could be the first byte of an escape sequence
+                    self.state = ParsingEscChars(1);
+                } else {
+                    // regular data
+                    self.push(sink, b)?;
+                }
+            }
+            ParsingEscChars(n) => {
+                self.crc.update(&[b]);
+                if b != 0x1b {
+                    // push previous 0x1b bytes as they didn't belong to an escape sequence
+                    for _ in 0..n {
+                        self.push(sink, 0x1b)?;
+                    }
+                    // push current byte
+                    self.push(sink, b)?;
+                    // continue in regular parsing state
+                    self.state = ParsingNormal;
+                } else if n == 3 {
+                    // this is the fourth 0x1b byte, so we're seeing an escape sequence.
+                    // continue by parsing the escape sequence's payload.
+
+                    self.state = ParsingEscPayload {
+                        step: 0,
+                        payload: Default::default(),
+                    };
+                } else {
+                    self.state = ParsingEscChars(n + 1);
+                }
+            }
+            ParsingEscPayload { step, mut payload } => {
+                payload[step as usize] = b;
+                if step < 3 {
+                    self.state = ParsingEscPayload {
+                        step: step + 1,
+                        payload,
+                    };
+                } else {
+                    // `payload` contains the escape sequence payload
+                    if payload == [0x1b, 0x1b, 0x1b, 0x1b] {
+                        // escape sequence in user data
+
+                        self.crc.update(&payload);
+                        self.num_escape_sequences = self.num_escape_sequences.saturating_add(1);
+
+                        // push escape sequence bytes
+                        for b in payload {
+                            self.push(sink, b)?;
+                        }
+
+                        // nothing to do here as the input has already been added to the buffer (see above)
+                        self.state = ParsingNormal;
+                    } else if payload[..] == start_seq[4..] {
+                        // another transmission start
+
+                        // ignore everything that has previously been read and start reading a new transmission
+                        let ignored_bytes = self.raw_msg_len - 8;
+                        self.zero_cache = 0;
+                        sink.clear();
+                        self.start_message();
+                        return Err(DecodeErr::DiscardedBytes(ignored_bytes));
+                    } else if self.params_allowed
+                        && matches!(payload[0], v2::TIMEOUT | v2::BLOCK_SIZE)
+                        && payload.iter().all(|x| *x == payload[0])
+                    {
+                        // v2 parameter sequence, followed by the 4-byte value of the parameter
+                        self.crc.update(&payload);
+                        self.state = ParsingParam {
+                            kind: payload[0],
+                            step: 0,
+                            value: [0; 4],
+                        };
+                    } else if payload[0] == 0x1a {
+                        // end sequence (layout: [0x1a, num_padding_bytes, crc, crc])
+
+                        // check number of padding bytes
+                        let num_padding_bytes = payload[1];
+
+                        // compute and compare checksum
+                        let read_crc = u16::from_le_bytes([payload[2], payload[3]]);
+                        // update the crc, but exclude the last two bytes (which contain the crc itself)
+                        self.crc.update(&[payload[0], payload[1]]);
+                        // get the calculated crc and reset it afterwards
+                        let calculated_crc = {
+                            let mut crc = CRC_X25.digest();
+                            core::mem::swap(&mut crc, &mut self.crc);
+                            crc.finalize()
+                        };
+
+                        // check alignment (end marker needs to have 4-byte alignment)
+                        let misaligned = self.raw_msg_len % 4 != 0;
+
+                        // check if padding is larger than the message length
+                        let padding_too_large = num_padding_bytes > 3;
+                        // hint: start esc + end esc = 16 bytes
+                        let padding_larger_than_msg_size =
+                            self.raw_msg_len < (num_padding_bytes as usize) + 16;
+
+                        // check whether padding bytes are correct
+                        let invalid_padding_bytes = num_padding_bytes > self.zero_cache;
+                        // in lenient mode, nonzero padding bytes are removed from the sink
+                        let tolerated = read_crc == calculated_crc
+                            && !misaligned
+                            && !padding_too_large
+                            && !padding_larger_than_msg_size
+                            && invalid_padding_bytes
+                            && self.lenient_padding
+                            && sink.remove_last(usize::from(num_padding_bytes - self.zero_cache));
+                        if tolerated {
+                            self.num_tolerated_padding =
+                                self.num_tolerated_padding.saturating_add(1);
+                            self.zero_cache = num_padding_bytes;
+                        }
+
+                        if read_crc != calculated_crc
+                            || misaligned
+                            || padding_too_large
+                            || padding_larger_than_msg_size
+                            || (invalid_padding_bytes && !tolerated)
+                        {
+                            self.reset(sink);
+                            return Err(DecodeErr::InvalidMessage {
+                                checksum_mismatch: (read_crc != calculated_crc).then_some(
+                                    CrcMismatch {
+                                        expected: read_crc,
+                                        computed: calculated_crc,
+                                    },
+                                ),
+                                end_esc_misaligned: misaligned,
+                                num_padding_bytes,
+                                invalid_padding_bytes,
+                            });
+                        }
+
+                        // remove padding bytes
+                        self.zero_cache -= num_padding_bytes;
+                        self.num_padding_bytes = num_padding_bytes;
+
+                        self.flush(sink)?;
+
+                        self.set_done();
+
+                        return Ok(Event::MessageComplete);
+                    } else {
+                        // special case of message ending with incomplete escape sequence
+                        // Explanation:
+                        // when a message ends with 1-3 0x1b bytes and there's no padding bytes,
+                        // we end up in this branch because there's four consecutive 0x1b bytes
+                        // that aren't followed by a known escape sequence. The problem is that
+                        // the first 1-3 0x1b bytes belong to the message, not to the end escape
+                        // code.
+                        // Example:
+                        //                  detected as escape sequence
+                        //                  vvvv vvvv
+                        // Message: ... 12341b1b 1b1b1b1b 1a00abcd
+                        //                       ^^^^^^^^
+                        //                       real escape sequence
+                        //
+                        // The solution for this issue is to check whether the read escape code
+                        // isn't aligned to a 4-byte boundary and followed by an aligned end
+                        // escape sequence (`1b1b1b1b 1a...`).
+                        // If that's the case, simply reset the parser state by 1-3 steps. This
+                        // will parse the 0x1b bytes in the message as regular bytes and check
+                        // for the end escape code at the right position.
+                        let bytes_until_alignment = (4 - (self.raw_msg_len % 4)) % 4;
+                        if bytes_until_alignment > 0
+                            && payload[..bytes_until_alignment].iter().all(|x| *x == 0x1b)
+                            && payload[bytes_until_alignment] == 0x1a
+                        {
+                            self.crc.update(&payload[..bytes_until_alignment]);
+                            // push bytes that are in payload but belong to the message
+                            for _ in 0..bytes_until_alignment {
+                                self.push(sink, 0x1b)?;
+                            }
+                            // shift the remaining bytes to the beginning of `payload`
+                            payload.copy_within(bytes_until_alignment.., 0);
+                            self.state = ParsingEscPayload {
+                                step: 4 - bytes_until_alignment as u8,
+                                payload,
+                            };
+                            return Ok(Event::NeedMoreBytes);
+                        }
+
+                        // invalid escape sequence
+
+                        self.reset(sink);
+                        return Err(DecodeErr::InvalidEsc(payload));
+                    }
+                }
+            }
+            ParsingParam {
+                kind,
+                step,
+                mut value,
+            } => {
+                self.crc.update(&[b]);
+                value[usize::from(step)] = b;
+                if step < 3 {
+                    self.state = ParsingParam {
+                        kind,
+                        step: step + 1,
+                        value,
+                    };
+                } else {
+                    let value = Some(u32::from_be_bytes(value));
+                    if kind == v2::TIMEOUT {
+                        self.params.timeout = value;
+                    } else {
+                        self.params.block_size = value;
+                    }
+                    self.state = ParsingNormal;
+                }
+            }
+            Done => {
+                // reset and let's go again
+                self.reset(sink);
+                return self.push_byte(sink, b);
+            }
+        }
+        Ok(Event::NeedMoreBytes)
+    }
+
+    // Looks for a start sequence that may contain a single corrupted byte.
+    //
+    // A start sequence with one corrupted byte isn't accepted immediately, because
+    // the following byte could complete an exact start sequence (e.g. when the
+    // data is preceded by an additional `0x1b` byte). Instead, it is accepted when
+    // the next byte is read and doesn't complete an exact start sequence. That byte
+    // is then processed as the first byte of the message.
+    fn push_byte_fuzzy_start(&mut self, sink: &mut impl Sink, b: u8) -> Result<Event, DecodeErr> {
+        self.start_window = (self.start_window << 8) | u64::from(b);
+        let num_mismatches = if self.raw_msg_len >= 8 {
+            self.start_window
+                .to_be_bytes()
+                .iter()
+                .zip(self.start_seq())
+                .filter(|(a, b)| **a != *b)
+                .count()
+        } else {
+            usize::MAX
+        };
+
+        if num_mismatches == 0 {
+            let num_discarded_bytes = self.raw_msg_len - 8;
+            self.start_message();
+            if num_discarded_bytes > 0 {
+                return Err(DecodeErr::DiscardedBytes(num_discarded_bytes));
+            }
+        } else if self.fuzzy_start_pending {
+            let num_discarded_bytes = self.raw_msg_len - 9;
+            self.start_message();
+            // the current byte is the first byte of the message
+            let res = self.push_byte(sink, b);
+            if matches!(res, Ok(Event::NeedMoreBytes)) && num_discarded_bytes > 0 {
+                return Err(DecodeErr::DiscardedBytes(num_discarded_bytes));
+            }
+            return res;
+        } else if num_mismatches == 1 {
+            self.fuzzy_start_pending = true;
+        }
+        Ok(Event::NeedMoreBytes)
+    }
+
+    // switches into the state used for parsing the message after a start sequence
+    fn start_message(&mut self) {
+        self.state = DecodeState::ParsingNormal;
+        self.raw_msg_len = 8;
+        self.num_escape_sequences = 0;
+        self.start_window = 0;
+        self.fuzzy_start_pending = false;
+        self.crc = CRC_X25.digest();
+        self.crc.update(&self.start_seq());
+        self.params = Params::default();
+        self.params_allowed = self.v2;
+    }
+
+    // the start sequence of the decoded protocol version
+    fn start_seq(&self) -> [u8; 8] {
+        if self.v2 {
+            v2::START_SEQ
+        } else {
+            START_SEQ
+        }
+    }
+
+    /// Resets the state machine and returns an error if it contained an incomplete message.
+    pub fn finalize(&mut self, sink: &mut impl Sink) -> Option<DecodeErr> {
+        use DecodeState::*;
+        let res = match self.state {
+            LookingForMessageStart { .. } if self.raw_msg_len == 0 => None,
+            Done => None,
+            _ => Some(DecodeErr::DiscardedBytes(self.raw_msg_len)),
+        };
+        self.reset(sink);
+        res
+    }
+
+    /// Resets the state machine and returns the number of bytes that were discarded
+    pub fn reset(&mut self, sink: &mut impl Sink) -> usize {
+        let num_discarded = match self.state {
+            DecodeState::Done => 0,
+            _ => self.raw_msg_len,
+        };
+        self.state = DecodeState::LookingForMessageStart {
+            num_discarded_bytes: 0,
+            num_init_seq_bytes: 0,
+        };
+        sink.clear();
+        self.raw_msg_len = 0;
+        self.num_escape_sequences = 0;
+        self.num_padding_bytes = 0;
+        self.zero_cache = 0;
+        self.start_window = 0;
+        self.fuzzy_start_pending = false;
+        self.params = Params::default();
+        self.params_allowed = false;
+        num_discarded
+    }
+
+    // pushes bytes from the `zero_cache` into the output buffer
+    fn flush(&mut self, sink: &mut impl Sink) -> Result<(), DecodeErr> {
+        for _ in 0..self.zero_cache {
+            self.push_inner(sink, 0)?;
+        }
+        self.zero_cache = 0;
+        Ok(())
+    }
+
+    fn push(&mut self, sink: &mut impl Sink, b: u8) -> Result<(), DecodeErr> {
+        // parameter sequences are only allowed before the data
+        self.params_allowed = false;
+        if b == 0 {
+            if self.zero_cache <= 3 {
+                self.zero_cache += 1;
+            } else {
+                // directly push into the output buffer if there are already 3
+                // zero bytes in the cache. Padding cannot be larger than three
+                // and this makes sure that the zero_cache cannot grow infinitely.
+                self.push_inner(sink, b)?;
+            }
+        } else {
+            self.flush(sink)?;
+            self.push_inner(sink, b)?;
+        }
+        Ok(())
+    }
+
+    fn push_inner(&mut self, sink: &mut impl Sink, b: u8) -> Result<(), DecodeErr> {
+        if sink.push(b).is_err() {
+            self.reset(sink);
+            return Err(DecodeErr::OutOfMemory);
+        }
+        Ok(())
+    }
+
+    fn set_done(&mut self) {
+        self.state = DecodeState::Done;
+    }
+
+    /// Returns whether the last pushed byte completed a message.
+    ///
+    /// If that's the case, `sink` contains the decoded message.
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, DecodeState::Done)
+    }
+
+    /// Returns metadata of the decoded transmission.
+    ///
+    /// Like the decoded message, the metadata is available after [`push_byte`](Self::push_byte)
+    /// returned [`Event::MessageComplete`] and until the next byte is pushed. Returns `None` otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::transport::sans_io::{FrameInfo, Machine};
+    /// # use sml_rs::util::ArrayBuf;
+    /// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x00, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x01, 0x91, 0xa5];
+    ///
+    /// let mut machine = Machine::new();
+    /// let mut buf = ArrayBuf::<8>::default();
+    /// for b in bytes {
+    ///     assert_eq!(machine.frame_info(), None);
+    ///     machine.push_byte(&mut buf, b).unwrap();
+    /// }
+    /// let info = FrameInfo { raw_len: 20, num_escape_sequences: 0, num_padding_bytes: 1 };
+    /// assert_eq!(machine.frame_info(), Some(info));
+    /// ```
+    #[must_use]
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.is_done().then_some(FrameInfo {
+            raw_len: self.raw_msg_len,
+            num_escape_sequences: self.num_escape_sequences,
+            num_padding_bytes: self.num_padding_bytes,
+        })
+    }
+
+    /// Returns the transmission parameters of the decoded transmission.
+    ///
+    /// Available under the same conditions as [`frame_info`](Self::frame_info) if the
+    /// machine decodes version 2 of the transport protocol (see [`new_v2`](Self::new_v2)).
+    /// Returns `None` otherwise.
+    #[must_use]
+    pub fn params(&self) -> Option<Params> {
+        (self.v2 && self.is_done()).then_some(self.params)
+    }
+
+    /// Length of the snapshots returned by [`snapshot`](Self::snapshot).
+    pub const SNAPSHOT_LEN: usize = 40;
+
+    /// Exports the state of the machine.
+    ///
+    /// See the [module documentation](self#snapshots) for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sml_rs::transport::sans_io::{Event, Machine};
+    /// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b];
+    /// // the sink is assumed to be located in memory that survives the reboot
+    /// let mut sink = sml_rs::util::ArrayBuf::<8>::default();
+    ///
+    /// let mut machine = Machine::new();
+    /// for &b in &bytes[..10] {
+    ///     machine.push_byte(&mut sink, b).unwrap();
+    /// }
+    /// let snapshot = machine.snapshot();
+    ///
+    /// // ... reboot ...
+    ///
+    /// let mut machine = Machine::restore(&snapshot).unwrap();
+    /// for &b in &bytes[10..] {
+    ///     machine.push_byte(&mut sink, b).unwrap();
+    /// }
+    /// assert!(machine.is_done());
+    /// assert_eq!(&*sink, &[0x12, 0x34, 0x56, 0x78]);
+    /// ```
+    #[must_use]
+    pub fn snapshot(&self) -> [u8; Self::SNAPSHOT_LEN] {
+        use DecodeState::*;
+        let mut res = [0; Self::SNAPSHOT_LEN];
+        res[0] = SNAPSHOT_VERSION;
+        // state (bytes 1..8)
+        match self.state {
+            LookingForMessageStart {
+                num_discarded_bytes,
+                num_init_seq_bytes,
+            } => {
+                res[1] = 0;
+                res[2] = num_init_seq_bytes;
+                let num_discarded_bytes = u32::try_from(num_discarded_bytes).unwrap_or(u32::MAX);
+                res[3..7].copy_from_slice(&num_discarded_bytes.to_le_bytes());
+            }
+            ParsingNormal => res[1] = 1,
+            ParsingEscChars(n) => {
+                res[1] = 2;
+                res[2] = n;
+            }
+            ParsingEscPayload { step, payload } => {
+                res[1] = 3;
+                res[2] = step;
+                res[3..7].copy_from_slice(&payload);
+            }
+            Done => {
+                res[1] = 4;
+                res[2] = self.num_padding_bytes;
+            }
+            ParsingParam { kind, step, value } => {
+                res[1] = 5;
+                res[2] = step;
+                res[3..7].copy_from_slice(&value);
+                res[7] = kind;
+            }
+        }
+        res[8..16].copy_from_slice(&(self.raw_msg_len as u64).to_le_bytes());
+        res[16..18].copy_from_slice(&crc_state(&self.crc).to_le_bytes());
+        res[18] = self.zero_cache;
+        res[19] = u8::from(self.fuzzy_resync)
+            | u8::from(self.fuzzy_start_pending) << 1
+            | u8::from(self.lenient_padding) << 2
+            | u8::from(self.v2) << 3
+            | u8::from(self.params_allowed) << 4
+            | u8::from(self.params.timeout.is_some()) << 5
+            | u8::from(self.params.block_size.is_some()) << 6;
+        res[20..28].copy_from_slice(&self.start_window.to_le_bytes());
+        res[28..32].copy_from_slice(&self.num_escape_sequences.to_le_bytes());
+        res[32..36].copy_from_slice(&self.params.timeout.unwrap_or_default().to_le_bytes());
+        res[36..40].copy_from_slice(&self.params.block_size.unwrap_or_default().to_le_bytes());
+        res
+    }
+
+    /// Restores a machine from a snapshot returned by [`snapshot`](Self::snapshot).
+    ///
+    /// See the [module documentation](self#snapshots) for more information.
+    pub fn restore(snapshot: &[u8]) -> Result<Self, SnapshotError> {
+        use DecodeState::*;
+        let snapshot: &[u8; Self::SNAPSHOT_LEN] = snapshot
+            .try_into()
+            .map_err(|_| SnapshotError::InvalidLength)?;
+        if snapshot[0] != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot[0]));
+        }
+        let state = match snapshot[1] {
+            0 if snapshot[2] <= 8 => LookingForMessageStart {
+                num_discarded_bytes: usize::try_from(u32::from_le_bytes([
+                    snapshot[3],
+                    snapshot[4],
+                    snapshot[5],
+                    snapshot[6],
+                ]))
+                .unwrap_or(usize::MAX),
+                num_init_seq_bytes: snapshot[2],
+            },
+            1 => ParsingNormal,
+            2 if (1..=3).contains(&snapshot[2]) => ParsingEscChars(snapshot[2]),
+            3 if snapshot[2] <= 3 => ParsingEscPayload {
+                step: snapshot[2],
+                payload: [snapshot[3], snapshot[4], snapshot[5], snapshot[6]],
+            },
+            4 if snapshot[2] <= 3 => Done,
+            5 if snapshot[2] <= 3 && matches!(snapshot[7], v2::TIMEOUT | v2::BLOCK_SIZE) => {
+                ParsingParam {
+                    kind: snapshot[7],
+                    step: snapshot[2],
+                    value: [snapshot[3], snapshot[4], snapshot[5], snapshot[6]],
+                }
+            }
+            _ => return Err(SnapshotError::InvalidState),
+        };
+        let u64_at = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&snapshot[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let u32_at = |i: usize| {
+            u32::from_le_bytes([
+                snapshot[i],
+                snapshot[i + 1],
+                snapshot[i + 2],
+                snapshot[i + 3],
+            ])
+        };
+        let zero_cache = snapshot[18];
+        let flags = snapshot[19];
+        if zero_cache > 4 || flags > 0b111_1111 {
+            return Err(SnapshotError::InvalidState);
+        }
+        Ok(Machine {
+            raw_msg_len: usize::try_from(u64_at(8)).map_err(|_| SnapshotError::InvalidState)?,
+            num_escape_sequences: u32_at(28),
+            num_padding_bytes: if matches!(state, Done) {
+                snapshot[2]
+            } else {
+                0
+            },
+            crc: crc_from_state(u16::from_le_bytes([snapshot[16], snapshot[17]])),
+            state,
+            zero_cache,
+            fuzzy_resync: flags & 1 != 0,
+            start_window: u64_at(20),
+            fuzzy_start_pending: flags & 2 != 0,
+            lenient_padding: flags & 4 != 0,
+            num_tolerated_padding: 0,
+            v2: flags & 8 != 0,
+            params: Params {
+                timeout: (flags & 32 != 0).then(|| u32_at(32)),
+                block_size: (flags & 64 != 0).then(|| u32_at(36)),
+            },
+            params_allowed: flags & 16 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    // sink that only counts the bytes written into it
+    #[derive(Default)]
+    struct CountingSink {
+        len: usize,
+        num_clears: usize,
+    }
+
+    impl Sink for CountingSink {
+        fn push(&mut self, _b: u8) -> Result<(), OutOfMemory> {
+            self.len += 1;
+            Ok(())
+        }
+
+        fn clear(&mut self) {
+            self.len = 0;
+            self.num_clears += 1;
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn snapshot() {
+        let bytes = include_bytes!("../../sample.bin");
+        for fuzzy_resync in [false, true] {
+            let mut expected = alloc::vec::Vec::new();
+            let mut machine = Machine::new().with_fuzzy_resync(fuzzy_resync);
+            let mut sink = crate::util::VecBuf::default();
+            for &b in bytes {
+                let res = machine.push_byte(&mut sink, b);
+                expected.push((res, sink.clone()));
+            }
+
+            // snapshot and restore the machine after each byte
+            let mut machine = Machine::new().with_fuzzy_resync(fuzzy_resync);
+            let mut sink = crate::util::VecBuf::default();
+            for (&b, expected) in bytes.iter().zip(&expected) {
+                machine = Machine::restore(&machine.snapshot()).unwrap();
+                let res = machine.push_byte(&mut sink, b);
+                assert_eq!(&(res, sink.clone()), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn snapshot_long_junk() {
+        let mut sink = crate::util::ArrayBuf::<8>::default();
+        let mut machine = Machine::new();
+        for _ in 0..70_000 {
+            assert_eq!(machine.push_byte(&mut sink, 0x42), Ok(Event::NeedMoreBytes));
+        }
+        let mut machine = Machine::restore(&machine.snapshot()).unwrap();
+        let res = START_SEQ.map(|b| machine.push_byte(&mut sink, b));
+        assert_eq!(res[7], Err(DecodeErr::DiscardedBytes(70_000)));
+    }
+
+    #[test]
+    fn snapshot_errors() {
+        let snapshot = Machine::new().snapshot();
+        assert!(Machine::restore(&snapshot).is_ok());
+        assert_eq!(
+            Machine::restore(&snapshot[1..]).map(|_| ()),
+            Err(SnapshotError::InvalidLength)
+        );
+        let mut invalid = snapshot;
+        invalid[0] = 0;
+        assert_eq!(
+            Machine::restore(&invalid).map(|_| ()),
+            Err(SnapshotError::UnsupportedVersion(0))
+        );
+        let mut invalid = snapshot;
+        invalid[1] = 5;
+        assert_eq!(
+            Machine::restore(&invalid).map(|_| ()),
+            Err(SnapshotError::InvalidState)
+        );
+        let mut invalid = snapshot;
+        invalid[1] = 2;
+        assert_eq!(
+            Machine::restore(&invalid).map(|_| ()),
+            Err(SnapshotError::InvalidState)
+        );
+    }
+
+    #[test]
+    fn custom_sink() {
+        let bytes = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let mut machine = Machine::new();
+        let mut sink = CountingSink::default();
+        for b in &bytes[..bytes.len() - 1] {
+            assert_eq!(machine.push_byte(&mut sink, *b), Ok(Event::NeedMoreBytes));
+            assert!(!machine.is_done());
+        }
+        assert_eq!(
+            machine.push_byte(&mut sink, bytes[bytes.len() - 1]),
+            Ok(Event::MessageComplete)
+        );
+        assert!(machine.is_done());
+        assert_eq!(sink.len, 4);
+        assert_eq!(sink.num_clears, 0);
+        assert_eq!(machine.finalize(&mut sink), None);
+        assert_eq!(sink.len, 0);
+    }
+
+    #[test]
+    fn sink_full() {
+        struct FullSink;
+
+        impl Sink for FullSink {
+            fn push(&mut self, _b: u8) -> Result<(), OutOfMemory> {
+                Err(OutOfMemory {
+                    needed: 1,
+                    available: 0,
+                })
+            }
+
+            fn clear(&mut self) {}
+        }
+
+        let bytes = hex!("1b1b1b1b 01010101 12");
+        let mut machine = Machine::new();
+        let res: Result<(), _> = bytes
+            .iter()
+            .try_for_each(|b| machine.push_byte(&mut FullSink, *b).map(|_| ()));
+        assert_eq!(res, Err(DecodeErr::OutOfMemory));
+        assert!(!machine.is_done());
+    }
+}
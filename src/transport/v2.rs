@@ -0,0 +1,464 @@
+//! SML transport protocol (version 2).
+//!
+//! Version 2 of the SML Transport Protocol extends [version 1](super) by transmission
+//! parameters. A transmission consists of the following parts (numbers in hex):
+//!
+//! - **Start sequence**: `1b1b1b1b 02020202`
+//! - **Parameter sequences** (optional, directly after the start sequence):
+//!   - `1b1b1b1b 03030303 TTTTTTTT`: timeout in milliseconds (big endian)
+//!   - `1b1b1b1b 04040404 BBBBBBBB`: block size in bytes (big endian)
+//! - **Escaped data**, **padding** and **end sequence**: as in version 1
+//!
+//! The checksum covers the whole transmission including the start and parameter
+//! sequences. The parameters of a decoded transmission are available via
+//! [`DecoderV2::params`].
+//!
+//! The API mirrors the one of version 1: [`DecoderV2`] corresponds to
+//! [`Decoder`], `decode_v2` to `decode`, [`decode_streaming_v2`] to
+//! [`decode_streaming`](super::decode_streaming) and [`encode_v2`] to
+//! [`encode`](super::encode). The decoders only accept version 2 transmissions;
+//! version 1 transmissions are discarded. They are based on the same state machine
+//! as version 1, which decodes version 2 if constructed using [`Machine::new_v2`].
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{transport::{DecoderV2, encode_v2, v2::Params}, util::ArrayBuf};
+//! let params = Params { timeout: Some(1000), block_size: None };
+//! let encoded = encode_v2::<ArrayBuf<64>>(&[0x12, 0x34, 0x56, 0x78], params).unwrap();
+//!
+//! let mut decoder = DecoderV2::<ArrayBuf<64>>::new();
+//! let mut decoded = None;
+//! for &b in encoded.iter() {
+//!     if let Some(bytes) = decoder.push_byte(b).unwrap() {
+//!         decoded = Some(bytes.to_vec());
+//!     }
+//! }
+//! assert_eq!(decoded.as_deref(), Some([0x12, 0x34, 0x56, 0x78].as_slice()));
+//! assert_eq!(decoder.params(), Some(params));
+//! ```
+
+use core::borrow::Borrow;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::{
+    encode::encode_with_header, sans_io::Machine, DecodeErr, DecodeIterator, Decoder, FrameInfo,
+    PushBytes,
+};
+use crate::util::{Buffer, OutOfMemory};
+
+pub(super) const START_SEQ: [u8; 8] = [0x1b, 0x1b, 0x1b, 0x1b, 0x02, 0x02, 0x02, 0x02];
+// payload bytes of the parameter escape sequences
+pub(super) const TIMEOUT: u8 = 0x03;
+pub(super) const BLOCK_SIZE: u8 = 0x04;
+
+/// Transmission parameters of a version 2 transmission.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Params {
+    /// timeout in milliseconds
+    pub timeout: Option<u32>,
+    /// block size in bytes
+    pub block_size: Option<u32>,
+}
+
+/// Decoder for sml transport v2.
+///
+/// Works like [`Decoder`] and uses the same state machine
+/// ([`Machine::new_v2`]). See the [module documentation](self) for more information.
+pub struct DecoderV2<B: Buffer> {
+    decoder: Decoder<B>,
+}
+
+impl<B: Buffer> Default for DecoderV2<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Buffer> DecoderV2<B> {
+    /// Constructs a new decoder.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_buf(B::default())
+    }
+
+    /// Constructs a new decoder using an existing buffer `buf`.
+    ///
+    /// The buffer is cleared before use.
+    pub fn from_buf(buf: B) -> Self {
+        DecoderV2 {
+            decoder: Decoder::with_machine(buf, Machine::new_v2()),
+        }
+    }
+
+    /// Consumes the decoder and returns the internal buffer.
+    #[must_use]
+    pub fn into_buf(self) -> B {
+        self.decoder.into_buf()
+    }
+
+    /// Enables or disables fuzzy resynchronization (disabled by default).
+    ///
+    /// See [`Decoder::with_fuzzy_resync`]. The start sequence of version 2 is
+    /// `1b1b1b1b 02020202`.
+    #[must_use]
+    pub fn with_fuzzy_resync(mut self, enabled: bool) -> Self {
+        self.decoder = self.decoder.with_fuzzy_resync(enabled);
+        self
+    }
+
+    /// Enables or disables accepting nonzero padding bytes (disabled by default).
+    ///
+    /// See [`Decoder::with_lenient_padding`].
+    #[must_use]
+    pub fn with_lenient_padding(mut self, enabled: bool) -> Self {
+        self.decoder = self.decoder.with_lenient_padding(enabled);
+        self
+    }
+
+    /// Returns the number of transmissions with nonzero padding bytes that have been accepted.
+    ///
+    /// See [`Decoder::num_tolerated_padding`].
+    #[must_use]
+    pub fn num_tolerated_padding(&self) -> u32 {
+        self.decoder.num_tolerated_padding()
+    }
+
+    /// Pushes a byte `b` into the decoder, advances the parser state and possibly returns
+    /// a transmission or an decoder error.
+    pub fn push_byte(&mut self, b: u8) -> Result<Option<&[u8]>, DecodeErr> {
+        self.decoder.push_byte(b)
+    }
+
+    /// Pushes a chunk of bytes into the decoder.
+    ///
+    /// See [`Decoder::push_bytes`].
+    pub fn push_bytes<'d, 'b>(&'d mut self, bytes: &'b [u8]) -> PushBytes<'d, 'b, B> {
+        self.decoder.push_bytes(bytes)
+    }
+
+    /// Returns the decoded message if the decoder currently holds a complete message.
+    ///
+    /// See [`Decoder::try_message`].
+    #[must_use]
+    pub fn try_message(&self) -> Option<&[u8]> {
+        self.decoder.try_message()
+    }
+
+    /// Returns metadata (length, escape sequences, padding) of the decoded message.
+    ///
+    /// See [`Decoder::frame_info`]. The raw length includes the parameter sequences.
+    #[must_use]
+    pub fn frame_info(&self) -> Option<FrameInfo> {
+        self.decoder.frame_info()
+    }
+
+    /// Returns the transmission parameters of the decoded message.
+    ///
+    /// Available under the same conditions as [`try_message`](Self::try_message).
+    #[must_use]
+    pub fn params(&self) -> Option<Params> {
+        self.decoder.machine().params()
+    }
+
+    /// Resets the decoder and returns an error if it contained an incomplete message.
+    pub fn finalize(&mut self) -> Option<DecodeErr> {
+        self.decoder.finalize()
+    }
+
+    /// Resets the decoder and returns the number of bytes that were discarded
+    pub fn reset(&mut self) -> usize {
+        self.decoder.reset()
+    }
+}
+
+/// Decodes a sequence of bytes containing version 2 transmissions.
+///
+/// Same as [`decode`](super::decode), but for version 2 of the transport protocol.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn decode_v2(
+    iter: impl IntoIterator<Item = impl Borrow<u8>>,
+) -> Vec<Result<Vec<u8>, DecodeErr>> {
+    let mut res = Vec::new();
+    let mut decode_iterator = decode_streaming_v2::<Vec<u8>>(iter);
+    while let Some(item) = decode_iterator.next() {
+        res.push(item.map(<[u8]>::to_vec));
+    }
+    res
+}
+
+/// Takes an iterator over bytes and returns an iterator that yields decoded version 2
+/// transmissions / decoding errors.
+///
+/// Same as [`decode_streaming`](super::decode_streaming), but for version 2 of the
+/// transport protocol.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{transport::{decode_streaming_v2, encode_v2, v2::Params}, util::ArrayBuf};
+/// let encoded = encode_v2::<ArrayBuf<64>>(&[0x12, 0x34, 0x56, 0x78], Params::default()).unwrap();
+///
+/// let mut decode_iterator = decode_streaming_v2::<ArrayBuf<10>>(encoded.iter());
+/// assert_eq!(decode_iterator.next(), Some(Ok([0x12, 0x34, 0x56, 0x78].as_slice())));
+/// assert_eq!(decode_iterator.next(), None);
+/// ```
+pub fn decode_streaming_v2<B: Buffer>(
+    iter: impl IntoIterator<Item = impl Borrow<u8>>,
+) -> DecodeIterator<B, impl Iterator<Item = u8>> {
+    DecodeIterator::from_decoder(
+        DecoderV2::new().decoder,
+        iter.into_iter().map(|x| *x.borrow()),
+    )
+}
+
+/// Encodes a sequence of bytes as version 2 transmission with the given parameters.
+///
+/// Same as [`encode`](super::encode), but for version 2 of the transport protocol.
+/// Parameters that are `None` aren't transmitted.
+pub fn encode_v2<B: Buffer>(
+    iter: impl IntoIterator<Item = impl Borrow<u8>>,
+    params: Params,
+) -> Result<B, OutOfMemory> {
+    let mut header = [0u8; 32];
+    header[..8].copy_from_slice(&START_SEQ);
+    let mut len = 8;
+    for (kind, value) in [(TIMEOUT, params.timeout), (BLOCK_SIZE, params.block_size)] {
+        if let Some(value) = value {
+            header[len..len + 4].fill(0x1b);
+            header[len + 4..len + 8].fill(kind);
+            header[len + 8..len + 12].copy_from_slice(&value.to_be_bytes());
+            len += 12;
+        }
+    }
+    encode_with_header(&header[..len], iter)
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use hex_literal::hex;
+
+    fn roundtrip(data: &[u8], params: Params) {
+        let encoded = encode_v2::<Vec<u8>>(data, params).unwrap();
+        let mut decoder = DecoderV2::<Vec<u8>>::new();
+        let mut decoded = vec![];
+        for b in encoded {
+            if let Some(bytes) = decoder.push_byte(b).unwrap() {
+                decoded.push(bytes.to_vec());
+            }
+        }
+        assert_eq!(decoded, vec![data.to_vec()]);
+        assert_eq!(decoder.params(), Some(params));
+        assert_eq!(decoder.finalize(), None);
+    }
+
+    #[test]
+    fn encode() {
+        let encoded = encode_v2::<Vec<u8>>(hex!("123456"), Params::default()).unwrap();
+        assert_eq!(encoded[..16], hex!("1b1b1b1b 02020202 12345600 1b1b1b1b"));
+        let params = Params {
+            timeout: Some(0x0102),
+            block_size: Some(0x0304),
+        };
+        let encoded = encode_v2::<Vec<u8>>(hex!("12345678"), params).unwrap();
+        assert_eq!(
+            encoded[..36],
+            hex!(
+                "1b1b1b1b 02020202 1b1b1b1b 03030303 00000102 1b1b1b1b 04040404 00000304 12345678"
+            )
+        );
+    }
+
+    #[test]
+    fn roundtrips() {
+        let timeout = Params {
+            timeout: Some(500),
+            block_size: None,
+        };
+        let block_size = Params {
+            timeout: None,
+            block_size: Some(1024),
+        };
+        for data in [
+            &hex!("")[..],
+            &hex!("12345678"),
+            &hex!("123456"),
+            &hex!("121b1b1b1b"),
+            &hex!("1b1b1b1b 12"),
+            &hex!("12345678 12341b1b"),
+            &hex!("03030303 00"),
+        ] {
+            roundtrip(data, Params::default());
+            roundtrip(data, timeout);
+            roundtrip(data, block_size);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let msg = encode_v2::<Vec<u8>>(hex!("12345678"), Params::default()).unwrap();
+
+        // version 1 transmissions are discarded
+        let v1 = hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        assert_eq!(decode_v2(v1), vec![Err(DecodeErr::DiscardedBytes(20))]);
+
+        // bytes before the start sequence
+        let mut bytes = hex!("1b 00 1b1b1b1b 1b").to_vec();
+        bytes.extend_from_slice(&msg);
+        assert_eq!(
+            decode_v2(&bytes),
+            vec![
+                Err(DecodeErr::DiscardedBytes(7)),
+                Ok(hex!("12345678").to_vec())
+            ]
+        );
+
+        // restart within a transmission
+        let mut bytes = msg[..12].to_vec();
+        bytes.extend_from_slice(&msg);
+        assert_eq!(
+            decode_v2(&bytes),
+            vec![
+                Err(DecodeErr::DiscardedBytes(12)),
+                Ok(hex!("12345678").to_vec())
+            ]
+        );
+
+        // checksum mismatch
+        let mut bytes = msg.clone();
+        bytes[8] = 0x13;
+        assert!(matches!(
+            decode_v2(&bytes)[..],
+            [Err(DecodeErr::InvalidMessage {
+                checksum_mismatch: Some(_),
+                ..
+            })]
+        ));
+
+        // parameter sequences are only allowed before the data
+        let bytes = hex!("1b1b1b1b 02020202 12345678 1b1b1b1b 03030303 000001f4");
+        assert_eq!(
+            decode_v2(bytes),
+            vec![
+                Err(DecodeErr::InvalidEsc(hex!("03030303"))),
+                Err(DecodeErr::DiscardedBytes(4))
+            ]
+        );
+
+        // out of memory
+        let mut decoder = DecoderV2::<crate::util::ArrayBuf<3>>::new();
+        let res: Vec<_> = msg
+            .iter()
+            .filter_map(|b| decoder.push_byte(*b).err())
+            .collect();
+        assert_eq!(res, vec![DecodeErr::OutOfMemory]);
+
+        // incomplete transmission
+        assert_eq!(
+            decode_v2(&msg[..14]),
+            vec![Err(DecodeErr::DiscardedBytes(14))]
+        );
+    }
+
+    #[test]
+    fn frame_info() {
+        let params = Params {
+            timeout: Some(500),
+            block_size: None,
+        };
+        let msg = encode_v2::<Vec<u8>>(hex!("1b1b1b1b 12"), params).unwrap();
+        let mut decoder = DecoderV2::<Vec<u8>>::new();
+        let mut messages = decoder.push_bytes(&msg);
+        assert_eq!(
+            messages.next_message(),
+            Some(Ok(hex!("1b1b1b1b 12").as_slice()))
+        );
+        assert_eq!(
+            decoder.frame_info(),
+            Some(FrameInfo {
+                raw_len: msg.len(),
+                num_escape_sequences: 1,
+                num_padding_bytes: 3,
+            })
+        );
+        assert_eq!(msg.len(), 40);
+        assert_eq!(decoder.params(), Some(params));
+    }
+
+    #[test]
+    fn fuzzy_resync() {
+        let mut msg = encode_v2::<Vec<u8>>(hex!("12345678"), Params::default()).unwrap();
+        msg[5] = 0x00;
+        assert_eq!(
+            decode_v2(&msg),
+            vec![Err(DecodeErr::DiscardedBytes(msg.len()))]
+        );
+        let mut decoder = DecoderV2::<Vec<u8>>::new().with_fuzzy_resync(true);
+        let mut messages = decoder.push_bytes(&msg);
+        assert_eq!(
+            messages.next_message(),
+            Some(Ok(hex!("12345678").as_slice()))
+        );
+    }
+
+    #[test]
+    fn lenient_padding() {
+        let mut msg = hex!("1b1b1b1b 02020202 123456ff 1b1b1b1b 1a010000").to_vec();
+        let mut crc = crate::util::CRC_X25.digest();
+        crc.update(&msg[..18]);
+        msg[18..].copy_from_slice(&crc.finalize().to_le_bytes());
+
+        let mut decoder = DecoderV2::<Vec<u8>>::new();
+        let mut messages = decoder.push_bytes(&msg);
+        assert!(matches!(
+            messages.next_message(),
+            Some(Err(DecodeErr::InvalidMessage {
+                invalid_padding_bytes: true,
+                ..
+            }))
+        ));
+        let mut decoder = DecoderV2::<Vec<u8>>::new().with_lenient_padding(true);
+        let mut messages = decoder.push_bytes(&msg);
+        assert_eq!(messages.next_message(), Some(Ok(hex!("123456").as_slice())));
+        assert_eq!(decoder.num_tolerated_padding(), 1);
+    }
+
+    #[test]
+    fn snapshot() {
+        let params = Params {
+            timeout: Some(500),
+            block_size: Some(1024),
+        };
+        let mut bytes = encode_v2::<Vec<u8>>(hex!("12 1b1b1b1b 00"), params).unwrap();
+        bytes.extend(encode_v2::<Vec<u8>>(hex!("12345678"), Params::default()).unwrap());
+
+        let mut expected = vec![];
+        let mut machine = Machine::new_v2();
+        let mut sink = Vec::new();
+        for &b in &bytes {
+            let res = machine.push_byte(&mut sink, b);
+            expected.push((res, sink.clone(), machine.params()));
+        }
+        assert_eq!(expected.iter().filter(|(_, _, p)| p.is_some()).count(), 2);
+
+        // snapshot and restore the machine after each byte
+        let mut machine = Machine::new_v2();
+        let mut sink = Vec::new();
+        for (&b, expected) in bytes.iter().zip(&expected) {
+            machine = Machine::restore(&machine.snapshot()).unwrap();
+            let res = machine.push_byte(&mut sink, b);
+            assert_eq!(&(res, sink.clone(), machine.params()), expected);
+        }
+    }
+}
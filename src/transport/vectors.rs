@@ -0,0 +1,345 @@
+//! Test vectors for the SML transport protocol v1.
+//!
+//! The vectors in this module cover edge cases of the transport protocol such as
+//! escape sequences at the end of a message, padding and additional start sequences.
+//! They are used by the tests of sml-rs and can be used to test alternative decoders
+//! or drivers for the same cases.
+//!
+//! # Examples
+//!
+//! ```
+//! # use sml_rs::{transport::{decode_streaming, vectors}, util::ArrayBuf};
+//! for vector in vectors::ALL {
+//!     let mut decoder = decode_streaming::<ArrayBuf<128>>(vector.input);
+//!     for expected in vector.expected {
+//!         assert_eq!(decoder.next().as_ref(), Some(expected), "{}", vector.name);
+//!     }
+//!     assert_eq!(decoder.next(), None, "{}", vector.name);
+//! }
+//! ```
+
+use super::DecodeErr;
+use crate::util::CrcMismatch;
+
+/// A transport protocol test vector.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vector {
+    /// name of the vector
+    pub name: &'static str,
+    /// raw input bytes
+    pub input: &'static [u8],
+    /// decoded messages and errors in the order returned by the decoder
+    ///
+    /// The results assume a buffer that is large enough for the decoded messages
+    /// (128 bytes are sufficient for all vectors).
+    pub expected: &'static [Result<&'static [u8], DecodeErr>],
+}
+
+/// All vectors defined in this module.
+pub const ALL: &[Vector] = &[
+    BASIC,
+    INVALID_CRC,
+    MSG_END_MISALIGNED,
+    PADDING_TOO_LARGE,
+    EMPTY_MSG_WITH_PADDING,
+    ADDITIONAL_BYTES,
+    INCOMPLETE_MESSAGE,
+    INVALID_ESC_SEQUENCE,
+    INCOMPLETE_ESC_SEQUENCE,
+    DOUBLE_MSG_START,
+    PADDING,
+    ESCAPE_IN_USER_DATA,
+    ENDING_WITH_1B_NO_PADDING_1,
+    ENDING_WITH_1B_NO_PADDING_2,
+    ENDING_WITH_1B_NO_PADDING_3,
+    INVALID_PADDING_BYTES_1,
+    INVALID_PADDING_BYTES_2,
+    INVALID_PADDING_BYTES_3,
+    ANOTHER_MSG_START_AFTER_PADDING_1,
+    ANOTHER_MSG_START_AFTER_PADDING_2,
+    ANOTHER_MSG_START_AFTER_PADDING_3,
+    MSG_END_WITH_ZEROES_AND_PADDING,
+];
+
+/// A valid message without padding.
+pub const BASIC: Vector = Vector {
+    name: "basic",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1a, 0x00, 0xb8, 0x7b,
+    ],
+    expected: &[Ok(&[0x12, 0x34, 0x56, 0x78])],
+};
+
+/// A message whose checksum doesn't match.
+pub const INVALID_CRC: Vector = Vector {
+    name: "invalid_crc",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1a, 0x00, 0xb8, 0xff,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: Some(CrcMismatch {
+            expected: 0xffb8,
+            computed: 0x7bb8,
+        }),
+        end_esc_misaligned: false,
+        num_padding_bytes: 0,
+        invalid_padding_bytes: false,
+    })],
+};
+
+/// A message whose end sequence isn't aligned to four bytes.
+pub const MSG_END_MISALIGNED: Vector = Vector {
+    name: "msg_end_misaligned",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0xff, 0x1b, 0x1b,
+        0x1b, 0x1b, 0x1a, 0x00, 0x13, 0xb6,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: true,
+        num_padding_bytes: 0,
+        invalid_padding_bytes: false,
+    })],
+};
+
+/// An end sequence announcing more than three padding bytes.
+pub const PADDING_TOO_LARGE: Vector = Vector {
+    name: "padding_too_large",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56,
+        0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x04, 0xf9, 0x50,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: false,
+        num_padding_bytes: 4,
+        invalid_padding_bytes: true,
+    })],
+};
+
+/// An empty message announcing a padding byte.
+pub const EMPTY_MSG_WITH_PADDING: Vector = Vector {
+    name: "empty_msg_with_padding",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x01, 0x4f,
+        0xf4,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: false,
+        num_padding_bytes: 1,
+        invalid_padding_bytes: true,
+    })],
+};
+
+/// A valid message surrounded by bytes that don't belong to a message.
+pub const ADDITIONAL_BYTES: Vector = Vector {
+    name: "additional_bytes",
+    input: &[
+        0x00, 0x01, 0x02, 0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78,
+        0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xb8, 0x7b, 0x12, 0x34,
+    ],
+    expected: &[
+        Err(DecodeErr::DiscardedBytes(3)),
+        Ok(&[0x12, 0x34, 0x56, 0x78]),
+        Err(DecodeErr::DiscardedBytes(2)),
+    ],
+};
+
+/// A message that ends before its end sequence.
+pub const INCOMPLETE_MESSAGE: Vector = Vector {
+    name: "incomplete_message",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56,
+    ],
+    expected: &[Err(DecodeErr::DiscardedBytes(11))],
+};
+
+/// A message containing an unknown escape sequence.
+pub const INVALID_ESC_SEQUENCE: Vector = Vector {
+    name: "invalid_esc_sequence",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1c, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x03,
+        0xbe, 0x25,
+    ],
+    expected: &[
+        Err(DecodeErr::InvalidEsc([0x1c, 0x0, 0x0, 0x0])),
+        Err(DecodeErr::DiscardedBytes(12)),
+    ],
+};
+
+/// A message containing three `0x1b` bytes that aren't an escape sequence.
+pub const INCOMPLETE_ESC_SEQUENCE: Vector = Vector {
+    name: "incomplete_esc_sequence",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b,
+        0x00, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0x91, 0x35,
+    ],
+    expected: &[Ok(&[
+        0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x00, 0x12, 0x34, 0x56, 0x78,
+    ])],
+};
+
+/// A start sequence inside a message, which discards the bytes read so far.
+pub const DOUBLE_MSG_START: Vector = Vector {
+    name: "double_msg_start",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x09, 0x87, 0x65, 0x43, 0x21, 0x1b, 0x1b,
+        0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a,
+        0x00, 0xb8, 0x7b,
+    ],
+    expected: &[
+        Err(DecodeErr::DiscardedBytes(13)),
+        Ok(&[0x12, 0x34, 0x56, 0x78]),
+    ],
+};
+
+/// A valid message with a padding byte.
+pub const PADDING: Vector = Vector {
+    name: "padding",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x00, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1a, 0x01, 0x91, 0xa5,
+    ],
+    expected: &[Ok(&[0x12, 0x34, 0x56])],
+};
+
+/// A message containing an escaped escape sequence.
+pub const ESCAPE_IN_USER_DATA: Vector = Vector {
+    name: "escape_in_user_data",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x1b, 0x1b, 0x1b, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1b, 0x00, 0x00, 0x00, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x03, 0xbe, 0x25,
+    ],
+    expected: &[Ok(&[0x12, 0x1b, 0x1b, 0x1b, 0x1b])],
+};
+
+/// A message ending with one `0x1b` byte right before the end sequence.
+pub const ENDING_WITH_1B_NO_PADDING_1: Vector = Vector {
+    name: "ending_with_1b_no_padding_1",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56,
+        0x1b, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0x36, 0x1a,
+    ],
+    expected: &[Ok(&[0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56, 0x1b])],
+};
+
+/// A message ending with two `0x1b` bytes right before the end sequence.
+pub const ENDING_WITH_1B_NO_PADDING_2: Vector = Vector {
+    name: "ending_with_1b_no_padding_2",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x1b,
+        0x1b, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0x1a, 0xc5,
+    ],
+    expected: &[Ok(&[0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x1b, 0x1b])],
+};
+
+/// A message ending with three `0x1b` bytes right before the end sequence.
+pub const ENDING_WITH_1B_NO_PADDING_3: Vector = Vector {
+    name: "ending_with_1b_no_padding_3",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x1b, 0x1b,
+        0x1b, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0x0b, 0xa4,
+    ],
+    expected: &[Ok(&[0x12, 0x34, 0x56, 0x78, 0x12, 0x1b, 0x1b, 0x1b])],
+};
+
+/// A message whose padding byte isn't zero.
+pub const INVALID_PADDING_BYTES_1: Vector = Vector {
+    name: "invalid_padding_bytes_1",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x34, 0x56,
+        0x01, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x01, 0x21, 0x57,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: false,
+        num_padding_bytes: 1,
+        invalid_padding_bytes: true,
+    })],
+};
+
+/// A message whose padding bytes aren't all zero (non-zero byte in the middle).
+pub const INVALID_PADDING_BYTES_2: Vector = Vector {
+    name: "invalid_padding_bytes_2",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0x00, 0x01,
+        0x00, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x03, 0x29, 0x7e,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: false,
+        num_padding_bytes: 3,
+        invalid_padding_bytes: true,
+    })],
+};
+
+/// A message whose padding bytes aren't all zero (non-zero first byte).
+pub const INVALID_PADDING_BYTES_3: Vector = Vector {
+    name: "invalid_padding_bytes_3",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x12, 0xff, 0x00,
+        0x00, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x03, 0xa7, 0x43,
+    ],
+    expected: &[Err(DecodeErr::InvalidMessage {
+        checksum_mismatch: None,
+        end_esc_misaligned: false,
+        num_padding_bytes: 3,
+        invalid_padding_bytes: true,
+    })],
+};
+
+/// A start sequence following zero bytes that look like padding.
+pub const ANOTHER_MSG_START_AFTER_PADDING_1: Vector = Vector {
+    name: "another_msg_start_after_padding_1",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x00, 0x00, 0x1b, 0x1b, 0x1b, 0x1b,
+        0x01, 0x01, 0x01, 0x01, 0x87, 0x65, 0x43, 0x21, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0x42,
+        0x3c,
+    ],
+    expected: &[
+        Err(DecodeErr::DiscardedBytes(11)),
+        Ok(&[0x87, 0x65, 0x43, 0x21]),
+    ],
+};
+
+/// A start sequence following zero bytes, followed by an empty message.
+pub const ANOTHER_MSG_START_AFTER_PADDING_2: Vector = Vector {
+    name: "another_msg_start_after_padding_2",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x00, 0x00, 0x1b, 0x1b, 0x1b, 0x1b,
+        0x01, 0x01, 0x01, 0x01, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x00, 0xc6, 0xe5,
+    ],
+    expected: &[Err(DecodeErr::DiscardedBytes(11)), Ok(&[])],
+};
+
+/// A start sequence following zero bytes, followed by an empty message announcing padding.
+pub const ANOTHER_MSG_START_AFTER_PADDING_3: Vector = Vector {
+    name: "another_msg_start_after_padding_3",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x00, 0x00, 0x1b, 0x1b, 0x1b, 0x1b,
+        0x01, 0x01, 0x01, 0x01, 0x1b, 0x1b, 0x1b, 0x1b, 0x1a, 0x01, 0x4f, 0xf4,
+    ],
+    expected: &[
+        Err(DecodeErr::DiscardedBytes(11)),
+        Err(DecodeErr::InvalidMessage {
+            checksum_mismatch: None,
+            end_esc_misaligned: false,
+            num_padding_bytes: 1,
+            invalid_padding_bytes: true,
+        }),
+    ],
+};
+
+/// A message ending with zero bytes, of which only the last one is padding.
+pub const MSG_END_WITH_ZEROES_AND_PADDING: Vector = Vector {
+    name: "msg_end_with_zeroes_and_padding",
+    input: &[
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x00, 0x00, 0x00, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1a, 0x01, 0xe1, 0xb1,
+    ],
+    expected: &[Ok(&[0x12, 0x00, 0x00])],
+};
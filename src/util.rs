@@ -1,8 +1,110 @@
 //! utility stuff
 
-use core::{borrow::Borrow, fmt::Debug, ops::Deref};
-
+use core::{
+    borrow::Borrow,
+    cell::{RefCell, RefMut},
+    fmt::Debug,
+    ops::Deref,
+};
+
+#[cfg(not(feature = "small-crc"))]
 pub(crate) static CRC_X25: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_IBM_SDLC);
+#[cfg(feature = "small-crc")]
+pub(crate) static CRC_X25: small_crc::Crc = small_crc::Crc;
+
+/// Running CRC-16/X-25 computation returned by `CRC_X25.digest()`
+#[cfg(not(feature = "small-crc"))]
+pub(crate) type CrcDigest = crc::Digest<'static, u16>;
+#[cfg(feature = "small-crc")]
+pub(crate) type CrcDigest = small_crc::Digest;
+
+// returns the internal state of a digest (used for `Machine` snapshots)
+//
+// The state is the reflected register value, which is the same for both CRC implementations.
+#[cfg(not(feature = "small-crc"))]
+pub(crate) fn crc_state(digest: &CrcDigest) -> u16 {
+    digest.clone().finalize() ^ 0xffff
+}
+#[cfg(feature = "small-crc")]
+pub(crate) fn crc_state(digest: &CrcDigest) -> u16 {
+    digest.0
+}
+
+// constructs a digest from a state returned by `crc_state`
+#[cfg(not(feature = "small-crc"))]
+pub(crate) fn crc_from_state(state: u16) -> CrcDigest {
+    CRC_X25.digest_with_initial(state.reverse_bits())
+}
+#[cfg(feature = "small-crc")]
+pub(crate) fn crc_from_state(state: u16) -> CrcDigest {
+    small_crc::Digest(state)
+}
+
+// bitwise CRC-16/X-25 implementation without lookup table, mirroring the API of the `crc` crate
+#[cfg(feature = "small-crc")]
+mod small_crc {
+    // reflected polynomial 0x1021
+    const POLY: u16 = 0x8408;
+
+    pub(crate) struct Crc;
+
+    impl Crc {
+        pub(crate) const fn digest(&self) -> Digest {
+            Digest(0xffff)
+        }
+
+        pub(crate) fn checksum(&self, bytes: &[u8]) -> u16 {
+            let mut digest = self.digest();
+            digest.update(bytes);
+            digest.finalize()
+        }
+    }
+
+    #[derive(Clone)]
+    pub(crate) struct Digest(pub(super) u16);
+
+    impl Digest {
+        pub(crate) fn update(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 ^= u16::from(b);
+                for _ in 0..8 {
+                    self.0 = if self.0 & 1 != 0 {
+                        (self.0 >> 1) ^ POLY
+                    } else {
+                        self.0 >> 1
+                    };
+                }
+            }
+        }
+
+        pub(crate) fn finalize(self) -> u16 {
+            !self.0
+        }
+    }
+}
+
+/// Checksum mismatch detected while decoding or parsing.
+///
+/// Both values are CRC-16/X-25 checksums as returned by the checksum algorithm,
+/// independent of the byte order used to transmit them.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcMismatch {
+    /// checksum contained in the input
+    pub expected: u16,
+    /// checksum computed over the input
+    pub computed: u16,
+}
+
+impl core::fmt::Display for CrcMismatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "CRC mismatch (expected: {:#06x}, computed: {:#06x})",
+            self.expected, self.computed
+        )
+    }
+}
 
 pub(crate) mod private {
     pub trait Sealed {}
@@ -37,6 +139,10 @@ pub trait Buffer: Default + Deref<Target = [u8]> + private::Sealed {
     fn extend_from_slice(&mut self, other: &[u8]) -> Result<(), OutOfMemory>;
 }
 
+// initial capacity of a `VecBuf` growing via `Buffer::push`
+#[cfg(feature = "alloc")]
+const MIN_VEC_CAPACITY: usize = 1024;
+
 /// Type alias for `alloc::Vec<u8>`
 #[cfg(feature = "alloc")]
 pub type VecBuf = alloc::vec::Vec<u8>;
@@ -44,12 +150,23 @@ pub type VecBuf = alloc::vec::Vec<u8>;
 #[cfg(feature = "alloc")]
 impl Buffer for VecBuf {
     fn push(&mut self, b: u8) -> Result<(), OutOfMemory> {
-        match self.try_reserve(1) {
+        // Grow exponentially starting at `MIN_VEC_CAPACITY` bytes. Transmissions
+        // are usually 300 bytes to 2 KiB long, which avoids reallocating several
+        // times for each of the first transmissions.
+        let additional = if self.len() == self.capacity() {
+            self.len().max(MIN_VEC_CAPACITY)
+        } else {
+            1
+        };
+        match self.try_reserve(additional) {
             Ok(()) => {
                 VecBuf::push(self, b);
                 Ok(())
             }
-            Err(_) => Err(OutOfMemory),
+            Err(_) => Err(OutOfMemory {
+                needed: self.len() + 1,
+                available: self.capacity(),
+            }),
         }
     }
 
@@ -67,7 +184,10 @@ impl Buffer for VecBuf {
                 VecBuf::extend_from_slice(self, other);
                 Ok(())
             }
-            Err(_) => Err(OutOfMemory),
+            Err(_) => Err(OutOfMemory {
+                needed: self.len() + other.len(),
+                available: self.capacity(),
+            }),
         }
     }
 }
@@ -123,7 +243,10 @@ impl<const N: usize> FromIterator<u8> for ArrayBuf<N> {
 impl<const N: usize> Buffer for ArrayBuf<N> {
     fn push(&mut self, b: u8) -> Result<(), OutOfMemory> {
         if self.num_elements == N {
-            Err(OutOfMemory)
+            Err(OutOfMemory {
+                needed: N + 1,
+                available: N,
+            })
         } else {
             self.buffer[self.num_elements] = b;
             self.num_elements += 1;
@@ -141,7 +264,10 @@ impl<const N: usize> Buffer for ArrayBuf<N> {
 
     fn extend_from_slice(&mut self, other: &[u8]) -> Result<(), OutOfMemory> {
         if self.num_elements + other.len() > N {
-            return Err(OutOfMemory);
+            return Err(OutOfMemory {
+                needed: self.num_elements + other.len(),
+                available: N,
+            });
         }
         self.buffer[self.num_elements..][..other.len()].copy_from_slice(other);
         self.num_elements += other.len();
@@ -151,9 +277,166 @@ impl<const N: usize> Buffer for ArrayBuf<N> {
 
 impl<const N: usize> private::Sealed for ArrayBuf<N> {}
 
+/// Fixed number of equally-sized byte buffers that can be leased by multiple users.
+///
+/// When reading several meters on a device with little RAM, giving each
+/// `Decoder` its own worst-case-sized `ArrayBuf` can be wasteful. Instead,
+/// decoders can lease buffers from a shared pool using [`BufferPool::lease`].
+/// A leased buffer is returned to the pool when it is dropped, e.g. after
+/// retrieving it from a decoder using [`Decoder::into_buf`](crate::transport::Decoder::into_buf).
+///
+/// The pool doesn't support concurrent access from multiple threads or interrupts.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{util::BufferPool, transport::Decoder};
+/// let pool = BufferPool::<512, 2>::new();
+///
+/// let mut decoder_a = Decoder::from_buf(pool.lease().unwrap());
+/// let decoder_b = Decoder::from_buf(pool.lease().unwrap());
+/// // all slots are in use
+/// assert!(pool.lease().is_none());
+///
+/// // return the buffer of `decoder_b` to the pool
+/// drop(decoder_b.into_buf());
+/// assert!(pool.lease().is_some());
+/// # let _ = decoder_a.push_byte(0x1b);
+/// ```
+pub struct BufferPool<const N: usize, const SLOTS: usize> {
+    slots: [RefCell<[u8; N]>; SLOTS],
+}
+
+impl<const N: usize, const SLOTS: usize> Default for BufferPool<N, SLOTS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const SLOTS: usize> BufferPool<N, SLOTS> {
+    /// Constructs a new pool of `SLOTS` buffers with a capacity of `N` bytes each.
+    #[must_use]
+    pub const fn new() -> Self {
+        BufferPool {
+            slots: [const { RefCell::new([0; N]) }; SLOTS],
+        }
+    }
+
+    /// Leases a buffer from the pool.
+    ///
+    /// Returns `None` if all buffers are currently in use.
+    pub fn lease(&self) -> Option<PooledBuf<'_, N>> {
+        self.slots
+            .iter()
+            .find_map(|slot| slot.try_borrow_mut().ok())
+            .map(|slot| PooledBuf {
+                slot: Some(slot),
+                num_elements: 0,
+            })
+    }
+
+    /// Returns the number of buffers that are currently available.
+    #[must_use]
+    pub fn available(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.try_borrow_mut().is_ok())
+            .count()
+    }
+}
+
+/// Byte buffer leased from a [`BufferPool`].
+///
+/// The buffer is returned to the pool when it is dropped. A default-constructed
+/// `PooledBuf` isn't backed by the pool and has a capacity of zero bytes.
+pub struct PooledBuf<'a, const N: usize> {
+    slot: Option<RefMut<'a, [u8; N]>>,
+    num_elements: usize,
+}
+
+impl<const N: usize> Default for PooledBuf<'_, N> {
+    fn default() -> Self {
+        PooledBuf {
+            slot: None,
+            num_elements: 0,
+        }
+    }
+}
+
+impl<const N: usize> Debug for PooledBuf<'_, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+impl<const N: usize> Deref for PooledBuf<'_, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.slot {
+            Some(slot) => &slot[..self.num_elements],
+            None => &[],
+        }
+    }
+}
+
+impl<const N: usize> Buffer for PooledBuf<'_, N> {
+    fn push(&mut self, b: u8) -> Result<(), OutOfMemory> {
+        self.extend_from_slice(&[b])
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.num_elements = self.num_elements.min(len);
+    }
+
+    fn clear(&mut self) {
+        self.num_elements = 0;
+    }
+
+    fn extend_from_slice(&mut self, other: &[u8]) -> Result<(), OutOfMemory> {
+        match &mut self.slot {
+            Some(slot) if self.num_elements + other.len() <= N => {
+                slot[self.num_elements..][..other.len()].copy_from_slice(other);
+                self.num_elements += other.len();
+                Ok(())
+            }
+            Some(_) => Err(OutOfMemory {
+                needed: self.num_elements + other.len(),
+                available: N,
+            }),
+            None => Err(OutOfMemory {
+                needed: other.len(),
+                available: 0,
+            }),
+        }
+    }
+}
+
+impl<const N: usize> private::Sealed for PooledBuf<'_, N> {}
+
 /// Error type indicating that an operation failed due to lack of memory.
+///
+/// For buffers with a fixed capacity, use [`recommended_buffer_size`](crate::transport::recommended_buffer_size)
+/// and [`max_encoded_len`](crate::transport::max_encoded_len) to choose the capacity.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct OutOfMemory;
+pub struct OutOfMemory {
+    /// the number of bytes the buffer would have to hold
+    pub needed: usize,
+    /// the capacity of the buffer
+    pub available: usize,
+}
+
+impl core::fmt::Display for OutOfMemory {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "out of memory: {} bytes needed, but only {} bytes available",
+            self.needed, self.available
+        )
+    }
+}
+
+impl core::error::Error for OutOfMemory {}
 
 // ===========================================================================
 // ===========================================================================
@@ -208,7 +491,8 @@ impl<R> IoByteSource<R>
 where
     R: std::io::Read,
 {
-    pub(crate) fn new(reader: R) -> Self {
+    /// Constructs a byte source reading from `reader`.
+    pub fn new(reader: R) -> Self {
         IoByteSource { inner: reader }
     }
 }
@@ -294,6 +578,272 @@ impl<E> ByteSourceErr for nb::Error<E> {
 #[cfg(feature = "embedded-hal-02")]
 impl<E> private::Sealed for nb::Error<E> {}
 
+/// Wraps types that implement `embedded_io::Read` and implements `ByteSource`
+///
+/// If the wrapped type also implements `embedded_io::ReadReady`,
+/// [`DecoderReader::poll_read`](crate::transport::DecoderReader::poll_read) can be
+/// used to read only the bytes that are already available.
+///
+/// *This type is available only if sml-rs is built with the `"embedded-io"` feature.*
+#[cfg(feature = "embedded-io")]
+pub struct EioByteSource<R>
+where
+    R: embedded_io::Read,
+{
+    pub(crate) inner: R,
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R> EioByteSource<R>
+where
+    R: embedded_io::Read,
+{
+    /// Constructs a byte source reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        EioByteSource { inner: reader }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R> ByteSource for EioByteSource<R>
+where
+    R: embedded_io::Read,
+{
+    type ReadError = embedded_io::ReadExactError<R::Error>;
+
+    fn read_byte(&mut self) -> Result<u8, Self::ReadError> {
+        let mut b = 0u8;
+        self.inner.read_exact(core::slice::from_mut(&mut b))?;
+        Ok(b)
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<R> private::Sealed for EioByteSource<R> where R: embedded_io::Read {}
+
+#[cfg(feature = "embedded-io")]
+impl<E> ByteSourceErr for embedded_io::ReadExactError<E> {
+    fn kind(&self) -> ErrKind {
+        match self {
+            embedded_io::ReadExactError::UnexpectedEof => ErrKind::Eof,
+            embedded_io::ReadExactError::Other(_) => ErrKind::Other,
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+impl<E> private::Sealed for embedded_io::ReadExactError<E> {}
+
+/// Wraps the reading end of an `embassy_sync::pipe::Pipe` and implements `ByteSource`
+///
+/// Embassy applications often forward the bytes received by the UART to another task
+/// using a pipe. Reading from an empty pipe returns a `WouldBlock` error, so the
+/// non-blocking APIs (e.g. [`SmlReader::next_nb`](crate::SmlReader::next_nb)) can be
+/// used. In async code, [`read_byte_async`](PipeByteSource::read_byte_async) waits
+/// for the next byte instead.
+///
+/// *This type is available only if sml-rs is built with the `"embassy-sync"` feature.*
+#[cfg(feature = "embassy-sync")]
+pub struct PipeByteSource<'p, M, const N: usize>
+where
+    M: embassy_sync::blocking_mutex::raw::RawMutex,
+{
+    inner: embassy_sync::pipe::Reader<'p, M, N>,
+}
+
+#[cfg(feature = "embassy-sync")]
+impl<'p, M, const N: usize> PipeByteSource<'p, M, N>
+where
+    M: embassy_sync::blocking_mutex::raw::RawMutex,
+{
+    /// Constructs a byte source reading from `reader`.
+    pub fn new(reader: embassy_sync::pipe::Reader<'p, M, N>) -> Self {
+        PipeByteSource { inner: reader }
+    }
+
+    /// Waits until a byte is available and returns it.
+    ///
+    /// The returned future is cancel-safe: no byte is lost if it is dropped before completion.
+    pub async fn read_byte_async(&mut self) -> u8 {
+        let mut b = 0u8;
+        // reading into a non-empty buffer only completes once a byte has been read
+        self.inner.read(core::slice::from_mut(&mut b)).await;
+        b
+    }
+
+    /// Returns the wrapped pipe reader.
+    pub fn into_inner(self) -> embassy_sync::pipe::Reader<'p, M, N> {
+        self.inner
+    }
+}
+
+#[cfg(feature = "embassy-sync")]
+impl<M, const N: usize> ByteSource for PipeByteSource<'_, M, N>
+where
+    M: embassy_sync::blocking_mutex::raw::RawMutex,
+{
+    type ReadError = embassy_sync::pipe::TryReadError;
+
+    fn read_byte(&mut self) -> Result<u8, Self::ReadError> {
+        let mut b = 0u8;
+        self.inner.try_read(core::slice::from_mut(&mut b))?;
+        Ok(b)
+    }
+}
+
+#[cfg(feature = "embassy-sync")]
+impl<M, const N: usize> private::Sealed for PipeByteSource<'_, M, N> where
+    M: embassy_sync::blocking_mutex::raw::RawMutex
+{
+}
+
+#[cfg(feature = "embassy-sync")]
+impl ByteSourceErr for embassy_sync::pipe::TryReadError {
+    fn kind(&self) -> ErrKind {
+        ErrKind::WouldBlock
+    }
+}
+
+#[cfg(feature = "embassy-sync")]
+impl private::Sealed for embassy_sync::pipe::TryReadError {}
+
+/// Helper trait that allows reading bytes asynchronously
+///
+/// This is the poll-based core shared by the adapters for the different async I/O
+/// ecosystems. It's used by [`AsyncDecoderReader`](crate::transport::AsyncDecoderReader).
+/// Unlike `AsyncRead` traits, reaching the end of the input is reported as error
+/// whose [`kind`](ByteSourceErr::kind) is [`ErrKind::Eof`].
+pub trait AsyncByteSource: private::Sealed {
+    /// Type of errors that can occur while reading bytes
+    type ReadError: ByteSourceErr;
+
+    /// Tries to read bytes into `buf` and returns the number of bytes read (at least one).
+    ///
+    /// Returns `Poll::Pending` and arranges for the current task to be woken up if
+    /// no data is available yet.
+    fn poll_read(
+        self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> core::task::Poll<Result<usize, Self::ReadError>>;
+}
+
+/// Wraps types that implement `futures_io::AsyncRead` and implements `AsyncByteSource`
+///
+/// This allows reading from the I/O types of the async-std and smol ecosystems.
+///
+/// *This type is available only if sml-rs is built with the `"futures-io"` feature.*
+#[cfg(feature = "futures-io")]
+pub struct FuturesIoByteSource<R>
+where
+    R: futures_io::AsyncRead + Unpin,
+{
+    inner: R,
+}
+
+#[cfg(feature = "futures-io")]
+impl<R> FuturesIoByteSource<R>
+where
+    R: futures_io::AsyncRead + Unpin,
+{
+    /// Constructs a byte source reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        FuturesIoByteSource { inner: reader }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R> AsyncByteSource for FuturesIoByteSource<R>
+where
+    R: futures_io::AsyncRead + Unpin,
+{
+    type ReadError = std::io::Error;
+
+    fn poll_read(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> core::task::Poll<Result<usize, Self::ReadError>> {
+        core::pin::Pin::new(&mut self.inner)
+            .poll_read(cx, buf)
+            .map(|res| match res {
+                Ok(0) if !buf.is_empty() => Err(std::io::ErrorKind::UnexpectedEof.into()),
+                res => res,
+            })
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R> private::Sealed for FuturesIoByteSource<R> where R: futures_io::AsyncRead + Unpin {}
+
+/// Wraps types that implement `tokio::io::AsyncRead` and implements `AsyncByteSource`
+///
+/// This allows reading from the I/O types of the tokio ecosystem (e.g.
+/// `tokio_serial::SerialStream`).
+///
+/// *This type is available only if sml-rs is built with the `"tokio"` feature.*
+#[cfg(feature = "tokio")]
+pub struct TokioByteSource<R>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    inner: R,
+}
+
+#[cfg(feature = "tokio")]
+impl<R> TokioByteSource<R>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    /// Constructs a byte source reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        TokioByteSource { inner: reader }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> AsyncByteSource for TokioByteSource<R>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    type ReadError = std::io::Error;
+
+    fn poll_read(
+        mut self: core::pin::Pin<&mut Self>,
+        cx: &mut core::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> core::task::Poll<Result<usize, Self::ReadError>> {
+        let mut read_buf = tokio::io::ReadBuf::new(buf);
+        core::pin::Pin::new(&mut self.inner)
+            .poll_read(cx, &mut read_buf)
+            .map(|res| match res {
+                Ok(()) if read_buf.filled().is_empty() && read_buf.capacity() > 0 => {
+                    Err(std::io::ErrorKind::UnexpectedEof.into())
+                }
+                Ok(()) => Ok(read_buf.filled().len()),
+                Err(e) => Err(e),
+            })
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R> private::Sealed for TokioByteSource<R> where R: tokio::io::AsyncRead + Unpin {}
+
 /// Error type indicating that the end of the input has been reached
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Eof;
@@ -377,43 +927,747 @@ where
 {
 }
 
-// ===========================================================================
-// ===========================================================================
-//      Tests
-// ===========================================================================
-// ===========================================================================
+/// Error type returned by the closure of a [`CustomByteSource`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CustomReadError<E> {
+    /// The end of the input has been reached
+    Eof,
+    /// No byte is available yet, but reading can be successful again later
+    WouldBlock,
+    /// Any other error of the underlying I/O layer
+    Other(E),
+}
 
-#[cfg(test)]
-mod test_arraybuf {
-    use crate::util::{Buffer, OutOfMemory};
+impl<E> ByteSourceErr for CustomReadError<E> {
+    fn kind(&self) -> ErrKind {
+        match self {
+            CustomReadError::Eof => ErrKind::Eof,
+            CustomReadError::WouldBlock => ErrKind::WouldBlock,
+            CustomReadError::Other(_) => ErrKind::Other,
+        }
+    }
+}
 
-    use super::ArrayBuf;
+impl<E> private::Sealed for CustomReadError<E> {}
 
-    #[test]
-    fn test_basic() {
-        let mut buf: ArrayBuf<5> = (0..3).collect();
-        assert_eq!(buf.len(), 3);
-        assert_eq!(buf.push(10), Ok(()));
-        assert_eq!(buf.len(), 4);
-        assert_eq!(buf.push(20), Ok(()));
-        assert_eq!(buf.len(), 5);
-        assert_eq!(buf.push(30), Err(OutOfMemory));
-        assert_eq!(buf.len(), 5);
-        assert_eq!(&*buf, &[0, 1, 2, 10, 20]);
-        buf.truncate(1000);
-        assert_eq!(&*buf, &[0, 1, 2, 10, 20]);
-        buf.truncate(1);
-        assert_eq!(&*buf, &[0]);
-        buf.truncate(0);
-        assert_eq!(&*buf, &[]);
-        assert_eq!(buf.extend_from_slice(&[7, 6, 5, 4, 3]), Ok(()));
-        assert_eq!(&*buf, &[7, 6, 5, 4, 3]);
-        buf.truncate(1);
-        assert_eq!(&*buf, &[7]);
-        assert_eq!(buf.extend_from_slice(&[10, 11]), Ok(()));
-        assert_eq!(&*buf, &[7, 10, 11]);
-        assert_eq!(buf.extend_from_slice(&[25, 26, 27]), Err(OutOfMemory));
-        buf.clear();
+/// Wraps a closure reading single bytes and implements `ByteSource`
+///
+/// This allows using I/O layers that aren't supported directly (e.g. custom UART
+/// drivers) with [`DecoderReader`](crate::transport::DecoderReader) and [`SmlReader`](crate::SmlReader).
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, CustomByteSource, CustomReadError}};
+/// let mut bytes = include_bytes!("../sample.bin").iter();
+/// let source = CustomByteSource::new(|| match bytes.next() {
+///     Some(b) => Ok(*b),
+///     None => Err(CustomReadError::<()>::Eof),
+/// });
+/// let mut reader = DecoderReader::<ArrayBuf<512>, _>::new(source);
+/// assert!(matches!(reader.next(), Some(Ok(_))));
+/// assert!(reader.next().is_none());
+/// ```
+pub struct CustomByteSource<F> {
+    read: F,
+}
+
+impl<F, E> CustomByteSource<F>
+where
+    F: FnMut() -> Result<u8, CustomReadError<E>>,
+{
+    /// Constructs a `CustomByteSource` calling `read` for each byte.
+    pub fn new(read: F) -> Self {
+        CustomByteSource { read }
+    }
+}
+
+impl<F, E> ByteSource for CustomByteSource<F>
+where
+    F: FnMut() -> Result<u8, CustomReadError<E>>,
+{
+    type ReadError = CustomReadError<E>;
+
+    fn read_byte(&mut self) -> Result<u8, Self::ReadError> {
+        (self.read)()
+    }
+}
+
+impl<F> private::Sealed for CustomByteSource<F> {}
+
+/// Wraps a `ByteSource` and drops duplicated bytes caused by glitches on optical links
+///
+/// Some IR reading heads deliver bytes twice under bounce conditions. The
+/// duplicate arrives immediately after the original byte, much faster than the
+/// next byte could be transmitted at the link's baud rate. `Debounce` timestamps
+/// every byte using a caller-provided clock and drops bytes that are equal to the
+/// previous byte and arrive within `window` clock ticks of it. A run of such
+/// duplicates is dropped completely, as the window is measured from the last
+/// accepted byte.
+///
+/// The window has to be shorter than the transmission time of a single byte
+/// (about 1 ms at 9600 baud), otherwise legitimate repetitions such as the escape
+/// sequence `1b1b1b1b` are dropped as well. The clock must reflect the time
+/// at which the bytes are received, which means that the wrapped source shouldn't
+/// buffer bytes internally.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, CustomByteSource, CustomReadError, Debounce}};
+/// // (timestamp in µs, byte): the byte `0x12` is duplicated after 20µs
+/// let bytes = [
+///     (0, 0x1b), (1040, 0x1b), (2080, 0x1b), (3120, 0x1b), (4160, 0x01), (5200, 0x01), (6240, 0x01), (7280, 0x01),
+///     (8320, 0x12), (8340, 0x12), (9360, 0x34), (10400, 0x56), (11440, 0x78),
+///     (12480, 0x1b), (13520, 0x1b), (14560, 0x1b), (15600, 0x1b), (16640, 0x1a), (17680, 0x00), (18720, 0xb8), (19760, 0x7b),
+/// ];
+/// let now = core::cell::Cell::new(0u64);
+/// let mut iter = bytes.iter();
+/// let source = CustomByteSource::new(|| match iter.next() {
+///     Some((t, b)) => {
+///         now.set(*t);
+///         Ok(*b)
+///     }
+///     None => Err(CustomReadError::<()>::Eof),
+/// });
+/// // drop duplicates arriving within 500µs
+/// let debounced = Debounce::new(source, || now.get(), 500);
+/// let mut reader = DecoderReader::<ArrayBuf<64>, _>::new(debounced);
+/// assert_eq!(reader.next(), Some(Ok([0x12, 0x34, 0x56, 0x78].as_slice())));
+/// ```
+pub struct Debounce<R, C> {
+    inner: R,
+    clock: C,
+    window: u64,
+    last: Option<(u8, u64)>,
+    num_dropped: usize,
+}
+
+impl<R, C> Debounce<R, C>
+where
+    R: ByteSource,
+    C: FnMut() -> u64,
+{
+    /// Wraps `inner`, using `clock` to timestamp received bytes.
+    ///
+    /// Bytes equal to the previous byte are dropped if they arrive within `window`
+    /// ticks of `clock`.
+    pub fn new(inner: R, clock: C, window: u64) -> Self {
+        Debounce {
+            inner,
+            clock,
+            window,
+            last: None,
+            num_dropped: 0,
+        }
+    }
+
+    /// Returns the number of bytes dropped so far.
+    pub fn num_dropped(&self) -> usize {
+        self.num_dropped
+    }
+
+    /// Consumes the `Debounce` and returns the wrapped byte source.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R, C> ByteSource for Debounce<R, C>
+where
+    R: ByteSource,
+    C: FnMut() -> u64,
+{
+    type ReadError = R::ReadError;
+
+    fn read_byte(&mut self) -> Result<u8, Self::ReadError> {
+        loop {
+            let b = self.inner.read_byte()?;
+            let now = (self.clock)();
+            match self.last {
+                Some((last_b, t)) if last_b == b && now.wrapping_sub(t) < self.window => {
+                    self.num_dropped += 1;
+                }
+                _ => {
+                    self.last = Some((b, now));
+                    return Ok(b);
+                }
+            }
+        }
+    }
+}
+
+impl<R, C> private::Sealed for Debounce<R, C> {}
+
+/// Delays between the reconnection attempts of [`Reconnecting`]
+///
+/// The delay starts at `initial` and doubles after each failed attempt until it
+/// reaches `max`.
+///
+/// *This type is available only if sml-rs is built with the `"std"` feature.*
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// delay before the first attempt
+    pub initial: std::time::Duration,
+    /// upper limit of the delay
+    pub max: std::time::Duration,
+    /// number of failed attempts after which the error is returned (`None`: retry forever)
+    pub max_attempts: Option<u32>,
+}
+
+#[cfg(feature = "std")]
+impl Default for Backoff {
+    /// Retries forever, starting with a delay of 500ms and waiting at most 30s.
+    fn default() -> Self {
+        Backoff {
+            initial: std::time::Duration::from_millis(500),
+            max: std::time::Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Backoff {
+    /// Returns the delay before the attempt with index `attempt` (starting at 0).
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// Wraps a `ByteSource` and recreates it after I/O errors
+///
+/// Long-running services reading from USB serial adapters lose their port when the
+/// adapter is re-enumerated. `Reconnecting` calls `factory` to (re)open the source
+/// when it is first read from and after each error of kind [`ErrKind::Other`]. Failed
+/// attempts are retried according to the configured [`Backoff`], blocking the calling
+/// thread in between. `Eof` and `WouldBlock` errors are passed through.
+///
+/// Bytes that were lost while reconnecting cause the current transmission to be
+/// discarded by the decoder, which resynchronizes on the next transmission.
+///
+/// *This type is available only if sml-rs is built with the `"std"` feature.*
+///
+/// # Examples
+///
+/// ```no_run
+/// # use sml_rs::{transport::DecoderReader, util::{ArrayBuf, Backoff, IoByteSource, Reconnecting}};
+/// let source = Reconnecting::new(
+///     || std::fs::File::open("/dev/ttyUSB0").map(IoByteSource::new),
+///     Backoff::default(),
+/// );
+/// let mut reader = DecoderReader::<ArrayBuf<1024>, _>::new(source);
+/// while let Some(res) = reader.next() {
+///     println!("{res:?}");
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub struct Reconnecting<R, F> {
+    inner: Option<R>,
+    factory: F,
+    backoff: Backoff,
+    num_connects: u32,
+    num_disconnects: u32,
+}
+
+#[cfg(feature = "std")]
+impl<R, F> Reconnecting<R, F>
+where
+    R: ByteSource,
+    F: FnMut() -> Result<R, R::ReadError>,
+{
+    /// Constructs a byte source that is opened by `factory` when it is first read from.
+    pub fn new(factory: F, backoff: Backoff) -> Self {
+        Reconnecting {
+            inner: None,
+            factory,
+            backoff,
+            num_connects: 0,
+            num_disconnects: 0,
+        }
+    }
+
+    /// Returns whether the wrapped source is currently open.
+    pub fn is_connected(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Returns the number of times the source has been opened successfully (including the first time).
+    pub fn num_connects(&self) -> u32 {
+        self.num_connects
+    }
+
+    /// Returns the number of times the source has been dropped after an error.
+    pub fn num_disconnects(&self) -> u32 {
+        self.num_disconnects
+    }
+
+    /// Consumes the `Reconnecting` and returns the wrapped byte source if it is open.
+    pub fn into_inner(self) -> Option<R> {
+        self.inner
+    }
+
+    // opens the source, returning the last error if `max_attempts` is exceeded
+    fn connect(&mut self) -> Result<(), R::ReadError> {
+        let mut attempt = 0;
+        loop {
+            // the first connection is established without delay
+            if self.num_connects > 0 || attempt > 0 {
+                std::thread::sleep(self.backoff.delay(attempt));
+            }
+            match (self.factory)() {
+                Ok(inner) => {
+                    self.inner = Some(inner);
+                    self.num_connects += 1;
+                    return Ok(());
+                }
+                Err(e) => {
+                    attempt += 1;
+                    if self.backoff.max_attempts.is_some_and(|max| attempt >= max) {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, F> ByteSource for Reconnecting<R, F>
+where
+    R: ByteSource,
+    F: FnMut() -> Result<R, R::ReadError>,
+{
+    type ReadError = R::ReadError;
+
+    fn read_byte(&mut self) -> Result<u8, Self::ReadError> {
+        loop {
+            match &mut self.inner {
+                Some(inner) => match inner.read_byte() {
+                    Err(e) if matches!(e.kind(), ErrKind::Other) => {
+                        self.inner = None;
+                        self.num_disconnects += 1;
+                    }
+                    res => return res,
+                },
+                None => self.connect()?,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, F> private::Sealed for Reconnecting<R, F> {}
+
+// ===========================================================================
+// ===========================================================================
+//      `Clock` trait
+// ===========================================================================
+// ===========================================================================
+
+/// Source of timestamps used to record when transmissions were received.
+///
+/// The unit and epoch of the returned values are defined by the implementation
+/// (e.g. milliseconds since boot or a Unix timestamp). Values should be monotonic
+/// for latency measurements to be meaningful.
+///
+/// The trait is implemented for closures returning `u64`.
+///
+/// See [`DecoderReader::with_clock`](crate::transport::DecoderReader::with_clock).
+pub trait Clock {
+    /// Returns the current time.
+    fn now(&self) -> u64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> u64,
+{
+    fn now(&self) -> u64 {
+        self()
+    }
+}
+
+// ===========================================================================
+// ===========================================================================
+//      Non-short-circuiting comparisons
+// ===========================================================================
+// ===========================================================================
+
+/// Compares two byte slices without short-circuiting.
+///
+/// Unlike `==`, the comparison doesn't stop at the first differing byte, which makes
+/// it harder to learn how many leading bytes of e.g. a secret `server_id` match. The
+/// lengths themselves aren't considered secret.
+///
+/// This is best-effort and not a cryptographic guarantee: the compiler is kept from
+/// adding early exits using [`core::hint::black_box`], which doesn't guarantee
+/// anything. Use a dedicated crate such as `subtle` if timing side channels are part
+/// of your threat model.
+///
+/// See also [`OctetStrExt`](crate::parser::OctetStrExt).
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::util::eq_ct;
+/// assert!(eq_ct(b"\x0a\x01ISK", b"\x0a\x01ISK"));
+/// assert!(!eq_ct(b"\x0a\x01ISK", b"\x0a\x01ISX"));
+/// assert!(!eq_ct(b"\x0a\x01ISK", b"\x0a\x01IS"));
+/// ```
+#[must_use]
+pub fn eq_ct(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && diff_ct(a, b) == 0
+}
+
+/// Checks without short-circuiting whether `bytes` starts with `prefix`.
+///
+/// Compares all bytes of `prefix` as [`eq_ct`] does, with the same best-effort
+/// caveats. If `bytes` is shorter than `prefix`, `false` is returned. Unlike
+/// comparing a truncated copy of `bytes`, an empty `bytes` never matches a
+/// non-empty `prefix`.
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::util::starts_with_ct;
+/// let server_id = b"\x0a\x01ISK\x00\x04\x7a\x55\x44";
+/// assert!(starts_with_ct(server_id, b"\x0a\x01ISK"));
+/// assert!(!starts_with_ct(server_id, b"\x0a\x01EMH"));
+/// assert!(!starts_with_ct(b"\x0a\x01", b"\x0a\x01ISK"));
+/// ```
+#[must_use]
+pub fn starts_with_ct(bytes: &[u8], prefix: &[u8]) -> bool {
+    match bytes.get(..prefix.len()) {
+        Some(head) => diff_ct(head, prefix) == 0,
+        None => false,
+    }
+}
+
+// returns zero iff the slices (of equal length) are equal, without short-circuiting
+// (`black_box` is only a best-effort barrier against optimizations)
+fn diff_ct(a: &[u8], b: &[u8]) -> u8 {
+    a.iter()
+        .zip(b)
+        .fold(0, |acc, (x, y)| core::hint::black_box(acc | (x ^ y)))
+}
+
+// ===========================================================================
+// ===========================================================================
+//      Hex and base64 capture parsing
+// ===========================================================================
+// ===========================================================================
+
+/// Error type used by `parse_hex_stream`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexParseError {
+    /// The input contains a character that is neither a hex digit, whitespace nor part of a comment
+    ///
+    /// (character, byte_offset)
+    InvalidChar(char, usize),
+    /// The input contains an odd number of hex digits
+    OddLength,
+}
+
+impl core::fmt::Display for HexParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HexParseError::InvalidChar(c, offset) => {
+                write!(
+                    f,
+                    "invalid character {c:?} at byte offset {offset} in hex capture"
+                )
+            }
+            HexParseError::OddLength => {
+                f.write_str("hex capture contains an odd number of hex digits")
+            }
+        }
+    }
+}
+
+impl core::error::Error for HexParseError {}
+
+/// Parses a textual hex capture (such as the `.hex` files of the libsml-testing corpus) into bytes.
+///
+/// Hex digits may be upper- or lowercase. Whitespace (including newlines) is ignored
+/// and may appear anywhere, also between the two digits of a byte. Comments start
+/// with `#` or `//` and extend until the end of the line.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::util::{parse_hex_stream, HexParseError};
+/// let s = "
+///     ## start sequence
+///     1b1b1b1b 01010101
+///     12345678  // payload
+///     1B1B1B1B 1A00B87B
+/// ";
+/// let bytes = parse_hex_stream(s).unwrap();
+/// assert_eq!(bytes.len(), 20);
+/// assert_eq!(&bytes[8..12], &[0x12, 0x34, 0x56, 0x78]);
+///
+/// assert_eq!(parse_hex_stream("1b1b1"), Err(HexParseError::OddLength));
+/// assert_eq!(parse_hex_stream("1b1x"), Err(HexParseError::InvalidChar('x', 3)));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn parse_hex_stream(s: &str) -> Result<alloc::vec::Vec<u8>, HexParseError> {
+    let mut res = alloc::vec::Vec::with_capacity(s.len() / 2);
+    let mut high_nibble = None;
+    for line in s.split_inclusive('\n') {
+        let line_offset = line.as_ptr() as usize - s.as_ptr() as usize;
+        let line = match (line.find('#'), line.find("//")) {
+            (Some(a), Some(b)) => &line[..a.min(b)],
+            (Some(a), None) | (None, Some(a)) => &line[..a],
+            (None, None) => line,
+        };
+        for (idx, c) in line.char_indices() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let Some(nibble) = c.to_digit(16) else {
+                return Err(HexParseError::InvalidChar(c, line_offset + idx));
+            };
+            let nibble = nibble as u8;
+            match high_nibble.take() {
+                None => high_nibble = Some(nibble),
+                Some(high) => res.push((high << 4) | nibble),
+            }
+        }
+    }
+    if high_nibble.is_some() {
+        return Err(HexParseError::OddLength);
+    }
+    Ok(res)
+}
+
+/// Length of the lines of `to_hex_dump`'s output
+pub const HEX_DUMP_LINE_LEN: usize = 8192;
+
+/// Formats bytes in the format of the `.hex` files of the libsml-testing corpus.
+///
+/// The output consists of uppercase hex digits without separators, wrapped into lines
+/// of [`HEX_DUMP_LINE_LEN`] characters and without a trailing newline. This is the
+/// inverse of [`parse_hex_stream`] and gives a canonical text representation of
+/// captures, e.g. to attach them to issues or to compare them in tests.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::util::{parse_hex_stream, to_hex_dump};
+/// let bytes = [0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01];
+/// let s = to_hex_dump(&bytes);
+/// assert_eq!(s, "1B1B1B1B01010101");
+/// assert_eq!(parse_hex_stream(&s).unwrap(), bytes);
+/// ```
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn to_hex_dump(bytes: &[u8]) -> alloc::string::String {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    let mut res = alloc::string::String::with_capacity(2 * bytes.len() + bytes.len() / 4096);
+    for (idx, b) in bytes.iter().enumerate() {
+        if idx > 0 && (2 * idx) % HEX_DUMP_LINE_LEN == 0 {
+            res.push('\n');
+        }
+        res.push(DIGITS[usize::from(b >> 4)].into());
+        res.push(DIGITS[usize::from(b & 0x0f)].into());
+    }
+    res
+}
+
+/// Error type used by `parse_base64`
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Base64ParseError {
+    /// The input contains a character that is neither part of the base64 alphabet nor whitespace
+    ///
+    /// (character, byte_offset)
+    InvalidChar(char, usize),
+    /// The number of base64 characters doesn't correspond to a whole number of bytes
+    InvalidLength,
+}
+
+impl core::fmt::Display for Base64ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Base64ParseError::InvalidChar(c, offset) => {
+                write!(
+                    f,
+                    "invalid character {c:?} at byte offset {offset} in base64 input"
+                )
+            }
+            Base64ParseError::InvalidLength => {
+                f.write_str("base64 input doesn't encode a whole number of bytes")
+            }
+        }
+    }
+}
+
+impl core::error::Error for Base64ParseError {}
+
+/// Parses a base64-encoded capture (e.g. as delivered by web APIs or MQTT brokers) into bytes.
+///
+/// Both the standard (`+`, `/`) and the URL-safe (`-`, `_`) alphabet are accepted.
+/// Whitespace (including newlines) is ignored. Padding (`=`) is optional but may only
+/// appear at the end of the input.
+///
+/// *This function is available only if sml-rs is built with the `"alloc"` feature.*
+///
+/// # Examples
+///
+/// ```
+/// # use sml_rs::util::{parse_base64, Base64ParseError};
+/// assert_eq!(parse_base64("GxsbGwEB\nAQESNFZ4"), Ok(vec![0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78]));
+/// assert_eq!(parse_base64("EjQ="), Ok(vec![0x12, 0x34]));
+/// assert_eq!(parse_base64("EjQ"), Ok(vec![0x12, 0x34]));
+///
+/// assert_eq!(parse_base64("EjQ=x"), Err(Base64ParseError::InvalidChar('x', 4)));
+/// assert_eq!(parse_base64("EjQ0E"), Err(Base64ParseError::InvalidLength));
+/// ```
+#[cfg(feature = "alloc")]
+pub fn parse_base64(s: &str) -> Result<alloc::vec::Vec<u8>, Base64ParseError> {
+    let mut res = alloc::vec::Vec::with_capacity(s.len() / 4 * 3);
+    // bits that haven't been written to `res` yet
+    let mut acc = 0u32;
+    let mut num_bits = 0;
+    let mut num_chars = 0usize;
+    let mut padding = false;
+    for (idx, c) in s.char_indices() {
+        if c.is_whitespace() {
+            continue;
+        }
+        if c == '=' {
+            padding = true;
+            continue;
+        }
+        let value = match c {
+            _ if padding => None,
+            'A'..='Z' => Some(c as u32 - 'A' as u32),
+            'a'..='z' => Some(c as u32 - 'a' as u32 + 26),
+            '0'..='9' => Some(c as u32 - '0' as u32 + 52),
+            '+' | '-' => Some(62),
+            '/' | '_' => Some(63),
+            _ => None,
+        };
+        let Some(value) = value else {
+            return Err(Base64ParseError::InvalidChar(c, idx));
+        };
+        acc = (acc << 6) | value;
+        num_bits += 6;
+        num_chars += 1;
+        if num_bits >= 8 {
+            num_bits -= 8;
+            res.push((acc >> num_bits) as u8);
+            acc &= (1 << num_bits) - 1;
+        }
+    }
+    if num_chars % 4 == 1 {
+        return Err(Base64ParseError::InvalidLength);
+    }
+    Ok(res)
+}
+
+// ===========================================================================
+// waker that does nothing, used to poll futures in tests
+#[cfg(all(
+    test,
+    any(
+        feature = "embassy-sync",
+        feature = "embedded-io-async",
+        feature = "futures-io",
+        feature = "tokio"
+    )
+))]
+pub(crate) fn noop_waker() -> core::task::Waker {
+    struct NoopWaker;
+
+    impl alloc::task::Wake for NoopWaker {
+        fn wake(self: alloc::sync::Arc<Self>) {}
+    }
+
+    alloc::sync::Arc::new(NoopWaker).into()
+}
+
+// ===========================================================================
+//      Tests
+// ===========================================================================
+// ===========================================================================
+
+#[cfg(test)]
+mod test_crc {
+    use super::CRC_X25;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(CRC_X25.checksum(b"123456789"), 0x906e);
+        let mut digest = CRC_X25.digest();
+        digest.update(b"1234");
+        digest.update(b"56789");
+        assert_eq!(digest.finalize(), 0x906e);
+        assert_eq!(CRC_X25.checksum(&[]), 0x0000);
+    }
+
+    #[test]
+    fn state() {
+        let mut digest = CRC_X25.digest();
+        digest.update(b"1234");
+        let mut restored = super::crc_from_state(super::crc_state(&digest));
+        restored.update(b"56789");
+        assert_eq!(restored.finalize(), 0x906e);
+    }
+}
+
+#[cfg(test)]
+mod test_arraybuf {
+    use crate::util::{Buffer, OutOfMemory};
+
+    use super::ArrayBuf;
+
+    #[test]
+    fn test_basic() {
+        let mut buf: ArrayBuf<5> = (0..3).collect();
+        assert_eq!(buf.len(), 3);
+        assert_eq!(buf.push(10), Ok(()));
+        assert_eq!(buf.len(), 4);
+        assert_eq!(buf.push(20), Ok(()));
+        assert_eq!(buf.len(), 5);
+        assert_eq!(
+            buf.push(30),
+            Err(OutOfMemory {
+                needed: 6,
+                available: 5
+            })
+        );
+        assert_eq!(buf.len(), 5);
+        assert_eq!(&*buf, &[0, 1, 2, 10, 20]);
+        buf.truncate(1000);
+        assert_eq!(&*buf, &[0, 1, 2, 10, 20]);
+        buf.truncate(1);
+        assert_eq!(&*buf, &[0]);
+        buf.truncate(0);
+        assert_eq!(&*buf, &[]);
+        assert_eq!(buf.extend_from_slice(&[7, 6, 5, 4, 3]), Ok(()));
+        assert_eq!(&*buf, &[7, 6, 5, 4, 3]);
+        buf.truncate(1);
+        assert_eq!(&*buf, &[7]);
+        assert_eq!(buf.extend_from_slice(&[10, 11]), Ok(()));
+        assert_eq!(&*buf, &[7, 10, 11]);
+        assert_eq!(
+            buf.extend_from_slice(&[25, 26, 27]),
+            Err(OutOfMemory {
+                needed: 6,
+                available: 5
+            })
+        );
+        buf.clear();
         assert_eq!(&*buf, &[]);
     }
 
@@ -464,6 +1718,333 @@ mod test_arraybuf {
     fn test_n0() {
         let mut buf = ArrayBuf::<0>::default();
         assert_eq!(buf.len(), 0);
-        assert_eq!(buf.push(30), Err(OutOfMemory));
+        let err = buf.push(30).unwrap_err();
+        assert_eq!(
+            err,
+            OutOfMemory {
+                needed: 1,
+                available: 0
+            }
+        );
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            alloc::string::ToString::to_string(&err),
+            "out of memory: 1 bytes needed, but only 0 bytes available"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_debounce {
+    use core::cell::Cell;
+
+    use super::{ArrayBuf, CustomByteSource, CustomReadError, Debounce};
+    use crate::transport::{DecoderReader, ReadDecodedError};
+
+    const SAMPLE: &[u8] = include_bytes!("../sample.bin");
+    // transmission time of a byte at 9600 baud in µs
+    const BYTE_TIME: u64 = 1042;
+
+    // simulates a glitchy capture by duplicating the bytes at `glitches` 15µs after the original byte
+    fn read_all(glitches: &[usize], window: Option<u64>) -> (usize, usize) {
+        let now = Cell::new(0);
+        let mut idx = 0;
+        let mut duplicate = false;
+        let source = CustomByteSource::new(|| {
+            let b = *SAMPLE.get(idx).ok_or(CustomReadError::<()>::Eof)?;
+            if duplicate || !glitches.contains(&idx) {
+                now.set(idx as u64 * BYTE_TIME + if duplicate { 15 } else { 0 });
+                duplicate = false;
+                idx += 1;
+            } else {
+                now.set(idx as u64 * BYTE_TIME);
+                duplicate = true;
+            }
+            Ok(b)
+        });
+        match window {
+            Some(window) => {
+                let mut reader = DecoderReader::<ArrayBuf<512>, _>::new(Debounce::new(
+                    source,
+                    || now.get(),
+                    window,
+                ));
+                let num_ok = count_ok(&mut reader);
+                (num_ok, reader.into_parts().1.num_dropped())
+            }
+            None => (
+                count_ok(&mut DecoderReader::<ArrayBuf<512>, _>::new(source)),
+                0,
+            ),
+        }
+    }
+
+    fn count_ok<R: crate::util::ByteSource>(reader: &mut DecoderReader<ArrayBuf<512>, R>) -> usize {
+        let mut num_ok = 0;
+        while let Some(res) = reader.next() {
+            match res {
+                Ok(_) => num_ok += 1,
+                Err(ReadDecodedError::IoErr(..)) => break,
+                Err(_) => {}
+            }
+        }
+        num_ok
+    }
+
+    #[test]
+    fn clean_capture() {
+        assert_eq!(read_all(&[], Some(BYTE_TIME / 2)), (1, 0));
+    }
+
+    #[test]
+    fn glitchy_capture() {
+        // glitches in the start sequence, in the data and in the end sequence
+        let glitches = [2, 5, 40, 41, 100, SAMPLE.len() - 6];
+        assert_eq!(read_all(&glitches, None), (0, 0));
+        assert_eq!(
+            read_all(&glitches, Some(BYTE_TIME / 2)),
+            (1, glitches.len())
+        );
+    }
+
+    #[test]
+    fn window_too_large() {
+        // legitimate repetitions are dropped as well
+        assert_eq!(read_all(&[], Some(2 * BYTE_TIME)).0, 0);
+    }
+}
+
+#[cfg(test)]
+mod test_buffer_pool {
+    use crate::transport::Decoder;
+    use crate::util::{Buffer, OutOfMemory};
+
+    use super::{BufferPool, PooledBuf};
+
+    #[test]
+    fn test_lease() {
+        let pool = BufferPool::<4, 2>::new();
+        assert_eq!(pool.available(), 2);
+        let mut a = pool.lease().unwrap();
+        let b = pool.lease().unwrap();
+        assert_eq!(pool.available(), 0);
+        assert!(pool.lease().is_none());
+        drop(b);
+        assert_eq!(pool.available(), 1);
+
+        assert_eq!(a.extend_from_slice(&[1, 2, 3]), Ok(()));
+        assert_eq!(a.push(4), Ok(()));
+        assert_eq!(
+            a.push(5),
+            Err(OutOfMemory {
+                needed: 5,
+                available: 4
+            })
+        );
+        assert_eq!(&*a, &[1, 2, 3, 4]);
+        a.truncate(1);
+        assert_eq!(&*a, &[1]);
+        a.clear();
+        assert_eq!(&*a, &[]);
+    }
+
+    #[test]
+    fn test_default() {
+        let mut buf = PooledBuf::<4>::default();
+        assert_eq!(&*buf, &[]);
+        assert_eq!(
+            buf.push(1),
+            Err(OutOfMemory {
+                needed: 1,
+                available: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_decoders() {
+        let bytes = hex_literal::hex!("1b1b1b1b 01010101 12345678 1b1b1b1b 1a00b87b");
+        let pool = BufferPool::<8, 2>::new();
+        let mut decoders = [
+            Decoder::from_buf(pool.lease().unwrap()),
+            Decoder::from_buf(pool.lease().unwrap()),
+        ];
+        // interleave the bytes of both decoders
+        for b in bytes {
+            for decoder in &mut decoders {
+                if let Some(msg) = decoder.push_byte(b).unwrap() {
+                    assert_eq!(msg, &[0x12, 0x34, 0x56, 0x78]);
+                }
+            }
+        }
+        let [a, b] = decoders;
+        drop(a.into_buf());
+        assert_eq!(pool.available(), 1);
+        drop(b);
+        assert_eq!(pool.available(), 2);
+    }
+}
+
+#[cfg(test)]
+mod test_ct {
+    use super::{eq_ct, starts_with_ct};
+
+    #[test]
+    fn compare() {
+        assert!(eq_ct(b"", b""));
+        assert!(eq_ct(b"abc", b"abc"));
+        assert!(!eq_ct(b"abc", b"abd"));
+        assert!(!eq_ct(b"abc", b"xbc"));
+        assert!(!eq_ct(b"abc", b"abcd"));
+        assert!(!eq_ct(b"", b"a"));
+
+        assert!(starts_with_ct(b"", b""));
+        assert!(starts_with_ct(b"abc", b""));
+        assert!(starts_with_ct(b"abc", b"ab"));
+        assert!(starts_with_ct(b"abc", b"abc"));
+        assert!(!starts_with_ct(b"abc", b"abcd"));
+        assert!(!starts_with_ct(b"abc", b"b"));
+        assert!(!starts_with_ct(b"", b"a"));
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test_hex_stream {
+    use super::{parse_hex_stream, to_hex_dump, HexParseError, HEX_DUMP_LINE_LEN};
+    use alloc::{vec, vec::Vec};
+
+    #[test]
+    fn basic() {
+        assert_eq!(parse_hex_stream(""), Ok(vec![]));
+        assert_eq!(parse_hex_stream("00ff1B"), Ok(vec![0x00, 0xff, 0x1b]));
+        assert_eq!(
+            parse_hex_stream("00 ff\n1B\r\n"),
+            Ok(vec![0x00, 0xff, 0x1b])
+        );
+        assert_eq!(parse_hex_stream(" 0 0f\tf "), Ok(vec![0x00, 0xff]));
+    }
+
+    #[test]
+    fn comments() {
+        let s = "# header\n12 34 # comment 56\n// another comment\n78 // 9a";
+        assert_eq!(parse_hex_stream(s), Ok(vec![0x12, 0x34, 0x78]));
+        assert_eq!(parse_hex_stream("12 // a # b\n34"), Ok(vec![0x12, 0x34]));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_hex_stream("123"), Err(HexParseError::OddLength));
+        assert_eq!(parse_hex_stream("1\n# 2"), Err(HexParseError::OddLength));
+        assert_eq!(
+            parse_hex_stream("12\n3g"),
+            Err(HexParseError::InvalidChar('g', 4))
+        );
+        assert_eq!(
+            parse_hex_stream("0x12"),
+            Err(HexParseError::InvalidChar('x', 1))
+        );
+        assert_eq!(
+            parse_hex_stream("12 ä"),
+            Err(HexParseError::InvalidChar('ä', 3))
+        );
+    }
+    #[test]
+    fn hex_dump() {
+        assert_eq!(to_hex_dump(&[]), "");
+        assert_eq!(to_hex_dump(&[0x00, 0xab, 0x1b]), "00AB1B");
+
+        let bytes: Vec<u8> = (0..=255).cycle().take(HEX_DUMP_LINE_LEN + 1).collect();
+        let s = to_hex_dump(&bytes);
+        let lines: Vec<_> = s.split('\n').map(str::len).collect();
+        assert_eq!(lines, [HEX_DUMP_LINE_LEN, HEX_DUMP_LINE_LEN, 2]);
+        assert_eq!(parse_hex_stream(&s), Ok(bytes));
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test_base64 {
+    use super::{parse_base64, Base64ParseError};
+    use alloc::vec;
+
+    #[test]
+    fn basic() {
+        assert_eq!(parse_base64(""), Ok(vec![]));
+        assert_eq!(parse_base64("AP8b"), Ok(vec![0x00, 0xff, 0x1b]));
+        assert_eq!(parse_base64("AP\r\n8b \n"), Ok(vec![0x00, 0xff, 0x1b]));
+        assert_eq!(parse_base64("AA=="), Ok(vec![0x00]));
+        assert_eq!(parse_base64("AAA"), Ok(vec![0x00, 0x00]));
+        // standard and URL-safe alphabet
+        assert_eq!(parse_base64("+/+/"), Ok(vec![0xfb, 0xff, 0xbf]));
+        assert_eq!(parse_base64("-_-_"), Ok(vec![0xfb, 0xff, 0xbf]));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_base64("A"), Err(Base64ParseError::InvalidLength));
+        assert_eq!(
+            parse_base64("AAAAA=="),
+            Err(Base64ParseError::InvalidLength)
+        );
+        assert_eq!(
+            parse_base64("AA*A"),
+            Err(Base64ParseError::InvalidChar('*', 2))
+        );
+        assert_eq!(
+            parse_base64("AA=A"),
+            Err(Base64ParseError::InvalidChar('A', 3))
+        );
+        assert_eq!(
+            parse_base64("AAä"),
+            Err(Base64ParseError::InvalidChar('ä', 2))
+        );
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test_reconnecting {
+    use super::{Backoff, ByteSource, CustomByteSource, CustomReadError, Reconnecting};
+    use core::cell::RefCell;
+    use std::time::Duration;
+
+    #[test]
+    fn backoff() {
+        let backoff = Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            max_attempts: None,
+        };
+        let delays = [0, 1, 2, 3, 4, 100].map(|attempt| backoff.delay(attempt).as_millis());
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[test]
+    fn reconnect() {
+        // each session yields some bytes before the device disappears
+        let sessions = RefCell::new(vec![Some(vec![1, 2]), None, Some(vec![3])].into_iter());
+        let factory = || match sessions.borrow_mut().next() {
+            Some(Some(bytes)) => {
+                let mut bytes = bytes.into_iter();
+                Ok(CustomByteSource::new(move || {
+                    bytes.next().ok_or(CustomReadError::Other(()))
+                }))
+            }
+            _ => Err(CustomReadError::Other(())),
+        };
+        let backoff = Backoff {
+            initial: Duration::ZERO,
+            max: Duration::ZERO,
+            max_attempts: Some(3),
+        };
+        let mut source = Reconnecting::new(factory, backoff);
+        assert!(!source.is_connected());
+        assert_eq!(source.read_byte(), Ok(1));
+        assert_eq!(source.read_byte(), Ok(2));
+        // reopened after a failed attempt
+        assert_eq!(source.read_byte(), Ok(3));
+        assert_eq!((source.num_connects(), source.num_disconnects()), (2, 1));
+        // gives up after three failed attempts
+        assert_eq!(source.read_byte(), Err(CustomReadError::Other(())));
+        assert!(!source.is_connected());
+        assert_eq!((source.num_connects(), source.num_disconnects()), (2, 2));
     }
 }
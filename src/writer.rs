@@ -0,0 +1,234 @@
+// Encoding of SML messages, shared by the simulator, the session driver and
+// `File::to_hex_dump`.
+
+use alloc::vec::Vec;
+
+use crate::{
+    parser::{
+        common::{ListEntry, ListType, Status, Time, Value},
+        complete::{File, MessageBody},
+    },
+    util::CRC_X25,
+};
+
+// type nibbles of the SML type-length field
+const TY_OCTET_STRING: u8 = 0x00;
+const TY_BOOLEAN: u8 = 0x40;
+const TY_INTEGER: u8 = 0x50;
+const TY_UNSIGNED: u8 = 0x60;
+const TY_LIST: u8 = 0x70;
+// encoding of omitted optional fields
+pub(crate) const NONE: u8 = 0x01;
+
+// writes a message with the given body tag and body into `out`
+pub(crate) fn write_message(
+    out: &mut Vec<u8>,
+    transaction_id: &mut u32,
+    tag: u32,
+    body: impl FnOnce(&mut Writer),
+) {
+    *transaction_id = transaction_id.wrapping_add(1);
+    write_message_with_header(out, &transaction_id.to_be_bytes(), 0, 0, tag, body);
+}
+
+// writes a message with the given header fields, body tag and body into `out`
+fn write_message_with_header(
+    out: &mut Vec<u8>,
+    transaction_id: &[u8],
+    group_no: u8,
+    abort_on_error: u8,
+    tag: u32,
+    body: impl FnOnce(&mut Writer),
+) {
+    let start = out.len();
+    let mut w = Writer(out);
+    w.list(6);
+    w.octet_string(transaction_id);
+    w.unsigned(group_no);
+    w.unsigned(abort_on_error);
+    w.list(2);
+    w.tag(tag);
+    body(&mut w);
+    let crc = CRC_X25.checksum(&w.0[start..]);
+    w.push(TY_UNSIGNED | 3);
+    w.0.extend_from_slice(&crc.to_le_bytes());
+    w.push(0x00); // end of message
+}
+
+// writes all messages of `file` into `out`
+//
+// Integers and status words keep their size. Checksums are recomputed and skipped
+// unknown fields are omitted.
+pub(crate) fn write_file(out: &mut Vec<u8>, file: &File<'_>) {
+    for msg in &file.messages {
+        let (tag, body): (u32, &dyn Fn(&mut Writer)) = match &msg.message_body {
+            MessageBody::OpenResponse(open) => (0x0101, &|w| {
+                w.list(6);
+                w.optional_octet_string(open.codepage);
+                w.optional_octet_string(open.client_id);
+                w.octet_string(open.req_file_id);
+                w.octet_string(open.server_id);
+                w.optional_time(open.ref_time.as_ref());
+                match open.sml_version {
+                    Some(version) => w.unsigned(version),
+                    None => w.push(NONE),
+                }
+            }),
+            MessageBody::CloseResponse(close) => (0x0201, &|w| {
+                w.list(1);
+                w.optional_octet_string(close.global_signature);
+            }),
+            MessageBody::GetListResponse(glr) => (0x0701, &|w| {
+                w.list(7);
+                w.optional_octet_string(glr.client_id);
+                w.octet_string(glr.server_id);
+                w.optional_octet_string(glr.list_name);
+                w.optional_time(glr.act_sensor_time.as_ref());
+                w.list(glr.val_list.len());
+                for entry in &glr.val_list {
+                    w.list_entry(entry);
+                }
+                w.optional_octet_string(glr.list_signature);
+                w.optional_time(glr.act_gateway_time.as_ref());
+            }),
+        };
+        write_message_with_header(
+            out,
+            msg.transaction_id,
+            msg.group_no,
+            msg.abort_on_error,
+            tag,
+            body,
+        );
+    }
+}
+
+// writes SML-encoded data
+pub(crate) struct Writer<'a>(&'a mut Vec<u8>);
+
+impl Writer<'_> {
+    pub(crate) fn push(&mut self, b: u8) {
+        self.0.push(b);
+    }
+
+    pub(crate) fn tlf(&mut self, ty: u8, len: usize) {
+        // the length field includes the type-length field itself except for lists
+        let mut num_tlf_bytes = 1;
+        while len + if ty == TY_LIST { 0 } else { num_tlf_bytes } >= 1 << (4 * num_tlf_bytes) {
+            num_tlf_bytes += 1;
+        }
+        let len = len + if ty == TY_LIST { 0 } else { num_tlf_bytes };
+        for i in (0..num_tlf_bytes).rev() {
+            let more = if i > 0 { 0x80 } else { 0x00 };
+            let ty = if i == num_tlf_bytes - 1 { ty } else { 0x00 };
+            self.push(more | ty | ((len >> (4 * i)) & 0x0f) as u8);
+        }
+    }
+
+    pub(crate) fn list(&mut self, len: usize) {
+        self.tlf(TY_LIST, len);
+    }
+
+    pub(crate) fn octet_string(&mut self, bytes: &[u8]) {
+        self.tlf(TY_OCTET_STRING, bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn optional_octet_string(&mut self, bytes: Option<&[u8]>) {
+        match bytes {
+            Some(bytes) => self.octet_string(bytes),
+            None => self.push(NONE),
+        }
+    }
+
+    // writes an integer using the smallest of the sizes 1, 2, 4 and 8 bytes
+    pub(crate) fn integer(&mut self, value: i64) {
+        let bytes = value.to_be_bytes();
+        let size = [1, 2, 4]
+            .into_iter()
+            .find(|&size| (-(1i64 << (8 * size - 1))..(1i64 << (8 * size - 1))).contains(&value))
+            .unwrap_or(8);
+        self.tlf(TY_INTEGER, size);
+        self.0.extend_from_slice(&bytes[8 - size..]);
+    }
+
+    fn signed_bytes(&mut self, bytes: &[u8]) {
+        self.tlf(TY_INTEGER, bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn unsigned_bytes(&mut self, bytes: &[u8]) {
+        self.tlf(TY_UNSIGNED, bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn unsigned(&mut self, value: u8) {
+        self.tlf(TY_UNSIGNED, 1);
+        self.push(value);
+    }
+
+    pub(crate) fn tag(&mut self, tag: u32) {
+        self.tlf(TY_UNSIGNED, 2);
+        self.0.extend_from_slice(&tag.to_be_bytes()[2..]);
+    }
+
+    pub(crate) fn sec_index(&mut self, secs: u32) {
+        self.list(2);
+        self.unsigned(1);
+        self.tlf(TY_UNSIGNED, 4);
+        self.0.extend_from_slice(&secs.to_be_bytes());
+    }
+
+    fn optional_time(&mut self, time: Option<&Time>) {
+        match time {
+            Some(Time::SecIndex(secs)) => self.sec_index(*secs),
+            None => self.push(NONE),
+        }
+    }
+
+    fn value(&mut self, value: &Value<'_>) {
+        match value {
+            Value::Bool(b) => {
+                self.tlf(TY_BOOLEAN, 1);
+                self.push(u8::from(*b));
+            }
+            Value::Bytes(bytes) => self.octet_string(bytes),
+            Value::I8(x) => self.signed_bytes(&x.to_be_bytes()),
+            Value::I16(x) => self.signed_bytes(&x.to_be_bytes()),
+            Value::I32(x) => self.signed_bytes(&x.to_be_bytes()),
+            Value::I64(x) => self.signed_bytes(&x.to_be_bytes()),
+            Value::U8(x) => self.unsigned_bytes(&x.to_be_bytes()),
+            Value::U16(x) => self.unsigned_bytes(&x.to_be_bytes()),
+            Value::U32(x) => self.unsigned_bytes(&x.to_be_bytes()),
+            Value::U64(x) => self.unsigned_bytes(&x.to_be_bytes()),
+            Value::List(ListType::Time(time)) => {
+                self.list(2);
+                self.unsigned(1);
+                self.optional_time(Some(time));
+            }
+        }
+    }
+
+    fn list_entry(&mut self, entry: &ListEntry<'_>) {
+        self.list(7);
+        self.octet_string(entry.obj_name);
+        match entry.status {
+            Some(Status::Status8(x)) => self.unsigned_bytes(&x.to_be_bytes()),
+            Some(Status::Status16(x)) => self.unsigned_bytes(&x.to_be_bytes()),
+            Some(Status::Status32(x)) => self.unsigned_bytes(&x.to_be_bytes()),
+            Some(Status::Status64(x)) => self.unsigned_bytes(&x.to_be_bytes()),
+            None => self.push(NONE),
+        }
+        self.optional_time(entry.val_time.as_ref());
+        match entry.unit {
+            Some(unit) => self.unsigned(unit),
+            None => self.push(NONE),
+        }
+        match entry.scaler {
+            Some(scaler) => self.signed_bytes(&scaler.to_be_bytes()),
+            None => self.push(NONE),
+        }
+        self.value(&entry.value);
+        self.optional_octet_string(entry.value_signature);
+    }
+}
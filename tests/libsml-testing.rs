@@ -41,11 +41,18 @@ fn test_repo_validation() {
         let hex_string = std::fs::read_to_string(hex_path).expect("Couldn't read file");
         let hex_bytes = hex::decode(hex_string.trim()).expect("Couldn't decode hex string");
 
+        #[cfg(feature = "alloc")]
+        assert_eq!(
+            sml_rs::util::parse_hex_stream(&hex_string).as_ref(),
+            Ok(&bin_bytes)
+        );
+        #[cfg(feature = "alloc")]
+        assert_eq!(sml_rs::util::to_hex_dump(&bin_bytes), hex_string.trim_end());
         assert_eq!(bin_bytes, hex_bytes);
     }
 }
 
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", feature = "fmt-debug"))]
 #[test]
 fn test_files() {
     use std::fmt::Write;
@@ -54,14 +61,14 @@ fn test_files() {
         let bytes = std::fs::read(path).unwrap();
 
         let mut decoder =
-            sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<2048>>(bytes);
+            sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<2048>>(&bytes);
 
         let mut s = String::new();
         while let Some(result) = decoder.next() {
             // write!(s, "{:?}\n", result.map(|x| x.len())).unwrap();
-            write!(
+            writeln!(
                 s,
-                "{:#?}\n",
+                "{:#?}",
                 result.map(|x| {
                     let res = sml_rs::parser::complete::parse(x);
                     res.expect("Error while parsing:").messages
@@ -69,6 +76,49 @@ fn test_files() {
             )
             .unwrap();
         }
+        #[cfg(feature = "test-util")]
+        assert_eq!(s, sml_rs::test_util::dump(&bytes));
         insta::assert_snapshot!(s);
     });
 }
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_files_sequence() {
+    use sml_rs::parser::sequence::SequenceChecker;
+
+    insta::glob!("libsml-testing/*.bin", |path| {
+        let bytes = std::fs::read(path).unwrap();
+        let mut decoder =
+            sml_rs::transport::decode_streaming::<sml_rs::util::ArrayBuf<2048>>(&bytes);
+
+        // Holley meters restart the transaction ids in each file
+        let per_file_ids = path.to_string_lossy().contains("HOLLEY");
+        let mut checker = SequenceChecker::new().with_per_file_ids(per_file_ids);
+        while let Some(result) = decoder.next() {
+            let Ok(x) = result else {
+                checker.reset();
+                continue;
+            };
+            let file = sml_rs::parser::complete::parse(x).unwrap();
+            assert_eq!(checker.check_file(&file), Ok(()), "{}", path.display());
+        }
+    });
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_files_hex_dump() {
+    use sml_rs::{parser::complete::parse, transport::decode, util::parse_hex_stream};
+
+    insta::glob!("libsml-testing/*.bin", |path| {
+        let bytes = std::fs::read(path).unwrap();
+        for transmission in decode(&bytes).into_iter().flatten() {
+            let file = parse(&transmission).unwrap();
+            let hex = file.to_hex_dump();
+            let encoded = parse_hex_stream(&hex).unwrap();
+            let decoded = decode(&encoded).remove(0).unwrap();
+            assert_eq!(parse(&decoded), Ok(file), "{}", path.display());
+        }
+    });
+}
@@ -0,0 +1,105 @@
+//! Feeds corrupted versions of the transmissions in `tests/libsml-testing` into the
+//! decoders and parsers and checks that none of them panics.
+
+use sml_rs::{
+    parser::{split_messages, streaming, ParseOptions},
+    transport::{decode_streaming, Decoder},
+    util::ArrayBuf,
+};
+
+const OPTIONS: [ParseOptions; 3] = [
+    ParseOptions::new(),
+    ParseOptions::new().verify_crc(false),
+    ParseOptions::new()
+        .verify_crc(false)
+        .ignore_trailing_zeros(true)
+        .empty_octet_strings_as_none(true)
+        .keep_truncated_lists(true),
+];
+
+fn corpus() -> Vec<Vec<u8>> {
+    let mut files: Vec<_> = std::fs::read_dir("./tests/libsml-testing")
+        .expect("test folder does not exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "bin"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty());
+    files
+        .into_iter()
+        .map(|path| std::fs::read(path).unwrap())
+        .collect()
+}
+
+// runs all parsers on `bytes` and consumes their results
+fn parse(bytes: &[u8]) {
+    for options in OPTIONS {
+        streaming::Parser::with_options(bytes, options).for_each(drop);
+        #[cfg(feature = "alloc")]
+        let _ = sml_rs::parser::complete::parse_with_options(bytes, options);
+    }
+    split_messages(bytes).for_each(drop);
+}
+
+// decodes `bytes` and returns the decoded transmissions
+fn decode(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut decoder = Decoder::<ArrayBuf<16>>::new();
+    for &b in bytes {
+        let _ = decoder.push_byte(b);
+    }
+    let _ = decoder.finalize();
+
+    let mut res = Vec::new();
+    let mut iter = decode_streaming::<ArrayBuf<4096>>(bytes);
+    while let Some(item) = iter.next() {
+        if let Ok(msg) = item {
+            res.push(msg.to_vec());
+        }
+    }
+    res
+}
+
+#[test]
+fn corrupted_transmissions() {
+    for file in corpus() {
+        // the raw transport stream isn't valid SML, but mustn't panic either
+        parse(&file[..file.len().min(1024)]);
+
+        // corrupt the transport layer
+        let head = &file[..file.len().min(1024)];
+        for i in (0..head.len()).step_by(7) {
+            let mut bytes = head.to_vec();
+            bytes[i] ^= 0xff;
+            decode(&bytes);
+            decode(&head[..i]);
+        }
+
+        // transmissions of a file are very similar, so only the first ones are corrupted
+        for msg in decode(&file).into_iter().take(2) {
+            // truncated transmissions
+            for len in 0..msg.len() {
+                parse(&msg[..len]);
+            }
+            // single corrupted bytes
+            for i in 0..msg.len() {
+                let mut bytes = msg.clone();
+                bytes[i] ^= [0x01, 0x10, 0x80, 0xff][i % 4];
+                parse(&bytes);
+            }
+        }
+    }
+}
+
+#[test]
+fn long_junk() {
+    // the decoders count discarded bytes, which mustn't overflow
+    let msg = [
+        0x1b, 0x1b, 0x1b, 0x1b, 0x01, 0x01, 0x01, 0x01, 0x12, 0x34, 0x56, 0x78, 0x1b, 0x1b, 0x1b,
+        0x1b, 0x1a, 0x00, 0xb8, 0x7b,
+    ];
+    for junk in [0x00, 0x01, 0x42, 0xff] {
+        let mut bytes = vec![junk; 70_000];
+        bytes.extend_from_slice(&msg);
+        assert_eq!(decode(&bytes), [vec![0x12, 0x34, 0x56, 0x78]]);
+    }
+}